@@ -9,6 +9,12 @@ use serde::{Deserialize, Serialize};
 use tsify::Tsify;
 use wasm_bindgen::{JsValue, prelude::wasm_bindgen};
 
+use crate::PachisloError;
+use crate::curve::ContinuationCurve;
+use crate::engine::{Jitan, Launch, Mechanics, RushModel};
+use crate::guard;
+use crate::warmup::RandomizedStart;
+
 /// Represents a state transition in the pachislo game.
 ///
 /// This structure captures a change from one game state to another,
@@ -37,16 +43,22 @@ impl From<pachislo::game::Transition> for Transition {
 
 /// Represents the current state of a pachislo game session.
 ///
-/// The game can be in one of three states:
+/// The game can be in one of four states:
 /// - `Uninitialized`: Game hasn't started yet
 /// - `Normal`: Standard gameplay mode
 /// - `Rush`: Special high-probability bonus mode
+/// - `Jitan`: Time-shortened mode after a rush, see `Config::with_jitan`
 ///
 /// # Variants
 ///
 /// * `Uninitialized` - Initial state before game starts
 /// * `Normal { balls }` - Standard mode with current ball count
-/// * `Rush { balls, rush_balls, n }` - Rush mode with ball counts and continuation counter
+/// * `Rush { balls, rush_balls, n }` - Rush mode with ball counts and continuation counter;
+///   with an ST rush (see `Config::with_st_rush`), `rush_balls` counts the spins left
+/// * `Jitan { balls, spins }` - Jitan mode with the current ball count and spins left
+///
+/// With the `camel-case` feature, `rush_balls` is serialized as `rushBalls`;
+/// `rush_balls` is still accepted, e.g. from older snapshots.
 #[derive(Clone, Copy, Debug, Deserialize, Serialize, Tsify)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub enum GameState {
@@ -61,11 +73,22 @@ pub enum GameState {
     Rush {
         /// Current number of regular balls
         balls: usize,
-        /// Number of special rush balls
+        /// Number of special rush balls, or the spins left of an ST rush
+        #[cfg_attr(
+            feature = "camel-case",
+            serde(rename = "rushBalls", alias = "rush_balls")
+        )]
         rush_balls: usize,
         /// Rush continuation counter
         n: usize,
     },
+    /// Time-shortened mode with boosted normal probabilities
+    Jitan {
+        /// Current number of balls the player has
+        balls: usize,
+        /// Spins left before normal mode resumes
+        spins: usize,
+    },
 }
 
 impl From<pachislo::game::GameState> for GameState {
@@ -73,6 +96,15 @@ impl From<pachislo::game::GameState> for GameState {
         match state {
             pachislo::game::GameState::Uninitialized => GameState::Uninitialized,
             pachislo::game::GameState::Normal { balls } => GameState::Normal { balls },
+            // The engine keeps jitan mode as a rush without continuation count
+            pachislo::game::GameState::Rush {
+                balls,
+                rush_balls,
+                n: 0,
+            } => GameState::Jitan {
+                balls,
+                spins: rush_balls,
+            },
             pachislo::game::GameState::Rush {
                 balls,
                 rush_balls,
@@ -86,6 +118,29 @@ impl From<pachislo::game::GameState> for GameState {
     }
 }
 
+impl From<GameState> for pachislo::game::GameState {
+    fn from(state: GameState) -> Self {
+        match state {
+            GameState::Uninitialized => pachislo::game::GameState::Uninitialized,
+            GameState::Normal { balls } => pachislo::game::GameState::Normal { balls },
+            GameState::Rush {
+                balls,
+                rush_balls,
+                n,
+            } => pachislo::game::GameState::Rush {
+                balls,
+                rush_balls,
+                n,
+            },
+            GameState::Jitan { balls, spins } => pachislo::game::GameState::Rush {
+                balls,
+                rush_balls: spins,
+                n: 0,
+            },
+        }
+    }
+}
+
 /// Represents the result of a lottery draw in the pachislo game.
 ///
 /// Each lottery can result in either a win or a loss, with different
@@ -110,6 +165,8 @@ pub enum Win {
     Default,
     /// A win that appears as a loss initially but reveals as a win (surprise element)
     FakeWin,
+    /// A win that guarantees the rush and the largest payout (premium cut-in)
+    Premium,
 }
 
 /// Types of losing lottery results.
@@ -176,7 +233,11 @@ pub struct Config {
     /// Ball-related configuration settings
     pub balls: BallsConfig,
     /// Probability settings for different game modes
-    probability: Probability,
+    pub(crate) probability: Probability,
+    /// Warm-up of newly created games, see `Config::with_randomized_start`
+    pub(crate) randomized_start: Option<RandomizedStart>,
+    /// Rush model and jitan mode, see `Config::with_st_rush` and `Config::with_jitan`
+    pub(crate) mechanics: Mechanics,
 }
 
 /// Configuration for ball-related game mechanics.
@@ -192,6 +253,11 @@ pub struct BallsConfig {
     pub incremental_balls: usize,
     /// Number of balls gained when entering rush mode
     pub incremental_rush: usize,
+    /// Number of regular balls consumed per `LaunchBall`, 1 by default
+    pub launch_cost: usize,
+    /// Chance of a launched ball entering the start pocket, 1.0 by default;
+    /// below 1.0, every `CauseLottery` needs a launch that entered it
+    pub start_rate: f64,
 }
 
 /// Probability settings for slot machine outcomes.
@@ -213,7 +279,7 @@ pub struct SlotProbability {
 /// Complete probability configuration for all game modes.
 ///
 /// This structure contains probability settings for each game mode
-/// and a function to calculate rush continuation probability.
+/// and a function or built-in curve to calculate rush continuation probability.
 #[derive(Debug, Clone)]
 #[wasm_bindgen]
 pub struct Probability {
@@ -223,8 +289,17 @@ pub struct Probability {
     pub rush: SlotProbability,
     /// Probabilities for rush continuation
     pub rush_continue: SlotProbability,
-    /// JavaScript function that calculates rush continuation probability based on current count
-    rush_continue_fn: Function,
+    /// Calculates rush continuation probability based on current count
+    rush_continue_fn: RushContinueFn,
+}
+
+/// Source of the rush continuation multiplier.
+#[derive(Debug, Clone)]
+enum RushContinueFn {
+    /// A JavaScript function, called for every continuation lottery
+    Js(Function),
+    /// A built-in curve, evaluated natively
+    Curve(ContinuationCurve),
 }
 
 impl From<SlotProbability> for pachislo::config::SlotProbability {
@@ -237,6 +312,16 @@ impl From<SlotProbability> for pachislo::config::SlotProbability {
     }
 }
 
+impl From<pachislo::config::SlotProbability> for SlotProbability {
+    fn from(probability: pachislo::config::SlotProbability) -> Self {
+        SlotProbability {
+            win: probability.win,
+            fake_win: probability.fake_win,
+            fake_lose: probability.fake_lose,
+        }
+    }
+}
+
 #[wasm_bindgen]
 impl SlotProbability {
     /// Creates a new SlotProbability configuration.
@@ -278,6 +363,9 @@ impl Probability {
     /// ```javascript
     /// const rushContinueFn = (n) => Math.max(0.1, 0.8 - n * 0.1);
     /// ```
+    ///
+    /// Every rush continuation lottery calls the function; prefer
+    /// [`Probability::with_curve`] if a built-in curve fits.
     #[wasm_bindgen(constructor)]
     pub fn new(
         normal: SlotProbability,
@@ -289,7 +377,76 @@ impl Probability {
             normal,
             rush,
             rush_continue,
-            rush_continue_fn,
+            rush_continue_fn: RushContinueFn::Js(rush_continue_fn),
+        }
+    }
+
+    /// Creates a Probability configuration whose rush continuation follows a
+    /// built-in curve, evaluated without calling into JavaScript.
+    ///
+    /// # Arguments
+    ///
+    /// * `normal` - Probability settings for normal mode
+    /// * `rush` - Probability settings for rush mode
+    /// * `rush_continue` - Probability settings for rush continuation
+    /// * `curve` - The rush continuation multiplier per consecutive rush count
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const probability = Probability.with_curve(normal, rush, rushContinue,
+    ///     { type: "Exponential", start: 1.0, ratio: 0.9, floor: 0.3 });
+    /// ```
+    #[wasm_bindgen]
+    pub fn with_curve(
+        normal: SlotProbability,
+        rush: SlotProbability,
+        rush_continue: SlotProbability,
+        curve: ContinuationCurve,
+    ) -> Self {
+        Probability {
+            normal,
+            rush,
+            rush_continue,
+            rush_continue_fn: RushContinueFn::Curve(curve),
+        }
+    }
+}
+
+impl Probability {
+    /// Evaluates the rush continuation multiplier for the `n`-th consecutive rush.
+    ///
+    /// A JavaScript `rush_continue_fn` returning a number outside `[0, 1]` is
+    /// clamped; one that throws or returns anything else counts as 0. Both are
+    /// reported through [`guard::set_invalid_return_handler`].
+    pub(crate) fn rush_continue_multiplier(&self, n: usize) -> f64 {
+        match &self.rush_continue_fn {
+            RushContinueFn::Js(function) => guard::probability(
+                "rush_continue_fn",
+                n,
+                guard::Returned::from_call(function.call1(&JsValue::NULL, &JsValue::from(n))),
+            ),
+            RushContinueFn::Curve(curve) => curve.multiplier(n),
+        }
+    }
+
+    /// Returns the built-in curve, `None` with a JavaScript function.
+    pub(crate) fn curve(&self) -> Option<&ContinuationCurve> {
+        match &self.rush_continue_fn {
+            RushContinueFn::Js(_) => None,
+            RushContinueFn::Curve(curve) => Some(curve),
+        }
+    }
+
+    /// Evaluates the multiplier unguarded, for validation; `None` if a
+    /// JavaScript function throws or does not return a number.
+    pub(crate) fn try_rush_continue_multiplier(&self, n: usize) -> Option<f64> {
+        match &self.rush_continue_fn {
+            RushContinueFn::Js(function) => function
+                .call1(&JsValue::NULL, &JsValue::from(n))
+                .ok()?
+                .as_f64(),
+            RushContinueFn::Curve(curve) => Some(curve.multiplier(n)),
         }
     }
 }
@@ -300,14 +457,7 @@ impl From<Probability> for pachislo::config::Probability<Box<dyn FnMut(usize) ->
             normal: probability.normal.into(),
             rush: probability.rush.into(),
             rush_continue: probability.rush_continue.into(),
-            rush_continue_fn: Box::new(move |n| {
-                probability
-                    .rush_continue_fn
-                    .call1(&JsValue::NULL, &JsValue::from(n))
-                    .unwrap()
-                    .as_f64()
-                    .unwrap()
-            }),
+            rush_continue_fn: Box::new(move |n| probability.rush_continue_multiplier(n)),
         }
     }
 }
@@ -332,6 +482,9 @@ impl BallsConfig {
     /// * `incremental_balls` - Balls gained on normal wins
     /// * `incremental_rush` - Balls gained when entering rush mode
     ///
+    /// Every launch consumes one ball and enters the start pocket; set
+    /// `launch_cost` and `start_rate` to change that.
+    ///
     /// # Example
     ///
     /// ```javascript
@@ -343,6 +496,8 @@ impl BallsConfig {
             init_balls,
             incremental_balls,
             incremental_rush,
+            launch_cost: 1,
+            start_rate: 1.0,
         }
     }
 }
@@ -361,7 +516,235 @@ impl Config {
     /// A complete configuration ready to be used with WasmGame.
     #[wasm_bindgen(constructor)]
     pub fn new(balls: BallsConfig, probability: Probability) -> Self {
-        Config { balls, probability }
+        Config {
+            balls,
+            probability,
+            randomized_start: None,
+            mechanics: Mechanics::default(),
+        }
+    }
+
+    /// Returns a copy of the configuration with an ST (spin time) rush that
+    /// lasts exactly `spins` spins instead of drawing continuation lotteries.
+    ///
+    /// Entering the rush pays `incremental_balls` and starts the count; every
+    /// rush win pays `incremental_balls` again and restarts it, and the rush
+    /// ends after `spins` lost spins in a row. `GameState::Rush.rush_balls`
+    /// counts the spins left, and balls are launched from the regular balls.
+    /// `incremental_rush`, `rush_continue` and the continuation function are
+    /// not used, and `solve_markov_chain` still models a continuation rush.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if `spins` is 0.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// // Rush lasts 100 spins at the rush win probability
+    /// const st = config.with_st_rush(100);
+    /// ```
+    #[wasm_bindgen]
+    pub fn with_st_rush(&self, spins: usize) -> Result<Config, PachisloError> {
+        if spins == 0 {
+            return Err(PachisloError::InvalidConfig {
+                message: "an ST rush needs at least 1 spin".to_string(),
+            });
+        }
+
+        let mut config = self.clone();
+        config.mechanics.rush_model = RushModel::SpinBudget(spins);
+
+        Ok(config)
+    }
+
+    /// Spins an ST rush lasts, or `undefined` for a continuation rush.
+    #[wasm_bindgen(getter)]
+    pub fn st_spins(&self) -> Option<usize> {
+        match self.mechanics.rush_model {
+            RushModel::Continuation => None,
+            RushModel::SpinBudget(spins) => Some(spins),
+        }
+    }
+
+    /// Returns a copy of the configuration that enters jitan (time-shortened)
+    /// mode whenever a rush ends.
+    ///
+    /// Jitan mode lasts `spins` spins drawn at `probability` instead of the
+    /// normal probabilities, launching from the regular balls. A win enters
+    /// a new rush as in normal mode; after `spins` lost spins the game
+    /// returns to normal mode. Its lotteries are reported like normal ones,
+    /// and the state is `GameState::Jitan`.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if `spins` is 0. The probabilities are checked by
+    /// `Config::validate`.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// // 100 spins at a boosted 1/10 after every rush
+    /// const withJitan = config.with_jitan(100, new SlotProbability(0.1, 0.0, 0.0));
+    /// ```
+    #[wasm_bindgen]
+    pub fn with_jitan(
+        &self,
+        spins: usize,
+        probability: SlotProbability,
+    ) -> Result<Config, PachisloError> {
+        if spins == 0 {
+            return Err(PachisloError::InvalidConfig {
+                message: "jitan mode needs at least 1 spin".to_string(),
+            });
+        }
+
+        let mut config = self.clone();
+        config.mechanics.jitan = Some(Jitan {
+            spins,
+            probability: probability.into(),
+        });
+
+        Ok(config)
+    }
+
+    /// Spins jitan mode lasts, or `undefined` without jitan mode.
+    #[wasm_bindgen(getter)]
+    pub fn jitan_spins(&self) -> Option<usize> {
+        self.mechanics.jitan.map(|jitan| jitan.spins)
+    }
+
+    /// Returns a copy of the configuration with a ceiling (天井): after
+    /// `spins` losing normal-mode spins in a row, the next normal-mode
+    /// lottery is a guaranteed win.
+    ///
+    /// The guaranteed spin is drawn at a win probability of 1.0, so it can
+    /// still be a `FakeWin`. Any win resets the count, spins in rush and
+    /// jitan mode are not counted, and the count carries over from one game
+    /// to the next like on a real machine; see `WasmGame::spins_since_win`.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if `spins` is 0.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// // The 1000th spin without a win is guaranteed to win
+    /// const withCeiling = config.with_ceiling(999);
+    /// ```
+    #[wasm_bindgen]
+    pub fn with_ceiling(&self, spins: usize) -> Result<Config, PachisloError> {
+        if spins == 0 {
+            return Err(PachisloError::InvalidConfig {
+                message: "a ceiling needs at least 1 spin".to_string(),
+            });
+        }
+
+        let mut config = self.clone();
+        config.mechanics.ceiling = Some(spins);
+
+        Ok(config)
+    }
+
+    /// Losing spins before the ceiling applies, or `undefined` without one.
+    #[wasm_bindgen(getter)]
+    pub fn ceiling_spins(&self) -> Option<usize> {
+        self.mechanics.ceiling
+    }
+
+    /// Returns a copy of the configuration that stocks up to `cap` wins drawn
+    /// during a rush.
+    ///
+    /// A stocked win is not resolved when drawn; once the current rush ends,
+    /// it is paid out as the next jackpot of the chain, without a
+    /// continuation lottery. Wins beyond the cap are resolved immediately.
+    /// See `WasmGame::stock` and the `"stock_gained"` and `"stock_released"`
+    /// events.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if `cap` is 0.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const withStock = config.with_stock(4);
+    /// ```
+    #[wasm_bindgen]
+    pub fn with_stock(&self, cap: usize) -> Result<Config, PachisloError> {
+        if cap == 0 {
+            return Err(PachisloError::InvalidConfig {
+                message: "a stock needs a cap of at least 1".to_string(),
+            });
+        }
+
+        let mut config = self.clone();
+        config.mechanics.stock_cap = Some(cap);
+
+        Ok(config)
+    }
+
+    /// Most wins a stock holds, or `undefined` without a stock.
+    #[wasm_bindgen(getter)]
+    pub fn stock_cap(&self) -> Option<usize> {
+        self.mechanics.stock_cap
+    }
+
+    /// Returns a copy of the configuration in which a plain win is a
+    /// `Win::Premium` with the given probability.
+    ///
+    /// The premium roll follows a `Win::Default` result of a normal, jitan or
+    /// rush lottery; fake wins are never premium. A premium win enters the
+    /// rush like any win, continues a running rush without a continuation
+    /// lottery, and pays the most rounds with `Config::with_rounds`. Lottery
+    /// events report it as `Win::Premium`, so the UI can play its cut-in.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if `probability` is not between 0.0 (exclusive) and
+    /// 1.0.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// // One win in twenty is a premium win
+    /// const withPremium = config.with_premium(0.05);
+    /// ```
+    #[wasm_bindgen]
+    pub fn with_premium(&self, probability: f64) -> Result<Config, PachisloError> {
+        if !(probability > 0.0 && probability <= 1.0) {
+            return Err(PachisloError::InvalidConfig {
+                message: format!(
+                    "premium probability is {probability}; it must be greater than 0.0 and at most 1.0"
+                ),
+            });
+        }
+
+        let mut config = self.clone();
+        config.mechanics.premium = Some(probability);
+
+        Ok(config)
+    }
+
+    /// Chance of a plain win being premium, or `undefined` without premium wins.
+    #[wasm_bindgen(getter)]
+    pub fn premium_probability(&self) -> Option<f64> {
+        self.mechanics.premium
+    }
+}
+
+impl Config {
+    /// The rules the engine applies on top of the upstream game, including
+    /// the launch settings of `balls` the upstream configuration lacks.
+    pub(crate) fn mechanics(&self) -> Mechanics {
+        Mechanics {
+            launch: Launch {
+                cost: self.balls.launch_cost,
+                start_rate: self.balls.start_rate,
+            },
+            ..self.mechanics.clone()
+        }
     }
 }
 
@@ -458,6 +841,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_jitan_state_conversions() {
+        use pachislo::game::GameState as PachisloGameState;
+
+        let jitan = PachisloGameState::Rush {
+            balls: 80,
+            rush_balls: 40,
+            n: 0,
+        };
+        let converted = GameState::from(jitan);
+        assert!(matches!(
+            converted,
+            GameState::Jitan {
+                balls: 80,
+                spins: 40
+            }
+        ));
+        assert!(matches!(
+            PachisloGameState::from(converted),
+            PachisloGameState::Rush {
+                balls: 80,
+                rush_balls: 40,
+                n: 0
+            }
+        ));
+    }
+
     #[test]
     fn test_lottery_result_conversions() {
         use pachislo::lottery::{
@@ -546,6 +956,23 @@ mod tests {
         assert_eq!(pachislo_config.incremental_rush, 80);
     }
 
+    #[test]
+    fn test_curve_probability_runs_natively() {
+        let probability = Probability::with_curve(
+            SlotProbability::new(0.1, 0.0, 0.0),
+            SlotProbability::new(0.5, 0.0, 0.0),
+            SlotProbability::new(0.8, 0.0, 0.0),
+            ContinuationCurve::Steps {
+                rates: vec![1.0, 0.5],
+            },
+        );
+        assert_eq!(probability.try_rush_continue_multiplier(1), Some(1.0));
+
+        let mut converted: pachislo::config::Probability<Box<dyn FnMut(usize) -> f64>> =
+            probability.into();
+        assert_eq!((converted.rush_continue_fn)(3), 0.5);
+    }
+
     // WebAssembly-specific tests (Probability, Config creation/conversion) are disabled for non-WASM targets
     // These should be run using `wasm-pack test` in a browser environment
 
@@ -584,6 +1011,7 @@ mod tests {
         // Test Win variants
         let default_win = Win::Default;
         let fake_win = Win::FakeWin;
+        let premium = Win::Premium;
 
         // Test Lose variants
         let default_lose = Lose::Default;
@@ -593,11 +1021,18 @@ mod tests {
         match default_win {
             Win::Default => assert!(true),
             Win::FakeWin => panic!("Should be Default"),
+            Win::Premium => panic!("Should be Default"),
         }
 
         match fake_win {
             Win::Default => panic!("Should be FakeWin"),
             Win::FakeWin => assert!(true),
+            Win::Premium => panic!("Should be FakeWin"),
+        }
+
+        match premium {
+            Win::Premium => {}
+            _ => panic!("Should be Premium"),
         }
 
         match default_lose {
@@ -0,0 +1,518 @@
+//! # Analytic Solvers
+//!
+//! This module contains exact, simulation-free analyses of a game configuration.
+//! The results are computed entirely in Rust and returned to JavaScript as plain
+//! serialized objects.
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::PachisloError;
+use crate::alias::{BallsConfig, Config, SlotProbability};
+
+/// Deepest rush followed by analyses that do not take an explicit depth.
+pub(crate) const DEFAULT_MAX_DEPTH: usize = 64;
+
+/// Result of modelling a configuration as a Markov chain.
+///
+/// The chain advances once per launched ball. Every launch enters the start
+/// hole (and therefore draws a lottery) with a fixed probability, matching
+/// pachislo's `LaunchBallFlow`. The ball economy of normal mode is ignored,
+/// so the figures describe a player who never runs out of balls.
+///
+/// Depth-indexed vectors start at `n = 1`, i.e. index `0` is the first rush.
+#[derive(Clone, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct MarkovReport {
+    /// Probability that a single launch in normal mode enters rush
+    pub rush_entry_probability: f64,
+    /// Expected number of normal-mode launches between two rushes
+    pub expected_normal_launches: f64,
+    /// Expected number of launches spent in a single rush
+    pub expected_rush_launches: f64,
+    /// Expected rush depth `n` reached before the rush ends
+    pub expected_chain_length: f64,
+    /// Expected balls paid out during a single rush, excluding the entry payout
+    pub expected_rush_payout: f64,
+    /// Long-run balls paid out per ball spent (出玉率)
+    pub payout_rate: f64,
+    /// Probability that a rush ends at each depth
+    pub absorption: Vec<f64>,
+    /// Probability mass that would continue past `max_depth` and was not followed
+    pub truncated_probability: f64,
+    /// Long-run share of launches spent in normal mode
+    pub stationary_normal: f64,
+    /// Long-run share of launches spent in rush mode, per depth
+    pub stationary_rush: Vec<f64>,
+}
+
+impl MarkovReport {
+    /// Probability that a rush continues at least once (継続率).
+    pub fn continuation_rate(&self) -> f64 {
+        1.0 - self.absorption.first().copied().unwrap_or(1.0)
+    }
+}
+
+/// Solves the Markov chain induced by a configuration.
+///
+/// # Arguments
+///
+/// * `config` - The configuration to analyse
+/// * `start_hole_probability` - Probability that a launched ball draws a lottery (0.0 to 1.0)
+/// * `max_depth` - Deepest rush continuation to follow; the remaining mass is
+///   reported as `truncated_probability`
+///
+/// # Example
+///
+/// ```javascript
+/// const report = solve_markov_chain(config, 0.12, 30);
+/// console.log(report.expected_chain_length);
+/// ```
+#[wasm_bindgen]
+pub fn solve_markov_chain(
+    config: &Config,
+    start_hole_probability: f64,
+    max_depth: usize,
+) -> MarkovReport {
+    let probability = &config.probability;
+
+    solve(
+        &config.balls,
+        probability.normal,
+        probability.rush,
+        probability.rush_continue,
+        |n| probability.rush_continue_multiplier(n),
+        start_hole_probability,
+        max_depth,
+    )
+}
+
+/// Core of [`solve_markov_chain`], independent of the JavaScript callback.
+///
+/// A rush state is `(n, r)`: depth `n` with `r` rush balls left. Each launch
+/// consumes a rush ball, so within one depth `r` only decreases and depth only
+/// increases; the chain is acyclic and every state is visited at most once.
+/// Forward-propagating the entry mass therefore yields exact visit
+/// probabilities, which double as expected launch counts.
+pub(crate) fn solve<F>(
+    balls: &BallsConfig,
+    normal: SlotProbability,
+    rush: SlotProbability,
+    rush_continue: SlotProbability,
+    mut rush_continue_fn: F,
+    start_hole_probability: f64,
+    max_depth: usize,
+) -> MarkovReport
+where
+    F: FnMut(usize) -> f64,
+{
+    let incremental_rush = balls.incremental_rush;
+    let rush_entry_probability = start_hole_probability * normal.win;
+
+    let mut absorption = vec![0.0; max_depth];
+    let mut visits = vec![0.0; max_depth];
+    let mut truncated_probability = 0.0;
+    let mut expected_rush_payout = 0.0;
+
+    if max_depth > 0 && incremental_rush > 0 {
+        let mut mass = vec![0.0; incremental_rush + 1];
+        mass[incremental_rush] = 1.0;
+
+        for depth in 1..=max_depth {
+            let continue_win = rush_continue.win * rush_continue_fn(depth);
+            // The engine refuses to draw with an out-of-range probability and
+            // skips the continuation (and its payout) entirely.
+            let (continuation, payout) = if (0.0..=1.0).contains(&continue_win) {
+                let rush_win = start_hole_probability * rush.win;
+                (
+                    rush_win * continue_win,
+                    rush_win * balls.incremental_balls as f64,
+                )
+            } else {
+                (0.0, 0.0)
+            };
+
+            let mut next = vec![0.0; (depth + 1) * incremental_rush + 1];
+
+            for remaining in (1..mass.len()).rev() {
+                let current = mass[remaining];
+                if current == 0.0 {
+                    continue;
+                }
+
+                visits[depth - 1] += current;
+
+                if remaining == 1 {
+                    absorption[depth - 1] += current;
+                    continue;
+                }
+
+                expected_rush_payout += current * payout;
+                mass[remaining - 1] += current * (1.0 - continuation);
+
+                if depth == max_depth {
+                    truncated_probability += current * continuation;
+                } else {
+                    next[remaining - 1 + incremental_rush] += current * continuation;
+                }
+            }
+
+            mass = next;
+        }
+    } else if max_depth > 0 {
+        // Without rush balls the first launch in rush drops straight back to normal.
+        absorption[0] = 1.0;
+    }
+
+    let expected_rush_launches: f64 = visits.iter().sum();
+    let expected_chain_length = absorption
+        .iter()
+        .enumerate()
+        .map(|(i, p)| (i + 1) as f64 * p)
+        .sum();
+
+    // After a rush ends the final launch still draws a normal lottery, so a new
+    // rush can start without any normal launch in between.
+    let (expected_normal_launches, stationary_normal, stationary_rush) =
+        if rush_entry_probability > 0.0 {
+            let expected_normal_launches = (1.0 - rush_entry_probability) / rush_entry_probability;
+            let cycle = expected_normal_launches + expected_rush_launches;
+
+            if cycle > 0.0 {
+                (
+                    expected_normal_launches,
+                    expected_normal_launches / cycle,
+                    visits.iter().map(|v| v / cycle).collect(),
+                )
+            } else {
+                (expected_normal_launches, 1.0, vec![0.0; max_depth])
+            }
+        } else {
+            (f64::INFINITY, 1.0, vec![0.0; max_depth])
+        };
+
+    // Only normal-mode launches cost balls; rush launches consume rush balls.
+    let payout_rate = if rush_entry_probability == 0.0 {
+        0.0
+    } else if expected_normal_launches == 0.0 {
+        f64::INFINITY
+    } else {
+        (balls.incremental_balls as f64 + expected_rush_payout) / expected_normal_launches
+    };
+
+    MarkovReport {
+        rush_entry_probability,
+        expected_normal_launches,
+        expected_rush_launches,
+        expected_chain_length,
+        expected_rush_payout,
+        payout_rate,
+        absorption,
+        truncated_probability,
+        stationary_normal,
+        stationary_rush,
+    }
+}
+
+/// Break-even figures of a configuration (ボーダーライン).
+///
+/// # Fields
+///
+/// * `payout_per_spin` - Expected balls paid out per normal-mode spin,
+///   including the rushes it leads to
+/// * `spins_per_1000_balls` - Spins per 1000 balls at which the payout
+///   equals the balls spent
+/// * `spins_per_1000_yen` - The same at the given ball purchase rate; the
+///   usual form of the border line
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct BorderLine {
+    pub payout_per_spin: f64,
+    pub spins_per_1000_balls: f64,
+    pub spins_per_1000_yen: f64,
+}
+
+/// Computes the border line, the spin rate at which a machine breaks even.
+///
+/// A machine that spins more often than the border line per 1000 yen pays
+/// out more than it takes in the long run. The payout per spin is that of
+/// [`solve_markov_chain`] with every launch drawing a lottery, so the same
+/// simplifications apply. Balls are exchanged at the purchase rate.
+///
+/// # Arguments
+///
+/// * `config` - The configuration to evaluate
+/// * `balls_per_1000_yen` - Balls bought for 1000 yen, e.g. 250 at 4 yen a ball
+///
+/// # Errors
+///
+/// Throws an `Error` if `balls_per_1000_yen` is not a positive number.
+///
+/// # Example
+///
+/// ```javascript
+/// const { spins_per_1000_yen } = border_line(config, 250);
+/// console.log(`border: ${spins_per_1000_yen.toFixed(1)} spins / 1000 yen`);
+/// ```
+#[wasm_bindgen]
+pub fn border_line(config: &Config, balls_per_1000_yen: f64) -> Result<BorderLine, PachisloError> {
+    if !(balls_per_1000_yen.is_finite() && balls_per_1000_yen > 0.0) {
+        return Err(PachisloError::InvalidArguments {
+            message: format!(
+                "balls_per_1000_yen is {balls_per_1000_yen}; it must be a positive number"
+            ),
+        });
+    }
+
+    let report = solve_markov_chain(config, 1.0, DEFAULT_MAX_DEPTH);
+
+    Ok(border(report.payout_rate, balls_per_1000_yen))
+}
+
+/// Core of [`border_line`]: every spin launches a ball, so the payout rate
+/// is the payout per spin.
+fn border(payout_per_spin: f64, balls_per_1000_yen: f64) -> BorderLine {
+    let spins_per_1000_balls = 1000.0 / payout_per_spin;
+
+    BorderLine {
+        payout_per_spin,
+        spins_per_1000_balls,
+        spins_per_1000_yen: spins_per_1000_balls * balls_per_1000_yen / 1000.0,
+    }
+}
+
+/// Spec sheet (スペック表) of a configuration, computed without simulation.
+///
+/// # Fields
+///
+/// * `hit_one_in` - `x` of the hit probability 1/x of a normal-mode spin
+/// * `rush_hit_one_in` - `x` of the hit probability 1/x of a rush-mode spin
+/// * `continuation_rate` - Probability that a rush continues at least once (継続率)
+/// * `expected_chain_length` - Expected consecutive rushes per rush entry
+/// * `expected_payout_per_jackpot` - Expected balls paid out by a jackpot in
+///   normal mode, including the rush it leads to
+/// * `payout_rate` - Theoretical balls paid out per ball spent (出玉率)
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct SpecReport {
+    pub hit_one_in: f64,
+    pub rush_hit_one_in: f64,
+    pub continuation_rate: f64,
+    pub expected_chain_length: f64,
+    pub expected_payout_per_jackpot: f64,
+    pub payout_rate: f64,
+}
+
+#[wasm_bindgen]
+impl Config {
+    /// Returns the spec sheet of the configuration.
+    ///
+    /// The figures come from [`solve_markov_chain`] with every launch drawing
+    /// a lottery, like [`border_line`], so they are available instantly after
+    /// every edit and the same simplifications apply. Probabilities of 0
+    /// give a `hit_one_in` of `Infinity`.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const spec = config.spec_report();
+    /// specSheet.textContent = `1/${spec.hit_one_in.toFixed(1)} · 継続率 ${(spec.continuation_rate * 100).toFixed(1)}%`;
+    /// ```
+    #[wasm_bindgen]
+    pub fn spec_report(&self) -> SpecReport {
+        let report = solve_markov_chain(self, 1.0, DEFAULT_MAX_DEPTH);
+
+        spec(
+            &self.balls,
+            self.probability.normal,
+            self.probability.rush,
+            &report,
+        )
+    }
+}
+
+/// Core of [`Config::spec_report`], from the Markov chain of the
+/// configuration.
+fn spec(
+    balls: &BallsConfig,
+    normal: SlotProbability,
+    rush: SlotProbability,
+    report: &MarkovReport,
+) -> SpecReport {
+    SpecReport {
+        hit_one_in: 1.0 / normal.win,
+        rush_hit_one_in: 1.0 / rush.win,
+        continuation_rate: report.continuation_rate(),
+        expected_chain_length: report.expected_chain_length,
+        expected_payout_per_jackpot: balls.incremental_balls as f64 + report.expected_rush_payout,
+        payout_rate: report.payout_rate,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx_eq(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_single_rush_ball_ends_immediately() {
+        let balls = BallsConfig::new(100, 15, 1);
+        let prob = SlotProbability::new(0.5, 0.0, 0.0);
+
+        let report = solve(&balls, prob, prob, prob, |_| 1.0, 1.0, 5);
+
+        assert!(approx_eq(report.expected_rush_launches, 1.0));
+        assert!(approx_eq(report.absorption[0], 1.0));
+        assert!(approx_eq(report.expected_chain_length, 1.0));
+        assert!(approx_eq(report.truncated_probability, 0.0));
+    }
+
+    #[test]
+    fn test_absorption_distribution() {
+        // Two rush balls: the first launch continues with probability 0.5,
+        // adding two more balls; depth 2 can never continue (f(2) = 0).
+        let balls = BallsConfig::new(100, 15, 2);
+        let certain = SlotProbability::new(1.0, 0.0, 0.0);
+        let rush_continue = SlotProbability::new(0.5, 0.0, 0.0);
+
+        let report = solve(
+            &balls,
+            certain,
+            certain,
+            rush_continue,
+            |n| if n == 1 { 1.0 } else { 0.0 },
+            1.0,
+            5,
+        );
+
+        assert!(approx_eq(report.absorption[0], 0.5));
+        assert!(approx_eq(report.absorption[1], 0.5));
+        assert!(approx_eq(report.expected_chain_length, 1.5));
+        // Depth 1: two launches without continuation, one with; depth 2: three launches
+        assert!(approx_eq(
+            report.expected_rush_launches,
+            0.5 * 2.0 + 0.5 * 4.0
+        ));
+        // Every launch but the last one of each depth wins a rush lottery
+        assert!(approx_eq(
+            report.expected_rush_payout,
+            15.0 * (1.0 + 0.5 + 0.5)
+        ));
+    }
+
+    #[test]
+    fn test_payout_rate() {
+        // Entering rush pays 15 and the single rush ball never draws, so each
+        // rush pays 15 for an expected (1 - 0.5) / 0.5 = 1 normal launch.
+        let balls = BallsConfig::new(100, 15, 1);
+        let prob = SlotProbability::new(0.5, 0.0, 0.0);
+
+        let report = solve(&balls, prob, prob, prob, |_| 1.0, 1.0, 5);
+
+        assert!(approx_eq(report.expected_normal_launches, 1.0));
+        assert!(approx_eq(report.payout_rate, 15.0));
+    }
+
+    #[test]
+    fn test_stationary_distribution_sums_to_one() {
+        let balls = BallsConfig::new(100, 15, 10);
+        let normal = SlotProbability::new(0.1, 0.05, 0.02);
+        let rush = SlotProbability::new(0.5, 0.1, 0.05);
+        let rush_continue = SlotProbability::new(0.8, 0.1, 0.05);
+
+        let report = solve(&balls, normal, rush, rush_continue, |_| 1.0, 0.12, 200);
+
+        let total = report.stationary_normal + report.stationary_rush.iter().sum::<f64>();
+        assert!(approx_eq(total, 1.0));
+        assert!(report.truncated_probability < 1e-6);
+        let absorbed: f64 = report.absorption.iter().sum();
+        assert!(approx_eq(absorbed + report.truncated_probability, 1.0));
+    }
+
+    #[test]
+    fn test_truncation() {
+        let balls = BallsConfig::new(100, 15, 2);
+        let certain = SlotProbability::new(1.0, 0.0, 0.0);
+
+        let report = solve(&balls, certain, certain, certain, |_| 1.0, 1.0, 3);
+
+        assert!(approx_eq(report.truncated_probability, 1.0));
+        assert!(report.absorption.iter().all(|p| *p == 0.0));
+    }
+
+    #[test]
+    fn test_out_of_range_continuation_never_continues() {
+        let balls = BallsConfig::new(100, 15, 3);
+        let certain = SlotProbability::new(1.0, 0.0, 0.0);
+
+        let report = solve(&balls, certain, certain, certain, |_| 2.0, 1.0, 3);
+
+        assert!(approx_eq(report.absorption[0], 1.0));
+        assert!(approx_eq(report.expected_rush_launches, 3.0));
+    }
+
+    #[test]
+    fn test_no_rush_entry() {
+        let balls = BallsConfig::new(100, 15, 10);
+        let never = SlotProbability::new(0.0, 0.0, 0.0);
+
+        let report = solve(&balls, never, never, never, |_| 1.0, 0.12, 3);
+
+        assert_eq!(report.rush_entry_probability, 0.0);
+        assert_eq!(report.stationary_normal, 1.0);
+        assert_eq!(report.payout_rate, 0.0);
+    }
+
+    #[test]
+    fn test_border_line() {
+        let balls = BallsConfig::new(100, 15, 1);
+        let prob = SlotProbability::new(0.5, 0.0, 0.0);
+        let report = solve(&balls, prob, prob, prob, |_| 1.0, 1.0, 5);
+
+        let line = border(report.payout_rate, 250.0);
+        assert!(approx_eq(line.payout_per_spin, 15.0));
+        assert!(approx_eq(line.spins_per_1000_balls, 1000.0 / 15.0));
+        assert!(approx_eq(line.spins_per_1000_yen, 250.0 / 15.0));
+
+        // A machine that never pays out cannot break even
+        assert_eq!(border(0.0, 250.0).spins_per_1000_yen, f64::INFINITY);
+    }
+
+    #[test]
+    fn test_spec_report() {
+        // Same chain as test_absorption_distribution, with 1/4 hits
+        let balls = BallsConfig::new(100, 15, 2);
+        let normal = SlotProbability::new(0.25, 0.0, 0.0);
+        let certain = SlotProbability::new(1.0, 0.0, 0.0);
+        let rush_continue = SlotProbability::new(0.5, 0.0, 0.0);
+        let report = solve(
+            &balls,
+            normal,
+            certain,
+            rush_continue,
+            |n| if n == 1 { 1.0 } else { 0.0 },
+            1.0,
+            5,
+        );
+
+        let sheet = spec(&balls, normal, certain, &report);
+        assert!(approx_eq(sheet.hit_one_in, 4.0));
+        assert!(approx_eq(sheet.rush_hit_one_in, 1.0));
+        assert!(approx_eq(sheet.continuation_rate, 0.5));
+        assert!(approx_eq(sheet.expected_chain_length, 1.5));
+        assert!(approx_eq(sheet.expected_payout_per_jackpot, 15.0 * 3.0));
+        // A jackpot every 3 normal spins on average
+        assert!(approx_eq(sheet.payout_rate, 15.0));
+
+        let never = SlotProbability::new(0.0, 0.0, 0.0);
+        let report = solve(&balls, never, never, never, |_| 1.0, 1.0, 5);
+        assert_eq!(
+            spec(&balls, never, never, &report).hit_one_in,
+            f64::INFINITY
+        );
+    }
+}
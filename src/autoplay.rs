@@ -0,0 +1,420 @@
+//! # Auto Play
+//!
+//! Plays a game automatically while still accepting manual commands. Manual
+//! commands and automatic steps share a priority queue, so a manual command is
+//! always executed before the next automatic step. Manual commands that would
+//! cut an automatic rush short are resolved by a [`ConflictPolicy`] and
+//! reported through a callback.
+
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use js_sys::Function;
+use pachislo::game::GameState;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    ControlFlow, InnerGame, JsOutput, PachisloError, alias, alias::Config, check_command,
+    check_probability, convert_string_to_command, engine::Command, new_inner_game, rng::GameRng,
+};
+
+/// How a manual command that conflicts with automatic play is resolved.
+///
+/// A conflict is a manual `"FinishGame"` or `"Finish"` sent while automatic
+/// play is running and the game is in rush mode.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Execute the manual command immediately
+    ManualWins,
+    /// Keep the manual command queued until the rush ends
+    #[default]
+    DeferUntilNormal,
+    /// Drop the manual command
+    RejectManual,
+}
+
+/// Outcome of an arbitrated manual command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum Resolution {
+    /// The command was executed despite the conflict
+    Applied,
+    /// The command was postponed until the rush ends
+    Deferred,
+    /// The command was dropped
+    Rejected,
+}
+
+/// Payload passed to the arbitration callback.
+///
+/// # Fields
+///
+/// * `command` - The manual command that conflicted with automatic play
+/// * `resolution` - How the conflict was resolved
+/// * `state` - The game state at the time of the conflict
+#[derive(Clone, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ArbitrationEvent {
+    pub command: String,
+    pub resolution: Resolution,
+    pub state: alias::GameState,
+}
+
+/// Priority of a queued step; higher priorities run first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Priority {
+    Auto,
+    Manual,
+}
+
+/// A step waiting in the queue.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct QueuedStep {
+    priority: Priority,
+    sequence: u64,
+    /// The manual command, or `None` for an automatic step
+    command: Option<String>,
+}
+
+impl Ord for QueuedStep {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Higher priority first, then first in, first out
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+impl PartialOrd for QueuedStep {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Plays a game automatically and arbitrates manual commands.
+///
+/// Every automatic step launches a ball and triggers a lottery with
+/// `start_hole_probability`. Automatic play stops by itself once the
+/// game runs out of balls.
+#[wasm_bindgen]
+pub struct AutoPlayer {
+    game: InnerGame,
+    rng: GameRng,
+    start_hole_probability: f64,
+    auto_running: bool,
+    policy: ConflictPolicy,
+    queue: BinaryHeap<QueuedStep>,
+    deferred: Vec<QueuedStep>,
+    next_sequence: u64,
+    on_arbitration: Function,
+}
+
+#[wasm_bindgen]
+impl AutoPlayer {
+    /// Creates an auto player with automatic play stopped.
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - The JavaScript output handler with callback functions
+    /// * `config` - Game configuration including ball settings and probabilities
+    /// * `start_hole_probability` - Probability that an automatic launch triggers a lottery
+    /// * `on_arbitration` - Called with an [`ArbitrationEvent`] whenever a conflict is resolved
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` describing every problem if the configuration is
+    /// invalid, or an `InvalidArguments` error if `start_hole_probability` is
+    /// not from 0 to 1.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const player = new AutoPlayer(output, config, 0.12, (event) => showToast(event));
+    /// player.queue_command("StartGame");
+    /// player.start_auto();
+    /// setInterval(() => player.tick(), 50);
+    /// finishButton.onclick = () => player.queue_command("FinishGame");
+    /// ```
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        output: JsOutput,
+        config: Config,
+        start_hole_probability: f64,
+        on_arbitration: Function,
+    ) -> Result<AutoPlayer, PachisloError> {
+        config.validate()?;
+        check_probability("start_hole_probability", start_hole_probability)?;
+
+        let mut rng = GameRng::default();
+        let game_rng = rng.split();
+
+        Ok(AutoPlayer {
+            game: new_inner_game(output, config, game_rng),
+            rng,
+            start_hole_probability,
+            auto_running: false,
+            policy: ConflictPolicy::default(),
+            queue: BinaryHeap::new(),
+            deferred: Vec::new(),
+            next_sequence: 0,
+            on_arbitration,
+        })
+    }
+
+    /// Starts automatic play.
+    #[wasm_bindgen]
+    pub fn start_auto(&mut self) {
+        self.auto_running = true;
+    }
+
+    /// Stops automatic play; queued manual commands are kept.
+    #[wasm_bindgen]
+    pub fn stop_auto(&mut self) {
+        self.auto_running = false;
+        self.queue.retain(|step| step.command.is_some());
+    }
+
+    /// Returns whether automatic play is running.
+    #[wasm_bindgen]
+    pub fn is_auto_running(&self) -> bool {
+        self.auto_running
+    }
+
+    /// Sets how conflicting manual commands are resolved.
+    #[wasm_bindgen]
+    pub fn set_conflict_policy(&mut self, policy: ConflictPolicy) {
+        self.policy = policy;
+    }
+
+    /// Queues a manual command; it runs before the next automatic step.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the command string is not recognized.
+    #[wasm_bindgen]
+    pub fn queue_command(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "Command")] command: String,
+    ) -> Result<(), PachisloError> {
+        convert_string_to_command(&command).ok_or_else(|| PachisloError::UnknownCommand {
+            command: command.clone(),
+        })?;
+
+        self.push(Priority::Manual, Some(command));
+
+        Ok(())
+    }
+
+    /// Returns the number of manual commands waiting, including deferred ones.
+    #[wasm_bindgen]
+    pub fn pending_commands(&self) -> usize {
+        let queued = self.queue.iter().filter(|step| step.command.is_some());
+        queued.count() + self.deferred.len()
+    }
+
+    /// Executes the highest-priority queued step, or an automatic step if
+    /// nothing is queued and automatic play is running.
+    ///
+    /// # Returns
+    ///
+    /// `ControlFlow::Break` after a `"Finish"` command or when there is
+    /// nothing left to do, `ControlFlow::Continue` otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if a manual command is not valid in the current
+    /// state, e.g. `"LaunchBall"` before `"StartGame"`. The command is dropped.
+    #[wasm_bindgen]
+    pub fn tick(&mut self) -> Result<ControlFlow, PachisloError> {
+        self.step()
+    }
+
+    /// Returns the current game state.
+    #[wasm_bindgen]
+    pub fn get_state(&self) -> alias::GameState {
+        alias::GameState::from(*self.game.state())
+    }
+}
+
+impl AutoPlayer {
+    fn push(&mut self, priority: Priority, command: Option<String>) {
+        self.queue.push(QueuedStep {
+            priority,
+            sequence: self.next_sequence,
+            command,
+        });
+        self.next_sequence += 1;
+    }
+
+    /// Executes the next step, then reports the first error a callback threw
+    /// during it, unless the step itself failed.
+    fn step(&mut self) -> Result<ControlFlow, PachisloError> {
+        let control_flow = self.advance();
+        let failure = self.game.output_mut().take_failure();
+
+        let control_flow = control_flow?;
+        failure?;
+
+        Ok(control_flow)
+    }
+
+    fn advance(&mut self) -> Result<ControlFlow, PachisloError> {
+        // Jitan mode is not part of the rush
+        if !matches!(self.game.state(), GameState::Rush { n: 1.., .. }) {
+            self.queue.extend(self.deferred.drain(..));
+        }
+
+        if self.auto_running && self.queue.iter().all(|step| step.command.is_some()) {
+            self.push(Priority::Auto, None);
+        }
+
+        let Some(step) = self.queue.pop() else {
+            return Ok(ControlFlow::Break);
+        };
+
+        let Some(input) = step.command.clone() else {
+            return Ok(self.auto_step());
+        };
+
+        if is_conflict(&input, self.game.state(), self.auto_running) {
+            let resolution = resolve(self.policy);
+            self.report(&input, resolution);
+
+            match resolution {
+                Resolution::Applied => {}
+                Resolution::Deferred => {
+                    self.deferred.push(step);
+                    return Ok(ControlFlow::Continue);
+                }
+                Resolution::Rejected => return Ok(ControlFlow::Continue),
+            }
+        }
+
+        let command =
+            convert_string_to_command(&input).ok_or_else(|| PachisloError::UnknownCommand {
+                command: input.clone(),
+            })?;
+
+        check_command(&input, self.game.state())?;
+
+        if command == Command::FinishGame {
+            self.stop_auto();
+        }
+
+        Ok(self.game.run_step_with_command(command).into())
+    }
+
+    fn auto_step(&mut self) -> ControlFlow {
+        if let GameState::Uninitialized = self.game.state() {
+            self.auto_running = false;
+            return ControlFlow::Break;
+        }
+
+        let _ = self.game.run_step_with_command(Command::LaunchBall);
+
+        if !matches!(self.game.state(), GameState::Uninitialized)
+            && self.rng.random_bool(self.start_hole_probability)
+        {
+            let _ = self.game.run_step_with_command(Command::CauseLottery);
+        }
+
+        ControlFlow::Continue
+    }
+
+    fn report(&mut self, command: &str, resolution: Resolution) {
+        let event = ArbitrationEvent {
+            command: command.to_string(),
+            resolution,
+            state: alias::GameState::from(*self.game.state()),
+        };
+
+        let notified = self.on_arbitration.call1(
+            &self.game.output().context,
+            &serde_wasm_bindgen::to_value(&event).unwrap(),
+        );
+        if let Err(reason) = notified {
+            self.game.output_mut().fail(reason);
+        }
+    }
+}
+
+/// Checks whether a manual command would cut an automatic rush short.
+fn is_conflict(input: &str, state: &GameState, auto_running: bool) -> bool {
+    auto_running
+        && matches!(state, GameState::Rush { n: 1.., .. })
+        && matches!(input, "FinishGame" | "Finish")
+}
+
+fn resolve(policy: ConflictPolicy) -> Resolution {
+    match policy {
+        ConflictPolicy::ManualWins => Resolution::Applied,
+        ConflictPolicy::DeferUntilNormal => Resolution::Deferred,
+        ConflictPolicy::RejectManual => Resolution::Rejected,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(priority: Priority, sequence: u64) -> QueuedStep {
+        QueuedStep {
+            priority,
+            sequence,
+            command: None,
+        }
+    }
+
+    #[test]
+    fn test_manual_steps_run_first_in_order() {
+        let mut queue = BinaryHeap::from(vec![
+            step(Priority::Auto, 0),
+            step(Priority::Manual, 2),
+            step(Priority::Manual, 1),
+            step(Priority::Auto, 3),
+        ]);
+
+        let order: Vec<_> = std::iter::from_fn(|| queue.pop())
+            .map(|step| (step.priority, step.sequence))
+            .collect();
+
+        assert_eq!(
+            order,
+            vec![
+                (Priority::Manual, 1),
+                (Priority::Manual, 2),
+                (Priority::Auto, 0),
+                (Priority::Auto, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let rush = GameState::Rush {
+            balls: 10,
+            rush_balls: 5,
+            n: 1,
+        };
+        let normal = GameState::Normal { balls: 10 };
+
+        assert!(is_conflict("FinishGame", &rush, true));
+        assert!(is_conflict("Finish", &rush, true));
+        assert!(!is_conflict("FinishGame", &rush, false));
+        assert!(!is_conflict("FinishGame", &normal, true));
+        assert!(!is_conflict("LaunchBall", &rush, true));
+    }
+
+    #[test]
+    fn test_resolve() {
+        assert_eq!(resolve(ConflictPolicy::ManualWins), Resolution::Applied);
+        assert_eq!(
+            resolve(ConflictPolicy::DeferUntilNormal),
+            Resolution::Deferred
+        );
+        assert_eq!(resolve(ConflictPolicy::RejectManual), Resolution::Rejected);
+    }
+}
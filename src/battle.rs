@@ -0,0 +1,232 @@
+//! # Battle Presentation
+//!
+//! Many machines present the rush continuation as a battle: the hero faces
+//! an enemy, and winning the battle continues the rush. With battles
+//! configured (see `Config::with_battles`), event outputs receive a
+//! `"battle"` event with a [`BattleEvent`] for every continuation lottery,
+//! after the transition of its spin.
+//!
+//! Everything in the event is derived from the continuation lottery, so
+//! battles draw no random numbers of their own. The enemy is picked by the
+//! rolled value: enemies are listed from the weakest to the strongest, and
+//! since low values win, lost battles tend to be fought against stronger
+//! enemies. A revival is a continuation `FakeWin`: the battle looks lost,
+//! then the hero gets back up and wins.
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    PachisloError, Session,
+    alias::{Config, LotteryResult, Win},
+    engine::Battles,
+    event,
+    explain::ContinuationExplanation,
+};
+
+/// How a battle ends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum BattleOutcome {
+    /// The rush continues
+    Victory,
+    /// The rush does not continue
+    Defeat,
+}
+
+/// Payload of the `"battle"` event.
+///
+/// # Fields
+///
+/// * `n` - Consecutive rush count of the continuation lottery
+/// * `enemy` - Name of the enemy fought
+/// * `enemy_index` - Position of the enemy in the list given to
+///   `Config::with_battles`, 0 being the weakest
+/// * `outcome` - How the battle ends
+/// * `revival` - Whether the battle looks lost before the victory
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct BattleEvent {
+    pub n: usize,
+    pub enemy: String,
+    pub enemy_index: usize,
+    pub outcome: BattleOutcome,
+    pub revival: bool,
+}
+
+#[wasm_bindgen]
+impl Config {
+    /// Returns a copy of the configuration that presents rush continuation
+    /// lotteries as battles.
+    ///
+    /// Every continuation lottery is followed by a `"battle"` event naming
+    /// one of `enemies`, listed from the weakest to the strongest, and its
+    /// outcome. `revival` replaces `rush_continue.fake_win`: it is the chance
+    /// of a won battle looking lost at first, a revival. The chance of
+    /// winning a battle is unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if `enemies` is empty or `revival` is not between
+    /// 0.0 and 1.0.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const withBattles = config.with_battles(["Slime", "Knight", "Dragon"], 0.1);
+    /// events.on("battle", ({ enemy, outcome, revival }) => playBattle(enemy, outcome, revival));
+    /// ```
+    #[wasm_bindgen]
+    pub fn with_battles(
+        &self,
+        enemies: Vec<String>,
+        revival: f64,
+    ) -> Result<Config, PachisloError> {
+        if enemies.is_empty() {
+            return Err(PachisloError::InvalidConfig {
+                message: "battles need at least 1 enemy".to_string(),
+            });
+        }
+        if !(0.0..=1.0).contains(&revival) {
+            return Err(PachisloError::InvalidConfig {
+                message: format!(
+                    "revival probability is {revival}; it must be between 0.0 and 1.0"
+                ),
+            });
+        }
+
+        let mut config = self.clone();
+        config.mechanics.battles = Some(Battles { enemies, revival });
+
+        Ok(config)
+    }
+
+    /// Enemies of the battle presentation, empty without battles.
+    #[wasm_bindgen(getter)]
+    pub fn battle_enemies(&self) -> Vec<String> {
+        self.mechanics
+            .battles
+            .as_ref()
+            .map_or(Vec::new(), |battles| battles.enemies.clone())
+    }
+}
+
+/// The battle presenting a continuation; `None` if its lottery was skipped.
+pub(crate) fn battle(
+    enemies: &[String],
+    continuation: &ContinuationExplanation,
+) -> Option<BattleEvent> {
+    let lottery = continuation.lottery?;
+
+    // Certain wins roll nothing and face the weakest enemy
+    let value = lottery.win.value.unwrap_or(0.0);
+    let enemy_index = ((value * enemies.len() as f64) as usize).min(enemies.len() - 1);
+
+    Some(BattleEvent {
+        n: continuation.n,
+        enemy: enemies[enemy_index].clone(),
+        enemy_index,
+        outcome: if lottery.result.is_win() {
+            BattleOutcome::Victory
+        } else {
+            BattleOutcome::Defeat
+        },
+        revival: matches!(lottery.result, LotteryResult::Win(Win::FakeWin)),
+    })
+}
+
+impl Session {
+    /// Emits `"battle"` for the continuation lottery of the last spin.
+    pub(crate) fn emit_battle(&mut self) {
+        let Some(battles) = self.engine.battles() else {
+            return;
+        };
+        let Some(continuation) = self.engine.last_spin().and_then(|spin| spin.continuation) else {
+            return;
+        };
+        let Some(battle) = battle(&battles.enemies, &continuation) else {
+            return;
+        };
+
+        let output = self.engine.output_mut();
+        if output.is_event_output() {
+            output.emit(
+                event::BATTLE,
+                serde_wasm_bindgen::to_value(&battle).unwrap(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        alias::Lose,
+        explain::{LotteryExplanation, Roll},
+    };
+
+    fn continuation(value: Option<f64>, result: LotteryResult) -> ContinuationExplanation {
+        let roll = Roll {
+            probability: 0.5,
+            value,
+            passed: result.is_win(),
+        };
+        ContinuationExplanation {
+            n: 2,
+            base_win: 0.5,
+            multiplier: 1.0,
+            effective_win: 0.5,
+            lottery: Some(LotteryExplanation {
+                win: roll,
+                detail: roll,
+                premium: None,
+                result,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_battle() {
+        let enemies = ["Slime", "Knight", "Dragon"].map(String::from);
+
+        let won = battle(
+            &enemies,
+            &continuation(Some(0.1), LotteryResult::Win(Win::Default)),
+        )
+        .unwrap();
+        assert_eq!(won.enemy, "Slime");
+        assert_eq!(won.outcome, BattleOutcome::Victory);
+        assert!(!won.revival);
+
+        let revived = battle(
+            &enemies,
+            &continuation(Some(0.4), LotteryResult::Win(Win::FakeWin)),
+        )
+        .unwrap();
+        assert_eq!(revived.enemy_index, 1);
+        assert!(revived.revival);
+
+        let lost = battle(
+            &enemies,
+            &continuation(Some(0.99), LotteryResult::Lose(Lose::FakeLose)),
+        )
+        .unwrap();
+        assert_eq!(lost.enemy, "Dragon");
+        assert_eq!(lost.outcome, BattleOutcome::Defeat);
+        assert!(!lost.revival);
+
+        let certain = battle(
+            &enemies,
+            &continuation(None, LotteryResult::Win(Win::Default)),
+        );
+        assert_eq!(certain.unwrap().enemy_index, 0);
+
+        let skipped = ContinuationExplanation {
+            lottery: None,
+            ..continuation(None, LotteryResult::Win(Win::Default))
+        };
+        assert!(battle(&enemies, &skipped).is_none());
+    }
+}
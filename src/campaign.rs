@@ -0,0 +1,239 @@
+//! # Campaign Mode
+//!
+//! A campaign chains several configurations into stages. A stage is cleared
+//! once the player holds a target number of balls; the next stage then starts
+//! immediately with a (typically harder) spec, carrying the balls over.
+
+use js_sys::Function;
+use pachislo::game::GameState;
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    ControlFlow, InnerGame, JsOutput, PachisloError, alias::Config, callback_failed, check_command,
+    convert_string_to_command, new_inner_game, rng::GameRng,
+};
+
+/// Payload passed to the stage transition callback.
+///
+/// # Fields
+///
+/// * `cleared_stage` - Index of the stage that was just cleared
+/// * `next_stage` - Index of the stage that starts now, `None` if the campaign is complete
+/// * `carried_balls` - Balls carried over into the next stage
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct StageTransition {
+    pub cleared_stage: usize,
+    pub next_stage: Option<usize>,
+    pub carried_balls: usize,
+}
+
+/// A single campaign stage: a configuration and the ball count that clears it.
+struct Stage {
+    config: Config,
+    target_balls: usize,
+}
+
+/// Runs a sequence of stages as one continuous session.
+///
+/// The first stage starts with its configured `init_balls`; every later stage
+/// ignores its own `init_balls` and starts with the balls carried over from the
+/// previous stage. Rush balls are not carried over.
+#[wasm_bindgen]
+pub struct Campaign {
+    stages: Vec<Stage>,
+    current: usize,
+    /// Whether the last stage was cleared
+    complete: bool,
+    game: Option<InnerGame>,
+    output: JsOutput,
+    on_stage_transition: Function,
+}
+
+#[wasm_bindgen]
+impl Campaign {
+    /// Creates an empty campaign.
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - Output handler shared by every stage
+    /// * `on_stage_transition` - Called with a [`StageTransition`] whenever a stage is cleared
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const campaign = new Campaign(output, (transition) => showStageBanner(transition));
+    /// campaign.add_stage(easyConfig, 1500);
+    /// campaign.add_stage(hardConfig, 5000);
+    /// campaign.run_step_with_command("StartGame");
+    /// ```
+    #[wasm_bindgen(constructor)]
+    pub fn new(output: JsOutput, on_stage_transition: Function) -> Self {
+        Campaign {
+            stages: Vec::new(),
+            current: 0,
+            complete: false,
+            game: None,
+            output,
+            on_stage_transition,
+        }
+    }
+
+    /// Appends a stage to the campaign.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Spec used while this stage is played
+    /// * `target_balls` - Ball count at which the stage is cleared
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` describing every problem if the configuration is
+    /// invalid; the stage is not added in that case.
+    #[wasm_bindgen]
+    pub fn add_stage(&mut self, config: Config, target_balls: usize) -> Result<(), PachisloError> {
+        config.validate()?;
+
+        self.stages.push(Stage {
+            config,
+            target_balls,
+        });
+
+        Ok(())
+    }
+
+    /// Returns the index of the stage currently being played.
+    #[wasm_bindgen]
+    pub fn current_stage(&self) -> usize {
+        self.current
+    }
+
+    /// Returns the number of stages in the campaign.
+    #[wasm_bindgen]
+    pub fn stage_count(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// Returns whether the last stage was cleared.
+    #[wasm_bindgen]
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// Executes a single game step in the current stage.
+    ///
+    /// After the step the ball count is compared with the stage target. Clearing
+    /// a stage emits a [`StageTransition`] and starts the next stage right away.
+    ///
+    /// # Returns
+    ///
+    /// `ControlFlow::Break` once the game finishes or the last stage is cleared.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the command string is not recognized or not valid
+    /// in the current state, exactly like `WasmGame::run_step_with_command`,
+    /// or an `InvalidArguments` error if the campaign has no stages or is
+    /// complete.
+    #[wasm_bindgen]
+    pub fn run_step_with_command(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "Command")] command: String,
+    ) -> Result<ControlFlow, PachisloError> {
+        self.step(&command)
+    }
+}
+
+impl Campaign {
+    fn step(&mut self, input: &str) -> Result<ControlFlow, PachisloError> {
+        let command =
+            convert_string_to_command(input).ok_or_else(|| PachisloError::UnknownCommand {
+                command: input.to_string(),
+            })?;
+
+        if self.complete {
+            return Err(PachisloError::InvalidArguments {
+                message: "the campaign is complete".to_string(),
+            });
+        }
+        let Some(stage) = self.stages.get(self.current) else {
+            return Err(PachisloError::InvalidArguments {
+                message: "the campaign has no stages".to_string(),
+            });
+        };
+        let target_balls = stage.target_balls;
+        let game = self
+            .game
+            .get_or_insert_with(|| new_stage_game(&self.output, stage.config.clone()));
+
+        check_command(input, game.state())?;
+
+        let stepped = game.run_step_with_command(command);
+        // A throwing callback is reported once the stage is settled
+        let failure = game.output_mut().take_failure();
+
+        let control_flow = if stepped.is_break() {
+            Ok(ControlFlow::Break)
+        } else {
+            self.settle_stage(target_balls)
+        };
+        failure?;
+
+        control_flow
+    }
+
+    /// Moves on to the next stage once the balls reach `target_balls`.
+    fn settle_stage(&mut self, target_balls: usize) -> Result<ControlFlow, PachisloError> {
+        let Some(game) = &self.game else {
+            return Ok(ControlFlow::Continue);
+        };
+        let balls = match *game.state() {
+            GameState::Uninitialized => return Ok(ControlFlow::Continue),
+            GameState::Normal { balls } | GameState::Rush { balls, .. } => balls,
+        };
+
+        if balls < target_balls {
+            return Ok(ControlFlow::Continue);
+        }
+
+        let cleared_stage = self.current;
+        let next_stage = (cleared_stage + 1 < self.stages.len()).then_some(cleared_stage + 1);
+
+        let transition = StageTransition {
+            cleared_stage,
+            next_stage,
+            carried_balls: balls,
+        };
+        let notified = self.on_stage_transition.call1(
+            &self.output.context,
+            &serde_wasm_bindgen::to_value(&transition).unwrap(),
+        );
+
+        let Some(next_stage) = next_stage else {
+            self.complete = true;
+            notified.map_err(callback_failed)?;
+            return Ok(ControlFlow::Break);
+        };
+
+        let mut config = self.stages[next_stage].config.clone();
+        config.balls.init_balls = balls;
+
+        let mut game = new_stage_game(&self.output, config);
+        game.start();
+        let started = game.output_mut().take_failure();
+
+        self.current = next_stage;
+        self.game = Some(game);
+
+        notified.map_err(callback_failed)?;
+        started?;
+
+        Ok(ControlFlow::Continue)
+    }
+}
+
+fn new_stage_game(output: &JsOutput, config: Config) -> InnerGame {
+    new_inner_game(output.clone(), config, GameRng::default())
+}
@@ -0,0 +1,216 @@
+//! # Daily Challenges
+//!
+//! Deterministically derives a seed and a slightly mutated configuration from a
+//! calendar date, so that every player of an app gets the identical challenge
+//! on the same day.
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::PachisloError;
+use crate::alias::{BallsConfig, Config, SlotProbability};
+use crate::hash::{self, Fnv1a};
+
+/// Largest relative change applied to the normal-mode win probability.
+const NORMAL_WIN_VARIATION: f64 = 0.1;
+
+/// Largest relative change applied to the rush-mode win probability.
+const RUSH_WIN_VARIATION: f64 = 0.05;
+
+/// Largest relative change applied to the balls paid out per win.
+const INCREMENTAL_BALLS_VARIATION: f64 = 0.1;
+
+/// A challenge derived from a date and a base configuration.
+///
+/// The same date and base configuration always produce the same seed and
+/// the same mutated configuration.
+#[wasm_bindgen]
+pub struct DailyChallenge {
+    date: String,
+    seed: u64,
+    config: Config,
+}
+
+#[wasm_bindgen]
+impl DailyChallenge {
+    /// Derives the challenge for a date.
+    ///
+    /// # Arguments
+    ///
+    /// * `date` - Calendar date in `YYYY-MM-DD` format
+    /// * `base_config` - Configuration the daily mutations are applied to
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if `date` is not a valid `YYYY-MM-DD` date.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const challenge = DailyChallenge.from_date("2025-08-01", baseConfig);
+    /// const game = new WasmGame(input, output, challenge.config);
+    /// ```
+    #[wasm_bindgen]
+    pub fn from_date(date: String, base_config: &Config) -> Result<DailyChallenge, PachisloError> {
+        if !is_valid_date(&date) {
+            return Err(PachisloError::InvalidArguments {
+                message: format!("invalid challenge date {date:?}; expected YYYY-MM-DD"),
+            });
+        }
+
+        let seed = derive_seed(&date);
+
+        let mut config = base_config.clone();
+        let probability = &mut config.probability;
+        (config.balls, probability.normal, probability.rush) =
+            mutate(config.balls, probability.normal, probability.rush, seed);
+
+        Ok(DailyChallenge { date, seed, config })
+    }
+
+    /// The date this challenge was derived from.
+    #[wasm_bindgen(getter)]
+    pub fn date(&self) -> String {
+        self.date.clone()
+    }
+
+    /// The seed shared by every player of this challenge.
+    #[wasm_bindgen(getter)]
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The mutated configuration to play this challenge with.
+    #[wasm_bindgen(getter)]
+    pub fn config(&self) -> Config {
+        self.config.clone()
+    }
+
+    /// Hashes a result so that a score can be submitted for this challenge.
+    ///
+    /// The hash binds the final ball count to the date, seed and configuration,
+    /// so a backend can recompute it and reject scores submitted for a
+    /// different challenge. It is a checksum, not a signature.
+    ///
+    /// # Returns
+    ///
+    /// The hash as a 16-digit lowercase hexadecimal string.
+    #[wasm_bindgen]
+    pub fn result_hash(&self, final_balls: usize) -> String {
+        let mut hasher = Fnv1a::new();
+        hasher.write_str(&self.date);
+        hasher.write_u64(self.seed);
+        hash::hash_config(&mut hasher, &self.config);
+        hasher.write_usize(final_balls);
+
+        format!("{:016x}", hasher.finish())
+    }
+}
+
+/// Checks that `date` is a `YYYY-MM-DD` calendar date.
+fn is_valid_date(date: &str) -> bool {
+    let bytes = date.as_bytes();
+    if bytes.len() != 10 || bytes[4] != b'-' || bytes[7] != b'-' {
+        return false;
+    }
+
+    let number = |range: std::ops::Range<usize>| -> Option<u32> {
+        let part = &date[range];
+        part.bytes()
+            .all(|byte| byte.is_ascii_digit())
+            .then(|| part.parse().ok())
+            .flatten()
+    };
+
+    let (Some(year), Some(month), Some(day)) = (number(0..4), number(5..7), number(8..10)) else {
+        return false;
+    };
+
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return false,
+    };
+
+    (1..=days_in_month).contains(&day)
+}
+
+/// Derives the challenge seed from a date.
+fn derive_seed(date: &str) -> u64 {
+    let mut hasher = Fnv1a::new();
+    hasher.write_str("pachislo-wasm daily challenge");
+    hasher.write_str(date);
+
+    let mut state = hasher.finish();
+    hash::splitmix64(&mut state)
+}
+
+/// Applies the small, seed-dependent daily mutations.
+fn mutate(
+    mut balls: BallsConfig,
+    mut normal: SlotProbability,
+    mut rush: SlotProbability,
+    seed: u64,
+) -> (BallsConfig, SlotProbability, SlotProbability) {
+    let mut state = seed;
+    let mut factor =
+        |variation: f64| 1.0 + variation * (2.0 * hash::splitmix64_f64(&mut state) - 1.0);
+
+    normal.win = (normal.win * factor(NORMAL_WIN_VARIATION)).clamp(0.0, 1.0);
+    rush.win = (rush.win * factor(RUSH_WIN_VARIATION)).clamp(0.0, 1.0);
+    balls.incremental_balls =
+        (balls.incremental_balls as f64 * factor(INCREMENTAL_BALLS_VARIATION)).round() as usize;
+
+    (balls, normal, rush)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_date_validation() {
+        assert!(is_valid_date("2025-08-01"));
+        assert!(is_valid_date("2024-02-29"));
+        assert!(!is_valid_date("2023-02-29"));
+        assert!(!is_valid_date("2025-13-01"));
+        assert!(!is_valid_date("2025-8-01"));
+        assert!(!is_valid_date("2025/08/01"));
+        assert!(!is_valid_date("2025-08-+1"));
+        assert!(!is_valid_date(""));
+    }
+
+    #[test]
+    fn test_seed_is_deterministic() {
+        assert_eq!(derive_seed("2025-08-01"), derive_seed("2025-08-01"));
+        assert_ne!(derive_seed("2025-08-01"), derive_seed("2025-08-02"));
+    }
+
+    #[test]
+    fn test_mutations_are_small_and_deterministic() {
+        let balls = BallsConfig::new(1000, 100, 300);
+        let normal = SlotProbability::new(0.1, 0.05, 0.02);
+        let rush = SlotProbability::new(0.5, 0.1, 0.05);
+
+        for date in ["2025-08-01", "2025-08-02", "2025-12-31"] {
+            let seed = derive_seed(date);
+            let (mutated_balls, mutated_normal, mutated_rush) = mutate(balls, normal, rush, seed);
+            let (again_balls, again_normal, again_rush) = mutate(balls, normal, rush, seed);
+
+            assert_eq!(mutated_normal.win, again_normal.win);
+            assert_eq!(mutated_rush.win, again_rush.win);
+            assert_eq!(
+                mutated_balls.incremental_balls,
+                again_balls.incremental_balls
+            );
+
+            assert!((mutated_normal.win - 0.1).abs() <= 0.1 * NORMAL_WIN_VARIATION + 1e-12);
+            assert!((mutated_rush.win - 0.5).abs() <= 0.5 * RUSH_WIN_VARIATION + 1e-12);
+            assert!((90..=110).contains(&mutated_balls.incremental_balls));
+            assert_eq!(mutated_balls.init_balls, 1000);
+            assert_eq!(mutated_normal.fake_win, 0.05);
+        }
+    }
+}
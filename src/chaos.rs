@@ -0,0 +1,248 @@
+//! # Fault Injection
+//!
+//! Only compiled with the `chaos` feature. Chaos mode makes a game fail the
+//! way it can fail in production, at random but reproducibly, so applications
+//! can test how they cope before it happens for real:
+//!
+//! - Event output callbacks are delayed: an event is held back and delivered
+//!   after some later events, i.e. late and out of order
+//! - Serializing a result fails, for `get_state`, `stats`, `snapshot` and
+//!   `get_history`
+//! - Game steps fail as if another call held the game lock
+//!
+//! Injected errors are thrown like real ones, with messages starting with
+//! `"injected fault: "`.
+
+use std::cell::RefCell;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{PachisloError, WasmGame, rng::GameRng};
+
+/// Which faults chaos mode injects and how often.
+///
+/// # Fields
+///
+/// * `seed` - Seed for the fault decisions, independent of the game's seed;
+///   random if omitted
+/// * `delay_probability` - Probability that an event of an event output is delayed
+/// * `max_delay_events` - A delayed event is delivered after up to this many later events
+/// * `serialization_failure_probability` - Probability that serializing a result fails
+/// * `lock_contention_probability` - Probability that a step fails as if the game were locked
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(default)]
+pub struct ChaosOptions {
+    pub seed: Option<u64>,
+    pub delay_probability: f64,
+    pub max_delay_events: usize,
+    pub serialization_failure_probability: f64,
+    pub lock_contention_probability: f64,
+}
+
+impl Default for ChaosOptions {
+    fn default() -> Self {
+        ChaosOptions {
+            seed: None,
+            delay_probability: 0.0,
+            max_delay_events: 3,
+            serialization_failure_probability: 0.0,
+            lock_contention_probability: 0.0,
+        }
+    }
+}
+
+/// A fault chaos mode can inject into a `WasmGame` call.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Fault {
+    Serialization,
+    LockContention,
+}
+
+#[wasm_bindgen]
+impl WasmGame {
+    /// Starts injecting faults as described by `options`, or stops if omitted.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if a probability is outside 0.0 to 1.0 or the game
+    /// mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// game.set_chaos({ seed: 1n, delay_probability: 0.1, lock_contention_probability: 0.05 });
+    /// ```
+    #[wasm_bindgen]
+    pub fn set_chaos(&self, options: Option<ChaosOptions>) -> Result<(), PachisloError> {
+        let mut session = self.session()?;
+
+        let Some(options) = options else {
+            session.chaos = None;
+            session.engine.output_mut().set_event_delay(None);
+            return Ok(());
+        };
+
+        let mut chaos = Chaos::new(options).map_err(|message| PachisloError::InvalidArguments {
+            message: message.to_string(),
+        })?;
+        let delay = EventDelay::new(&options, chaos.rng.get_mut().split());
+
+        session.chaos = Some(chaos);
+        session.engine.output_mut().set_event_delay(Some(delay));
+
+        Ok(())
+    }
+}
+
+/// Decides which calls fail.
+pub(crate) struct Chaos {
+    options: ChaosOptions,
+    rng: RefCell<GameRng>,
+}
+
+impl Chaos {
+    fn new(options: ChaosOptions) -> Result<Self, &'static str> {
+        let probabilities = [
+            options.delay_probability,
+            options.serialization_failure_probability,
+            options.lock_contention_probability,
+        ];
+        if !probabilities.iter().all(|p| (0.0..=1.0).contains(p)) {
+            return Err("chaos probabilities must be between 0.0 and 1.0");
+        }
+
+        let rng = options
+            .seed
+            .map_or_else(GameRng::default, GameRng::from_seed);
+
+        Ok(Chaos {
+            options,
+            rng: RefCell::new(rng),
+        })
+    }
+
+    /// Fails with the fault if the dice say so.
+    fn inject(&self, fault: Fault) -> Result<(), PachisloError> {
+        let (probability, description) = match fault {
+            Fault::Serialization => (
+                self.options.serialization_failure_probability,
+                "the result could not be serialized",
+            ),
+            Fault::LockContention => (
+                self.options.lock_contention_probability,
+                "the game is locked by another call",
+            ),
+        };
+
+        if self.rng.borrow_mut().random_bool(probability) {
+            Err(PachisloError::InjectedFault { fault: description })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Injects `fault` if chaos mode is on and decides so.
+pub(crate) fn inject(chaos: &Option<Chaos>, fault: Fault) -> Result<(), PachisloError> {
+    chaos.as_ref().map_or(Ok(()), |chaos| chaos.inject(fault))
+}
+
+/// Holds back events of an event output to deliver them late.
+pub(crate) struct EventDelay<T> {
+    probability: f64,
+    max_events: usize,
+    rng: GameRng,
+    /// Held events and how many later events they still wait for
+    held: Vec<(usize, T)>,
+}
+
+impl<T> EventDelay<T> {
+    fn new(options: &ChaosOptions, rng: GameRng) -> Self {
+        EventDelay {
+            probability: options.delay_probability,
+            max_events: options.max_delay_events.max(1),
+            rng,
+            held: Vec::new(),
+        }
+    }
+
+    /// Takes an event and returns the events to deliver now: the event itself
+    /// unless it is held back, followed by held events that are due.
+    pub(crate) fn pass(&mut self, event: T) -> Vec<T> {
+        let mut deliveries = Vec::new();
+
+        for (remaining, _) in &mut self.held {
+            *remaining -= 1;
+        }
+
+        if self.rng.random_bool(self.probability) {
+            let wait = self.rng.random_range(1..=self.max_events);
+            self.held.push((wait, event));
+        } else {
+            deliveries.push(event);
+        }
+
+        let (due, held) = self
+            .held
+            .drain(..)
+            .partition::<Vec<_>, _>(|(remaining, _)| *remaining == 0);
+        self.held = held;
+        deliveries.extend(due.into_iter().map(|(_, event)| event));
+
+        deliveries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(delay_probability: f64) -> ChaosOptions {
+        ChaosOptions {
+            seed: Some(7),
+            delay_probability,
+            max_delay_events: 2,
+            ..ChaosOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_invalid_probabilities() {
+        assert!(Chaos::new(options(1.5)).is_err());
+        assert!(Chaos::new(options(f64::NAN)).is_err());
+    }
+
+    #[test]
+    fn test_faults_follow_probabilities() {
+        let never = Chaos::new(ChaosOptions::default()).unwrap();
+        assert!((0..100).all(|_| never.inject(Fault::LockContention).is_ok()));
+
+        let always = Chaos::new(ChaosOptions {
+            serialization_failure_probability: 1.0,
+            ..ChaosOptions::default()
+        })
+        .unwrap();
+        assert!(always.inject(Fault::Serialization).is_err());
+        assert!(always.inject(Fault::LockContention).is_ok());
+    }
+
+    #[test]
+    fn test_delayed_events_arrive_late() {
+        let mut delay = EventDelay::new(&options(1.0), GameRng::from_seed(1));
+        let mut delivered = Vec::new();
+        for event_type in ["a", "b", "c", "d", "e", "f"] {
+            delivered.extend(delay.pass(event_type));
+        }
+
+        // Every event is held back for at least one later event
+        assert!(!delivered.contains(&"f"));
+        assert!(delivered.len() >= 4);
+
+        let mut direct = EventDelay::new(&options(0.0), GameRng::from_seed(1));
+        assert_eq!(direct.pass("a"), ["a"]);
+    }
+}
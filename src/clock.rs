@@ -0,0 +1,57 @@
+//! # Clock
+//!
+//! Every timestamp a game takes — cooldowns, ghost timings, history entries
+//! and event retransmission — is read from a [`Clock`], so tests and replays
+//! can drive a game with a virtual clock and get deterministic timestamps.
+
+use std::rc::Rc;
+
+use js_sys::Function;
+use wasm_bindgen::JsValue;
+
+use crate::{PachisloError, callback_failed, environment};
+
+/// A source of timestamps in milliseconds.
+pub(crate) trait Clock {
+    fn now(&self) -> Result<f64, PachisloError>;
+}
+
+/// A clock shared between a game and its output.
+pub(crate) type SharedClock = Rc<dyn Clock>;
+
+/// The wall clock, `Date.now()`, degrading on hosts without it; see
+/// [`environment::now`].
+pub(crate) struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Result<f64, PachisloError> {
+        Ok(environment::now())
+    }
+}
+
+/// A clock provided by JavaScript as a function returning milliseconds.
+pub(crate) struct JsClock(Function);
+
+impl Clock for JsClock {
+    /// # Errors
+    ///
+    /// Returns a `CallbackFailed` error if the function throws or does not
+    /// return a number.
+    fn now(&self) -> Result<f64, PachisloError> {
+        self.0
+            .call0(&JsValue::NULL)
+            .map_err(callback_failed)?
+            .as_f64()
+            .ok_or_else(|| PachisloError::CallbackFailed {
+                message: "the clock function must return a number".to_string(),
+            })
+    }
+}
+
+/// Returns the clock for an optional JavaScript clock function.
+pub(crate) fn from_function(now: Option<Function>) -> SharedClock {
+    match now {
+        Some(now) => Rc::new(JsClock(now)),
+        None => Rc::new(SystemClock),
+    }
+}
@@ -0,0 +1,288 @@
+//! # Replay Comparison
+//!
+//! Aggregates recorded sessions into per-machine figures that can be compared
+//! side by side, e.g. on an analysis page listing every machine of a hall.
+//! The sessions are the command histories recorded with
+//! `WasmGame::set_history_enabled`; everything is derived from their
+//! transitions, so no configuration or seed is needed.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{alias::GameState, history::HistoryEntry};
+
+/// A recorded session and the machine it was played on.
+///
+/// # Fields
+///
+/// * `machine` - Name of the machine; sessions with the same name are
+///   aggregated together
+/// * `history` - The session's history, as returned by `WasmGame::get_history`
+#[derive(Clone, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ReplaySession {
+    pub machine: String,
+    pub history: Vec<HistoryEntry>,
+}
+
+/// Observed figures of one machine over all of its sessions.
+///
+/// Spins are counted from `"CauseLottery"` entries, or from `"ConsumeHold"`
+/// entries in sessions that used the hold queue. A rush that is still
+/// running at the end of a session counts with the depth it reached.
+///
+/// # Fields
+///
+/// * `machine` - Name of the machine
+/// * `sessions` - Sessions played on the machine
+/// * `spins` - Lotteries drawn
+/// * `balls_launched` - Balls launched from the regular balls
+/// * `balls_paid_out` - Balls paid out by lotteries
+/// * `observed_rtp` - `balls_paid_out / balls_launched`, 0 without launches
+/// * `rush_entries` - Rushes entered
+/// * `mean_hit_interval` - Mean normal-mode spins per rush entry, counting
+///   jitan spins as normal ones; `None` without rush entries
+/// * `longest_hit_interval` - Most normal-mode spins without entering a rush
+/// * `chain_distribution` - Rushes by the depth they reached: index 0 counts
+///   rushes that ended at `n = 1`, index 1 those that ended at `n = 2`, ...
+/// * `mean_chain_length` - Mean depth reached per rush; `None` without rushes
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct MachineComparison {
+    pub machine: String,
+    pub sessions: usize,
+    pub spins: usize,
+    pub balls_launched: usize,
+    pub balls_paid_out: usize,
+    pub observed_rtp: f64,
+    pub rush_entries: usize,
+    pub mean_hit_interval: Option<f64>,
+    pub longest_hit_interval: usize,
+    pub chain_distribution: Vec<usize>,
+    pub mean_chain_length: Option<f64>,
+}
+
+/// Result of [`Analysis::from_replays`].
+///
+/// # Fields
+///
+/// * `machines` - One entry per machine, sorted by name
+#[derive(Clone, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ComparisonReport {
+    pub machines: Vec<MachineComparison>,
+}
+
+/// Analyses of recorded play.
+#[wasm_bindgen]
+pub struct Analysis;
+
+#[wasm_bindgen]
+impl Analysis {
+    /// Aggregates recorded sessions into comparable per-machine figures.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const report = Analysis.from_replays([
+    ///     { machine: "Sea Story", history: gameA.get_history() },
+    ///     { machine: "Sea Story", history: gameB.get_history() },
+    ///     { machine: "Evangelion", history: gameC.get_history() },
+    /// ]);
+    /// report.machines.forEach((m) => table.addRow(m.machine, m.observed_rtp));
+    /// ```
+    #[wasm_bindgen]
+    pub fn from_replays(replays: Vec<ReplaySession>) -> ComparisonReport {
+        let mut tallies = BTreeMap::<String, Tally>::new();
+        for replay in &replays {
+            tallies
+                .entry(replay.machine.clone())
+                .or_default()
+                .add_session(&replay.history);
+        }
+
+        ComparisonReport {
+            machines: tallies
+                .into_iter()
+                .map(|(machine, tally)| tally.finish(machine))
+                .collect(),
+        }
+    }
+}
+
+/// Running totals of one machine.
+#[derive(Debug, Default)]
+struct Tally {
+    sessions: usize,
+    spins: usize,
+    normal_spins: usize,
+    balls_launched: usize,
+    balls_paid_out: usize,
+    longest_hit_interval: usize,
+    chains: Vec<usize>,
+}
+
+impl Tally {
+    fn add_session(&mut self, history: &[HistoryEntry]) {
+        self.sessions += 1;
+
+        let spin_command = if history.iter().any(|entry| entry.command == "ConsumeHold") {
+            "ConsumeHold"
+        } else {
+            "CauseLottery"
+        };
+        let mut interval = 0;
+        let mut chain = None;
+
+        for entry in history {
+            let Some(before) = entry.transition.before else {
+                continue;
+            };
+            let after = entry.transition.after;
+
+            if entry.command == spin_command {
+                self.spins += 1;
+                if let GameState::Normal { .. } | GameState::Jitan { .. } = before {
+                    self.normal_spins += 1;
+                    interval += 1;
+                }
+            }
+
+            match entry.command.as_str() {
+                "LaunchBall" if balls(after) < balls(before) => self.balls_launched += 1,
+                "StartGame" | "LaunchBall" => {}
+                _ => self.balls_paid_out += balls(after).saturating_sub(balls(before)),
+            }
+
+            match (before, after) {
+                (GameState::Rush { .. }, GameState::Rush { n, .. }) => {
+                    chain = chain.max(Some(n));
+                }
+                (_, GameState::Rush { n, .. }) => {
+                    self.longest_hit_interval = self.longest_hit_interval.max(interval);
+                    interval = 0;
+                    chain = Some(n);
+                }
+                (GameState::Rush { .. }, _) => self.chains.extend(chain.take()),
+                _ => {}
+            }
+        }
+
+        self.chains.extend(chain);
+        // Spins after the last rush still bound the longest interval
+        self.longest_hit_interval = self.longest_hit_interval.max(interval);
+    }
+
+    fn finish(self, machine: String) -> MachineComparison {
+        let mean = |total: usize, count: usize| (count > 0).then(|| total as f64 / count as f64);
+
+        let mut chain_distribution = vec![0; self.chains.iter().copied().max().unwrap_or(0)];
+        for &chain in &self.chains {
+            if chain > 0 {
+                chain_distribution[chain - 1] += 1;
+            }
+        }
+
+        MachineComparison {
+            machine,
+            sessions: self.sessions,
+            spins: self.spins,
+            balls_launched: self.balls_launched,
+            balls_paid_out: self.balls_paid_out,
+            observed_rtp: mean(self.balls_paid_out, self.balls_launched).unwrap_or(0.0),
+            rush_entries: self.chains.len(),
+            mean_hit_interval: mean(self.normal_spins, self.chains.len()),
+            longest_hit_interval: self.longest_hit_interval,
+            mean_chain_length: mean(self.chains.iter().sum(), self.chains.len()),
+            chain_distribution,
+        }
+    }
+}
+
+/// Balls held in a state, 0 before the game starts.
+fn balls(state: GameState) -> usize {
+    match state {
+        GameState::Uninitialized => 0,
+        GameState::Normal { balls }
+        | GameState::Rush { balls, .. }
+        | GameState::Jitan { balls, .. } => balls,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alias::Transition;
+
+    fn entry(command: &str, before: GameState, after: GameState) -> HistoryEntry {
+        HistoryEntry {
+            time: 0.0,
+            command: command.to_string(),
+            transition: Transition {
+                before: Some(before),
+                after,
+            },
+            spin: None,
+        }
+    }
+
+    fn rush(balls: usize, rush_balls: usize, n: usize) -> GameState {
+        GameState::Rush {
+            balls,
+            rush_balls,
+            n,
+        }
+    }
+
+    #[test]
+    fn test_from_replays() {
+        let normal = |balls| GameState::Normal { balls };
+        let session = vec![
+            entry("StartGame", GameState::Uninitialized, normal(10)),
+            entry("LaunchBall", normal(10), normal(9)),
+            entry("CauseLottery", normal(9), normal(9)),
+            entry("LaunchBall", normal(9), normal(8)),
+            entry("CauseLottery", normal(8), rush(23, 3, 1)),
+            entry("LaunchBall", rush(23, 3, 1), rush(23, 2, 1)),
+            entry("CauseLottery", rush(23, 2, 1), rush(38, 5, 2)),
+            entry("CauseLottery", rush(38, 5, 2), rush(53, 5, 2)),
+            entry("LaunchBall", rush(53, 1, 2), normal(53)),
+            entry("CauseLottery", normal(53), normal(53)),
+        ];
+
+        let report = Analysis::from_replays(vec![
+            ReplaySession {
+                machine: "b".to_string(),
+                history: session.clone(),
+            },
+            ReplaySession {
+                machine: "a".to_string(),
+                history: session[..5].to_vec(),
+            },
+            ReplaySession {
+                machine: "b".to_string(),
+                history: session,
+            },
+        ]);
+
+        let [a, b] = report.machines.as_slice() else {
+            panic!("expected two machines");
+        };
+        assert_eq!(a.machine, "a");
+        assert_eq!(a.chain_distribution, [1]);
+
+        assert_eq!(b.sessions, 2);
+        assert_eq!(b.spins, 10);
+        assert_eq!(b.balls_launched, 4);
+        assert_eq!(b.balls_paid_out, 90);
+        assert_eq!(b.observed_rtp, 22.5);
+        assert_eq!(b.rush_entries, 2);
+        assert_eq!(b.mean_hit_interval, Some(3.0));
+        assert_eq!(b.longest_hit_interval, 2);
+        assert_eq!(b.chain_distribution, [0, 2]);
+        assert_eq!(b.mean_chain_length, Some(2.0));
+    }
+}
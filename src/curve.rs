@@ -0,0 +1,195 @@
+//! # Rush Continuation Curves
+//!
+//! Built-in shapes for the rush continuation multiplier. A curve is evaluated
+//! natively, so rush continuation lotteries never call back into JavaScript
+//! and configurations using curves also work in headless simulations.
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+/// The rush continuation multiplier as a function of the consecutive rush count `n`.
+///
+/// Pass it to `Probability.with_curve` instead of a `rush_continue_fn`.
+///
+/// # Variants
+///
+/// * `Constant { value }` - `value` for every `n`
+/// * `LinearDecay { start, step, floor }` - `start - step * (n - 1)`, but at least `floor`
+/// * `Steps { rates }` - `rates[n - 1]`; the last rate applies to every later
+///   `n`, and an empty list means a constant 1.0
+/// * `Exponential { start, ratio, floor }` - `start * ratio ** (n - 1)`, but at least `floor`
+///
+/// Every parameter is between 0.0 and 1.0, except `step`, which is at least
+/// 0.0; `Config.validate` rejects other curves.
+///
+/// # Example
+///
+/// ```javascript
+/// const curve = { type: "LinearDecay", start: 1.0, step: 0.1, floor: 0.3 };
+/// ```
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(tag = "type")]
+pub enum ContinuationCurve {
+    Constant { value: f64 },
+    LinearDecay { start: f64, step: f64, floor: f64 },
+    Steps { rates: Vec<f64> },
+    Exponential { start: f64, ratio: f64, floor: f64 },
+}
+
+impl ContinuationCurve {
+    /// The multiplier for the `n`-th consecutive rush.
+    pub(crate) fn multiplier(&self, n: usize) -> f64 {
+        // `n` starts at 1; treat 0 like the first rush
+        let steps = n.saturating_sub(1);
+
+        match self {
+            ContinuationCurve::Constant { value } => *value,
+            ContinuationCurve::LinearDecay { start, step, floor } => {
+                (start - step * steps as f64).max(*floor)
+            }
+            ContinuationCurve::Steps { rates } => step_rate(rates, n),
+            ContinuationCurve::Exponential {
+                start,
+                ratio,
+                floor,
+            } => (start * ratio.powi(steps.try_into().unwrap_or(i32::MAX))).max(*floor),
+        }
+    }
+
+    /// Describes every parameter that could make the multiplier leave 0.0 to
+    /// 1.0 at some `n`.
+    ///
+    /// Checking the parameters covers every `n`, unlike evaluating the curve
+    /// at a few of them.
+    pub(crate) fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut check = |name: String, value: f64| {
+            if !(0.0..=1.0).contains(&value) {
+                problems.push(format!(
+                    "probability.curve.{name} is {value}; it must be between 0.0 and 1.0"
+                ));
+            }
+        };
+
+        match self {
+            ContinuationCurve::Constant { value } => check("value".to_string(), *value),
+            ContinuationCurve::LinearDecay { start, step, floor } => {
+                check("start".to_string(), *start);
+                check("floor".to_string(), *floor);
+                if !(*step >= 0.0 && step.is_finite()) {
+                    problems.push(format!(
+                        "probability.curve.step is {step}; it must be at least 0.0"
+                    ));
+                }
+            }
+            ContinuationCurve::Steps { rates } => {
+                for (i, rate) in rates.iter().enumerate() {
+                    check(format!("rates[{i}]"), *rate);
+                }
+            }
+            ContinuationCurve::Exponential {
+                start,
+                ratio,
+                floor,
+            } => {
+                check("start".to_string(), *start);
+                check("ratio".to_string(), *ratio);
+                check("floor".to_string(), *floor);
+            }
+        }
+
+        problems
+    }
+}
+
+/// `rates[n - 1]`, repeating the last rate; 1.0 without rates.
+pub(crate) fn step_rate(rates: &[f64], n: usize) -> f64 {
+    let index = n.saturating_sub(1).min(rates.len().saturating_sub(1));
+
+    rates.get(index).copied().unwrap_or(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_curves() {
+        let constant = ContinuationCurve::Constant { value: 0.7 };
+        assert_eq!(constant.multiplier(1), 0.7);
+        assert_eq!(constant.multiplier(100), 0.7);
+
+        let linear = ContinuationCurve::LinearDecay {
+            start: 1.0,
+            step: 0.25,
+            floor: 0.4,
+        };
+        assert_eq!(linear.multiplier(1), 1.0);
+        assert_eq!(linear.multiplier(2), 0.75);
+        assert_eq!(linear.multiplier(4), 0.4);
+
+        let exponential = ContinuationCurve::Exponential {
+            start: 1.0,
+            ratio: 0.5,
+            floor: 0.2,
+        };
+        assert_eq!(exponential.multiplier(1), 1.0);
+        assert_eq!(exponential.multiplier(3), 0.25);
+        assert_eq!(exponential.multiplier(4), 0.2);
+        assert_eq!(exponential.multiplier(usize::MAX), 0.2);
+    }
+
+    #[test]
+    fn test_steps() {
+        let steps = ContinuationCurve::Steps {
+            rates: vec![1.0, 0.8, 0.5],
+        };
+        assert_eq!(steps.multiplier(1), 1.0);
+        assert_eq!(steps.multiplier(2), 0.8);
+        assert_eq!(steps.multiplier(10), 0.5);
+
+        assert_eq!(step_rate(&[], 3), 1.0);
+    }
+
+    #[test]
+    fn test_problems() {
+        let below_zero = ContinuationCurve::LinearDecay {
+            start: 1.0,
+            step: 0.02,
+            floor: -1.0,
+        };
+        assert!(below_zero.multiplier(60) < 0.0);
+        assert_eq!(
+            below_zero.problems(),
+            ["probability.curve.floor is -1; it must be between 0.0 and 1.0"]
+        );
+
+        let late_rate = ContinuationCurve::Steps {
+            rates: [vec![0.9; 11], vec![1.5]].concat(),
+        };
+        assert_eq!(late_rate.problems().len(), 1);
+        assert!(late_rate.problems()[0].contains("rates[11] is 1.5"));
+
+        let negative_ratio = ContinuationCurve::Exponential {
+            start: 1.0,
+            ratio: -0.5,
+            floor: 0.0,
+        };
+        assert_eq!(negative_ratio.problems().len(), 1);
+
+        let growing = ContinuationCurve::LinearDecay {
+            start: 0.5,
+            step: -0.1,
+            floor: 0.0,
+        };
+        assert!(growing.problems()[0].contains("step is -0.1"));
+
+        let valid = ContinuationCurve::Exponential {
+            start: 1.0,
+            ratio: 0.9,
+            floor: 0.3,
+        };
+        assert!(valid.problems().is_empty());
+    }
+}
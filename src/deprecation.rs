@@ -0,0 +1,136 @@
+//! # Deprecation Notices
+//!
+//! Old API forms keep working while newer ones replace them, but each use is
+//! reported once as a structured warning with a migration hint. Warnings go to
+//! the handler installed with [`set_warning_handler`], or to `console.warn`
+//! when none is installed and the host has a console.
+
+use std::{cell::RefCell, collections::HashSet};
+
+use js_sys::Function;
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::{JsValue, prelude::wasm_bindgen};
+
+use crate::environment;
+
+/// A deprecated API form that was used.
+///
+/// # Fields
+///
+/// * `api` - The deprecated form
+/// * `replacement` - The API to use instead
+/// * `hint` - How to migrate
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct DeprecationWarning {
+    pub api: String,
+    pub replacement: String,
+    pub hint: String,
+}
+
+/// The positional-callback `JsOutput` constructor.
+pub(crate) const JS_OUTPUT_CONSTRUCTOR: Deprecation = Deprecation {
+    api: "new JsOutput(context, default, finish_game, lottery_normal, lottery_rush, lottery_rush_continue)",
+    replacement: "JsOutput.from_events",
+    hint: "wrap a single (eventType, payload) callback in a JsEventOutput; event types are named after the old callbacks, and \"transition\" replaces \"default\"",
+};
+
+/// A deprecated API form and its migration path.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Deprecation {
+    api: &'static str,
+    replacement: &'static str,
+    hint: &'static str,
+}
+
+impl Deprecation {
+    fn warning(&self) -> DeprecationWarning {
+        DeprecationWarning {
+            api: self.api.to_string(),
+            replacement: self.replacement.to_string(),
+            hint: self.hint.to_string(),
+        }
+    }
+}
+
+/// Installs the handler receiving every [`DeprecationWarning`].
+///
+/// Each deprecated form is reported once per module instance.
+///
+/// # Arguments
+///
+/// * `handler` - Called with a `DeprecationWarning`, or `undefined` to go back
+///   to `console.warn`
+///
+/// # Example
+///
+/// ```javascript
+/// set_warning_handler((warning) => telemetry.track("deprecated", warning));
+/// ```
+#[wasm_bindgen]
+pub fn set_warning_handler(handler: Option<Function>) {
+    CHANNEL.with(|channel| channel.borrow_mut().handler = handler);
+}
+
+thread_local! {
+    static CHANNEL: RefCell<Channel> = RefCell::new(Channel::default());
+}
+
+/// The warning handler and the deprecations reported so far.
+#[derive(Default)]
+struct Channel {
+    handler: Option<Function>,
+    reported: HashSet<&'static str>,
+}
+
+impl Channel {
+    /// Marks a deprecation as reported; returns the handler to report it to,
+    /// or `None` if it was reported before.
+    fn report(&mut self, deprecation: Deprecation) -> Option<Option<Function>> {
+        self.reported
+            .insert(deprecation.api)
+            .then(|| self.handler.clone())
+    }
+}
+
+/// Reports that a deprecated form was used, unless it was reported before.
+pub(crate) fn warn(deprecation: Deprecation) {
+    let handler = CHANNEL.with(|channel| channel.borrow_mut().report(deprecation));
+
+    // Called outside the borrow, so the handler may use any API itself
+    match handler {
+        None => {}
+        Some(Some(handler)) => {
+            let warning = serde_wasm_bindgen::to_value(&deprecation.warning()).unwrap();
+            // A throwing handler must not break the deprecated call itself
+            let _ = handler.call1(&JsValue::NULL, &warning);
+        }
+        Some(None) => environment::console_warn(&format!(
+            "pachislo-wasm: {} is deprecated; use {} instead: {}",
+            deprecation.api, deprecation.replacement, deprecation.hint
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warning_contents() {
+        let warning = JS_OUTPUT_CONSTRUCTOR.warning();
+
+        assert!(warning.api.starts_with("new JsOutput("));
+        assert_eq!(warning.replacement, "JsOutput.from_events");
+        assert!(warning.hint.contains("JsEventOutput"));
+    }
+
+    #[test]
+    fn test_reported_once() {
+        let mut channel = Channel::default();
+
+        assert_eq!(channel.report(JS_OUTPUT_CONSTRUCTOR), Some(None));
+        assert_eq!(channel.report(JS_OUTPUT_CONSTRUCTOR), None);
+    }
+}
@@ -0,0 +1,180 @@
+//! # State Diff Events
+//!
+//! An alternative payload for transition events: instead of both full states,
+//! only what changed, e.g. `{ balls: 15 }` after a win or
+//! `{ mode: "Rush", rush_balls: 3, n: 1 }` when a rush starts. UIs keep their
+//! own copy of the state and apply each diff to it.
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use crate::alias::{GameState, Transition};
+
+/// What changed in a transition. Fields that did not change are omitted.
+///
+/// Apply a diff by adding `balls`, `rush_balls` and `spins` to the current
+/// counts and replacing `mode` and `n`. Counts of a mode without them, like the rush
+/// balls in normal mode, are 0.
+///
+/// # Fields
+///
+/// * `mode` - The new mode, if the mode changed
+/// * `balls` - Change of the ball count, e.g. `15` or `-1`
+/// * `rush_balls` - Change of the rush ball count
+/// * `spins` - Change of the jitan spins left
+/// * `n` - The new consecutive rush count, if it changed while in rush mode
+///
+/// With the `camel-case` feature, `rush_balls` is serialized as `rushBalls`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct StateDiff {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<StateMode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub balls: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(
+        feature = "camel-case",
+        serde(rename = "rushBalls", alias = "rush_balls")
+    )]
+    pub rush_balls: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spins: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub n: Option<usize>,
+}
+
+/// The mode of a [`GameState`], without its counts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum StateMode {
+    Uninitialized,
+    Normal,
+    Rush,
+    Jitan,
+}
+
+impl StateDiff {
+    /// The diff from `before` to `after`; a missing `before` counts as
+    /// `Uninitialized`.
+    pub(crate) fn between(before: Option<GameState>, after: GameState) -> Self {
+        let (before_mode, before_balls, before_rush_balls, before_spins, before_n) =
+            parts(before.unwrap_or(GameState::Uninitialized));
+        let (mode, balls, rush_balls, spins, n) = parts(after);
+
+        let delta =
+            |before: usize, after: usize| (before != after).then(|| after as i64 - before as i64);
+
+        StateDiff {
+            mode: (before_mode != mode).then_some(mode),
+            balls: delta(before_balls, balls),
+            rush_balls: delta(before_rush_balls, rush_balls),
+            spins: delta(before_spins, spins),
+            n: n.filter(|_| n != before_n),
+        }
+    }
+
+    /// Whether nothing changed.
+    pub(crate) fn is_empty(&self) -> bool {
+        *self == StateDiff::default()
+    }
+}
+
+impl From<Transition> for StateDiff {
+    fn from(transition: Transition) -> Self {
+        StateDiff::between(transition.before, transition.after)
+    }
+}
+
+/// Splits a state into its mode, balls, rush balls, jitan spins and rush count.
+pub(crate) fn parts(state: GameState) -> (StateMode, usize, usize, usize, Option<usize>) {
+    match state {
+        GameState::Uninitialized => (StateMode::Uninitialized, 0, 0, 0, None),
+        GameState::Normal { balls } => (StateMode::Normal, balls, 0, 0, None),
+        GameState::Rush {
+            balls,
+            rush_balls,
+            n,
+        } => (StateMode::Rush, balls, rush_balls, 0, Some(n)),
+        GameState::Jitan { balls, spins } => (StateMode::Jitan, balls, 0, spins, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diffs() {
+        let start = StateDiff::between(None, GameState::Normal { balls: 100 });
+        assert_eq!(start.mode, Some(StateMode::Normal));
+        assert_eq!(start.balls, Some(100));
+        assert_eq!(start.rush_balls, None);
+
+        let launch = StateDiff::between(
+            Some(GameState::Normal { balls: 100 }),
+            GameState::Normal { balls: 99 },
+        );
+        assert_eq!(
+            launch,
+            StateDiff {
+                balls: Some(-1),
+                ..StateDiff::default()
+            }
+        );
+
+        let rush = StateDiff::between(
+            Some(GameState::Normal { balls: 99 }),
+            GameState::Rush {
+                balls: 114,
+                rush_balls: 3,
+                n: 1,
+            },
+        );
+        assert_eq!(
+            rush,
+            StateDiff {
+                mode: Some(StateMode::Rush),
+                balls: Some(15),
+                rush_balls: Some(3),
+                n: Some(1),
+                ..StateDiff::default()
+            }
+        );
+
+        let end = StateDiff::between(
+            Some(GameState::Rush {
+                balls: 114,
+                rush_balls: 3,
+                n: 1,
+            }),
+            GameState::Normal { balls: 114 },
+        );
+        assert_eq!(end.mode, Some(StateMode::Normal));
+        assert_eq!(end.rush_balls, Some(-3));
+        assert_eq!(end.n, None);
+
+        let jitan = StateDiff::between(
+            Some(GameState::Rush {
+                balls: 114,
+                rush_balls: 1,
+                n: 1,
+            }),
+            GameState::Jitan {
+                balls: 114,
+                spins: 50,
+            },
+        );
+        assert_eq!(jitan.mode, Some(StateMode::Jitan));
+        assert_eq!(jitan.rush_balls, Some(-1));
+        assert_eq!(jitan.spins, Some(50));
+    }
+
+    #[test]
+    fn test_unchanged_state_is_empty() {
+        let state = GameState::Normal { balls: 5 };
+
+        assert!(StateDiff::between(Some(state), state).is_empty());
+        assert!(!StateDiff::between(None, state).is_empty());
+    }
+}
@@ -0,0 +1,2611 @@
+//! # Game Engine
+//!
+//! An in-crate port of `pachislo::Game`. The upstream game always creates its
+//! random number generator through `Default` and keeps its state private, so
+//! this engine mirrors its rules while owning the state and the RNG. It reuses
+//! the upstream state, transition and lottery types, so outputs written for
+//! `pachislo` keep working unchanged.
+
+use std::{collections::VecDeque, error::Error, fmt::Display, ops::ControlFlow};
+
+use pachislo::{
+    config::{Probability, SlotProbability},
+    game::{GameState, Transition},
+    interface::UserOutput,
+    lottery::{Lose, LotteryResult, Win},
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use crate::{
+    alias::{self, BallsConfig},
+    explain::{ContinuationExplanation, LotteryExplanation, Roll, SpinExplanation, SpinMode},
+    hold::{HOLD_CAPACITY, HoldQueue},
+    reveal,
+    rng::GameRng,
+    stats::{DEFAULT_TICKER_SMOOTHING, GameStats, ModeCounters, ReturnTicker},
+    stop::{StopConditions, StopReason},
+    streak::Streaks,
+    summary::SessionSummary,
+};
+
+/// The scale `rand`'s Bernoulli distribution maps probabilities to `u64` with.
+const ROLL_SCALE: f64 = 2.0 * (1u64 << 63) as f64;
+
+/// A command understood by the engine.
+///
+/// Exported to TypeScript as a string-literal union typing every command
+/// string parameter, so misspelled commands fail to compile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, Tsify)]
+pub(crate) enum Command {
+    /// Launch a ball into the machine
+    LaunchBall,
+    /// Trigger the lottery mechanism, or hold it while the hold queue is enabled
+    CauseLottery,
+    /// Draw the oldest held lottery
+    ConsumeHold,
+    /// Start a new game session
+    StartGame,
+    /// End the current game session and notify the output
+    FinishGame,
+    /// Stop the game loop without touching the state
+    Finish,
+}
+
+impl Command {
+    /// Every command, in the order they are documented.
+    pub(crate) const ALL: [Command; 6] = [
+        Command::LaunchBall,
+        Command::CauseLottery,
+        Command::ConsumeHold,
+        Command::StartGame,
+        Command::FinishGame,
+        Command::Finish,
+    ];
+
+    /// The command string that selects this command.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Command::LaunchBall => "LaunchBall",
+            Command::CauseLottery => "CauseLottery",
+            Command::ConsumeHold => "ConsumeHold",
+            Command::StartGame => "StartGame",
+            Command::FinishGame => "FinishGame",
+            Command::Finish => "Finish",
+        }
+    }
+}
+
+/// How a rush continues once entered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum RushModel {
+    /// Every rush win draws a continuation lottery; the rush ends when its
+    /// rush balls run out
+    #[default]
+    Continuation,
+    /// ST (spin time) rush: the rush lasts this many spins, counted down in
+    /// `rush_balls`, and every rush win pays out and restarts the count.
+    /// Balls are launched from the regular balls
+    SpinBudget(usize),
+}
+
+/// Time-shortened (jitan) mode entered when a rush ends.
+///
+/// The upstream state has no variant for it, so it is kept as
+/// `GameState::Rush` with `n` 0 and `rush_balls` counting the spins left;
+/// see [`is_jitan`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Jitan {
+    /// Spins the mode lasts
+    pub(crate) spins: usize,
+    /// Probabilities drawn instead of the normal ones
+    pub(crate) probability: SlotProbability,
+}
+
+/// A rush variant with its own probabilities and payouts.
+///
+/// With tiers configured, every rush entry draws one by weight, and the
+/// rush keeps that tier until it ends. Its settings replace the flat rush
+/// probabilities, `rush_continue` and ball increments; the continuation
+/// function still applies.
+#[derive(Debug, Clone)]
+pub(crate) struct RushTier {
+    /// Name shown to players, e.g. "Super Rush"
+    pub(crate) name: String,
+    /// Relative chance of entering this tier
+    pub(crate) weight: f64,
+    /// Probabilities during the rush
+    pub(crate) rush: SlotProbability,
+    /// Probabilities of the continuation lottery
+    pub(crate) rush_continue: SlotProbability,
+    /// Balls paid out per jackpot
+    pub(crate) incremental_balls: usize,
+    /// Rush balls paid out per jackpot
+    pub(crate) incremental_rush: usize,
+}
+
+/// Payout rounds of a jackpot.
+///
+/// With rounds configured, every jackpot draws a round count by weight and
+/// pays `balls_per_round` per round instead of a flat ball increment.
+#[derive(Debug, Clone)]
+pub(crate) struct Rounds {
+    /// Round counts a jackpot can have and their relative chances
+    pub(crate) choices: Vec<(usize, f64)>,
+    /// Balls paid out per round
+    pub(crate) balls_per_round: usize,
+}
+
+/// How launched balls are paid for and whether they start a lottery.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Launch {
+    /// Regular balls consumed per launch
+    pub(crate) cost: usize,
+    /// Chance of a launched ball entering the start pocket
+    pub(crate) start_rate: f64,
+}
+
+impl Default for Launch {
+    fn default() -> Self {
+        Launch {
+            cost: 1,
+            start_rate: 1.0,
+        }
+    }
+}
+
+/// Rules layered on top of the upstream game by a configuration.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Mechanics {
+    /// Ball cost and start pocket of a launch
+    pub(crate) launch: Launch,
+    /// How rush mode continues
+    pub(crate) rush_model: RushModel,
+    /// The mode entered after a rush, if any
+    pub(crate) jitan: Option<Jitan>,
+    /// Losing normal-mode spins in a row after which the next one wins, if any
+    pub(crate) ceiling: Option<usize>,
+    /// Most rush wins stored as stock, if rush wins are stocked
+    pub(crate) stock_cap: Option<usize>,
+    /// Rush variants drawn on rush entry; empty for a single flat rush
+    pub(crate) tiers: Vec<RushTier>,
+    /// Payout rounds of a jackpot, if jackpots pay out in rounds
+    pub(crate) rounds: Option<Rounds>,
+    /// Chance of a plain win being premium, if premium wins are drawn
+    pub(crate) premium: Option<f64>,
+    /// Battle presentation of continuation lotteries, if enabled
+    pub(crate) battles: Option<Battles>,
+    /// Conditions ending a run
+    pub(crate) stop: StopConditions,
+}
+
+/// Rush continuation lotteries presented as battles.
+#[derive(Debug, Clone)]
+pub(crate) struct Battles {
+    /// Enemy names, from the weakest to the strongest
+    pub(crate) enemies: Vec<String>,
+    /// Chance of a won battle being shown lost at first, replacing the
+    /// continuation `fake_win`
+    pub(crate) revival: f64,
+}
+
+/// Whether an upstream state encodes jitan mode.
+pub(crate) fn is_jitan(state: &GameState) -> bool {
+    matches!(state, GameState::Rush { n: 0, .. })
+}
+
+/// Error returned when a configuration is rejected by [`Engine::new`].
+///
+/// The checks and messages match the upstream `pachislo::config::ConfigError`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ConfigError(Vec<String>);
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ConfigError: {}", self.0.join("\n"))
+    }
+}
+
+impl Error for ConfigError {}
+
+/// Output of an engine: the upstream callbacks, plus notices the upstream
+/// results cannot express.
+pub(crate) trait EngineOutput: UserOutput {
+    /// Called right before the lottery callback of a premium win, which the
+    /// upstream result reports as `Win::Default`.
+    fn premium_win(&mut self) {}
+}
+
+/// Output that discards every event, for headless games.
+pub(crate) struct NullOutput;
+
+impl EngineOutput for NullOutput {}
+
+impl UserOutput for NullOutput {
+    fn default(&mut self, _: Transition) {}
+
+    fn finish_game(&mut self, _: &GameState) {}
+
+    fn lottery_normal(&mut self, _: LotteryResult) {}
+
+    fn lottery_rush(&mut self, _: LotteryResult) {}
+
+    fn lottery_rush_continue(&mut self, _: LotteryResult) {}
+}
+
+/// Everything a command can change in an engine, see [`Engine::checkpoint`].
+#[derive(Clone, Debug)]
+pub(crate) struct Checkpoint {
+    before_state: Option<GameState>,
+    state: GameState,
+    rng: GameRng,
+    stats: GameStats,
+    counters: ModeCounters,
+    last_spin: Option<SpinExplanation>,
+    holds: Option<HoldQueue>,
+    unrevealed: Option<GameState>,
+    ticker: ReturnTicker,
+    launched_at_spin: usize,
+    spins_since_win: usize,
+    stock: usize,
+    tier: usize,
+    jackpot_rounds: Option<(usize, usize)>,
+    premium: bool,
+    starts: usize,
+    summary: Option<SessionSummary>,
+    streaks: Streaks,
+    stop_reason: Option<StopReason>,
+    script: VecDeque<alias::LotteryResult>,
+}
+
+/// Runs a pachislo game with an explicitly provided random number generator.
+pub(crate) struct Engine<O, F = Box<dyn FnMut(usize) -> f64>>
+where
+    O: EngineOutput,
+    F: FnMut(usize) -> f64,
+{
+    before_state: Option<GameState>,
+    state: GameState,
+    balls: BallsConfig,
+    probability: Probability<F>,
+    rng: GameRng,
+    output: O,
+    stats: GameStats,
+    counters: ModeCounters,
+    last_spin: Option<SpinExplanation>,
+    holds: Option<HoldQueue>,
+    staged_reveal: bool,
+    /// The state a fake outcome moves to once revealed
+    unrevealed: Option<GameState>,
+    mechanics: Mechanics,
+    ticker: ReturnTicker,
+    ticker_smoothing: f64,
+    /// `stats.balls_launched` at the last spin
+    launched_at_spin: usize,
+    /// Losing normal-mode spins since the last win, for the ceiling
+    spins_since_win: usize,
+    /// Rush wins stored to be paid out once the current rush ends
+    stock: usize,
+    /// Index of the tier of the current rush in `mechanics.tiers`
+    tier: usize,
+    /// Rounds of the jackpot paid out by the last command and balls per
+    /// round, until taken
+    jackpot_rounds: Option<(usize, usize)>,
+    /// Whether the spin being resolved is a premium win
+    premium: bool,
+    /// Launches that entered the start pocket and await their lottery
+    starts: usize,
+    /// Figures of the current or last game
+    summary: Option<SessionSummary>,
+    /// Runs of won and lost spins
+    streaks: Streaks,
+    /// The stop condition met by the last command
+    stop_reason: Option<StopReason>,
+    /// Results of the next lotteries, drawn before random ones
+    script: VecDeque<alias::LotteryResult>,
+    /// Tilt of rush wins for importance sampling, see [`Engine::set_tilt`]
+    tilt: Option<Tilt>,
+    /// Whether spins skip what only describes them, see
+    /// [`Engine::set_outcomes_only`]
+    outcomes_only: bool,
+}
+
+/// Rush and continuation wins drawn more often than configured, and the
+/// likelihood ratio of the draws made so, for importance sampling.
+#[derive(Clone, Copy, Debug)]
+struct Tilt {
+    /// Factor the odds `p / (1 - p)` of a win are multiplied by
+    odds: f64,
+    likelihood_ratio: f64,
+}
+
+impl<O, F> Engine<O, F>
+where
+    O: EngineOutput,
+    F: FnMut(usize) -> f64,
+{
+    /// Creates an engine, validating the configuration like `pachislo::Game::new`.
+    pub(crate) fn new(
+        config: pachislo::config::Config<F>,
+        output: O,
+        rng: GameRng,
+    ) -> Result<Self, ConfigError> {
+        validate(&config)?;
+
+        let balls = config.balls;
+
+        Ok(Engine {
+            before_state: None,
+            state: GameState::Uninitialized,
+            balls: BallsConfig::new(
+                balls.init_balls,
+                balls.incremental_balls,
+                balls.incremental_rush,
+            ),
+            probability: config.probability,
+            rng,
+            output,
+            stats: GameStats::default(),
+            counters: ModeCounters::default(),
+            last_spin: None,
+            holds: None,
+            staged_reveal: false,
+            unrevealed: None,
+            mechanics: Mechanics::default(),
+            ticker: ReturnTicker::default(),
+            ticker_smoothing: DEFAULT_TICKER_SMOOTHING,
+            launched_at_spin: 0,
+            spins_since_win: 0,
+            stock: 0,
+            tier: 0,
+            jackpot_rounds: None,
+            premium: false,
+            starts: 0,
+            summary: None,
+            streaks: Streaks::default(),
+            script: VecDeque::new(),
+            stop_reason: None,
+            tilt: None,
+            outcomes_only: false,
+        })
+    }
+
+    /// Replaces the ball and probability settings, keeping the state.
+    ///
+    /// New payouts and probabilities apply from the next lottery on, the new
+    /// initial balls from the next start.
+    pub(crate) fn set_config(
+        &mut self,
+        config: pachislo::config::Config<F>,
+    ) -> Result<(), ConfigError> {
+        validate(&config)?;
+
+        let balls = config.balls;
+        self.balls = BallsConfig::new(
+            balls.init_balls,
+            balls.incremental_balls,
+            balls.incremental_rush,
+        );
+        self.probability = config.probability;
+
+        Ok(())
+    }
+
+    /// Copies the engine for a game that continues independently from here.
+    ///
+    /// The probability holds the continuation function and cannot be copied,
+    /// so it is built from `config`, which must be the configuration the
+    /// engine currently runs with.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configuration is invalid.
+    pub(crate) fn fork(&self, config: pachislo::config::Config<F>, output: O) -> Self {
+        let mut engine = Engine::new(config, output, self.rng.clone()).unwrap();
+        engine.rewind(self.checkpoint());
+        engine.staged_reveal = self.staged_reveal;
+        engine.mechanics = self.mechanics.clone();
+        engine.ticker_smoothing = self.ticker_smoothing;
+
+        engine
+    }
+
+    /// Captures everything a command can change, including the generator,
+    /// so [`Engine::rewind`] can take the engine back to this point.
+    pub(crate) fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            before_state: self.before_state,
+            state: self.state,
+            rng: self.rng.clone(),
+            stats: self.stats,
+            counters: self.counters,
+            last_spin: self.last_spin,
+            holds: self.holds,
+            unrevealed: self.unrevealed,
+            ticker: self.ticker,
+            launched_at_spin: self.launched_at_spin,
+            spins_since_win: self.spins_since_win,
+            stock: self.stock,
+            tier: self.tier,
+            jackpot_rounds: self.jackpot_rounds,
+            premium: self.premium,
+            starts: self.starts,
+            summary: self.summary,
+            streaks: self.streaks,
+            stop_reason: self.stop_reason,
+            script: self.script.clone(),
+        }
+    }
+
+    /// Takes the engine back to a checkpoint without notifying the output.
+    pub(crate) fn rewind(&mut self, checkpoint: Checkpoint) {
+        let Checkpoint {
+            before_state,
+            state,
+            rng,
+            stats,
+            counters,
+            last_spin,
+            holds,
+            unrevealed,
+            ticker,
+            launched_at_spin,
+            spins_since_win,
+            stock,
+            tier,
+            jackpot_rounds,
+            premium,
+            starts,
+            summary,
+            streaks,
+            stop_reason,
+            script,
+        } = checkpoint;
+
+        self.before_state = before_state;
+        self.state = state;
+        self.rng = rng;
+        self.stats = stats;
+        self.counters = counters;
+        self.last_spin = last_spin;
+        self.holds = holds;
+        self.unrevealed = unrevealed;
+        self.ticker = ticker;
+        self.launched_at_spin = launched_at_spin;
+        self.spins_since_win = spins_since_win;
+        self.stock = stock;
+        self.tier = tier;
+        self.jackpot_rounds = jackpot_rounds;
+        self.premium = premium;
+        self.starts = starts;
+        self.summary = summary;
+        self.streaks = streaks;
+        self.stop_reason = stop_reason;
+        self.script = script;
+    }
+
+    /// The transition `command` would cause, drawing from `rng` instead of
+    /// the engine's generator. The engine and its output are left untouched;
+    /// `rush_continue_fn` may be called.
+    pub(crate) fn preview(&mut self, command: Command, rng: GameRng) -> Transition {
+        let mut preview = Engine {
+            before_state: self.before_state,
+            state: self.state,
+            balls: self.balls,
+            probability: Probability {
+                normal: self.probability.normal,
+                rush: self.probability.rush,
+                rush_continue: self.probability.rush_continue,
+                rush_continue_fn: &mut self.probability.rush_continue_fn,
+            },
+            rng,
+            output: NullOutput,
+            stats: self.stats,
+            counters: self.counters,
+            last_spin: self.last_spin,
+            holds: self.holds,
+            staged_reveal: self.staged_reveal,
+            unrevealed: self.unrevealed,
+            mechanics: self.mechanics.clone(),
+            ticker: self.ticker,
+            ticker_smoothing: self.ticker_smoothing,
+            launched_at_spin: self.launched_at_spin,
+            spins_since_win: self.spins_since_win,
+            stock: self.stock,
+            tier: self.tier,
+            jackpot_rounds: self.jackpot_rounds,
+            premium: self.premium,
+            starts: self.starts,
+            summary: self.summary,
+            streaks: self.streaks,
+            script: self.script.clone(),
+            stop_reason: self.stop_reason,
+            tilt: self.tilt,
+            outcomes_only: self.outcomes_only,
+        };
+
+        let before = preview.state;
+        let _ = preview.run_step_with_command(command);
+
+        Transition {
+            before: Some(before),
+            after: preview.state,
+        }
+    }
+
+    /// Executes a command and reports the resulting transition to the output.
+    ///
+    /// Breaks after `Command::Finish` or once a stop condition holds.
+    pub(crate) fn run_step_with_command(&mut self, command: Command) -> ControlFlow<()> {
+        self.jackpot_rounds = None;
+        self.stop_reason = None;
+
+        match command {
+            Command::LaunchBall => self.launch_ball(),
+            // Without a start, the lottery is not drawn
+            Command::CauseLottery if !self.take_start() => {}
+            Command::CauseLottery => match &mut self.holds {
+                Some(holds) if !matches!(self.state, GameState::Uninitialized) => {
+                    holds.add();
+                }
+                Some(_) => {}
+                None => self.cause_lottery(),
+            },
+            Command::ConsumeHold => {
+                if let Some(holds) = &mut self.holds
+                    && holds.take()
+                {
+                    self.cause_lottery();
+                }
+            }
+            Command::StartGame => self.start(),
+            Command::FinishGame => self.finish(),
+            Command::Finish => return ControlFlow::Break(()),
+        }
+
+        self.output.default(Transition {
+            before: self.before_state,
+            after: self.state,
+        });
+
+        self.before_state = Some(self.state);
+        self.observe_game();
+
+        // Held lotteries, starts and stock end with their game
+        if let GameState::Uninitialized = self.state {
+            if let Some(holds) = &mut self.holds {
+                holds.clear();
+            }
+            self.starts = 0;
+            self.stock = 0;
+        }
+
+        self.stop_reason = self.check_stop();
+        if self.stop_reason.is_some() {
+            return ControlFlow::Break(());
+        }
+
+        ControlFlow::Continue(())
+    }
+
+    /// The first stop condition the running game meets, if any.
+    pub(crate) fn check_stop(&self) -> Option<StopReason> {
+        let balls = match self.state {
+            GameState::Uninitialized => return None,
+            GameState::Normal { balls } | GameState::Rush { balls, .. } => balls,
+        };
+        let summary = self.summary?;
+
+        self.mechanics
+            .stop
+            .check(balls, summary.spins, summary.rush_entries)
+    }
+
+    /// Returns the figures of the current game, or of the last one once it
+    /// has ended; `None` before the first game.
+    pub(crate) fn summary(&self) -> Option<SessionSummary> {
+        self.summary
+    }
+
+    /// Sets the figures of the current game, e.g. when restoring a snapshot.
+    pub(crate) fn set_summary(&mut self, summary: Option<SessionSummary>) {
+        self.summary = summary;
+    }
+
+    /// Records the balls of the current game in its summary; a game found
+    /// over has run out of balls.
+    fn observe_game(&mut self) {
+        let Some(summary) = &mut self.summary else {
+            return;
+        };
+        if summary.finished {
+            return;
+        }
+
+        match self.state {
+            GameState::Uninitialized => {
+                summary.observe_balls(0);
+                summary.finished = true;
+            }
+            GameState::Normal { balls } | GameState::Rush { balls, .. } => {
+                summary.observe_balls(balls);
+            }
+        }
+    }
+
+    /// The stop condition met by the last command, if any.
+    pub(crate) fn stop_reason(&self) -> Option<StopReason> {
+        self.stop_reason
+    }
+
+    /// Starts the game with the configured initial balls; ignored while running.
+    pub(crate) fn start(&mut self) {
+        if let GameState::Uninitialized = self.state {
+            self.state = GameState::Normal {
+                balls: self.balls.init_balls,
+            };
+            self.summary = Some(SessionSummary::new(self.balls.init_balls));
+        }
+    }
+
+    /// Finishes the game and notifies the output; ignored before the game starts.
+    pub(crate) fn finish(&mut self) {
+        if let GameState::Uninitialized = self.state {
+            return;
+        }
+
+        self.output.finish_game(&self.state);
+
+        self.observe_game();
+        if let Some(summary) = &mut self.summary {
+            summary.finished = true;
+        }
+        self.state = GameState::Uninitialized;
+    }
+
+    /// Consumes a ball, or a rush ball while in a continuation rush.
+    pub(crate) fn launch_ball(&mut self) {
+        let cost = self.mechanics.launch.cost;
+        self.stats.balls_launched += match (self.state, self.mechanics.rush_model) {
+            (GameState::Uninitialized, _) => return,
+            // Continuation rushes launch single rush balls
+            (GameState::Rush { n: 1.., .. }, RushModel::Continuation) => 1,
+            (GameState::Normal { balls } | GameState::Rush { balls, .. }, _) => cost.min(balls),
+        };
+        self.roll_start();
+
+        self.state = match (self.state, self.mechanics.rush_model) {
+            // ST rushes and jitan mode launch from the regular balls
+            (GameState::Rush { balls, .. }, RushModel::SpinBudget(_))
+            | (GameState::Rush { balls, n: 0, .. }, _)
+                if balls <= cost =>
+            {
+                GameState::Uninitialized
+            }
+            (
+                GameState::Rush {
+                    balls,
+                    rush_balls,
+                    n,
+                },
+                RushModel::SpinBudget(_),
+            )
+            | (
+                GameState::Rush {
+                    balls,
+                    rush_balls,
+                    n: n @ 0,
+                },
+                _,
+            ) => GameState::Rush {
+                balls: balls - cost,
+                rush_balls,
+                n,
+            },
+            (state, _) => self.launch_continuation_ball(state),
+        };
+    }
+
+    /// Rolls whether a launched ball enters the start pocket, keeping the
+    /// start for a lottery; rolls nothing if every launch enters it.
+    fn roll_start(&mut self) {
+        let rate = self.mechanics.launch.start_rate;
+        if rate < 1.0 && self.roll(rate).passed && self.starts < HOLD_CAPACITY {
+            self.starts += 1;
+        }
+    }
+
+    /// Uses up a start for a lottery; `false` if launches can miss the start
+    /// pocket and none entered it.
+    fn take_start(&mut self) -> bool {
+        if self.mechanics.launch.start_rate >= 1.0 {
+            return true;
+        }
+        if self.starts == 0 {
+            return false;
+        }
+
+        self.starts -= 1;
+        true
+    }
+
+    /// Launches that entered the start pocket and await their lottery.
+    pub(crate) fn starts(&self) -> usize {
+        self.starts
+    }
+
+    /// Sets the pending starts, e.g. when restoring a snapshot.
+    pub(crate) fn set_starts(&mut self, starts: usize) {
+        self.starts = starts;
+    }
+
+    /// The state after launching a ball under the continuation rush model.
+    fn launch_continuation_ball(&mut self, state: GameState) -> GameState {
+        let cost = self.mechanics.launch.cost;
+        match state {
+            GameState::Uninitialized => GameState::Uninitialized,
+            GameState::Normal { balls } if balls <= cost => GameState::Uninitialized,
+            GameState::Normal { balls } => GameState::Normal {
+                balls: balls - cost,
+            },
+            GameState::Rush {
+                balls,
+                rush_balls: 1,
+                n,
+            } => self.end_rush(balls, n),
+            GameState::Rush {
+                balls,
+                rush_balls,
+                n,
+            } => GameState::Rush {
+                balls,
+                rush_balls: rush_balls - 1,
+                n,
+            },
+        }
+    }
+
+    /// The state a rush of depth `n` ends in: the next jackpot of the chain
+    /// if a win is stocked, otherwise jitan mode if configured, otherwise
+    /// normal mode.
+    fn end_rush(&mut self, balls: usize, n: usize) -> GameState {
+        if self.stock > 0 {
+            self.stock -= 1;
+            self.state = GameState::Rush {
+                balls,
+                rush_balls: 0,
+                n,
+            };
+            self.trigger_rush();
+            return self.state;
+        }
+
+        self.after_rush(balls)
+    }
+
+    /// The state after a rush: jitan mode if configured, otherwise normal mode.
+    fn after_rush(&self, balls: usize) -> GameState {
+        match self.mechanics.jitan {
+            Some(Jitan { spins, .. }) => GameState::Rush {
+                balls,
+                rush_balls: spins,
+                n: 0,
+            },
+            None => GameState::Normal { balls },
+        }
+    }
+
+    /// Draws the lottery for the current mode and applies its outcome.
+    pub(crate) fn cause_lottery(&mut self) {
+        let before = self.state;
+        let stats = self.stats;
+        let ceiling = matches!(before, GameState::Normal { .. }) && self.is_ceiling_spin();
+        self.jackpot_rounds = None;
+
+        let Some((mode, lottery, continuation)) = self.spin() else {
+            return;
+        };
+        self.premium = false;
+
+        if !self.outcomes_only {
+            self.ticker.record(
+                self.stats.balls_launched - self.launched_at_spin,
+                self.stats.balls_awarded - stats.balls_awarded,
+                self.ticker_smoothing,
+            );
+            self.launched_at_spin = self.stats.balls_launched;
+
+            self.last_spin = Some(SpinExplanation {
+                mode,
+                lottery,
+                ceiling,
+                continuation,
+                before: before.into(),
+                after: self.state.into(),
+                balls_awarded: self.stats.balls_awarded - stats.balls_awarded,
+                rush_balls_awarded: self.stats.rush_balls_awarded - stats.rush_balls_awarded,
+                rounds: self.jackpot_rounds.map(|(rounds, _)| rounds),
+            });
+        }
+
+        // A fake outcome keeps the state until it is revealed
+        if self.staged_reveal && reveal::apparent(lottery.result).is_some() {
+            self.unrevealed = Some(self.state);
+            self.state = before;
+        }
+    }
+
+    /// Applies the state change held by a staged reveal and reports it to
+    /// the output; `None` if no reveal is pending.
+    pub(crate) fn reveal(&mut self) -> Option<Transition> {
+        let after = self.unrevealed.take()?;
+        let transition = Transition {
+            before: Some(self.state),
+            after,
+        };
+
+        self.state = after;
+        self.before_state = Some(after);
+        self.output.default(transition);
+        self.observe_game();
+
+        Some(transition)
+    }
+
+    /// Draws and applies the lottery; `None` if the game has not started.
+    fn spin(
+        &mut self,
+    ) -> Option<(
+        SpinMode,
+        LotteryExplanation,
+        Option<ContinuationExplanation>,
+    )> {
+        let (mode, probability) = match self.state {
+            // Upstream panics if a lottery is won before the game has started
+            GameState::Uninitialized => return None,
+            GameState::Normal { .. } => {
+                let mut probability = self.probability.normal;
+                if self.is_ceiling_spin() {
+                    probability.win = 1.0;
+                }
+                (SpinMode::Normal, probability)
+            }
+            GameState::Rush { n: 0, .. } => {
+                let probability = self
+                    .mechanics
+                    .jitan
+                    .map_or(self.probability.normal, |jitan| jitan.probability);
+                (SpinMode::Jitan, probability)
+            }
+            GameState::Rush { .. } => (SpinMode::Rush, self.rush_probabilities().0),
+        };
+
+        let (result, mut lottery) = match mode {
+            SpinMode::Rush => self.draw_tilted(probability),
+            SpinMode::Normal | SpinMode::Jitan => self.draw(probability),
+        };
+        self.premium = self.draw_premium(result, &mut lottery);
+        if self.premium {
+            self.output.premium_win();
+        }
+        // Jitan is reported as normal mode; only its probabilities differ
+        match mode {
+            SpinMode::Normal | SpinMode::Jitan => self.output.lottery_normal(result),
+            SpinMode::Rush => self.output.lottery_rush(result),
+        }
+        self.stats.record_spin(result, self.premium);
+        if let Some(summary) = &mut self.summary {
+            summary.spins += 1;
+        }
+        if !self.outcomes_only {
+            self.counters.record_spin(mode, result.is_win());
+            self.streaks.record(result.is_win());
+        }
+
+        if result.is_win() {
+            self.spins_since_win = 0;
+        } else if let GameState::Normal { .. } = self.state {
+            self.spins_since_win += 1;
+        }
+
+        if !result.is_win() {
+            self.spend_spin();
+            return Some((mode, lottery, None));
+        }
+
+        let GameState::Rush { n: n @ 1.., .. } = self.state else {
+            self.trigger_rush();
+            return Some((mode, lottery, None));
+        };
+
+        // A premium win continues the rush without a continuation lottery
+        if self.premium {
+            self.trigger_rush();
+            return Some((mode, lottery, None));
+        }
+
+        // A stocked win is paid out once the rush ends
+        if self.mechanics.stock_cap.is_some_and(|cap| self.stock < cap) {
+            self.stock += 1;
+            return Some((mode, lottery, None));
+        }
+
+        // An ST rush has no continuation lottery; the win restarts the count
+        if let RushModel::SpinBudget(_) = self.mechanics.rush_model {
+            self.trigger_rush();
+            return Some((mode, lottery, None));
+        }
+
+        let (_, base) = self.rush_probabilities();
+        let mut probability = base;
+        let multiplier = (self.probability.rush_continue_fn)(n);
+        probability.win *= multiplier;
+        if let Some(battles) = &self.mechanics.battles {
+            probability.fake_win = battles.revival;
+        }
+
+        let mut continuation = ContinuationExplanation {
+            n,
+            base_win: base.win,
+            multiplier,
+            effective_win: probability.win,
+            lottery: None,
+        };
+
+        // Upstream only prints a warning and skips the continuation lottery
+        if probability.win > 1.0 {
+            return Some((mode, lottery, Some(continuation)));
+        }
+
+        let (continue_lottery, explanation) = self.draw_tilted(probability);
+        continuation.lottery = Some(explanation);
+        self.output.lottery_rush_continue(continue_lottery);
+        if !self.outcomes_only {
+            self.counters.record_continuation(continue_lottery.is_win());
+        }
+
+        if continue_lottery.is_win() {
+            self.trigger_rush();
+        } else {
+            let (incremental_balls, _) = self.rush_increments();
+            let incremental_balls = self.jackpot_balls(incremental_balls);
+            if let GameState::Normal { balls } | GameState::Rush { balls, .. } = &mut self.state {
+                *balls += incremental_balls;
+                self.stats.balls_awarded += incremental_balls;
+            }
+        }
+
+        Some((mode, lottery, Some(continuation)))
+    }
+
+    /// Enables or disables the hold queue; disabling discards held lotteries.
+    pub(crate) fn set_hold_queue(&mut self, enabled: bool) {
+        if !enabled {
+            self.holds = None;
+        } else if self.holds.is_none() {
+            self.holds = Some(HoldQueue::default());
+        }
+    }
+
+    /// Whether the next normal-mode spin is guaranteed to win by the ceiling.
+    pub(crate) fn is_ceiling_spin(&self) -> bool {
+        self.mechanics
+            .ceiling
+            .is_some_and(|ceiling| self.spins_since_win >= ceiling)
+    }
+
+    /// Returns the number of stocked rush wins.
+    pub(crate) fn stock(&self) -> usize {
+        self.stock
+    }
+
+    /// Replaces the stocked rush wins, e.g. when restoring a snapshot.
+    pub(crate) fn set_stock(&mut self, stock: usize) {
+        self.stock = stock;
+    }
+
+    /// Returns the losing normal-mode spins since the last win.
+    pub(crate) fn spins_since_win(&self) -> usize {
+        self.spins_since_win
+    }
+
+    /// Replaces the losing spin counter, e.g. when restoring a snapshot.
+    pub(crate) fn set_spins_since_win(&mut self, spins: usize) {
+        self.spins_since_win = spins;
+    }
+
+    /// Results of the next lotteries, drawn before random ones.
+    pub(crate) fn outcome_script(&self) -> &VecDeque<alias::LotteryResult> {
+        &self.script
+    }
+
+    /// Replaces the results of the next lotteries.
+    pub(crate) fn set_outcome_script(&mut self, script: VecDeque<alias::LotteryResult>) {
+        self.script = script;
+    }
+
+    /// Runs of won and lost spins, over all games.
+    pub(crate) fn streaks(&self) -> Streaks {
+        self.streaks
+    }
+
+    /// Sets the streaks, e.g. when restoring a snapshot.
+    pub(crate) fn set_streaks(&mut self, streaks: Streaks) {
+        self.streaks = streaks;
+    }
+
+    /// Enables or disables staged reveals of fake outcomes; disabling keeps a
+    /// pending reveal.
+    pub(crate) fn set_staged_reveal(&mut self, enabled: bool) {
+        self.staged_reveal = enabled;
+    }
+
+    /// Whether a fake outcome is waiting to be revealed.
+    pub(crate) fn is_reveal_pending(&self) -> bool {
+        self.unrevealed.is_some()
+    }
+
+    /// Changes the rush model and jitan mode, from the next lottery on.
+    pub(crate) fn set_mechanics(&mut self, mechanics: Mechanics) {
+        self.mechanics = mechanics;
+    }
+
+    /// Returns the number of held lotteries, 0 without a hold queue.
+    pub(crate) fn held(&self) -> usize {
+        self.holds.as_ref().map_or(0, HoldQueue::held)
+    }
+
+    /// Returns the current game state.
+    pub(crate) fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    /// Returns the balls held, 0 before the game starts.
+    pub(crate) fn balls(&self) -> usize {
+        match self.state {
+            GameState::Uninitialized => 0,
+            GameState::Normal { balls } | GameState::Rush { balls, .. } => balls,
+        }
+    }
+
+    /// Returns the rush balls left, 0 outside rush mode and in jitan mode.
+    pub(crate) fn rush_balls(&self) -> usize {
+        match self.state {
+            GameState::Rush {
+                rush_balls, n: 1.., ..
+            } => rush_balls,
+            _ => 0,
+        }
+    }
+
+    /// Returns the state before the last reported transition.
+    pub(crate) fn before_state(&self) -> Option<&GameState> {
+        self.before_state.as_ref()
+    }
+
+    /// Replaces the current state, e.g. when restoring a snapshot, dropping a
+    /// pending reveal.
+    pub(crate) fn restore_state(&mut self, state: GameState, before_state: Option<GameState>) {
+        self.unrevealed = None;
+        self.state = state;
+        self.before_state = before_state;
+    }
+
+    /// Returns how the last lottery was resolved, `None` before the first one.
+    pub(crate) fn last_spin(&self) -> Option<&SpinExplanation> {
+        self.last_spin.as_ref()
+    }
+
+    /// Returns the counters over every game run so far.
+    pub(crate) fn stats(&self) -> &GameStats {
+        &self.stats
+    }
+
+    /// Returns the per-mode lottery counters over every game run so far.
+    pub(crate) fn counters(&self) -> &ModeCounters {
+        &self.counters
+    }
+
+    /// Adds the counters of an earlier session.
+    pub(crate) fn merge_stats(&mut self, other: &GameStats) {
+        self.stats = self.stats.merge(other);
+        // Merged launches belong to no spin of this game
+        self.launched_at_spin += other.balls_launched;
+    }
+
+    /// Returns the smoothed return figures over the recent spins.
+    pub(crate) fn ticker(&self) -> &ReturnTicker {
+        &self.ticker
+    }
+
+    /// Replaces the return figures, e.g. when restoring a snapshot.
+    pub(crate) fn set_ticker(&mut self, ticker: ReturnTicker) {
+        self.ticker = ticker;
+    }
+
+    /// Changes the weight of each new spin in the return figures.
+    pub(crate) fn set_ticker_smoothing(&mut self, smoothing: f64) {
+        self.ticker_smoothing = smoothing;
+    }
+
+    /// Returns the output handler.
+    pub(crate) fn output(&self) -> &O {
+        &self.output
+    }
+
+    /// Returns the output handler mutably.
+    pub(crate) fn output_mut(&mut self) -> &mut O {
+        &mut self.output
+    }
+
+    /// Returns the random number generator.
+    pub(crate) fn rng(&self) -> &GameRng {
+        &self.rng
+    }
+
+    /// Replaces the random number generator.
+    pub(crate) fn set_rng(&mut self, rng: GameRng) {
+        self.rng = rng;
+    }
+
+    fn draw(&mut self, probability: SlotProbability) -> (LotteryResult, LotteryExplanation) {
+        if let Some(scripted) = self.script.pop_front() {
+            return self.scripted(scripted, probability);
+        }
+
+        let win = self.roll(probability.win);
+
+        let (result, detail) = if win.passed {
+            let detail = self.roll(probability.fake_win);
+            let win = if detail.passed {
+                Win::FakeWin
+            } else {
+                Win::Default
+            };
+            (LotteryResult::Win(win), detail)
+        } else {
+            let detail = self.roll(probability.fake_lose);
+            let lose = if detail.passed {
+                Lose::FakeLose
+            } else {
+                Lose::Default
+            };
+            (LotteryResult::Lose(lose), detail)
+        };
+
+        (
+            result,
+            LotteryExplanation {
+                win,
+                detail,
+                premium: None,
+                result: result.into(),
+            },
+        )
+    }
+
+    /// Draws like [`Engine::draw`], with the odds of a win multiplied by the
+    /// tilt if one is set.
+    fn draw_tilted(
+        &mut self,
+        mut probability: SlotProbability,
+    ) -> (LotteryResult, LotteryExplanation) {
+        let Some(tilt) = self.tilt else {
+            return self.draw(probability);
+        };
+
+        let p = probability.win;
+        let q = p * tilt.odds / (p * tilt.odds + 1.0 - p);
+        probability.win = q;
+        let drawn = self.draw(probability);
+
+        let ratio = if drawn.0.is_win() {
+            p / q
+        } else {
+            (1.0 - p) / (1.0 - q)
+        };
+        if let Some(tilt) = &mut self.tilt {
+            tilt.likelihood_ratio *= ratio;
+        }
+
+        drawn
+    }
+
+    /// A lottery with a scripted result; nothing is rolled.
+    fn scripted(
+        &self,
+        scripted: alias::LotteryResult,
+        probability: SlotProbability,
+    ) -> (LotteryResult, LotteryExplanation) {
+        let fixed = |probability, passed| Roll {
+            probability,
+            value: None,
+            passed,
+        };
+
+        let (result, detail) = match scripted {
+            alias::LotteryResult::Win(alias::Win::FakeWin) => (
+                LotteryResult::Win(Win::FakeWin),
+                fixed(probability.fake_win, true),
+            ),
+            alias::LotteryResult::Win(_) => (
+                LotteryResult::Win(Win::Default),
+                fixed(probability.fake_win, false),
+            ),
+            alias::LotteryResult::Lose(alias::Lose::FakeLose) => (
+                LotteryResult::Lose(Lose::FakeLose),
+                fixed(probability.fake_lose, true),
+            ),
+            alias::LotteryResult::Lose(alias::Lose::Default) => (
+                LotteryResult::Lose(Lose::Default),
+                fixed(probability.fake_lose, false),
+            ),
+        };
+        // Decides `draw_premium` for plain wins
+        let premium = match scripted {
+            alias::LotteryResult::Win(alias::Win::Premium) => {
+                Some(fixed(self.mechanics.premium.unwrap_or(0.0), true))
+            }
+            alias::LotteryResult::Win(alias::Win::Default) => {
+                self.mechanics.premium.map(|premium| fixed(premium, false))
+            }
+            _ => None,
+        };
+
+        (
+            result,
+            LotteryExplanation {
+                win: fixed(probability.win, result.is_win()),
+                detail,
+                premium,
+                result: scripted,
+            },
+        )
+    }
+
+    /// Rolls whether a plain win is premium, marking it in `lottery`; rolls
+    /// nothing without premium wins.
+    fn draw_premium(&mut self, result: LotteryResult, lottery: &mut LotteryExplanation) -> bool {
+        // Scripted lotteries are decided already
+        if let Some(roll) = lottery.premium {
+            return roll.passed;
+        }
+
+        let (Some(probability), LotteryResult::Win(Win::Default)) =
+            (self.mechanics.premium, result)
+        else {
+            return false;
+        };
+
+        let roll = self.roll(probability);
+        lottery.premium = Some(roll);
+        if roll.passed {
+            lottery.result = alias::LotteryResult::Win(alias::Win::Premium);
+        }
+
+        roll.passed
+    }
+
+    /// Rolls against a probability exactly like `Rng::random_bool`, keeping
+    /// the rolled value.
+    fn roll(&mut self, probability: f64) -> Roll {
+        // `random_bool` draws nothing for certain events
+        if probability == 1.0 {
+            return Roll {
+                probability,
+                value: None,
+                passed: true,
+            };
+        }
+        assert!(
+            (0.0..1.0).contains(&probability),
+            "p={probability:?} is outside range [0.0, 1.0]"
+        );
+
+        let value: u64 = self.rng.random();
+
+        Roll {
+            probability,
+            value: Some(value as f64 / ROLL_SCALE),
+            passed: value < (probability * ROLL_SCALE) as u64,
+        }
+    }
+
+    /// Counts down a lost spin of jitan mode or an ST rush, ending the mode
+    /// after the last.
+    fn spend_spin(&mut self) {
+        self.state = match (self.state, self.mechanics.rush_model) {
+            (
+                GameState::Rush {
+                    balls,
+                    rush_balls: 0 | 1,
+                    n: 0,
+                },
+                _,
+            ) => GameState::Normal { balls },
+            (
+                GameState::Rush {
+                    balls,
+                    rush_balls: 0 | 1,
+                    n,
+                },
+                RushModel::SpinBudget(_),
+            ) => self.end_rush(balls, n),
+            (
+                GameState::Rush {
+                    balls,
+                    rush_balls,
+                    n: n @ 0,
+                },
+                _,
+            )
+            | (
+                GameState::Rush {
+                    balls,
+                    rush_balls,
+                    n,
+                },
+                RushModel::SpinBudget(_),
+            ) => GameState::Rush {
+                balls,
+                rush_balls: rush_balls - 1,
+                n,
+            },
+            (state, _) => state,
+        };
+    }
+
+    /// The tier of the current rush, `None` without tiers.
+    pub(crate) fn rush_tier(&self) -> Option<(usize, &RushTier)> {
+        let GameState::Rush { n: 1.., .. } = self.state else {
+            return None;
+        };
+        let tier = self.mechanics.tiers.get(self.tier)?;
+        Some((self.tier, tier))
+    }
+
+    /// Sets the tier of the current rush, e.g. when restoring a snapshot.
+    pub(crate) fn set_rush_tier(&mut self, tier: usize) {
+        self.tier = tier;
+    }
+
+    /// Multiplies the odds of rush and continuation wins by `odds`, or
+    /// draws them as configured for `None`, and restarts the likelihood
+    /// ratio at 1.
+    pub(crate) fn set_tilt(&mut self, odds: Option<f64>) {
+        self.tilt = odds.map(|odds| Tilt {
+            odds,
+            likelihood_ratio: 1.0,
+        });
+    }
+
+    /// Skips what only describes spins: their explanations, the return
+    /// ticker, the mode counters and the streaks. The outcomes and the stats
+    /// stay exactly the same, so batch simulations can draw faster.
+    pub(crate) fn set_outcomes_only(&mut self, enabled: bool) {
+        self.outcomes_only = enabled;
+    }
+
+    /// How much more likely the draws since [`Engine::set_tilt`] are as
+    /// configured than with the tilt; 1 without a tilt.
+    pub(crate) fn likelihood_ratio(&self) -> f64 {
+        self.tilt.map_or(1.0, |tilt| tilt.likelihood_ratio)
+    }
+
+    /// Rush and continuation probabilities of the current rush.
+    fn rush_probabilities(&self) -> (SlotProbability, SlotProbability) {
+        match self.mechanics.tiers.get(self.tier) {
+            Some(tier) => (tier.rush, tier.rush_continue),
+            None => (self.probability.rush, self.probability.rush_continue),
+        }
+    }
+
+    /// Balls and rush balls paid out per jackpot of the current or entered
+    /// rush.
+    fn rush_increments(&self) -> (usize, usize) {
+        match self.mechanics.tiers.get(self.tier) {
+            Some(tier) => (tier.incremental_balls, tier.incremental_rush),
+            None => (self.balls.incremental_balls, self.balls.incremental_rush),
+        }
+    }
+
+    /// Draws the tier of a rush being entered; draws nothing without tiers.
+    fn draw_tier(&mut self) {
+        let weights = self.mechanics.tiers.iter().map(|tier| tier.weight);
+        self.tier = draw_weighted(&mut self.rng, weights);
+    }
+
+    /// The battle presentation of continuation lotteries, if enabled.
+    pub(crate) fn battles(&self) -> Option<&Battles> {
+        self.mechanics.battles.as_ref()
+    }
+
+    /// Rounds and balls per round of the jackpot paid out since the last
+    /// call, if it was paid out in rounds.
+    pub(crate) fn take_jackpot_rounds(&mut self) -> Option<(usize, usize)> {
+        self.jackpot_rounds.take()
+    }
+
+    /// Balls paid out by a jackpot, drawing its rounds if jackpots pay out in
+    /// rounds; `incremental_balls` otherwise.
+    fn jackpot_balls(&mut self, incremental_balls: usize) -> usize {
+        let Some(rounds) = &self.mechanics.rounds else {
+            return incremental_balls;
+        };
+
+        // A premium win pays the most rounds
+        let count = if self.premium {
+            rounds
+                .choices
+                .iter()
+                .map(|&(count, _)| count)
+                .max()
+                .unwrap_or(0)
+        } else {
+            let weights = rounds.choices.iter().map(|&(_, weight)| weight);
+            rounds.choices[draw_weighted(&mut self.rng, weights)].0
+        };
+        self.jackpot_rounds = Some((count, rounds.balls_per_round));
+
+        count * rounds.balls_per_round
+    }
+
+    /// Enters rush mode or continues it, paying out balls and rush balls.
+    pub(crate) fn trigger_rush(&mut self) {
+        if let GameState::Normal { .. } | GameState::Rush { n: 0, .. } = self.state {
+            self.draw_tier();
+        }
+        let (incremental_balls, incremental_rush) = self.rush_increments();
+
+        // A win in jitan mode enters a new rush, as one in normal mode does
+        let (balls, rush_balls, n) = match (self.state, self.mechanics.rush_model) {
+            (GameState::Uninitialized, _) => return,
+            (
+                GameState::Normal { balls } | GameState::Rush { balls, n: 0, .. },
+                RushModel::Continuation,
+            ) => (balls, incremental_rush, 1),
+            (
+                GameState::Rush {
+                    balls,
+                    rush_balls,
+                    n,
+                },
+                RushModel::Continuation,
+            ) => (balls, rush_balls + incremental_rush, n + 1),
+            // An ST rush restarts its spin count instead of adding rush balls
+            (
+                GameState::Normal { balls } | GameState::Rush { balls, n: 0, .. },
+                RushModel::SpinBudget(spins),
+            ) => (balls, spins, 1),
+            (GameState::Rush { balls, n, .. }, RushModel::SpinBudget(spins)) => {
+                (balls, spins, n + 1)
+            }
+        };
+
+        let incremental_balls = self.jackpot_balls(incremental_balls);
+        self.state = GameState::Rush {
+            balls: balls + incremental_balls,
+            rush_balls,
+            n,
+        };
+
+        self.stats.record_rush(n);
+        if let Some(summary) = &mut self.summary {
+            summary.record_rush(n);
+        }
+        self.stats.balls_awarded += incremental_balls;
+        if let RushModel::Continuation = self.mechanics.rush_model {
+            self.stats.rush_balls_awarded += incremental_rush;
+        }
+    }
+}
+
+/// Draws an index with a chance proportional to its weight; draws nothing
+/// with fewer than two weights.
+fn draw_weighted(rng: &mut GameRng, mut weights: impl Iterator<Item = f64> + Clone) -> usize {
+    let count = weights.clone().count();
+    if count < 2 {
+        return 0;
+    }
+
+    let mut value = rng.random::<f64>() * weights.clone().sum::<f64>();
+    weights
+        .position(|weight| {
+            value -= weight;
+            value < 0.0
+        })
+        .unwrap_or(count - 1)
+}
+
+/// Applies the same checks as the upstream `Config::validate`.
+fn validate<F: FnMut(usize) -> f64>(
+    config: &pachislo::config::Config<F>,
+) -> Result<(), ConfigError> {
+    let mut errors = Vec::new();
+
+    if config.balls.init_balls < 1 {
+        errors.push("initial balls must be greater than 0".to_string());
+    }
+
+    let probability = &config.probability;
+    for slot in [
+        probability.normal,
+        probability.rush,
+        probability.rush_continue,
+    ] {
+        for (name, value) in [
+            ("win", slot.win),
+            ("fake_win", slot.fake_win),
+            ("fake_lose", slot.fake_lose),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                errors.push(format!("{name} probability must be between 0.0 and 1.0"));
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ConfigError(errors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records every output call so tests can inspect them.
+    #[derive(Default)]
+    struct RecordingOutput {
+        transitions: Vec<Transition>,
+        lotteries: Vec<LotteryResult>,
+        finished: Vec<GameState>,
+        premium_wins: usize,
+    }
+
+    impl RecordingOutput {
+        fn new() -> Self {
+            Default::default()
+        }
+    }
+
+    impl EngineOutput for RecordingOutput {
+        fn premium_win(&mut self) {
+            self.premium_wins += 1;
+        }
+    }
+
+    impl UserOutput for RecordingOutput {
+        fn default(&mut self, state: Transition) {
+            self.transitions.push(state);
+        }
+
+        fn finish_game(&mut self, state: &GameState) {
+            self.finished.push(*state);
+        }
+
+        fn lottery_normal(&mut self, result: LotteryResult) {
+            self.lotteries.push(result);
+        }
+
+        fn lottery_rush(&mut self, result: LotteryResult) {
+            self.lotteries.push(result);
+        }
+
+        fn lottery_rush_continue(&mut self, result: LotteryResult) {
+            self.lotteries.push(result);
+        }
+    }
+
+    fn config(
+        normal_win: f64,
+        rush_win: f64,
+        continue_win: f64,
+    ) -> pachislo::config::Config<fn(usize) -> f64> {
+        let slot = |win| SlotProbability {
+            win,
+            fake_win: 0.5,
+            fake_lose: 0.5,
+        };
+
+        pachislo::config::Config {
+            balls: pachislo::config::BallsConfig {
+                init_balls: 10,
+                incremental_balls: 15,
+                incremental_rush: 3,
+            },
+            probability: Probability {
+                normal: slot(normal_win),
+                rush: slot(rush_win),
+                rush_continue: slot(continue_win),
+                rush_continue_fn: |_| 1.0,
+            },
+        }
+    }
+
+    fn engine(
+        normal_win: f64,
+        rush_win: f64,
+        continue_win: f64,
+        seed: u64,
+    ) -> Engine<RecordingOutput, fn(usize) -> f64> {
+        Engine::new(
+            config(normal_win, rush_win, continue_win),
+            RecordingOutput::new(),
+            GameRng::from_seed(seed),
+        )
+        .unwrap()
+    }
+
+    fn balls(state: &GameState) -> usize {
+        match *state {
+            GameState::Uninitialized => 0,
+            GameState::Normal { balls } | GameState::Rush { balls, .. } => balls,
+        }
+    }
+
+    #[test]
+    fn test_command_typescript_union() {
+        assert!(Command::DECL.ends_with(
+            "export type Command = \"LaunchBall\" | \"CauseLottery\" | \"ConsumeHold\" | \"StartGame\" | \"FinishGame\" | \"Finish\";"
+        ));
+    }
+
+    #[test]
+    fn test_invalid_config_is_rejected() {
+        let mut invalid = config(1.5, 0.5, 0.5);
+        invalid.balls.init_balls = 0;
+
+        let error = Engine::new(invalid, RecordingOutput::new(), GameRng::from_seed(0))
+            .err()
+            .unwrap();
+
+        assert_eq!(error.0.len(), 2);
+        assert!(error.to_string().contains("initial balls"));
+    }
+
+    #[test]
+    fn test_launch_ball_until_empty() {
+        let mut engine = engine(0.0, 0.0, 0.0, 0);
+
+        assert!(
+            engine
+                .run_step_with_command(Command::StartGame)
+                .is_continue()
+        );
+        for _ in 0..9 {
+            assert!(
+                engine
+                    .run_step_with_command(Command::LaunchBall)
+                    .is_continue()
+            );
+        }
+        assert_eq!(balls(engine.state()), 1);
+
+        assert!(
+            engine
+                .run_step_with_command(Command::LaunchBall)
+                .is_continue()
+        );
+        assert!(matches!(engine.state(), GameState::Uninitialized));
+        assert_eq!(engine.output.transitions.len(), 11);
+    }
+
+    #[test]
+    fn test_rush_entry_and_continuation() {
+        let mut engine = engine(1.0, 1.0, 1.0, 0);
+
+        engine.start();
+        engine.cause_lottery();
+        assert!(matches!(
+            engine.state(),
+            GameState::Rush {
+                balls: 25,
+                rush_balls: 3,
+                n: 1
+            }
+        ));
+
+        engine.cause_lottery();
+        assert!(matches!(
+            engine.state(),
+            GameState::Rush {
+                balls: 40,
+                rush_balls: 6,
+                n: 2
+            }
+        ));
+
+        // Normal, rush and continuation lotteries
+        assert_eq!(engine.output.lotteries.len(), 3);
+    }
+
+    #[test]
+    fn test_failed_continuation_pays_out() {
+        let mut engine = engine(1.0, 1.0, 0.0, 0);
+
+        engine.start();
+        engine.cause_lottery();
+        engine.cause_lottery();
+
+        assert!(matches!(
+            engine.state(),
+            GameState::Rush {
+                balls: 40,
+                rush_balls: 3,
+                n: 1
+            }
+        ));
+    }
+
+    #[test]
+    fn test_ball_counts() {
+        let mut engine = engine(1.0, 1.0, 1.0, 0);
+        assert_eq!((engine.balls(), engine.rush_balls()), (0, 0));
+
+        engine.start();
+        assert_eq!((engine.balls(), engine.rush_balls()), (10, 0));
+
+        engine.cause_lottery();
+        assert_eq!((engine.balls(), engine.rush_balls()), (25, 3));
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut chained = engine(1.0, 1.0, 1.0, 0);
+
+        chained.launch_ball();
+        chained.start();
+        chained.launch_ball();
+        chained.cause_lottery();
+        chained.cause_lottery();
+
+        let stats = *chained.stats();
+        assert_eq!(stats.spins, 2);
+        assert_eq!(stats.wins.default + stats.wins.fake_win, 2);
+        assert_eq!((stats.rush_entries, stats.longest_rush_chain), (1, 2));
+        assert_eq!(stats.balls_launched, 1);
+        assert_eq!((stats.balls_awarded, stats.rush_balls_awarded), (30, 6));
+
+        // A failed continuation still pays out balls
+        let mut ended = engine(1.0, 1.0, 0.0, 0);
+        ended.start();
+        ended.cause_lottery();
+        ended.cause_lottery();
+        assert_eq!(ended.stats().balls_awarded, 30);
+        assert_eq!(ended.stats().longest_rush_chain, 1);
+    }
+
+    #[test]
+    fn test_counters() {
+        use crate::stats::HitCounter;
+
+        let mut engine = engine(1.0, 1.0, 0.0, 0);
+
+        engine.start();
+        // Normal win, rush win and failed continuation, then a normal loss
+        engine.cause_lottery();
+        engine.cause_lottery();
+        engine.finish();
+        engine.probability.normal.win = 0.0;
+        engine.start();
+        engine.cause_lottery();
+
+        let counters = *engine.counters();
+        assert_eq!(
+            counters.normal,
+            HitCounter {
+                attempts: 2,
+                wins: 1
+            }
+        );
+        assert_eq!(
+            counters.rush,
+            HitCounter {
+                attempts: 1,
+                wins: 1
+            }
+        );
+        assert_eq!(
+            counters.rush_continue,
+            HitCounter {
+                attempts: 1,
+                wins: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_rewind() {
+        let mut engine = engine(0.5, 0.5, 0.5, 8);
+        engine.start();
+        let checkpoint = engine.checkpoint();
+
+        let play = |engine: &mut Engine<RecordingOutput, fn(usize) -> f64>| {
+            for _ in 0..10 {
+                engine.launch_ball();
+                engine.cause_lottery();
+            }
+            (format!("{:?}", engine.state()), *engine.stats())
+        };
+        let played = play(&mut engine);
+
+        engine.rewind(checkpoint);
+        assert!(matches!(engine.state(), GameState::Normal { balls: 10 }));
+        assert_eq!(*engine.stats(), GameStats::default());
+        // The generator is rewound too, so the same commands replay alike
+        assert_eq!(play(&mut engine), played);
+    }
+
+    #[test]
+    fn test_fork() {
+        let mut engine = engine(0.5, 0.5, 0.5, 8);
+        engine.start();
+        engine.set_ticker_smoothing(0.5);
+        for _ in 0..5 {
+            engine.launch_ball();
+            engine.cause_lottery();
+        }
+
+        let mut fork = engine.fork(config(0.5, 0.5, 0.5), RecordingOutput::new());
+        assert_eq!(
+            format!("{:?}", fork.state()),
+            format!("{:?}", engine.state())
+        );
+        assert_eq!(fork.stats(), engine.stats());
+        assert_eq!(fork.ticker_smoothing, 0.5);
+
+        let play = |engine: &mut Engine<RecordingOutput, fn(usize) -> f64>| {
+            for _ in 0..10 {
+                engine.launch_ball();
+                engine.cause_lottery();
+            }
+            (format!("{:?}", engine.state()), *engine.stats())
+        };
+        assert_eq!(play(&mut fork), play(&mut engine));
+    }
+
+    #[test]
+    fn test_preview() {
+        let mut engine = engine(0.5, 0.5, 0.5, 3);
+        engine.start();
+
+        let preview = engine.preview(Command::LaunchBall, engine.rng().clone());
+        assert!(matches!(preview.after, GameState::Normal { balls: 9 }));
+        assert!(matches!(engine.state(), GameState::Normal { balls: 10 }));
+        assert!(engine.output.transitions.is_empty());
+
+        // With the engine's generator, the preview is what happens
+        for _ in 0..10 {
+            let preview = engine.preview(Command::CauseLottery, engine.rng().clone());
+            engine.cause_lottery();
+            assert_eq!(
+                format!("{:?}", preview.after),
+                format!("{:?}", engine.state())
+            );
+        }
+        assert_eq!(engine.stats().spins, 10);
+    }
+
+    #[test]
+    fn test_outcome_script() {
+        use alias::{Lose as ScriptLose, LotteryResult as Scripted, Win as ScriptWin};
+
+        // Random draws would always lose
+        let mut engine = engine(0.0, 0.0, 0.0, 0);
+        engine.set_outcome_script(VecDeque::from([
+            Scripted::Lose(ScriptLose::Default),
+            Scripted::Win(ScriptWin::FakeWin),
+            Scripted::Win(ScriptWin::Default),
+            Scripted::Win(ScriptWin::Default),
+        ]));
+
+        engine.start();
+        for _ in 0..3 {
+            engine.cause_lottery();
+        }
+        // Lose, then the fake win enters rush and the rush win continues it
+        assert!(matches!(engine.state(), GameState::Rush { n: 2, .. }));
+        assert!(matches!(
+            engine.output.lotteries[..],
+            [
+                LotteryResult::Lose(Lose::Default),
+                LotteryResult::Win(Win::FakeWin),
+                LotteryResult::Win(Win::Default),
+                LotteryResult::Win(Win::Default),
+            ]
+        ));
+        let lottery = engine.last_spin().unwrap().lottery;
+        assert_eq!((lottery.win.value, lottery.win.passed), (None, true));
+        assert!(engine.outcome_script().is_empty());
+
+        // The script ran out, so the next rush spin loses at random
+        engine.cause_lottery();
+        assert!(!engine.output.lotteries.last().unwrap().is_win());
+    }
+
+    #[test]
+    fn test_st_rush_lasts_a_fixed_number_of_spins() {
+        let mut engine = engine(1.0, 0.0, 1.0, 0);
+        engine.set_mechanics(Mechanics {
+            rush_model: RushModel::SpinBudget(3),
+            ..Mechanics::default()
+        });
+        engine.start();
+        engine.cause_lottery();
+        assert!(matches!(
+            engine.state(),
+            GameState::Rush {
+                balls: 25,
+                rush_balls: 3,
+                n: 1
+            }
+        ));
+
+        // Balls are launched from the regular balls
+        engine.launch_ball();
+        assert!(matches!(
+            engine.state(),
+            GameState::Rush {
+                balls: 24,
+                rush_balls: 3,
+                ..
+            }
+        ));
+
+        engine.cause_lottery();
+        engine.cause_lottery();
+        assert!(matches!(
+            engine.state(),
+            GameState::Rush { rush_balls: 1, .. }
+        ));
+        engine.cause_lottery();
+        assert!(matches!(engine.state(), GameState::Normal { balls: 24 }));
+        assert_eq!(engine.stats().rush_balls_awarded, 0);
+    }
+
+    #[test]
+    fn test_st_rush_win_restarts_the_count() {
+        // A continuation lottery would end the rush; an ST rush has none
+        let mut engine = engine(1.0, 1.0, 0.0, 0);
+        engine.set_mechanics(Mechanics {
+            rush_model: RushModel::SpinBudget(5),
+            ..Mechanics::default()
+        });
+        engine.start();
+        engine.cause_lottery();
+        engine.cause_lottery();
+
+        assert!(matches!(
+            engine.state(),
+            GameState::Rush {
+                balls: 40,
+                rush_balls: 5,
+                n: 2
+            }
+        ));
+        assert_eq!(engine.stats().longest_rush_chain, 2);
+        assert!(engine.last_spin().unwrap().continuation.is_none());
+    }
+
+    #[test]
+    fn test_return_ticker() {
+        let mut engine = engine(1.0, 1.0, 1.0, 0);
+        engine.start();
+        engine.launch_ball();
+        engine.launch_ball();
+        engine.cause_lottery();
+
+        let ticker = *engine.ticker();
+        assert_eq!(ticker.samples, 1);
+        assert_eq!(ticker.balls_per_spin, 2.0);
+        assert_eq!(ticker.awarded_per_spin, engine.stats().balls_awarded as f64);
+        assert_eq!(ticker.rtp, ticker.awarded_per_spin / 2.0);
+
+        // Merged launches are not attributed to the next spin
+        engine.merge_stats(&GameStats {
+            balls_launched: 100,
+            ..GameStats::default()
+        });
+        engine.set_ticker_smoothing(0.5);
+        engine.cause_lottery();
+        assert_eq!(engine.ticker().balls_per_spin, 1.0);
+        assert_eq!(engine.ticker().samples, 2);
+    }
+
+    #[test]
+    fn test_set_config_keeps_state() {
+        let mut engine = engine(0.0, 0.0, 0.0, 0);
+
+        engine.start();
+        engine.launch_ball();
+
+        let mut hot = config(1.0, 0.0, 0.0);
+        hot.balls.incremental_balls = 100;
+        engine.set_config(hot).unwrap();
+        assert!(matches!(engine.state(), GameState::Normal { balls: 9 }));
+
+        engine.cause_lottery();
+        assert!(matches!(
+            engine.state(),
+            GameState::Rush {
+                balls: 109,
+                rush_balls: 3,
+                n: 1
+            }
+        ));
+
+        assert!(engine.set_config(config(-1.0, 0.0, 0.0)).is_err());
+        assert!(matches!(engine.state(), GameState::Rush { balls: 109, .. }));
+    }
+
+    #[test]
+    fn test_roll_matches_random_bool() {
+        let mut engine = engine(0.0, 0.0, 0.0, 42);
+        let mut rng = GameRng::from_seed(42);
+
+        for i in 0..=1000 {
+            let probability = i as f64 / 1000.0;
+            assert_eq!(
+                engine.roll(probability).passed,
+                rng.random_bool(probability)
+            );
+        }
+    }
+
+    #[test]
+    fn test_last_spin() {
+        let mut engine = engine(1.0, 1.0, 0.5, 3);
+
+        engine.cause_lottery();
+        assert!(engine.last_spin().is_none());
+
+        engine.start();
+        engine.cause_lottery();
+        let spin = *engine.last_spin().unwrap();
+        assert_eq!(spin.mode, SpinMode::Normal);
+        assert_eq!(spin.lottery.win.value, None);
+        assert!(spin.lottery.win.passed && spin.continuation.is_none());
+        assert_eq!((spin.balls_awarded, spin.rush_balls_awarded), (15, 3));
+
+        engine.cause_lottery();
+        let spin = *engine.last_spin().unwrap();
+        let continuation = spin.continuation.unwrap();
+        assert_eq!(spin.mode, SpinMode::Rush);
+        assert_eq!((continuation.n, continuation.multiplier), (1, 1.0));
+        assert_eq!(continuation.effective_win, 0.5);
+
+        let roll = continuation.lottery.unwrap().win;
+        assert_eq!(roll.passed, roll.value.unwrap() < 0.5);
+        assert_eq!(spin.balls_awarded, 15);
+        assert_eq!(spin.rush_balls_awarded, if roll.passed { 3 } else { 0 });
+    }
+
+    #[test]
+    fn test_rush_ends_when_rush_balls_run_out() {
+        let mut engine = engine(1.0, 0.0, 0.0, 0);
+
+        engine.start();
+        engine.cause_lottery();
+        for _ in 0..3 {
+            engine.launch_ball();
+        }
+
+        assert!(matches!(engine.state(), GameState::Normal { balls: 25 }));
+    }
+
+    #[test]
+    fn test_jitan_after_rush() {
+        let slot = |win| SlotProbability {
+            win,
+            fake_win: 0.0,
+            fake_lose: 0.0,
+        };
+        let mut engine = engine(1.0, 0.0, 0.0, 0);
+        engine.set_mechanics(Mechanics {
+            jitan: Some(Jitan {
+                spins: 2,
+                probability: slot(0.0),
+            }),
+            ..Mechanics::default()
+        });
+
+        engine.start();
+        engine.cause_lottery();
+        for _ in 0..3 {
+            engine.launch_ball();
+        }
+        assert!(matches!(
+            engine.state(),
+            GameState::Rush {
+                balls: 25,
+                rush_balls: 2,
+                n: 0
+            }
+        ));
+        assert_eq!(engine.rush_balls(), 0);
+
+        // Jitan launches from the regular balls and draws its own probabilities
+        engine.launch_ball();
+        engine.cause_lottery();
+        assert!(matches!(
+            engine.state(),
+            GameState::Rush {
+                balls: 24,
+                rush_balls: 1,
+                n: 0
+            }
+        ));
+        assert_eq!(engine.last_spin().unwrap().mode, SpinMode::Jitan);
+
+        engine.cause_lottery();
+        assert!(matches!(engine.state(), GameState::Normal { balls: 24 }));
+    }
+
+    #[test]
+    fn test_jitan_win_enters_rush() {
+        let mut engine = engine(0.0, 0.0, 0.0, 0);
+        engine.set_mechanics(Mechanics {
+            jitan: Some(Jitan {
+                spins: 10,
+                probability: SlotProbability {
+                    win: 1.0,
+                    fake_win: 0.0,
+                    fake_lose: 0.0,
+                },
+            }),
+            ..Mechanics::default()
+        });
+        engine.restore_state(
+            GameState::Rush {
+                balls: 5,
+                rush_balls: 10,
+                n: 0,
+            },
+            None,
+        );
+
+        engine.cause_lottery();
+        assert!(matches!(
+            engine.state(),
+            GameState::Rush {
+                balls: 20,
+                rush_balls: 3,
+                n: 1
+            }
+        ));
+        assert_eq!(engine.stats().rush_balls_awarded, 3);
+    }
+
+    #[test]
+    fn test_staged_reveal_holds_the_payout() {
+        let mut engine = (0..)
+            .map(|seed| {
+                let mut engine = engine(1.0, 0.0, 0.0, seed);
+                engine.set_staged_reveal(true);
+                engine.start();
+                engine.cause_lottery();
+                engine
+            })
+            .find(|engine| engine.is_reveal_pending())
+            .unwrap();
+
+        assert!(matches!(engine.state(), GameState::Normal { balls: 10 }));
+        assert!(matches!(
+            engine.last_spin().unwrap().lottery.result,
+            crate::alias::LotteryResult::Win(crate::alias::Win::FakeWin)
+        ));
+
+        let transition = engine.reveal().unwrap();
+        assert!(matches!(
+            transition.before,
+            Some(GameState::Normal { balls: 10 })
+        ));
+        assert!(matches!(
+            engine.state(),
+            GameState::Rush {
+                balls: 25,
+                rush_balls: 3,
+                n: 1
+            }
+        ));
+        assert!(matches!(
+            engine.output().transitions.last().unwrap().after,
+            GameState::Rush { balls: 25, .. }
+        ));
+        assert!(engine.reveal().is_none());
+    }
+
+    #[test]
+    fn test_ceiling() {
+        let mut engine = engine(0.0, 0.0, 0.0, 0);
+        engine.set_mechanics(Mechanics {
+            ceiling: Some(3),
+            ..Mechanics::default()
+        });
+        engine.start();
+
+        for spins in 1..=3 {
+            engine.cause_lottery();
+            assert_eq!(engine.spins_since_win(), spins);
+            assert!(!engine.last_spin().unwrap().ceiling);
+        }
+        assert!(engine.is_ceiling_spin());
+
+        engine.cause_lottery();
+        assert!(engine.last_spin().unwrap().ceiling);
+        assert!(matches!(engine.state(), GameState::Rush { n: 1, .. }));
+        assert_eq!(engine.spins_since_win(), 0);
+
+        // Rush spins do not count
+        engine.cause_lottery();
+        assert_eq!(engine.spins_since_win(), 0);
+    }
+
+    #[test]
+    fn test_stock_pays_out_after_the_rush() {
+        let mut engine = engine(1.0, 1.0, 0.0, 0);
+        engine.set_mechanics(Mechanics {
+            stock_cap: Some(2),
+            ..Mechanics::default()
+        });
+        engine.start();
+        engine.cause_lottery();
+
+        engine.cause_lottery();
+        engine.cause_lottery();
+        assert_eq!(engine.stock(), 2);
+        assert!(matches!(engine.state(), GameState::Rush { balls: 25, .. }));
+
+        // Beyond the cap, the failed continuation pays out right away
+        engine.cause_lottery();
+        assert_eq!(engine.stock(), 2);
+        assert!(matches!(engine.state(), GameState::Rush { balls: 40, .. }));
+
+        for _ in 0..3 {
+            engine.launch_ball();
+        }
+        assert!(matches!(
+            engine.state(),
+            GameState::Rush {
+                balls: 55,
+                rush_balls: 3,
+                n: 2
+            }
+        ));
+        assert_eq!(engine.stock(), 1);
+
+        for _ in 0..6 {
+            engine.launch_ball();
+        }
+        assert!(matches!(engine.state(), GameState::Normal { balls: 70 }));
+        assert_eq!(engine.stock(), 0);
+        assert_eq!(engine.stats().longest_rush_chain, 3);
+    }
+
+    #[test]
+    fn test_rush_tiers() {
+        let tier = |name: &str, win, incremental_balls| RushTier {
+            name: name.to_string(),
+            weight: 1.0,
+            rush: SlotProbability {
+                win,
+                fake_win: 0.0,
+                fake_lose: 0.0,
+            },
+            rush_continue: SlotProbability {
+                win: 1.0,
+                fake_win: 0.0,
+                fake_lose: 0.0,
+            },
+            incremental_balls,
+            incremental_rush: 7,
+        };
+        let mechanics = Mechanics {
+            tiers: vec![tier("Rush", 0.0, 20), tier("Super Rush", 1.0, 100)],
+            ..Mechanics::default()
+        };
+
+        let mut entered = [0; 2];
+        for seed in 0..20 {
+            let mut engine = engine(1.0, 0.0, 0.0, seed);
+            engine.set_mechanics(mechanics.clone());
+            engine.start();
+            assert!(engine.rush_tier().is_none());
+
+            engine.cause_lottery();
+            let (index, _) = engine.rush_tier().unwrap();
+            entered[index] += 1;
+
+            engine.cause_lottery();
+            if index == 0 {
+                assert!(matches!(
+                    engine.state(),
+                    GameState::Rush {
+                        balls: 30,
+                        rush_balls: 7,
+                        n: 1
+                    }
+                ));
+            } else {
+                assert!(matches!(
+                    engine.state(),
+                    GameState::Rush {
+                        balls: 210,
+                        rush_balls: 14,
+                        n: 2
+                    }
+                ));
+            }
+            // A continuation stays in the tier
+            assert_eq!(engine.rush_tier().unwrap().0, index);
+        }
+        assert!(entered.iter().all(|&count| count > 0));
+    }
+
+    #[test]
+    fn test_rounds() {
+        let mechanics = Mechanics {
+            rounds: Some(Rounds {
+                choices: vec![(4, 1.0), (16, 1.0)],
+                balls_per_round: 10,
+            }),
+            ..Mechanics::default()
+        };
+
+        let mut seen = Vec::new();
+        for seed in 0..20 {
+            let mut engine = engine(1.0, 0.0, 0.0, seed);
+            engine.set_mechanics(mechanics.clone());
+            engine.start();
+            engine.cause_lottery();
+
+            let spin = engine.last_spin().unwrap();
+            let rounds = spin.rounds.unwrap();
+            assert_eq!(spin.balls_awarded, rounds * 10);
+            assert_eq!(balls(engine.state()), 10 + rounds * 10);
+            assert_eq!(engine.take_jackpot_rounds(), Some((rounds, 10)));
+            assert_eq!(engine.take_jackpot_rounds(), None);
+            seen.push(rounds);
+        }
+        assert!(seen.contains(&4) && seen.contains(&16));
+
+        // A losing spin pays no rounds
+        let mut engine = engine(0.0, 0.0, 0.0, 0);
+        engine.set_mechanics(mechanics);
+        engine.start();
+        engine.cause_lottery();
+        assert_eq!(engine.last_spin().unwrap().rounds, None);
+    }
+
+    #[test]
+    fn test_premium_win_continues_the_rush() {
+        // Without the premium, every rush win would fail its continuation
+        let mut engine = engine(1.0, 1.0, 0.0, 0);
+        engine.set_mechanics(Mechanics {
+            premium: Some(1.0),
+            ..Mechanics::default()
+        });
+        engine.start();
+
+        let mut continued = 0;
+        for _ in 0..20 {
+            engine.cause_lottery();
+            let spin = engine.last_spin().unwrap();
+            let premium = matches!(
+                spin.lottery.result,
+                alias::LotteryResult::Win(alias::Win::Premium)
+            );
+            assert_eq!(spin.lottery.premium.is_some(), premium);
+
+            if let (
+                true,
+                alias::GameState::Rush { n: before, .. },
+                alias::GameState::Rush { n: after, .. },
+            ) = (premium, spin.before, spin.after)
+            {
+                assert!(spin.continuation.is_none());
+                assert_eq!(after, before + 1);
+                continued += 1;
+            }
+        }
+
+        assert!(continued > 0);
+        assert_eq!(engine.stats().wins.premium, engine.output().premium_wins);
+    }
+
+    #[test]
+    fn test_launch_cost() {
+        let mut engine = engine(0.0, 0.0, 0.0, 0);
+        engine.set_mechanics(Mechanics {
+            launch: Launch {
+                cost: 4,
+                start_rate: 1.0,
+            },
+            ..Mechanics::default()
+        });
+        engine.start();
+
+        engine.launch_ball();
+        engine.launch_ball();
+        assert!(matches!(engine.state(), GameState::Normal { balls: 2 }));
+
+        // The last launch uses up what is left
+        engine.launch_ball();
+        assert!(matches!(engine.state(), GameState::Uninitialized));
+        assert_eq!(engine.stats().balls_launched, 10);
+    }
+
+    #[test]
+    fn test_lottery_needs_a_start() {
+        let mut engine = engine(0.0, 0.0, 0.0, 0);
+        engine.set_mechanics(Mechanics {
+            launch: Launch {
+                cost: 1,
+                start_rate: 0.5,
+            },
+            ..Mechanics::default()
+        });
+        let _ = engine.run_step_with_command(Command::StartGame);
+
+        let _ = engine.run_step_with_command(Command::CauseLottery);
+        assert_eq!(engine.stats().spins, 0);
+
+        for _ in 0..8 {
+            let _ = engine.run_step_with_command(Command::LaunchBall);
+        }
+        let starts = engine.starts();
+        assert!((1..=HOLD_CAPACITY).contains(&starts));
+
+        for _ in 0..HOLD_CAPACITY + 1 {
+            let _ = engine.run_step_with_command(Command::CauseLottery);
+        }
+        assert_eq!(engine.stats().spins, starts);
+        assert_eq!(engine.starts(), 0);
+    }
+
+    #[test]
+    fn test_stop_conditions() {
+        let mut engine = engine(0.0, 0.0, 0.0, 0);
+        engine.set_mechanics(Mechanics {
+            stop: StopConditions {
+                stop_when_balls_below: Some(8),
+                stop_after_spins: Some(2),
+                ..StopConditions::default()
+            },
+            ..Mechanics::default()
+        });
+
+        // Nothing is checked before the game starts
+        assert!(
+            engine
+                .run_step_with_command(Command::CauseLottery)
+                .is_continue()
+        );
+        let _ = engine.run_step_with_command(Command::StartGame);
+        assert!(
+            engine
+                .run_step_with_command(Command::CauseLottery)
+                .is_continue()
+        );
+        assert!(
+            engine
+                .run_step_with_command(Command::CauseLottery)
+                .is_break()
+        );
+        assert_eq!(engine.stop_reason(), Some(StopReason::Spins));
+
+        // Spins count from the start of the game
+        let _ = engine.run_step_with_command(Command::FinishGame);
+        assert_eq!(engine.stop_reason(), None);
+        let _ = engine.run_step_with_command(Command::StartGame);
+        let _ = engine.run_step_with_command(Command::LaunchBall);
+        let _ = engine.run_step_with_command(Command::LaunchBall);
+        assert!(engine.run_step_with_command(Command::LaunchBall).is_break());
+        assert_eq!(engine.stop_reason(), Some(StopReason::BallsBelow));
+    }
+
+    #[test]
+    fn test_session_summary() {
+        let mut game = engine(1.0, 0.0, 0.0, 0);
+        assert_eq!(game.summary(), None);
+
+        let _ = game.run_step_with_command(Command::StartGame);
+        let _ = game.run_step_with_command(Command::CauseLottery);
+        let won = game.summary().unwrap();
+        assert_eq!(
+            (won.spins, won.rush_entries, won.longest_rush_chain),
+            (1, 1, 1)
+        );
+        assert_eq!(won.peak_balls, balls(game.state()));
+        assert!(!won.finished);
+
+        let _ = game.run_step_with_command(Command::FinishGame);
+        let finished = game.summary().unwrap();
+        assert!(finished.finished);
+        assert_eq!(finished.final_balls, won.peak_balls);
+        assert_eq!(finished.net_balls, (won.peak_balls - 10) as i64);
+
+        // A game that runs out ends with nothing
+        let mut losing = engine(0.0, 0.0, 0.0, 0);
+        let _ = losing.run_step_with_command(Command::StartGame);
+        for _ in 0..10 {
+            let _ = losing.run_step_with_command(Command::LaunchBall);
+        }
+        let ran_out = losing.summary().unwrap();
+        assert!(ran_out.finished);
+        assert_eq!((ran_out.peak_balls, ran_out.final_balls), (10, 0));
+        assert_eq!(ran_out.net_balls, -10);
+    }
+
+    #[test]
+    fn test_hold_queue() {
+        let mut engine = engine(0.0, 0.0, 0.0, 0);
+        engine.set_hold_queue(true);
+
+        let _ = engine.run_step_with_command(Command::CauseLottery);
+        assert_eq!(engine.held(), 0);
+
+        engine.start();
+        for _ in 0..6 {
+            let _ = engine.run_step_with_command(Command::CauseLottery);
+        }
+        assert_eq!(engine.held(), 4);
+        assert!(engine.output.lotteries.is_empty());
+
+        let _ = engine.run_step_with_command(Command::ConsumeHold);
+        assert_eq!(engine.held(), 3);
+        assert_eq!(engine.output.lotteries.len(), 1);
+        assert_eq!(engine.stats().spins, 1);
+
+        let _ = engine.run_step_with_command(Command::FinishGame);
+        assert_eq!(engine.held(), 0);
+
+        engine.set_hold_queue(false);
+        engine.start();
+        let _ = engine.run_step_with_command(Command::CauseLottery);
+        let _ = engine.run_step_with_command(Command::ConsumeHold);
+        assert_eq!(engine.output.lotteries.len(), 2);
+    }
+
+    #[test]
+    fn test_finish_notifies_output() {
+        let mut engine = engine(0.0, 0.0, 0.0, 0);
+
+        engine.finish();
+        assert!(engine.output.finished.is_empty());
+
+        engine.start();
+        assert!(
+            engine
+                .run_step_with_command(Command::FinishGame)
+                .is_continue()
+        );
+        assert_eq!(engine.output.finished.len(), 1);
+        assert!(matches!(engine.state(), GameState::Uninitialized));
+
+        assert!(engine.run_step_with_command(Command::Finish).is_break());
+    }
+
+    #[test]
+    fn test_same_seed_same_game() {
+        let play = |seed| {
+            let mut engine = engine(0.1, 0.5, 0.8, seed);
+            engine.start();
+            for _ in 0..500 {
+                engine.launch_ball();
+                engine.cause_lottery();
+            }
+            (balls(engine.state()), engine.output.lotteries.len())
+        };
+
+        assert_eq!(play(7), play(7));
+        assert_ne!(
+            (0..8).map(play).collect::<Vec<_>>(),
+            vec![play(7); 8],
+            "different seeds should not all produce the same game"
+        );
+    }
+}
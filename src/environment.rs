@@ -0,0 +1,159 @@
+//! # Runtime Environment
+//!
+//! Detects which JavaScript facilities the host provides, so the crate can
+//! run in browsers, workers, pure Node and minimal hosts alike. Features that
+//! depend on a missing facility degrade instead of throwing: the wall clock
+//! falls back to `performance.now()` and then to 0, and unseeded games fall
+//! back to a clock-derived seed without `crypto.getRandomValues`.
+
+use std::cell::OnceCell;
+
+use js_sys::{Function, Reflect};
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::{JsCast, JsValue, prelude::wasm_bindgen};
+
+/// The capabilities of the host environment.
+///
+/// # Fields
+///
+/// * `browser` - A browser main thread, with `window` and `document`
+/// * `worker` - A web worker, with `importScripts`
+/// * `node` - Node.js, with `process.versions.node`
+/// * `date` - `Date.now()` is available for wall-clock timestamps
+/// * `performance` - `performance.now()` is available
+/// * `console` - `console.log()` is available
+/// * `crypto` - `crypto.getRandomValues()` is available for random seeds
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct RuntimeEnvironment {
+    pub browser: bool,
+    pub worker: bool,
+    pub node: bool,
+    pub date: bool,
+    pub performance: bool,
+    pub console: bool,
+    pub crypto: bool,
+}
+
+/// Detects the capabilities of the host environment.
+///
+/// Never throws; facilities that cannot be inspected are reported as missing.
+///
+/// # Example
+///
+/// ```javascript
+/// const env = runtime_environment();
+/// if (!env.crypto) console.warn("unseeded games use a clock-derived seed");
+/// ```
+#[wasm_bindgen]
+pub fn runtime_environment() -> RuntimeEnvironment {
+    current()
+}
+
+thread_local! {
+    static ENVIRONMENT: OnceCell<RuntimeEnvironment> = const { OnceCell::new() };
+}
+
+/// Returns the detected environment, detecting it on first use.
+pub(crate) fn current() -> RuntimeEnvironment {
+    ENVIRONMENT
+        .with(|environment| *environment.get_or_init(|| detect(|path| lookup(path).is_some())))
+}
+
+/// Derives the capabilities from a predicate telling whether a dotted global
+/// path, e.g. `"process.versions.node"`, is defined.
+pub(crate) fn detect(defined: impl Fn(&str) -> bool) -> RuntimeEnvironment {
+    RuntimeEnvironment {
+        browser: defined("window") && defined("document"),
+        worker: defined("importScripts") && !defined("document"),
+        node: defined("process.versions.node"),
+        date: defined("Date.now"),
+        performance: defined("performance.now"),
+        console: defined("console.log"),
+        crypto: defined("crypto.getRandomValues"),
+    }
+}
+
+/// Current time in milliseconds from the best available source.
+pub(crate) fn now() -> f64 {
+    let environment = current();
+
+    if environment.date {
+        js_sys::Date::now()
+    } else if environment.performance {
+        call("performance.now").unwrap_or(0.0)
+    } else {
+        0.0
+    }
+}
+
+/// Writes a warning to `console.warn`; does nothing on hosts without a console.
+pub(crate) fn console_warn(message: &str) {
+    let Some(warn) = lookup("console.warn").and_then(|warn| warn.dyn_into::<Function>().ok())
+    else {
+        return;
+    };
+
+    if let Some(console) = lookup("console") {
+        let _ = warn.call1(&console, &JsValue::from_str(message));
+    }
+}
+
+/// Looks up a dotted path on the global object, `None` if any part is missing.
+fn lookup(path: &str) -> Option<JsValue> {
+    path.split('.')
+        .try_fold(JsValue::from(js_sys::global()), |object, key| {
+            Reflect::get(&object, &JsValue::from_str(key))
+                .ok()
+                .filter(|value| !value.is_undefined() && !value.is_null())
+        })
+}
+
+/// Calls a global method without arguments and returns its numeric result.
+fn call(path: &str) -> Option<f64> {
+    let (object, _) = path.rsplit_once('.')?;
+    let function = lookup(path)?.dyn_into::<Function>().ok()?;
+
+    function.call0(&lookup(object)?).ok()?.as_f64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_node() {
+        let environment = detect(|path| {
+            matches!(
+                path,
+                "process.versions.node" | "Date.now" | "performance.now" | "console.log"
+            )
+        });
+
+        assert_eq!(
+            environment,
+            RuntimeEnvironment {
+                node: true,
+                date: true,
+                performance: true,
+                console: true,
+                ..RuntimeEnvironment::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_detect_browser_and_worker() {
+        let browser = detect(|path| path != "importScripts" && path != "process.versions.node");
+        assert!(browser.browser && !browser.worker && !browser.node);
+
+        let worker = detect(|path| path != "window" && path != "document");
+        assert!(!worker.browser && worker.worker);
+    }
+
+    #[test]
+    fn test_detect_minimal_host() {
+        assert_eq!(detect(|_| false), RuntimeEnvironment::default());
+    }
+}
@@ -0,0 +1,897 @@
+//! # Event Emitter Output
+//!
+//! An alternative to the six positional callbacks of `JsOutput`: a single
+//! callback receives `(eventType, payload)` for every event, so frameworks can
+//! route events generically and new event types do not change any signature.
+//!
+//! For streamed and spectator scenarios, where events cross unreliable worker
+//! or network channels, an event output can number its events and keep them in
+//! a window until the receiver acknowledges them with `WasmGame::ack_through`.
+//!
+//! Events held back by a full window can be bounded with
+//! `JsEventOutput::set_buffer_limit`: when the consumer stalls, the least
+//! important events are dropped first, and critical ones never.
+//!
+//! While the page is in the background (see `WasmGame::set_visible`), events
+//! are coalesced into periodic [`EventSummary`] ticks instead, and one summary
+//! of the whole background period is delivered when the page comes back.
+
+use std::{collections::VecDeque, mem};
+
+use js_sys::Function;
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::{JsValue, prelude::wasm_bindgen};
+
+use crate::{
+    Handlers, JsOutput, PachisloError,
+    alias::{GameState, LotteryResult, Transition},
+    diff::StateDiff,
+    slot::Reels,
+};
+
+/// Event type of state transitions; the payload is a `Transition`.
+pub const TRANSITION: &str = "transition";
+
+/// Event type of state transitions of an output with diff events enabled
+/// (see `JsOutput::set_diff_events`); the payload is a `StateDiff`.
+pub const STATE_DIFF: &str = "state_diff";
+
+/// Event type of finished games; the payload is the final `GameState`.
+pub const FINISH_GAME: &str = "finish_game";
+
+/// Event type of normal-mode lotteries; the payload is a [`LotteryEvent`].
+pub const LOTTERY_NORMAL: &str = "lottery_normal";
+
+/// Event type of rush-mode lotteries; the payload is a [`LotteryEvent`].
+pub const LOTTERY_RUSH: &str = "lottery_rush";
+
+/// Event type of rush continuation lotteries; the payload is a [`LotteryEvent`].
+pub const LOTTERY_RUSH_CONTINUE: &str = "lottery_rush_continue";
+
+/// Event type of reaches, delivered right before the lottery event whose
+/// first slot is a reach; the payload is a [`ReachEvent`].
+pub const REACH: &str = "reach";
+
+/// Event type of the periodic summaries while the page is in the background;
+/// the payload is an [`EventSummary`] of the events since the previous tick.
+pub const BACKGROUND_TICK: &str = "background_tick";
+
+/// Event type of the summary delivered when the page comes back to the
+/// foreground; the payload is an [`EventSummary`] of the whole background period.
+pub const FOREGROUND_SUMMARY: &str = "foreground_summary";
+
+/// Event type of the latency echo after a command executed with
+/// `WasmGame::run_step_with_command_stamped`; the payload is a `CommandLatency`.
+pub const COMMAND_LATENCY: &str = "command_latency";
+
+/// Event type of a lottery added to the hold queue; the payload is a `HoldEvent`.
+pub const HOLD_ADDED: &str = "hold_added";
+
+/// Event type of a held lottery being drawn; the payload is a `HoldEvent`.
+pub const HOLD_CONSUMED: &str = "hold_consumed";
+
+/// Event type of a rush win being stocked; the payload is a `StockEvent`.
+pub const STOCK_GAINED: &str = "stock_gained";
+
+/// Event type of a stocked win being paid out; the payload is a `StockEvent`.
+pub const STOCK_RELEASED: &str = "stock_released";
+
+/// Event type of a tiered rush being entered; the payload is a `RushTierEvent`.
+pub const RUSH_TIER_ENTERED: &str = "rush_tier_entered";
+
+/// Event type of a rush continuation presented as a battle; the payload is a
+/// `BattleEvent`.
+pub const BATTLE: &str = "battle";
+
+/// Event type of a jackpot round starting; the payload is a `RoundEvent`.
+pub const ROUND_STARTED: &str = "round_started";
+
+/// Event type of a jackpot round finishing; the payload is a `RoundEvent`.
+pub const ROUND_FINISHED: &str = "round_finished";
+
+/// Event type of a streak reaching a threshold; the payload is a `StreakEvent`.
+pub const STREAK: &str = "streak";
+
+/// Event type of a game ending; the payload is a `SessionSummary`.
+pub const SESSION_SUMMARY: &str = "session_summary";
+
+/// Event type of a fake outcome as first shown, with staged reveals enabled;
+/// the payload is a `RevealEvent`.
+pub const APPARENT_RESULT: &str = "apparent_result";
+
+/// Event type of a fake outcome being revealed; the payload is a `RevealEvent`.
+pub const TRUE_RESULT: &str = "true_result";
+
+/// Event type of the current state, passed to a new listener of
+/// `WasmGame::subscribe`; the payload is a `GameState`.
+pub const STATE: &str = "state";
+
+/// Payload of the lottery events.
+///
+/// # Fields
+///
+/// * `result` - The lottery result
+/// * `slot` - The slot shown first, and for fake results the slot revealed
+///   afterwards; symbol names instead of symbols if the output names them
+#[derive(Clone, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct LotteryEvent {
+    pub result: LotteryResult,
+    pub slot: (Reels, Option<Reels>),
+}
+
+/// Payload of the reach event.
+///
+/// # Fields
+///
+/// * `reels` - The first two reels, showing the same symbol; the remaining
+///   reels follow with the lottery event
+#[derive(Clone, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ReachEvent {
+    pub reels: Reels,
+}
+
+/// Condensed record of the events coalesced while the page was in the background.
+///
+/// # Fields
+///
+/// * `since` - Time the summarized period started, in milliseconds
+/// * `until` - Time of the last summarized event, or of the summary itself
+/// * `transitions` - Number of state transitions
+/// * `lotteries` - Number of lotteries of any kind
+/// * `wins` - Number of lotteries won, including fake wins
+/// * `rush_entries` - Number of transitions into rush mode
+/// * `finished_games` - Number of finished games
+/// * `state` - The latest game state, `None` if no transition happened
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct EventSummary {
+    pub since: f64,
+    pub until: f64,
+    pub transitions: usize,
+    pub lotteries: usize,
+    pub wins: usize,
+    pub rush_entries: usize,
+    pub finished_games: usize,
+    pub state: Option<GameState>,
+}
+
+/// Which event a full event buffer drops.
+///
+/// Either way only events of the lowest priority in the buffer are dropped:
+/// first per-ball transitions and latency echoes, then other transitions,
+/// lotteries and background ticks. Finished games, mode changes such as rush
+/// entries and foreground summaries are critical and never dropped.
+///
+/// # Variants
+///
+/// * `DropOldest` - Drop the oldest event of the lowest priority
+/// * `DropNewest` - Drop the newest event of the lowest priority
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum DropPolicy {
+    DropOldest,
+    DropNewest,
+}
+
+/// How important an event is to a consumer that cannot keep up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Priority {
+    /// Routine updates that the next event supersedes
+    Low,
+    Normal,
+    /// Never dropped
+    Critical,
+}
+
+impl Priority {
+    /// The priority of events other than transitions.
+    pub(crate) fn of(event_type: &str) -> Self {
+        match event_type {
+            FINISH_GAME | SESSION_SUMMARY | FOREGROUND_SUMMARY => Priority::Critical,
+            COMMAND_LATENCY => Priority::Low,
+            _ => Priority::Normal,
+        }
+    }
+
+    /// Mode changes are critical; transitions that only spend balls, like
+    /// launches, are low.
+    pub(crate) fn of_transition(transition: Transition) -> Self {
+        let diff = StateDiff::from(transition);
+
+        if diff.mode.is_some() {
+            Priority::Critical
+        } else if diff.n.is_none()
+            && diff.balls.unwrap_or(0) <= 0
+            && diff.rush_balls.unwrap_or(0) <= 0
+        {
+            Priority::Low
+        } else {
+            Priority::Normal
+        }
+    }
+}
+
+/// An event on its way to the handlers.
+#[cfg(feature = "chaos")]
+pub(crate) type Outgoing = (&'static str, Priority, JsValue);
+
+/// Output that delivers every event to a single callback.
+///
+/// Pass it to a game through [`JsOutput::from_events`].
+#[wasm_bindgen]
+pub struct JsEventOutput {
+    context: JsValue,
+    emit: Function,
+    acks: Option<AckWindow<(&'static str, JsValue)>>,
+}
+
+#[wasm_bindgen]
+impl JsEventOutput {
+    /// Creates an event output.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - JavaScript context object passed as `this` to the callback
+    /// * `emit` - Called with `(eventType, payload)` for every event; event types
+    ///   are `"transition"`, `"finish_game"`, `"lottery_normal"`, `"lottery_rush"`
+    ///   and `"lottery_rush_continue"` (each preceded by `"reach"` if its
+    ///   first slot is a reach), plus `"state_diff"` replacing
+    ///   `"transition"` with diff events, `"background_tick"` and
+    ///   `"foreground_summary"` while throttled, `"command_latency"` for
+    ///   stamped commands, `"hold_added"` and `"hold_consumed"` with the
+    ///   hold queue, `"stock_gained"` and `"stock_released"` with a stock,
+    ///   `"rush_tier_entered"` with rush tiers, `"battle"` with battles,
+    ///   `"round_started"` and `"round_finished"` with rounds and
+    ///   `"apparent_result"` and `"true_result"` with staged reveals. See [`JsEventOutput::set_ack_window`] and
+    ///   `WasmGame::set_label` for the optional `seq` and `label` arguments
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const events = new JsEventOutput(this, (type, payload) => emitter.emit(type, payload));
+    /// const game = new WasmGame(new JsInput(), JsOutput.from_events(events), config);
+    /// ```
+    #[wasm_bindgen(constructor)]
+    pub fn new(context: JsValue, emit: Function) -> Self {
+        JsEventOutput {
+            context,
+            emit,
+            acks: None,
+        }
+    }
+
+    /// Numbers events and delivers them through an acknowledgement window.
+    ///
+    /// The callback then receives `(eventType, payload, seq)`. At most `window`
+    /// events are unacknowledged at a time; later events are held back until
+    /// `WasmGame::ack_through` acknowledges earlier ones. If the oldest
+    /// unacknowledged event is older than `retransmit_after` milliseconds, the
+    /// whole unacknowledged range is delivered again. This is checked whenever
+    /// an event is emitted or acknowledged.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if `window` is 0 or `retransmit_after` is not positive.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const events = new JsEventOutput(this, (type, payload, seq) => port.postMessage({ type, payload, seq }));
+    /// events.set_ack_window(32, 500);
+    /// const game = new WasmGame(new JsInput(), JsOutput.from_events(events), config);
+    /// port.onmessage = ({ data }) => game.ack_through(data.ack);
+    /// ```
+    #[wasm_bindgen]
+    pub fn set_ack_window(
+        &mut self,
+        window: usize,
+        retransmit_after: f64,
+    ) -> Result<(), PachisloError> {
+        self.acks = Some(AckWindow::new(window, retransmit_after).map_err(|message| {
+            PachisloError::InvalidArguments {
+                message: message.to_string(),
+            }
+        })?);
+
+        Ok(())
+    }
+
+    /// Bounds the events held back by a full ack window.
+    ///
+    /// Beyond `capacity` held events, events are dropped according to
+    /// `policy`, lowest priority first; critical events are kept even beyond
+    /// `capacity`. Dropped events never get a sequence number, so the
+    /// numbering stays gapless. `WasmGame::dropped_events` counts them.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if `capacity` is 0 or no ack window was set with
+    /// [`JsEventOutput::set_ack_window`] before.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// events.set_ack_window(32, 500);
+    /// events.set_buffer_limit(256, "DropOldest");
+    /// ```
+    #[wasm_bindgen]
+    pub fn set_buffer_limit(
+        &mut self,
+        capacity: usize,
+        policy: DropPolicy,
+    ) -> Result<(), PachisloError> {
+        let Some(acks) = &mut self.acks else {
+            return Err(PachisloError::NoAckWindow);
+        };
+        acks.set_limit(capacity, policy)
+            .map_err(|message| PachisloError::InvalidArguments {
+                message: message.to_string(),
+            })?;
+
+        Ok(())
+    }
+}
+
+#[wasm_bindgen]
+impl JsOutput {
+    /// Creates an output that delivers every event to a [`JsEventOutput`].
+    ///
+    /// The slot layout can be changed afterwards like for any other output.
+    #[wasm_bindgen]
+    pub fn from_events(output: JsEventOutput) -> JsOutput {
+        JsOutput::with_handlers(
+            output.context,
+            Handlers::Emitter {
+                emit: output.emit,
+                acks: output.acks,
+            },
+        )
+    }
+}
+
+impl JsOutput {
+    /// Acknowledges every event up to and including `seq`, then delivers the
+    /// events that now fit into the window and any due retransmissions.
+    pub(crate) fn ack_through(&mut self, seq: u32, now: f64) -> Result<(), PachisloError> {
+        let Handlers::Emitter {
+            emit,
+            acks: Some(acks),
+        } = &mut self.handlers
+        else {
+            return Err(PachisloError::NoAckWindow);
+        };
+
+        for (seq, (event_type, payload)) in acks.ack_through(seq, now) {
+            let returned = deliver(
+                emit,
+                &self.context,
+                event_type,
+                &payload,
+                Some(seq),
+                self.label.as_deref(),
+            );
+            crate::settle(&mut None, &mut self.failure, returned);
+        }
+
+        self.take_failure()
+    }
+
+    /// Switches between direct delivery and background throttling.
+    ///
+    /// Coming back to the foreground delivers the summary of the whole
+    /// background period. Repeating the current visibility only updates the
+    /// tick interval.
+    pub(crate) fn set_visible(
+        &mut self,
+        visible: bool,
+        interval: f64,
+        now: f64,
+    ) -> Result<(), PachisloError> {
+        if !self.is_event_output() {
+            return Err(PachisloError::NotAnEventOutput);
+        }
+
+        match (visible, &mut self.throttle) {
+            (true, throttle) => {
+                if let Some(throttle) = throttle.take() {
+                    let summary = throttle.finish(now);
+                    self.emit(
+                        FOREGROUND_SUMMARY,
+                        serde_wasm_bindgen::to_value(&summary).unwrap(),
+                    );
+                }
+            }
+            (false, Some(throttle)) => throttle.set_interval(interval)?,
+            (false, throttle) => *throttle = Some(Throttle::new(interval, now)?),
+        }
+
+        self.take_failure()
+    }
+
+    /// Records an event if the output is throttled, delivering a tick when
+    /// one is due. Returns `false` if the event should be delivered as usual.
+    pub(crate) fn throttled(&mut self, observed: Observed) -> bool {
+        let Some(throttle) = &mut self.throttle else {
+            return false;
+        };
+
+        let now = crate::read_clock(&self.clock, &mut self.failure);
+        if let Some(tick) = throttle.record(observed, now) {
+            self.emit(
+                BACKGROUND_TICK,
+                serde_wasm_bindgen::to_value(&tick).unwrap(),
+            );
+        }
+
+        true
+    }
+
+    /// Whether the output was created from a [`JsEventOutput`].
+    pub(crate) fn is_event_output(&self) -> bool {
+        matches!(self.handlers, Handlers::Emitter { .. })
+    }
+
+    /// Number of events that were not acknowledged yet; 0 without an ack window.
+    pub(crate) fn unacked_events(&self) -> usize {
+        match &self.handlers {
+            Handlers::Emitter {
+                acks: Some(acks), ..
+            } => acks.unacked(),
+            _ => 0,
+        }
+    }
+
+    /// Number of events dropped by a full buffer; 0 without an ack window.
+    pub(crate) fn dropped_events(&self) -> usize {
+        match &self.handlers {
+            Handlers::Emitter {
+                acks: Some(acks), ..
+            } => acks.dropped(),
+            _ => 0,
+        }
+    }
+}
+
+/// Calls an event callback with `(eventType, payload)`, followed by the
+/// sequence number if events are numbered and the game's label if it has one.
+///
+/// Returns what the callback returned, or what it threw.
+pub(crate) fn deliver(
+    emit: &Function,
+    context: &JsValue,
+    event_type: &str,
+    payload: &JsValue,
+    seq: Option<u32>,
+    label: Option<&str>,
+) -> Result<JsValue, JsValue> {
+    let mut args = vec![JsValue::from_str(event_type), payload.clone()];
+    if seq.is_some() || label.is_some() {
+        args.push(seq.map_or(JsValue::UNDEFINED, JsValue::from));
+    }
+    if let Some(label) = label {
+        args.push(JsValue::from_str(label));
+    }
+
+    emit.apply(context, &args.into_iter().collect())
+}
+
+/// Packs an event into `{ type, payload }`, as returned by batched steps and
+/// passed to subscribers.
+pub(crate) fn batched(event_type: &str, payload: &JsValue) -> JsValue {
+    let event = js_sys::Object::new();
+    js_sys::Reflect::set(&event, &"type".into(), &event_type.into()).unwrap();
+    js_sys::Reflect::set(&event, &"payload".into(), payload).unwrap();
+    event.into()
+}
+
+/// An event as seen by a [`Throttle`].
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Observed {
+    Transition(Transition),
+    FinishGame(GameState),
+    Lottery(LotteryResult),
+}
+
+impl EventSummary {
+    fn new(since: f64) -> Self {
+        EventSummary {
+            since,
+            until: since,
+            ..EventSummary::default()
+        }
+    }
+
+    fn record(&mut self, observed: Observed, now: f64) {
+        self.until = now;
+
+        match observed {
+            Observed::Transition(transition) => {
+                self.transitions += 1;
+                if matches!(transition.after, GameState::Rush { .. })
+                    && !matches!(transition.before, Some(GameState::Rush { .. }))
+                {
+                    self.rush_entries += 1;
+                }
+                self.state = Some(transition.after);
+            }
+            Observed::FinishGame(state) => {
+                self.finished_games += 1;
+                self.state = Some(state);
+            }
+            Observed::Lottery(result) => {
+                self.lotteries += 1;
+                if result.is_win() {
+                    self.wins += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Coalesces events into periodic ticks while the page is in the background.
+#[derive(Clone, Debug)]
+pub(crate) struct Throttle {
+    interval: f64,
+    tick: EventSummary,
+    total: EventSummary,
+}
+
+impl Throttle {
+    pub(crate) fn new(interval: f64, now: f64) -> Result<Self, PachisloError> {
+        check_interval(interval)?;
+
+        Ok(Throttle {
+            interval,
+            tick: EventSummary::new(now),
+            total: EventSummary::new(now),
+        })
+    }
+
+    fn set_interval(&mut self, interval: f64) -> Result<(), PachisloError> {
+        check_interval(interval)?;
+        self.interval = interval;
+
+        Ok(())
+    }
+
+    /// Records an event and returns the tick summary if the interval elapsed.
+    ///
+    /// Ticks are only checked for when events happen, so a game that is not
+    /// played in the background delivers none.
+    pub(crate) fn record(&mut self, observed: Observed, now: f64) -> Option<EventSummary> {
+        self.tick.record(observed, now);
+        self.total.record(observed, now);
+
+        (now - self.tick.since >= self.interval)
+            .then(|| mem::replace(&mut self.tick, EventSummary::new(now)))
+    }
+
+    /// Returns the summary of the whole background period.
+    pub(crate) fn finish(self, now: f64) -> EventSummary {
+        EventSummary {
+            until: now,
+            ..self.total
+        }
+    }
+}
+
+fn check_interval(interval: f64) -> Result<(), PachisloError> {
+    if interval.is_finite() && interval > 0.0 {
+        Ok(())
+    } else {
+        Err(PachisloError::InvalidArguments {
+            message: format!(
+                "the background tick interval must be a positive number of milliseconds, got {interval}"
+            ),
+        })
+    }
+}
+
+/// Sequence numbers, window and retransmission bookkeeping for acknowledged events.
+#[derive(Clone, Debug)]
+pub(crate) struct AckWindow<T> {
+    window: usize,
+    retransmit_after: f64,
+    next_seq: u32,
+    in_flight: VecDeque<InFlight<T>>,
+    /// Events waiting for room in the window; numbered once they are sent
+    held: VecDeque<(Priority, T)>,
+    limit: Option<(usize, DropPolicy)>,
+    dropped: usize,
+}
+
+/// An event that was delivered but not acknowledged yet.
+#[derive(Clone, Debug)]
+struct InFlight<T> {
+    seq: u32,
+    event: T,
+    sent_at: f64,
+}
+
+impl<T: Clone> AckWindow<T> {
+    pub(crate) fn new(window: usize, retransmit_after: f64) -> Result<Self, &'static str> {
+        if window == 0 {
+            return Err("the ack window must hold at least 1 event");
+        }
+        if retransmit_after.is_nan() || retransmit_after <= 0.0 {
+            return Err("the retransmission timeout must be positive");
+        }
+
+        Ok(AckWindow {
+            window,
+            retransmit_after,
+            next_seq: 0,
+            in_flight: VecDeque::new(),
+            held: VecDeque::new(),
+            limit: None,
+            dropped: 0,
+        })
+    }
+
+    /// Bounds the held events to `capacity`.
+    pub(crate) fn set_limit(
+        &mut self,
+        capacity: usize,
+        policy: DropPolicy,
+    ) -> Result<(), &'static str> {
+        if capacity == 0 {
+            return Err("the event buffer must hold at least 1 event");
+        }
+        self.limit = Some((capacity, policy));
+        self.shed();
+
+        Ok(())
+    }
+
+    /// Queues a new event and returns the events to deliver now.
+    pub(crate) fn push(&mut self, event: T, priority: Priority, now: f64) -> Vec<(u32, T)> {
+        self.held.push_back((priority, event));
+
+        let deliveries = self.deliveries(now);
+        self.shed();
+
+        deliveries
+    }
+
+    /// Acknowledges every event up to and including `seq` and returns the
+    /// events to deliver now.
+    pub(crate) fn ack_through(&mut self, seq: u32, now: f64) -> Vec<(u32, T)> {
+        while self.in_flight.front().is_some_and(|entry| entry.seq <= seq) {
+            self.in_flight.pop_front();
+        }
+
+        self.deliveries(now)
+    }
+
+    /// Number of events that were not acknowledged yet, including held ones.
+    pub(crate) fn unacked(&self) -> usize {
+        self.in_flight.len() + self.held.len()
+    }
+
+    /// Number of held events dropped because the buffer was full.
+    pub(crate) fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Drops held events beyond the limit, lowest priority first.
+    fn shed(&mut self) {
+        let Some((capacity, policy)) = self.limit else {
+            return;
+        };
+
+        while self.held.len() > capacity {
+            let Some(lowest) = self
+                .held
+                .iter()
+                .map(|(priority, _)| *priority)
+                .filter(|priority| *priority < Priority::Critical)
+                .min()
+            else {
+                // Only critical events are held
+                return;
+            };
+
+            let mut matching = self.held.iter().map(|(priority, _)| *priority == lowest);
+            let index = match policy {
+                DropPolicy::DropOldest => matching.position(|matches| matches),
+                DropPolicy::DropNewest => matching.rposition(|matches| matches),
+            };
+            self.held.remove(index.unwrap());
+            self.dropped += 1;
+        }
+    }
+
+    /// Retransmits the unacknowledged range if it timed out, then fills the window.
+    fn deliveries(&mut self, now: f64) -> Vec<(u32, T)> {
+        let mut deliveries = Vec::new();
+
+        if self
+            .in_flight
+            .front()
+            .is_some_and(|entry| now - entry.sent_at >= self.retransmit_after)
+        {
+            for entry in &mut self.in_flight {
+                entry.sent_at = now;
+                deliveries.push((entry.seq, entry.event.clone()));
+            }
+        }
+
+        while self.in_flight.len() < self.window
+            && let Some((_, event)) = self.held.pop_front()
+        {
+            let seq = self.next_seq;
+            self.next_seq += 1;
+            deliveries.push((seq, event.clone()));
+            self.in_flight.push_back(InFlight {
+                seq,
+                event,
+                sent_at: now,
+            });
+        }
+
+        deliveries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alias::Win;
+
+    fn seqs<T>(deliveries: &[(u32, T)]) -> Vec<u32> {
+        deliveries.iter().map(|(seq, _)| *seq).collect()
+    }
+
+    fn transition(before: GameState, after: GameState) -> Observed {
+        Observed::Transition(Transition {
+            before: Some(before),
+            after,
+        })
+    }
+
+    #[test]
+    fn test_throttle_ticks() {
+        let mut throttle = Throttle::new(1000.0, 0.0).unwrap();
+        let normal = GameState::Normal { balls: 10 };
+        let rush = GameState::Rush {
+            balls: 10,
+            rush_balls: 5,
+            n: 1,
+        };
+
+        assert!(throttle.record(transition(normal, normal), 10.0).is_none());
+        assert!(
+            throttle
+                .record(Observed::Lottery(LotteryResult::Win(Win::Default)), 500.0)
+                .is_none()
+        );
+
+        let tick = throttle.record(transition(normal, rush), 1000.0).unwrap();
+        assert_eq!((tick.since, tick.until), (0.0, 1000.0));
+        assert_eq!((tick.transitions, tick.lotteries, tick.wins), (2, 1, 1));
+        assert_eq!(tick.rush_entries, 1);
+
+        // Staying in rush is not another entry
+        let tick = throttle.record(transition(rush, rush), 2500.0).unwrap();
+        assert_eq!(
+            (tick.since, tick.transitions, tick.rush_entries),
+            (1000.0, 1, 0)
+        );
+
+        let summary = throttle.finish(3000.0);
+        assert_eq!((summary.since, summary.until), (0.0, 3000.0));
+        assert_eq!((summary.transitions, summary.rush_entries), (3, 1));
+        assert!(matches!(summary.state, Some(GameState::Rush { .. })));
+    }
+
+    #[test]
+    fn test_invalid_throttle_interval() {
+        assert!(Throttle::new(0.0, 0.0).is_err());
+        assert!(Throttle::new(f64::INFINITY, 0.0).is_err());
+        assert!(Throttle::new(f64::NAN, 0.0).is_err());
+    }
+
+    #[test]
+    fn test_invalid_windows() {
+        assert!(AckWindow::<()>::new(0, 100.0).is_err());
+        assert!(AckWindow::<()>::new(4, 0.0).is_err());
+        assert!(AckWindow::<()>::new(4, f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_window_holds_back_events() {
+        let mut acks = AckWindow::new(2, 1000.0).unwrap();
+
+        assert_eq!(acks.push("a", Priority::Normal, 0.0), [(0, "a")]);
+        assert_eq!(acks.push("b", Priority::Normal, 0.0), [(1, "b")]);
+        assert!(acks.push("c", Priority::Normal, 0.0).is_empty());
+        assert_eq!(acks.unacked(), 3);
+
+        assert_eq!(acks.ack_through(0, 0.0), [(2, "c")]);
+        assert_eq!(acks.ack_through(2, 0.0), []);
+        assert_eq!(acks.unacked(), 0);
+    }
+
+    #[test]
+    fn test_unacked_range_is_retransmitted() {
+        let mut acks = AckWindow::new(8, 100.0).unwrap();
+
+        acks.push("a", Priority::Normal, 0.0);
+        acks.push("b", Priority::Normal, 50.0);
+
+        // Not timed out yet
+        assert_eq!(seqs(&acks.push("c", Priority::Normal, 99.0)), [2]);
+
+        // The oldest event timed out, so the whole range is sent again
+        assert_eq!(seqs(&acks.push("d", Priority::Normal, 100.0)), [0, 1, 2, 3]);
+
+        // Acknowledging part of the range leaves the rest in flight
+        assert!(acks.ack_through(1, 150.0).is_empty());
+        assert_eq!(seqs(&acks.ack_through(1, 200.0)), [2, 3]);
+    }
+
+    #[test]
+    fn test_full_buffer_drops_lowest_priority_first() {
+        let mut acks = AckWindow::new(1, 1000.0).unwrap();
+        acks.set_limit(2, DropPolicy::DropOldest).unwrap();
+
+        acks.push("sent", Priority::Low, 0.0);
+        acks.push("finish", Priority::Critical, 0.0);
+        acks.push("launch 1", Priority::Low, 0.0);
+        acks.push("lottery", Priority::Normal, 0.0);
+        acks.push("launch 2", Priority::Low, 0.0);
+        assert_eq!(acks.dropped(), 2);
+
+        assert_eq!(acks.ack_through(0, 0.0), [(1, "finish")]);
+        assert_eq!(acks.ack_through(1, 0.0), [(2, "lottery")]);
+
+        // Critical events exceed the capacity rather than being dropped
+        let mut newest = AckWindow::new(1, 1000.0).unwrap();
+        newest.set_limit(1, DropPolicy::DropNewest).unwrap();
+        newest.push("sent", Priority::Normal, 0.0);
+        newest.push("start", Priority::Critical, 0.0);
+        newest.push("end", Priority::Critical, 0.0);
+        newest.push("lottery 1", Priority::Normal, 0.0);
+        newest.push("lottery 2", Priority::Normal, 0.0);
+        assert_eq!(newest.unacked(), 3);
+        assert_eq!(newest.dropped(), 2);
+
+        assert!(
+            AckWindow::<()>::new(1, 1000.0)
+                .unwrap()
+                .set_limit(0, DropPolicy::DropOldest)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_transition_priorities() {
+        let transition = |before, after| Transition {
+            before: Some(before),
+            after,
+        };
+        let normal = |balls| GameState::Normal { balls };
+
+        assert_eq!(
+            Priority::of_transition(transition(normal(10), normal(9))),
+            Priority::Low
+        );
+        assert_eq!(
+            Priority::of_transition(transition(normal(9), normal(24))),
+            Priority::Normal
+        );
+        let rush = GameState::Rush {
+            balls: 24,
+            rush_balls: 3,
+            n: 1,
+        };
+        assert_eq!(
+            Priority::of_transition(transition(normal(9), rush)),
+            Priority::Critical
+        );
+        assert_eq!(Priority::of(FINISH_GAME), Priority::Critical);
+    }
+}
@@ -0,0 +1,103 @@
+//! # Spin Explanations
+//!
+//! A breakdown of how the last lottery was resolved, for educational apps
+//! that show why a spin won or lost: the probabilities that applied, every
+//! value rolled against them, the rush continuation math and the payout.
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use crate::alias::{GameState, LotteryResult};
+
+/// How the last spin was resolved.
+///
+/// # Fields
+///
+/// * `mode` - The lottery table that applied
+/// * `lottery` - The spin itself
+/// * `ceiling` - Whether the ceiling guaranteed the win, see `Config::with_ceiling`
+/// * `continuation` - The rush continuation, only for spins won in rush mode
+/// * `before` - State before the spin
+/// * `after` - State after the spin
+/// * `balls_awarded` - Balls paid out by the spin
+/// * `rush_balls_awarded` - Rush balls paid out by the spin
+/// * `rounds` - Rounds of the jackpot paid out by the spin, see
+///   `Config::with_rounds`
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct SpinExplanation {
+    pub mode: SpinMode,
+    pub lottery: LotteryExplanation,
+    pub ceiling: bool,
+    pub continuation: Option<ContinuationExplanation>,
+    pub before: GameState,
+    pub after: GameState,
+    pub balls_awarded: usize,
+    pub rush_balls_awarded: usize,
+    pub rounds: Option<usize>,
+}
+
+/// The lottery table a spin was drawn from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum SpinMode {
+    Normal,
+    Rush,
+    Jitan,
+}
+
+/// A single lottery: first a roll for the win, then one deciding between a
+/// plain and a fake result.
+///
+/// # Fields
+///
+/// * `win` - Roll against the win probability
+/// * `detail` - Roll against `fake_win` after a win, or `fake_lose` after a loss
+/// * `premium` - Roll against the premium probability after a plain win,
+///   see `Config::with_premium`; `None` if not rolled
+/// * `result` - The resulting lottery result
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct LotteryExplanation {
+    pub win: Roll,
+    pub detail: Roll,
+    pub premium: Option<Roll>,
+    pub result: LotteryResult,
+}
+
+/// A random value compared with a probability.
+///
+/// # Fields
+///
+/// * `probability` - The probability in effect
+/// * `value` - The rolled value in 0.0 to 1.0; `None` if nothing was rolled
+///   because the probability is 1.0 or the result was scripted, see
+///   `WasmGame::set_outcome_script`
+/// * `passed` - Whether the roll passed, i.e. `value < probability`
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct Roll {
+    pub probability: f64,
+    pub value: Option<f64>,
+    pub passed: bool,
+}
+
+/// The rush continuation lottery after a spin won in rush mode.
+///
+/// # Fields
+///
+/// * `n` - Consecutive rush count passed to `rush_continue_fn`
+/// * `base_win` - The configured `rush_continue.win`
+/// * `multiplier` - What `rush_continue_fn(n)` returned
+/// * `effective_win` - `base_win * multiplier`, the probability rolled against
+/// * `lottery` - The continuation lottery; `None` if it was skipped because
+///   `effective_win` exceeds 1.0
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ContinuationExplanation {
+    pub n: usize,
+    pub base_win: f64,
+    pub multiplier: f64,
+    pub effective_win: f64,
+    pub lottery: Option<LotteryExplanation>,
+}
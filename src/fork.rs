@@ -0,0 +1,82 @@
+//! # Forks
+//!
+//! A fork is an independent copy of a running game, so a UI can explore
+//! "what happens if I keep playing" branches, or run a projection in the
+//! background while the player continues on the original.
+//!
+//! Without a new seed, a fork continues the random streams of its original:
+//! the same commands draw the same outcomes on both. With a seed, the fork
+//! draws its own future from the current state on.
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    JsOutput, PachisloError, Session, SessionCell, WasmGame, rng::GameRng, undo::UndoStack,
+};
+
+#[wasm_bindgen]
+impl WasmGame {
+    /// Creates an independent copy of the game in its current state.
+    ///
+    /// The copy has the state, statistics, counters, configuration, command
+    /// rules and their usage, streak thresholds, history and recorded ball
+    /// counts of this game, and a pending fake outcome stays pending on both.
+    /// It starts without a ghost recording, undo checkpoints or chaos faults.
+    /// Commands sent to either game afterwards do not affect the other.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - Reseeds the copy like [`WasmGame::set_seed`]; without one,
+    ///   the copy draws the same outcomes as this game would
+    /// * `output` - Output of the copy; without one, the copy reports to a
+    ///   clone of this game's output, i.e. to the same callbacks
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const branch = game.fork(BigInt(Date.now()), JsOutput.from_events(quietEvents));
+    /// branch.run_step_with_command_args("LaunchBall", { count: 1000 });
+    /// projection.textContent = branch.balls();
+    /// ```
+    #[wasm_bindgen]
+    pub fn fork(
+        &self,
+        seed: Option<u64>,
+        output: Option<JsOutput>,
+    ) -> Result<WasmGame, PachisloError> {
+        let session = self.session()?;
+
+        let original = session.engine.output();
+        let mut output = output.unwrap_or_else(|| original.clone());
+        output.set_slot_rng(original.slot_rng().clone());
+        let mut engine = session.engine.fork(session.config.clone().into(), output);
+
+        if let Some(seed) = seed {
+            let mut rng = GameRng::from_seed(seed);
+            engine.output_mut().set_slot_rng(rng.split());
+            engine.set_rng(rng);
+        }
+
+        Ok(WasmGame {
+            game: SessionCell::new(Session {
+                engine,
+                config: session.config.clone(),
+                ghost: None,
+                history: session.history.clone(),
+                balls_series: session.balls_series.clone(),
+                streak_alerts: session.streak_alerts.clone(),
+                rules: session.rules.clone(),
+                clock: session.clock.clone(),
+                reveal: session.reveal,
+                seeded: session.seeded || seed.is_some(),
+                undo: UndoStack::default(),
+                #[cfg(feature = "chaos")]
+                chaos: None,
+            }),
+        })
+    }
+}
@@ -0,0 +1,238 @@
+//! # Ghost Data
+//!
+//! A ghost is the input track of a game: which commands were sent and when,
+//! without any of their outcomes. Replaying a ghost against a different seed or
+//! configuration produces a separate outcome track, so apps can show "your
+//! inputs on a different machine".
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    PachisloError, alias,
+    alias::Config,
+    check_command, convert_string_to_command,
+    engine::{Engine, Mechanics, NullOutput},
+    rng::GameRng,
+};
+
+/// A single recorded input.
+///
+/// # Fields
+///
+/// * `time` - Milliseconds since the recording started
+/// * `command` - The command string that was executed
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct GhostInput {
+    pub time: f64,
+    pub command: String,
+}
+
+/// The input track of a game; serializable so apps can store and share it.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct Ghost {
+    pub inputs: Vec<GhostInput>,
+}
+
+/// The outcome track produced by replaying a ghost.
+///
+/// # Fields
+///
+/// * `outcomes` - The state after each input, aligned with `Ghost::inputs`
+/// * `skipped` - Inputs that were not valid in the replayed game, e.g. launches
+///   after it already ran out of balls; they leave the state unchanged
+/// * `final_state` - The state after the last input
+#[derive(Clone, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct GhostReplay {
+    pub outcomes: Vec<alias::GameState>,
+    pub skipped: usize,
+    pub final_state: alias::GameState,
+}
+
+/// Records the input track of a running game.
+#[derive(Debug)]
+pub(crate) struct GhostRecorder {
+    origin: f64,
+    ghost: Ghost,
+}
+
+impl GhostRecorder {
+    pub(crate) fn new(now: f64) -> Self {
+        GhostRecorder {
+            origin: now,
+            ghost: Ghost::default(),
+        }
+    }
+
+    pub(crate) fn record(&mut self, now: f64, command: &str) {
+        self.ghost.inputs.push(GhostInput {
+            time: now - self.origin,
+            command: command.to_string(),
+        });
+    }
+
+    pub(crate) fn finish(self) -> Ghost {
+        self.ghost
+    }
+}
+
+/// Replays a ghost headlessly against a configuration and seed.
+///
+/// No output callbacks are called; the outcome of every input is returned
+/// instead, so it can be compared with the original game.
+///
+/// # Arguments
+///
+/// * `ghost` - The input track to replay
+/// * `config` - Configuration of the machine to replay on
+/// * `seed` - Seed of the machine to replay on
+///
+/// # Errors
+///
+/// Throws an `Error` describing every problem if the configuration is
+/// invalid.
+///
+/// # Example
+///
+/// ```javascript
+/// const ghost = game.stop_ghost_recording();
+/// const replay = replay_ghost(ghost, otherConfig, 7n);
+/// console.log(replay.final_state);
+/// ```
+#[wasm_bindgen]
+pub fn replay_ghost(
+    ghost: Ghost,
+    config: &Config,
+    seed: u64,
+) -> Result<GhostReplay, PachisloError> {
+    config.validate()?;
+
+    Ok(replay(
+        &ghost,
+        config.clone().into(),
+        config.mechanics(),
+        seed,
+    ))
+}
+
+/// Core of [`replay_ghost`], independent of the JavaScript callback.
+pub(crate) fn replay<F>(
+    ghost: &Ghost,
+    config: pachislo::config::Config<F>,
+    mechanics: Mechanics,
+    seed: u64,
+) -> GhostReplay
+where
+    F: FnMut(usize) -> f64,
+{
+    let mut engine = Engine::new(config, NullOutput, GameRng::from_seed(seed)).unwrap();
+    engine.set_mechanics(mechanics);
+
+    let mut outcomes = Vec::with_capacity(ghost.inputs.len());
+    let mut skipped = 0;
+
+    for input in &ghost.inputs {
+        let command = convert_string_to_command(&input.command);
+
+        match command {
+            Some(command) if check_command(&input.command, engine.state()).is_ok() => {
+                let _ = engine.run_step_with_command(command);
+            }
+            _ => skipped += 1,
+        }
+
+        outcomes.push(alias::GameState::from(*engine.state()));
+    }
+
+    GhostReplay {
+        outcomes,
+        skipped,
+        final_state: alias::GameState::from(*engine.state()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pachislo::config::{BallsConfig, Probability, SlotProbability};
+
+    fn config(win: f64) -> pachislo::config::Config<fn(usize) -> f64> {
+        let slot = SlotProbability {
+            win,
+            fake_win: 0.0,
+            fake_lose: 0.0,
+        };
+
+        pachislo::config::Config {
+            balls: BallsConfig {
+                init_balls: 2,
+                incremental_balls: 10,
+                incremental_rush: 1,
+            },
+            probability: Probability {
+                normal: slot,
+                rush: slot,
+                rush_continue: slot,
+                rush_continue_fn: |_| 1.0,
+            },
+        }
+    }
+
+    fn ghost(commands: &[&str]) -> Ghost {
+        let mut recorder = GhostRecorder::new(1000.0);
+        for (i, command) in commands.iter().enumerate() {
+            recorder.record(1000.0 + 10.0 * i as f64, command);
+        }
+        recorder.finish()
+    }
+
+    #[test]
+    fn test_recorder_times_are_relative() {
+        let ghost = ghost(&["StartGame", "LaunchBall"]);
+
+        assert_eq!(ghost.inputs[0].time, 0.0);
+        assert_eq!(ghost.inputs[1].time, 10.0);
+        assert_eq!(ghost.inputs[1].command, "LaunchBall");
+    }
+
+    #[test]
+    fn test_replay_on_different_machines() {
+        let ghost = ghost(&["StartGame", "CauseLottery", "LaunchBall", "LaunchBall"]);
+
+        // On a machine that never wins, the second launch ends the game
+        let losing = replay(&ghost, config(0.0), Mechanics::default(), 0);
+        assert_eq!(losing.outcomes.len(), 4);
+        assert_eq!(losing.skipped, 0);
+        assert!(matches!(
+            losing.final_state,
+            alias::GameState::Uninitialized
+        ));
+
+        // On a machine that always wins, the lottery pays out
+        let winning = replay(&ghost, config(1.0), Mechanics::default(), 0);
+        assert!(matches!(
+            winning.final_state,
+            alias::GameState::Normal { balls: 11 }
+        ));
+    }
+
+    #[test]
+    fn test_invalid_inputs_are_skipped() {
+        let ghost = ghost(&[
+            "StartGame",
+            "LaunchBall",
+            "LaunchBall",
+            "LaunchBall",
+            "Jump",
+        ]);
+
+        let replay = replay(&ghost, config(0.0), Mechanics::default(), 0);
+
+        assert_eq!(replay.skipped, 2);
+        assert_eq!(replay.outcomes.len(), 5);
+    }
+}
@@ -0,0 +1,183 @@
+//! # Callback Guards
+//!
+//! JavaScript callbacks can return anything. Values the engine computes with,
+//! like the rush continuation multiplier, are checked before use: a number
+//! outside `[0, 1]` is clamped, and anything else, including `NaN`, strings,
+//! `undefined` and thrown errors, is replaced by 0. Every invalid return is
+//! reported with the offending value to the handler installed with
+//! [`set_invalid_return_handler`], or once to `console.warn` when none is
+//! installed.
+
+use std::cell::RefCell;
+
+use js_sys::Function;
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::{JsValue, prelude::wasm_bindgen};
+
+use crate::environment;
+
+/// An invalid value returned by a JavaScript callback.
+///
+/// # Fields
+///
+/// * `callback` - The callback, e.g. `"rush_continue_fn"`
+/// * `argument` - The argument it was called with
+/// * `returned` - The returned value or the thrown error, e.g. `"NaN"`,
+///   `"undefined"` or `"\"0.5\""`
+/// * `used` - The value used instead
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct InvalidReturn {
+    pub callback: String,
+    pub argument: usize,
+    pub returned: String,
+    pub used: f64,
+}
+
+/// What a callback returned.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Returned {
+    /// A number, possibly `NaN` or out of range
+    Number(f64),
+    /// Any other value, described for the report
+    Other(String),
+    /// The thrown error, described for the report
+    Threw(String),
+}
+
+impl Returned {
+    /// Captures the result of calling a JavaScript function.
+    pub(crate) fn from_call(result: Result<JsValue, JsValue>) -> Self {
+        match result {
+            Ok(value) => match value.as_f64() {
+                Some(number) => Returned::Number(number),
+                None => Returned::Other(describe(&value)),
+            },
+            Err(error) => Returned::Threw(describe(&error)),
+        }
+    }
+}
+
+/// Installs the handler receiving every [`InvalidReturn`].
+///
+/// # Arguments
+///
+/// * `handler` - Called with an `InvalidReturn`, or `undefined` to go back to
+///   a single `console.warn`
+///
+/// # Example
+///
+/// ```javascript
+/// set_invalid_return_handler((report) => console.error(`${report.callback}(${report.argument}) returned ${report.returned}`));
+/// ```
+#[wasm_bindgen]
+pub fn set_invalid_return_handler(handler: Option<Function>) {
+    CHANNEL.with(|channel| channel.borrow_mut().handler = handler);
+}
+
+thread_local! {
+    static CHANNEL: RefCell<Channel> = RefCell::new(Channel::default());
+}
+
+/// The report handler and whether the console was warned already.
+#[derive(Default)]
+struct Channel {
+    handler: Option<Function>,
+    warned: bool,
+}
+
+/// Checks a probability returned by `callback` for `argument`, reporting it
+/// if it is invalid, and returns the value to use.
+pub(crate) fn probability(callback: &str, argument: usize, returned: Returned) -> f64 {
+    let (used, returned) = match returned {
+        Returned::Number(number) if (0.0..=1.0).contains(&number) => return number,
+        Returned::Number(number) if number.is_nan() => (0.0, "NaN".to_string()),
+        Returned::Number(number) => (number.clamp(0.0, 1.0), number.to_string()),
+        Returned::Other(value) => (0.0, value),
+        Returned::Threw(error) => (0.0, format!("thrown {error}")),
+    };
+
+    report(InvalidReturn {
+        callback: callback.to_string(),
+        argument,
+        returned,
+        used,
+    });
+
+    used
+}
+
+fn report(invalid: InvalidReturn) {
+    let handler = CHANNEL.with(|channel| {
+        let mut channel = channel.borrow_mut();
+        match &channel.handler {
+            Some(handler) => Some(Some(handler.clone())),
+            None if channel.warned => None,
+            None => {
+                channel.warned = true;
+                Some(None)
+            }
+        }
+    });
+
+    // Called outside the borrow, so the handler may use any API itself
+    match handler {
+        None => {}
+        Some(Some(handler)) => {
+            let report = serde_wasm_bindgen::to_value(&invalid).unwrap();
+            // A throwing handler must not break the lottery being drawn
+            let _ = handler.call1(&JsValue::NULL, &report);
+        }
+        Some(None) => environment::console_warn(&format!(
+            "pachislo-wasm: {}({}) returned {}; using {} instead (further invalid returns are not logged)",
+            invalid.callback, invalid.argument, invalid.returned, invalid.used
+        )),
+    }
+}
+
+/// Describes a JavaScript value like the console does, e.g. `undefined` or `"0.5"`.
+fn describe(value: &JsValue) -> String {
+    let debug = format!("{value:?}");
+
+    debug
+        .strip_prefix("JsValue(")
+        .and_then(|debug| debug.strip_suffix(')'))
+        .map_or_else(|| debug.clone(), str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn warned() -> bool {
+        CHANNEL.with(|channel| channel.borrow().warned)
+    }
+
+    #[test]
+    fn test_valid_probabilities_are_used() {
+        assert_eq!(probability("f", 1, Returned::Number(0.0)), 0.0);
+        assert_eq!(probability("f", 1, Returned::Number(0.75)), 0.75);
+        assert_eq!(probability("f", 1, Returned::Number(1.0)), 1.0);
+        assert!(!warned());
+    }
+
+    #[test]
+    fn test_invalid_probabilities_are_replaced() {
+        // The console is only warned once; later reports are dropped natively
+        CHANNEL.with(|channel| channel.borrow_mut().warned = true);
+
+        assert_eq!(probability("f", 1, Returned::Number(f64::NAN)), 0.0);
+        assert_eq!(probability("f", 1, Returned::Number(1.5)), 1.0);
+        assert_eq!(probability("f", 1, Returned::Number(-0.5)), 0.0);
+        assert_eq!(probability("f", 1, Returned::Number(f64::INFINITY)), 1.0);
+        assert_eq!(
+            probability("f", 1, Returned::Other("undefined".to_string())),
+            0.0
+        );
+        assert_eq!(
+            probability("f", 1, Returned::Threw("Error".to_string())),
+            0.0
+        );
+    }
+}
@@ -0,0 +1,404 @@
+//! # Halls
+//!
+//! A hall runs many machines side by side, each with its own spec. The specs
+//! come from a [`PresetCatalog`], a plain JSON document of named machines, so
+//! a whole floor can be set up in one call instead of building a `Config`
+//! per machine.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    ControlFlow, InnerGame, JsOutput, PachisloError,
+    alias::{BallsConfig, Config, GameState, Probability, SlotProbability},
+    check_command, convert_string_to_command,
+    curve::{self, ContinuationCurve},
+    new_engine,
+    rng::GameRng,
+    validation,
+};
+
+/// A collection of named machine specs.
+///
+/// Catalogs are plain data, so they can be stored as JSON and passed in after
+/// `JSON.parse`.
+///
+/// # Fields
+///
+/// * `presets` - The machines, in the order they are placed in a hall
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct PresetCatalog {
+    pub presets: Vec<MachinePreset>,
+}
+
+/// The spec of a single machine in a [`PresetCatalog`].
+///
+/// # Fields
+///
+/// * `name` - Unique name of the machine, used as the label of its events
+/// * `balls` - Ball settings, as in `BallsConfig`
+/// * `normal` - Probabilities during normal mode
+/// * `rush` - Probabilities during rush mode
+/// * `rush_continue` - Probabilities for rush continuation
+/// * `rush_continue_rates` - Rush continuation multiplier for the 1st, 2nd,
+///   ... consecutive rush; the last rate applies to every later rush, and an
+///   empty list means a constant 1.0
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct MachinePreset {
+    pub name: String,
+    pub balls: PresetBalls,
+    pub normal: PresetSlot,
+    pub rush: PresetSlot,
+    pub rush_continue: PresetSlot,
+    #[serde(default)]
+    pub rush_continue_rates: Vec<f64>,
+}
+
+/// Ball settings of a [`MachinePreset`].
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct PresetBalls {
+    pub init_balls: usize,
+    pub incremental_balls: usize,
+    pub incremental_rush: usize,
+}
+
+/// Slot probabilities of a [`MachinePreset`].
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct PresetSlot {
+    pub win: f64,
+    pub fake_win: f64,
+    pub fake_lose: f64,
+}
+
+impl From<PresetSlot> for SlotProbability {
+    fn from(slot: PresetSlot) -> Self {
+        SlotProbability::new(slot.win, slot.fake_win, slot.fake_lose)
+    }
+}
+
+impl From<PresetBalls> for BallsConfig {
+    fn from(balls: PresetBalls) -> Self {
+        BallsConfig::new(
+            balls.init_balls,
+            balls.incremental_balls,
+            balls.incremental_rush,
+        )
+    }
+}
+
+impl MachinePreset {
+    /// Rush continuation multiplier for the `n`-th consecutive rush.
+    fn rush_continue_rate(&self, n: usize) -> f64 {
+        curve::step_rate(&self.rush_continue_rates, n)
+    }
+
+    /// Checks the preset like `Config::validate`; every error names the preset.
+    fn validate(&self) -> Vec<String> {
+        validation::validate(
+            &self.balls.into(),
+            &[
+                ("normal", self.normal.into()),
+                ("rush", self.rush.into()),
+                ("rush_continue", self.rush_continue.into()),
+            ],
+            |n| Some(self.rush_continue_rate(n)),
+        )
+        .into_iter()
+        .map(|error| format!("preset {:?}: {error}", self.name))
+        .collect()
+    }
+
+    /// Builds the engine configuration; the continuation runs natively.
+    fn config(&self) -> pachislo::config::Config<Box<dyn FnMut(usize) -> f64>> {
+        let preset = self.clone();
+
+        pachislo::config::Config {
+            balls: BallsConfig::from(self.balls).into(),
+            probability: pachislo::config::Probability {
+                normal: SlotProbability::from(self.normal).into(),
+                rush: SlotProbability::from(self.rush).into(),
+                rush_continue: SlotProbability::from(self.rush_continue).into(),
+                rush_continue_fn: Box::new(move |n| preset.rush_continue_rate(n)),
+            },
+        }
+    }
+}
+
+impl From<&MachinePreset> for Config {
+    /// The preset as a configuration; the continuation rates become a
+    /// `Steps` curve.
+    fn from(preset: &MachinePreset) -> Self {
+        Config::new(
+            preset.balls.into(),
+            Probability::with_curve(
+                preset.normal.into(),
+                preset.rush.into(),
+                preset.rush_continue.into(),
+                ContinuationCurve::Steps {
+                    rates: preset.rush_continue_rates.clone(),
+                },
+            ),
+        )
+    }
+}
+
+impl PresetCatalog {
+    /// Checks every preset, and that names are unique among themselves and
+    /// the names already `taken`.
+    fn validate(&self, taken: &[String]) -> Vec<String> {
+        let mut names: HashSet<&str> = taken.iter().map(String::as_str).collect();
+        let mut errors = Vec::new();
+
+        for preset in &self.presets {
+            if !names.insert(&preset.name) {
+                errors.push(format!(
+                    "preset {:?}: the name is already used",
+                    preset.name
+                ));
+            }
+            errors.extend(preset.validate());
+        }
+
+        errors
+    }
+}
+
+/// A machine placed in a hall.
+struct Machine {
+    name: String,
+    engine: InnerGame,
+}
+
+/// Many independent machines sharing one output.
+///
+/// Every machine reports to a clone of the hall's output, labelled with the
+/// machine's name, so a single event handler can tell the machines apart.
+///
+/// # Example
+///
+/// ```javascript
+/// const hall = new WasmHall(output);
+/// hall.populate_from_catalog(JSON.parse(catalogJson));
+/// for (let i = 0; i < hall.machine_count(); i++) {
+///     hall.run_step_with_command(i, "StartGame");
+/// }
+/// ```
+#[wasm_bindgen]
+pub struct WasmHall {
+    output: JsOutput,
+    machines: Vec<Machine>,
+}
+
+#[wasm_bindgen]
+impl WasmHall {
+    /// Creates an empty hall.
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - Output handler; each machine gets a clone labelled with its name
+    #[wasm_bindgen(constructor)]
+    pub fn new(output: JsOutput) -> Self {
+        WasmHall {
+            output,
+            machines: Vec::new(),
+        }
+    }
+
+    /// Places one machine per catalog entry, after the machines already in the hall.
+    ///
+    /// Either every preset is placed or none is.
+    ///
+    /// # Returns
+    ///
+    /// The number of machines placed.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` listing every problem, one per line, if a preset is
+    /// invalid in the sense of `Config.validate` or its name is already used.
+    #[wasm_bindgen]
+    pub fn populate_from_catalog(
+        &mut self,
+        catalog: PresetCatalog,
+    ) -> Result<usize, PachisloError> {
+        let errors = catalog.validate(&self.machine_names());
+        if !errors.is_empty() {
+            return Err(PachisloError::InvalidConfig {
+                message: format!("invalid preset catalog:\n{}", errors.join("\n")),
+            });
+        }
+
+        for preset in &catalog.presets {
+            let mut output = self.output.clone();
+            output.set_label(Some(preset.name.clone()));
+
+            self.machines.push(Machine {
+                name: preset.name.clone(),
+                engine: new_engine(output, preset.config(), GameRng::default()),
+            });
+        }
+
+        Ok(catalog.presets.len())
+    }
+
+    /// Returns the number of machines in the hall.
+    #[wasm_bindgen]
+    pub fn machine_count(&self) -> usize {
+        self.machines.len()
+    }
+
+    /// Returns the machine names, in index order.
+    #[wasm_bindgen]
+    pub fn machine_names(&self) -> Vec<String> {
+        self.machines
+            .iter()
+            .map(|machine| machine.name.clone())
+            .collect()
+    }
+
+    /// Executes a single game step on one machine.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Index of the machine, in catalog order
+    /// * `command` - Command string, as for `WasmGame::run_step_with_command`
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if there is no machine at `index`, or the command is
+    /// not recognized or not valid in the machine's state.
+    #[wasm_bindgen]
+    pub fn run_step_with_command(
+        &mut self,
+        index: usize,
+        #[wasm_bindgen(unchecked_param_type = "Command")] command: String,
+    ) -> Result<ControlFlow, PachisloError> {
+        let input = command.as_str();
+        let command =
+            convert_string_to_command(input).ok_or_else(|| PachisloError::UnknownCommand {
+                command: input.to_string(),
+            })?;
+        let engine = &mut self.machine(index)?.engine;
+
+        check_command(input, engine.state())?;
+
+        let stepped = engine.run_step_with_command(command);
+        engine.output_mut().take_failure()?;
+
+        Ok(if stepped.is_break() {
+            ControlFlow::Break
+        } else {
+            ControlFlow::Continue
+        })
+    }
+
+    /// Returns the state of one machine.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if there is no machine at `index`.
+    #[wasm_bindgen]
+    pub fn get_state(&mut self, index: usize) -> Result<GameState, PachisloError> {
+        Ok(GameState::from(*self.machine(index)?.engine.state()))
+    }
+}
+
+impl WasmHall {
+    fn machine(&mut self, index: usize) -> Result<&mut Machine, PachisloError> {
+        let count = self.machines.len();
+
+        self.machines
+            .get_mut(index)
+            .ok_or(PachisloError::NoSuchMachine { index, count })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slot(win: f64) -> PresetSlot {
+        PresetSlot {
+            win,
+            fake_win: 0.1,
+            fake_lose: 0.1,
+        }
+    }
+
+    fn preset(name: &str, rates: Vec<f64>) -> MachinePreset {
+        MachinePreset {
+            name: name.to_string(),
+            balls: PresetBalls {
+                init_balls: 100,
+                incremental_balls: 10,
+                incremental_rush: 50,
+            },
+            normal: slot(0.1),
+            rush: slot(0.5),
+            rush_continue: slot(0.8),
+            rush_continue_rates: rates,
+        }
+    }
+
+    #[test]
+    fn test_rush_continue_rates() {
+        let tiered = preset("A", vec![1.0, 0.8, 0.5]);
+        assert_eq!(tiered.rush_continue_rate(1), 1.0);
+        assert_eq!(tiered.rush_continue_rate(2), 0.8);
+        assert_eq!(tiered.rush_continue_rate(3), 0.5);
+        assert_eq!(tiered.rush_continue_rate(10), 0.5);
+
+        let mut config = tiered.config();
+        assert_eq!((config.probability.rush_continue_fn)(2), 0.8);
+
+        assert_eq!(preset("B", vec![]).rush_continue_rate(4), 1.0);
+    }
+
+    #[test]
+    fn test_preset_as_config() {
+        let tiered = preset("A", vec![1.0, 0.8, 0.5]);
+        let config = Config::from(&tiered);
+
+        assert_eq!(config.balls.init_balls, 100);
+        assert_eq!(config.probability.rush.win, 0.5);
+        for n in 1..5 {
+            assert_eq!(
+                config.probability.rush_continue_multiplier(n),
+                tiered.rush_continue_rate(n)
+            );
+        }
+    }
+
+    #[test]
+    fn test_catalog_validation() {
+        let mut invalid = preset("B", vec![0.5, -0.5]);
+        invalid.normal.win = 1.5;
+
+        let catalog = PresetCatalog {
+            presets: vec![preset("A", vec![]), invalid, preset("A", vec![])],
+        };
+        let errors = catalog.validate(&[]);
+
+        assert_eq!(errors.len(), 3);
+        assert!(errors[0].starts_with("preset \"B\": probability.normal.win is 1.5"));
+        assert!(errors[1].starts_with("preset \"B\": rush_continue_fn(2) returned -0.5"));
+        assert_eq!(errors[2], "preset \"A\": the name is already used");
+    }
+
+    #[test]
+    fn test_names_already_in_hall() {
+        let catalog = PresetCatalog {
+            presets: vec![preset("A", vec![]), preset("B", vec![])],
+        };
+
+        assert!(catalog.validate(&["C".to_string()]).is_empty());
+        assert_eq!(catalog.validate(&["B".to_string()]).len(), 1);
+    }
+}
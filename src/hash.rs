@@ -0,0 +1,275 @@
+//! # Stable Hashing
+//!
+//! Hashes and seed derivation that must give identical results on every
+//! platform and toolchain, e.g. so that all players derive the same daily
+//! challenge. `std`'s `DefaultHasher` makes no such guarantee.
+//!
+//! None of these functions are cryptographically secure.
+
+use crate::{
+    alias::{Config, SlotProbability},
+    curve::ContinuationCurve,
+    engine::RushModel,
+};
+
+/// Number of `rush_continue_fn` samples mixed into a configuration hash.
+const CONTINUATION_SAMPLES: usize = 10;
+
+/// 64-bit FNV-1a hasher with explicit little-endian encoding of numbers.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Fnv1a(u64);
+
+impl Fnv1a {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    pub(crate) fn new() -> Self {
+        Fnv1a(Self::OFFSET_BASIS)
+    }
+
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    pub(crate) fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    pub(crate) fn write_usize(&mut self, value: usize) {
+        self.write_u64(value as u64);
+    }
+
+    pub(crate) fn write_f64(&mut self, value: f64) {
+        self.write_u64(value.to_bits());
+    }
+
+    /// Writes a length-prefixed string so that adjacent strings cannot collide.
+    pub(crate) fn write_str(&mut self, value: &str) {
+        self.write_usize(value.len());
+        self.write(value.as_bytes());
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Advances a SplitMix64 state and returns the next output.
+pub(crate) fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Returns a uniformly distributed `f64` in `[0, 1)` from a SplitMix64 state.
+pub(crate) fn splitmix64_f64(state: &mut u64) -> f64 {
+    (splitmix64(state) >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Writes whether an optional setting is present, then the setting itself.
+fn write_option<T>(hasher: &mut Fnv1a, value: Option<T>, write: impl FnOnce(&mut Fnv1a, T)) {
+    match value {
+        None => hasher.write(&[0]),
+        Some(value) => {
+            hasher.write(&[1]);
+            write(hasher, value);
+        }
+    }
+}
+
+/// Writes which curve it is, then its parameters.
+fn write_curve(hasher: &mut Fnv1a, curve: &ContinuationCurve) {
+    match curve {
+        ContinuationCurve::Constant { value } => {
+            hasher.write(&[0]);
+            hasher.write_f64(*value);
+        }
+        ContinuationCurve::LinearDecay { start, step, floor } => {
+            hasher.write(&[1]);
+            hasher.write_f64(*start);
+            hasher.write_f64(*step);
+            hasher.write_f64(*floor);
+        }
+        ContinuationCurve::Steps { rates } => {
+            hasher.write(&[2]);
+            hasher.write_usize(rates.len());
+            for &rate in rates {
+                hasher.write_f64(rate);
+            }
+        }
+        ContinuationCurve::Exponential {
+            start,
+            ratio,
+            floor,
+        } => {
+            hasher.write(&[3]);
+            hasher.write_f64(*start);
+            hasher.write_f64(*ratio);
+            hasher.write_f64(*floor);
+        }
+    }
+}
+
+fn write_slot_probability(hasher: &mut Fnv1a, probability: &SlotProbability) {
+    hasher.write_f64(probability.win);
+    hasher.write_f64(probability.fake_win);
+    hasher.write_f64(probability.fake_lose);
+}
+
+/// Hashes every setting of a configuration.
+///
+/// A built-in continuation curve is hashed by its parameters. A JavaScript
+/// `rush_continue_fn` cannot be hashed directly, so its values for the first
+/// few rush counts are hashed instead.
+pub(crate) fn hash_config(hasher: &mut Fnv1a, config: &Config) {
+    hasher.write_usize(config.balls.init_balls);
+    hasher.write_usize(config.balls.incremental_balls);
+    hasher.write_usize(config.balls.incremental_rush);
+    hasher.write_usize(config.balls.launch_cost);
+    hasher.write_f64(config.balls.start_rate);
+
+    let probability = &config.probability;
+    write_slot_probability(hasher, &probability.normal);
+    write_slot_probability(hasher, &probability.rush);
+    write_slot_probability(hasher, &probability.rush_continue);
+
+    write_option(hasher, probability.curve(), write_curve);
+    if probability.curve().is_none() {
+        for n in 1..=CONTINUATION_SAMPLES {
+            hasher.write_f64(probability.rush_continue_multiplier(n));
+        }
+    }
+
+    write_option(hasher, config.randomized_start, |hasher, start| {
+        hasher.write_usize(start.min_spins);
+        hasher.write_usize(start.max_spins);
+    });
+
+    let mechanics = &config.mechanics;
+    let st_spins = match mechanics.rush_model {
+        RushModel::Continuation => None,
+        RushModel::SpinBudget(spins) => Some(spins),
+    };
+    write_option(hasher, st_spins, Fnv1a::write_usize);
+    write_option(hasher, mechanics.jitan, |hasher, jitan| {
+        hasher.write_usize(jitan.spins);
+        write_slot_probability(hasher, &jitan.probability.into());
+    });
+    write_option(hasher, mechanics.ceiling, Fnv1a::write_usize);
+    write_option(hasher, mechanics.stock_cap, Fnv1a::write_usize);
+
+    hasher.write_usize(mechanics.tiers.len());
+    for tier in &mechanics.tiers {
+        hasher.write_str(&tier.name);
+        hasher.write_f64(tier.weight);
+        write_slot_probability(hasher, &tier.rush.into());
+        write_slot_probability(hasher, &tier.rush_continue.into());
+        hasher.write_usize(tier.incremental_balls);
+        hasher.write_usize(tier.incremental_rush);
+    }
+
+    write_option(hasher, mechanics.rounds.as_ref(), |hasher, rounds| {
+        hasher.write_usize(rounds.choices.len());
+        for &(count, weight) in &rounds.choices {
+            hasher.write_usize(count);
+            hasher.write_f64(weight);
+        }
+        hasher.write_usize(rounds.balls_per_round);
+    });
+    write_option(hasher, mechanics.premium, Fnv1a::write_f64);
+    write_option(hasher, mechanics.battles.as_ref(), |hasher, battles| {
+        hasher.write_usize(battles.enemies.len());
+        for enemy in &battles.enemies {
+            hasher.write_str(enemy);
+        }
+        hasher.write_f64(battles.revival);
+    });
+
+    let stop = &mechanics.stop;
+    write_option(hasher, stop.stop_when_balls_below, Fnv1a::write_usize);
+    write_option(hasher, stop.stop_after_spins, Fnv1a::write_usize);
+    write_option(hasher, stop.stop_after_rushes, Fnv1a::write_usize);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fnv1a_known_values() {
+        assert_eq!(Fnv1a::new().finish(), 0xcbf2_9ce4_8422_2325);
+
+        let mut hasher = Fnv1a::new();
+        hasher.write(b"a");
+        assert_eq!(hasher.finish(), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn test_write_str_is_length_prefixed() {
+        let mut a = Fnv1a::new();
+        a.write_str("ab");
+        a.write_str("c");
+
+        let mut b = Fnv1a::new();
+        b.write_str("a");
+        b.write_str("bc");
+
+        assert_ne!(a.finish(), b.finish());
+    }
+
+    #[test]
+    fn test_write_option_tells_absent_from_present() {
+        let hash = |value: Option<u64>| {
+            let mut hasher = Fnv1a::new();
+            write_option(&mut hasher, value, Fnv1a::write_u64);
+            hasher.finish()
+        };
+
+        assert_ne!(hash(None), hash(Some(0)));
+        assert_ne!(hash(Some(0)), hash(Some(1)));
+    }
+
+    #[test]
+    fn test_curves_are_hashed_by_parameters() {
+        use crate::alias::{BallsConfig, Probability};
+
+        let hash = |rates: Vec<f64>| {
+            let slot = SlotProbability::new(0.5, 0.0, 0.0);
+            let probability =
+                Probability::with_curve(slot, slot, slot, ContinuationCurve::Steps { rates });
+            let mut hasher = Fnv1a::new();
+            hash_config(
+                &mut hasher,
+                &Config::new(BallsConfig::new(100, 10, 5), probability),
+            );
+            hasher.finish()
+        };
+
+        let rates = vec![0.9; 10];
+        assert_ne!(
+            hash([rates.clone(), vec![0.5]].concat()),
+            hash([rates, vec![0.6]].concat())
+        );
+    }
+
+    #[test]
+    fn test_splitmix64_known_values() {
+        let mut state = 0;
+        assert_eq!(splitmix64(&mut state), 0xe220_a839_7b1d_cdaf);
+        assert_eq!(splitmix64(&mut state), 0x6e78_9e6a_a1b9_65f4);
+    }
+
+    #[test]
+    fn test_splitmix64_f64_range() {
+        let mut state = 42;
+        for _ in 0..1000 {
+            let value = splitmix64_f64(&mut state);
+            assert!((0.0..1.0).contains(&value));
+        }
+    }
+}
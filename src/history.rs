@@ -0,0 +1,261 @@
+//! # Command History
+//!
+//! An opt-in log of every executed command and the transition it caused, so
+//! replays, debugging and post-game analysis don't need every consumer to
+//! re-implement logging in its output callbacks.
+//!
+//! The spins of the log can be exported as CSV or NDJSON (one JSON object
+//! per line) with `WasmGame::export_history`, for spreadsheets and pandas.
+
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use crate::{
+    alias::{GameState, Lose, LotteryResult, Transition, Win},
+    explain::{SpinExplanation, SpinMode},
+    undo::SavedGame,
+};
+
+/// A single executed command.
+///
+/// # Fields
+///
+/// * `time` - Timestamp of the command in milliseconds
+/// * `command` - The command string that was executed, or `"Reveal"` for a
+///   staged reveal completed in between (see `WasmGame::set_staged_reveal`)
+/// * `transition` - The state before and after the command
+/// * `spin` - The lottery the command drew, if any
+#[derive(Clone, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct HistoryEntry {
+    pub time: f64,
+    pub command: String,
+    pub transition: Transition,
+    #[serde(default)]
+    pub spin: Option<SpinRecord>,
+}
+
+/// A lottery drawn by a recorded command.
+///
+/// The balls are those of the outcome, also for a fake outcome whose
+/// transition is held back by a staged reveal.
+///
+/// # Fields
+///
+/// * `mode` - The lottery table the spin was drawn from
+/// * `result` - The lottery result
+/// * `balls_before` - Balls before the spin
+/// * `balls_after` - Balls after the spin, including its payout
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct SpinRecord {
+    pub mode: SpinMode,
+    pub result: LotteryResult,
+    pub balls_before: usize,
+    pub balls_after: usize,
+}
+
+impl From<&SpinExplanation> for SpinRecord {
+    fn from(spin: &SpinExplanation) -> Self {
+        SpinRecord {
+            mode: spin.mode,
+            result: spin.lottery.result,
+            balls_before: balls(&spin.before),
+            balls_after: balls(&spin.after),
+        }
+    }
+}
+
+/// Text formats of [`History::export`].
+///
+/// # Variants
+///
+/// * `csv` - A header line, then one line per spin
+/// * `ndjson` - One JSON object per spin and line
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    Ndjson,
+}
+
+/// Records executed commands while enabled.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct History {
+    enabled: bool,
+    entries: Vec<HistoryEntry>,
+    /// The game after each entry, for `WasmGame::seek`
+    states: Vec<SavedGame>,
+}
+
+impl History {
+    /// Starts or stops recording; recorded entries are kept either way.
+    pub(crate) fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Records a command and the spin it drew if recording is enabled.
+    pub(crate) fn record(
+        &mut self,
+        time: f64,
+        command: &str,
+        transition: Transition,
+        spin: Option<SpinRecord>,
+    ) {
+        if self.enabled {
+            self.entries.push(HistoryEntry {
+                time,
+                command: command.to_string(),
+                transition,
+                spin,
+            });
+        }
+    }
+
+    pub(crate) fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    /// Keeps the game after the entry recorded last.
+    pub(crate) fn save_state(&mut self, state: SavedGame) {
+        self.states.push(state);
+    }
+
+    /// The game after the entry at `index`.
+    pub(crate) fn state(&self, index: usize) -> Option<&SavedGame> {
+        self.states.get(index)
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.states.clear();
+    }
+
+    /// Writes the recorded spins, oldest first, with the time and command
+    /// of each.
+    pub(crate) fn export(&self, format: ExportFormat) -> String {
+        let mut out = String::new();
+        if let ExportFormat::Csv = format {
+            out.push_str("time,command,mode,result,balls_before,balls_after\n");
+        }
+
+        for entry in &self.entries {
+            let Some(spin) = entry.spin else {
+                continue;
+            };
+            let (time, command, mode, result) = (
+                entry.time,
+                &entry.command,
+                format!("{:?}", spin.mode),
+                result_name(spin.result),
+            );
+            let (before, after) = (spin.balls_before, spin.balls_after);
+
+            // Commands and names are plain identifiers, so nothing needs quoting
+            let _ = match format {
+                ExportFormat::Csv => {
+                    writeln!(out, "{time},{command},{mode},{result},{before},{after}")
+                }
+                ExportFormat::Ndjson => writeln!(
+                    out,
+                    "{{\"time\":{time},\"command\":\"{command}\",\"mode\":\"{mode}\",\"result\":\"{result}\",\"balls_before\":{before},\"balls_after\":{after}}}"
+                ),
+            };
+        }
+
+        out
+    }
+}
+
+/// The name of a result in exports: `"Win"`, `"FakeWin"`, `"Premium"`,
+/// `"Lose"` or `"FakeLose"`.
+fn result_name(result: LotteryResult) -> &'static str {
+    match result {
+        LotteryResult::Win(Win::Default) => "Win",
+        LotteryResult::Win(Win::FakeWin) => "FakeWin",
+        LotteryResult::Win(Win::Premium) => "Premium",
+        LotteryResult::Lose(Lose::Default) => "Lose",
+        LotteryResult::Lose(Lose::FakeLose) => "FakeLose",
+    }
+}
+
+fn balls(state: &GameState) -> usize {
+    match *state {
+        GameState::Uninitialized => 0,
+        GameState::Normal { balls }
+        | GameState::Rush { balls, .. }
+        | GameState::Jitan { balls, .. } => balls,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alias::GameState;
+
+    fn transition(balls: usize) -> Transition {
+        Transition {
+            before: Some(GameState::Normal { balls: balls + 1 }),
+            after: GameState::Normal { balls },
+        }
+    }
+
+    #[test]
+    fn test_records_only_while_enabled() {
+        let mut history = History::default();
+
+        history.record(0.0, "LaunchBall", transition(9), None);
+        assert!(history.entries().is_empty());
+
+        history.set_enabled(true);
+        history.record(1.0, "LaunchBall", transition(8), None);
+        history.record(2.0, "LaunchBall", transition(7), None);
+
+        history.set_enabled(false);
+        history.record(3.0, "LaunchBall", transition(6), None);
+
+        let times: Vec<f64> = history.entries().iter().map(|entry| entry.time).collect();
+        assert_eq!(times, [1.0, 2.0]);
+        assert!(matches!(
+            history.entries()[1].transition.after,
+            GameState::Normal { balls: 7 }
+        ));
+
+        history.clear();
+        assert!(history.entries().is_empty());
+    }
+
+    #[test]
+    fn test_export() {
+        let mut history = History::default();
+        history.set_enabled(true);
+        history.record(1.0, "LaunchBall", transition(9), None);
+        history.record(
+            2.5,
+            "CauseLottery",
+            transition(9),
+            Some(SpinRecord {
+                mode: SpinMode::Normal,
+                result: LotteryResult::Win(Win::FakeWin),
+                balls_before: 9,
+                balls_after: 24,
+            }),
+        );
+
+        assert_eq!(
+            history.export(ExportFormat::Csv),
+            "time,command,mode,result,balls_before,balls_after\n2.5,CauseLottery,Normal,FakeWin,9,24\n"
+        );
+        assert_eq!(
+            history.export(ExportFormat::Ndjson),
+            "{\"time\":2.5,\"command\":\"CauseLottery\",\"mode\":\"Normal\",\"result\":\"FakeWin\",\"balls_before\":9,\"balls_after\":24}\n"
+        );
+    }
+}
@@ -0,0 +1,133 @@
+//! # Hold Queue (保留)
+//!
+//! A real machine keeps up to 4 lotteries on hold while one is being
+//! displayed: a ball entering the start hole during a reel animation is
+//! remembered instead of drawn. With the hold queue enabled, `"CauseLottery"`
+//! adds a hold and `"ConsumeHold"` draws the oldest one, so the UI decides
+//! when the next lottery is shown, e.g. once the previous animation ends.
+//!
+//! Event outputs receive a `"hold_added"` or `"hold_consumed"` event with a
+//! [`HoldEvent`] after the transition of the command that changed the queue.
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{PachisloError, WasmGame};
+
+/// Number of lotteries a hold queue keeps; further lotteries are lost.
+pub const HOLD_CAPACITY: usize = 4;
+
+/// Payload of the `"hold_added"` and `"hold_consumed"` events.
+///
+/// # Fields
+///
+/// * `held` - Lotteries held after the change, at most `HOLD_CAPACITY`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct HoldEvent {
+    pub held: usize,
+}
+
+/// Counts the held lotteries; they are only drawn when consumed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct HoldQueue {
+    held: usize,
+}
+
+impl HoldQueue {
+    /// Holds a lottery; returns `false` if the queue is full.
+    pub(crate) fn add(&mut self) -> bool {
+        if self.held == HOLD_CAPACITY {
+            return false;
+        }
+
+        self.held += 1;
+        true
+    }
+
+    /// Takes the oldest held lottery; returns `false` if none is held.
+    pub(crate) fn take(&mut self) -> bool {
+        if self.held == 0 {
+            return false;
+        }
+
+        self.held -= 1;
+        true
+    }
+
+    /// Discards every held lottery.
+    pub(crate) fn clear(&mut self) {
+        self.held = 0;
+    }
+
+    /// Number of held lotteries.
+    pub(crate) fn held(&self) -> usize {
+        self.held
+    }
+}
+
+#[wasm_bindgen]
+impl WasmGame {
+    /// Enables or disables the hold queue.
+    ///
+    /// While enabled, `"CauseLottery"` holds up to `HOLD_CAPACITY` (4)
+    /// lotteries instead of drawing them, and `"ConsumeHold"` draws the oldest
+    /// one; it is ignored if nothing is held. Held lotteries are discarded
+    /// when the game ends or the queue is disabled.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// game.set_hold_queue(true);
+    /// game.run_step_with_command("CauseLottery");  // "hold_added" with { held: 1 }
+    /// reels.onAnimationEnd = () => {
+    ///     if (game.held_count() > 0) game.run_step_with_command("ConsumeHold");
+    /// };
+    /// ```
+    #[wasm_bindgen]
+    pub fn set_hold_queue(&self, enabled: bool) -> Result<(), PachisloError> {
+        let mut session = self.session()?;
+        session.engine.set_hold_queue(enabled);
+
+        Ok(())
+    }
+
+    /// Returns the number of held lotteries, 0 without a hold queue.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    #[wasm_bindgen]
+    pub fn held_count(&self) -> Result<usize, PachisloError> {
+        let session = self.session()?;
+
+        Ok(session.engine.held())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capacity() {
+        let mut holds = HoldQueue::default();
+
+        assert!(!holds.take());
+        for _ in 0..HOLD_CAPACITY {
+            assert!(holds.add());
+        }
+        assert!(!holds.add());
+        assert_eq!(holds.held(), HOLD_CAPACITY);
+
+        assert!(holds.take());
+        assert_eq!(holds.held(), HOLD_CAPACITY - 1);
+        holds.clear();
+        assert_eq!(holds.held(), 0);
+    }
+}
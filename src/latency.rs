@@ -0,0 +1,122 @@
+//! # Latency Measurement
+//!
+//! Lets a deployed game measure input-to-render latency end to end. JavaScript
+//! stamps a command with its own timestamp, e.g. the time of the input event;
+//! after the command's events, the event output receives a
+//! `"command_latency"` event echoing the stamp together with the times the
+//! engine received and completed the command. Rendering code can then compare
+//! the stamp with the time the frame showing the result is presented.
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{ControlFlow, PachisloError, WasmGame, event::COMMAND_LATENCY};
+
+/// Payload of the `"command_latency"` event.
+///
+/// `received_at` and `completed_at` are read from the game's clock, so they
+/// are comparable with `client_timestamp` only if both use the same time base
+/// (`Date.now()` unless the game was created with a custom clock).
+///
+/// # Fields
+///
+/// * `command` - The stamped command
+/// * `client_timestamp` - The timestamp passed with the command, echoed unchanged
+/// * `received_at` - Time the engine started processing the command, in milliseconds
+/// * `completed_at` - Time the engine finished, after all of the command's
+///   events were delivered
+/// * `processing_time` - `completed_at - received_at`, including the time
+///   spent in event callbacks
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct CommandLatency {
+    #[tsify(type = "Command")]
+    pub command: String,
+    pub client_timestamp: f64,
+    pub received_at: f64,
+    pub completed_at: f64,
+    pub processing_time: f64,
+}
+
+impl CommandLatency {
+    fn new(command: String, client_timestamp: f64, received_at: f64, completed_at: f64) -> Self {
+        CommandLatency {
+            command,
+            client_timestamp,
+            received_at,
+            completed_at,
+            processing_time: completed_at - received_at,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl WasmGame {
+    /// Executes a command like [`WasmGame::run_step_with_command`], then
+    /// emits a `"command_latency"` event with a [`CommandLatency`] echoing
+    /// `client_timestamp`.
+    ///
+    /// The latency event is delivered after every event of the command; none
+    /// is emitted if the command fails. Only available for outputs created
+    /// from a [`JsEventOutput`](crate::event::JsEventOutput).
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the output is not an event output, for the same
+    /// reasons as `run_step_with_command`, or if the game mutex cannot be
+    /// acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// button.addEventListener("click", (event) => {
+    ///     game.run_step_with_command_stamped("CauseLottery", performance.timeOrigin + event.timeStamp);
+    /// });
+    /// // in the event callback
+    /// if (type === "command_latency") {
+    ///     requestAnimationFrame(() => metrics.record(Date.now() - payload.client_timestamp));
+    /// }
+    /// ```
+    #[wasm_bindgen]
+    pub fn run_step_with_command_stamped(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "Command")] command: String,
+        client_timestamp: f64,
+    ) -> Result<ControlFlow, PachisloError> {
+        let received_at = {
+            let session = self.session()?;
+            if !session.engine.output().is_event_output() {
+                return Err(PachisloError::NotAnEventOutput);
+            }
+
+            session.clock.now()?
+        };
+
+        let control_flow = self.step(&command)?;
+
+        let mut session = self.session()?;
+        let completed_at = session.clock.now()?;
+        let latency = CommandLatency::new(command, client_timestamp, received_at, completed_at);
+        session.engine.output_mut().emit(
+            COMMAND_LATENCY,
+            serde_wasm_bindgen::to_value(&latency).unwrap(),
+        );
+        session.engine.output_mut().take_failure()?;
+
+        Ok(control_flow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_processing_time() {
+        let latency = CommandLatency::new("LaunchBall".to_string(), 100.0, 120.0, 123.5);
+
+        assert_eq!(latency.client_timestamp, 100.0);
+        assert_eq!(latency.processing_time, 3.5);
+    }
+}
@@ -15,7 +15,7 @@
 //! ## Usage
 //!
 //! ```javascript
-//! import { WasmGame, JsInput, JsOutput, Config, BallsConfig, Probability, SlotProbability } from 'pachislo-wasm';
+//! import { WasmGame, JsInput, JsOutput, JsEventOutput, Config, BallsConfig, Probability, SlotProbability } from 'pachislo-wasm';
 //!
 //! // Configure the game
 //! const ballsConfig = new BallsConfig(100, 10, 5);
@@ -27,30 +27,109 @@
 //!
 //! // Set up input/output handlers
 //! const input = new JsInput();
-//! const output = new JsOutput(context, defaultHandler, finishHandler, normalLotteryHandler, rushLotteryHandler, rushContinueHandler);
+//! const output = JsOutput.from_events(new JsEventOutput(context, (eventType, payload) => {
+//!     // "transition", "finish_game", "lottery_normal", "lottery_rush", ...
+//!     console.log(eventType, payload);
+//! }));
 //!
 //! // Create and run the game
 //! const game = new WasmGame(input, output, config);
 //! game.run_step_with_command("StartGame");
 //! ```
 
-use std::sync::Mutex;
+#[cfg(feature = "single-threaded")]
+use std::cell::{RefCell, RefMut};
+#[cfg(not(feature = "single-threaded"))]
+use std::sync::{Mutex, MutexGuard, TryLockError};
+use std::{cmp::Ordering, error::Error, fmt::Display};
 
 use js_sys::Function;
 use pachislo::{
-    Game,
-    command::{CauseLottery, Command, FinishGame, LaunchBall, StartGame},
+    command::Command as PachisloCommand,
     interface::{UserInput, UserOutput},
     slot::SlotProducer,
 };
 use rand::Rng;
-use wasm_bindgen::{JsValue, prelude::wasm_bindgen};
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::{JsCast, JsValue, prelude::wasm_bindgen};
+use wasm_bindgen_futures::JsFuture;
 
-use crate::alias::{Config, GameState, LotteryResult, Transition};
+use crate::alias::{Config, GameState, LotteryResult, Transition, Win};
+use crate::clock::SharedClock;
+use crate::diff::StateDiff;
+use crate::engine::{Command, Engine, EngineOutput};
+use crate::explain::SpinExplanation;
+use crate::ghost::{Ghost, GhostRecorder};
+use crate::history::{ExportFormat, History, HistoryEntry};
+use crate::rng::GameRng;
+use crate::rules::{CommandAvailability, CommandRules};
+use crate::series::BallsSeries;
+use crate::snapshot::{GameSnapshot, RngSnapshot};
+use crate::stats::GameStats;
+use crate::streak::StreakAlerts;
+use crate::undo::UndoStack;
 
 pub mod alias;
+pub mod analysis;
+pub mod autoplay;
+pub mod battle;
+pub mod campaign;
+pub mod challenge;
+#[cfg(feature = "chaos")]
+pub mod chaos;
+mod clock;
+pub mod comparison;
+pub mod curve;
+pub mod deprecation;
+pub mod diff;
+mod engine;
+pub mod environment;
+pub mod event;
+pub mod explain;
+pub mod fork;
+pub mod ghost;
+pub mod guard;
+pub mod hall;
+mod hash;
+pub mod history;
+pub mod hold;
+pub mod latency;
+pub mod lint;
+pub mod manager;
+pub mod migration;
+pub mod preview;
+pub mod proof;
+pub mod rare;
+pub mod registry;
+pub mod regulation;
+pub mod remote;
+pub mod reveal;
+mod rng;
+pub mod rounds;
+pub mod rpc;
+pub mod rules;
+pub mod schema;
+pub mod script;
+pub mod series;
+pub mod simulation;
+pub mod slot;
+pub mod snapshot;
+pub mod stats;
+pub mod stock;
+pub mod stop;
+pub mod streak;
+pub mod subscription;
+pub mod summary;
+pub mod tier;
+pub mod timeline;
+pub mod tournament;
+pub mod undo;
+pub mod validation;
+pub mod view;
+mod warmup;
 
-/// Converts a string command to a pachislo Command enum.
+/// Converts a string command to an engine command.
 ///
 /// # Arguments
 ///
@@ -65,24 +144,272 @@ pub mod alias;
 ///
 /// - `"LaunchBall"` - Launch a ball into the machine
 /// - `"CauseLottery"` - Trigger the lottery mechanism
+/// - `"ConsumeHold"` - Draw the oldest held lottery
 /// - `"StartGame"` - Start a new game session
 /// - `"FinishGame"` - End the current game session
 /// - `"Finish"` - Alias for finishing the game
-fn convert_string_to_command<F, R>(input: &str) -> Option<Command<JsInput, JsOutput, F, R>>
-where
-    F: FnMut(usize) -> f64,
-    R: Rng,
-{
+fn convert_string_to_command(input: &str) -> Option<Command> {
     match input {
-        "LaunchBall" => Some(Command::control(LaunchBall)),
-        "CauseLottery" => Some(Command::control(CauseLottery)),
-        "StartGame" => Some(Command::control(StartGame)),
-        "FinishGame" => Some(Command::control(FinishGame)),
-        "Finish" => Some(Command::FinishGame),
+        "LaunchBall" => Some(Command::LaunchBall),
+        "CauseLottery" => Some(Command::CauseLottery),
+        "ConsumeHold" => Some(Command::ConsumeHold),
+        "StartGame" => Some(Command::StartGame),
+        "FinishGame" => Some(Command::FinishGame),
+        "Finish" => Some(Command::Finish),
         _ => None,
     }
 }
 
+/// Arguments of a parameterized command. Every field is optional in JavaScript.
+///
+/// # Fields
+///
+/// * `count` - How often to repeat the command; only `"LaunchBall"` and
+///   `"CauseLottery"` accept a count other than 1
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(default)]
+pub struct CommandArgs {
+    pub count: Option<usize>,
+}
+
+/// Returns how often a command is repeated for the given arguments.
+fn repetitions(command: Command, args: &CommandArgs) -> Result<usize, PachisloError> {
+    match (command, args.count) {
+        (_, None) => Ok(1),
+        (_, Some(0)) => Err(PachisloError::InvalidArguments {
+            message: "\"count\" must be at least 1".to_string(),
+        }),
+        (Command::LaunchBall | Command::CauseLottery, Some(count)) => Ok(count),
+        (_, Some(1)) => Ok(1),
+        (command, Some(_)) => Err(PachisloError::InvalidArguments {
+            message: format!(
+                "{:?} cannot be repeated; only \"LaunchBall\" and \"CauseLottery\" accept a \"count\"",
+                command.name()
+            ),
+        }),
+    }
+}
+
+/// Checks that an argument is a probability from 0 to 1.
+pub(crate) fn check_probability(name: &str, probability: f64) -> Result<(), PachisloError> {
+    if (0.0..=1.0).contains(&probability) {
+        Ok(())
+    } else {
+        Err(PachisloError::InvalidArguments {
+            message: format!("\"{name}\" must be from 0 to 1, got {probability}"),
+        })
+    }
+}
+
+/// Errors thrown by every fallible method of the crate.
+///
+/// They are thrown as JavaScript `Error` objects named `"PachisloError"`,
+/// whose `message` describes the problem and which carry the fields of this
+/// type, so handlers can branch on `kind` instead of parsing messages:
+///
+/// ```javascript
+/// try {
+///     game.run_step_with_command(command);
+/// } catch (error) {
+///     if (error.kind === "GameNotStarted") showStartButton();
+///     else throw error;
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Tsify)]
+#[serde(tag = "kind")]
+pub enum PachisloError {
+    /// The command string does not name a known command
+    UnknownCommand { command: String },
+    /// The configuration or a setting of it is invalid
+    InvalidConfig { message: String },
+    /// The game mutex was poisoned by an earlier panic; never thrown by
+    /// `single-threaded` builds
+    LockPoisoned,
+    /// The game is in use, e.g. by the step whose callback called back into it
+    Busy,
+    /// A ghost recording was stopped without being started
+    NotRecording,
+    /// The command requires a running game
+    GameNotStarted { command: String },
+    /// `StartGame` was sent while a game is already running
+    AlreadyStarted,
+    /// The command was used as often as its usage limit allows
+    UsageLimitReached { command: String, limit: usize },
+    /// The command is still cooling down from its previous use
+    CoolingDown { command: String, remaining: f64 },
+    /// `CauseLottery` was sent without a `LaunchBall` since the previous lottery
+    LaunchRequired,
+    /// Events were acknowledged although the output has no ack window
+    NoAckWindow,
+    /// The arguments are not valid for the method
+    InvalidArguments { message: String },
+    /// The output does not deliver through a single event callback
+    NotAnEventOutput,
+    /// A fault injected by chaos mode
+    #[cfg(feature = "chaos")]
+    InjectedFault { fault: &'static str },
+    /// A hall has no machine at the index
+    NoSuchMachine { index: usize, count: usize },
+    /// A game manager has no game with the id
+    NoSuchGame { id: String },
+    /// A game manager or tournament already has a game with the id
+    DuplicateGame { id: String },
+    /// A fake outcome has not been revealed yet
+    RevealPending,
+    /// The random number generators of an unseeded game were requested
+    NotSeeded,
+    /// A snapshot, random number generator state or proof cannot be restored
+    InvalidSnapshot { message: String },
+    /// A JavaScript callback threw, or a promise it returned was rejected
+    CallbackFailed { message: String },
+    /// A command of `WasmGame.replay` failed
+    ReplayFailed {
+        index: usize,
+        command: String,
+        cause: Box<PachisloError>,
+    },
+}
+
+impl PachisloError {
+    /// Returns whether the game state or the rules rejected a command before
+    /// it ran, which leaves the game unchanged.
+    fn is_rejection(&self) -> bool {
+        matches!(
+            self,
+            PachisloError::GameNotStarted { .. }
+                | PachisloError::AlreadyStarted
+                | PachisloError::UsageLimitReached { .. }
+                | PachisloError::CoolingDown { .. }
+                | PachisloError::LaunchRequired
+                | PachisloError::RevealPending
+        )
+    }
+}
+
+impl Display for PachisloError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PachisloError::UnknownCommand { command } => write!(
+                f,
+                "unknown command {command:?}; expected one of \"LaunchBall\", \"CauseLottery\", \"ConsumeHold\", \"StartGame\", \"FinishGame\" or \"Finish\""
+            ),
+            PachisloError::LockPoisoned => {
+                write!(f, "the game is unusable because an earlier step panicked")
+            }
+            PachisloError::Busy => write!(
+                f,
+                "the game is in use; callbacks cannot call back into the game during a step"
+            ),
+            PachisloError::NotRecording => write!(
+                f,
+                "no ghost recording is running; call \"start_ghost_recording\" first"
+            ),
+            PachisloError::GameNotStarted { command } => write!(
+                f,
+                "cannot run {command:?} before the game is started; send \"StartGame\" first"
+            ),
+            PachisloError::AlreadyStarted => write!(
+                f,
+                "the game is already running; send \"FinishGame\" before starting a new one"
+            ),
+            PachisloError::UsageLimitReached { command, limit } => write!(
+                f,
+                "{command:?} may only be used {limit} time(s) per session"
+            ),
+            PachisloError::CoolingDown { command, remaining } => write!(
+                f,
+                "{command:?} is cooling down; try again in {remaining:.0} ms"
+            ),
+            PachisloError::LaunchRequired => write!(
+                f,
+                "\"CauseLottery\" requires a \"LaunchBall\" since the previous lottery"
+            ),
+            PachisloError::NoAckWindow => write!(
+                f,
+                "the output has no ack window; call \"set_ack_window\" on its JsEventOutput first"
+            ),
+            PachisloError::InvalidArguments { message } => {
+                write!(f, "invalid arguments: {message}")
+            }
+            PachisloError::NotAnEventOutput => write!(
+                f,
+                "only outputs created with \"JsOutput.from_events\" support this"
+            ),
+            #[cfg(feature = "chaos")]
+            PachisloError::InjectedFault { fault } => write!(f, "injected fault: {fault}"),
+            PachisloError::NoSuchMachine { index, count } => write!(
+                f,
+                "no machine at index {index}; the hall has {count} machine(s)"
+            ),
+            PachisloError::NoSuchGame { id } => write!(f, "no game with id {id:?}"),
+            PachisloError::DuplicateGame { id } => {
+                write!(f, "a game with id {id:?} already exists")
+            }
+            PachisloError::RevealPending => write!(
+                f,
+                "a fake outcome has not been revealed yet; call \"ack_reveal\" first"
+            ),
+            PachisloError::NotSeeded => write!(
+                f,
+                "the game is not seeded; create it with \"new_with_seed\" or call \"set_seed\" first"
+            ),
+            PachisloError::InvalidConfig { message }
+            | PachisloError::InvalidSnapshot { message } => {
+                write!(f, "{message}")
+            }
+            PachisloError::CallbackFailed { message } => write!(f, "a callback failed: {message}"),
+            PachisloError::ReplayFailed {
+                index,
+                command,
+                cause,
+            } => write!(f, "replay failed at command {index} ({command:?}): {cause}"),
+        }
+    }
+}
+
+impl Error for PachisloError {}
+
+impl From<PachisloError> for JsValue {
+    fn from(error: PachisloError) -> Self {
+        let thrown = js_sys::Error::new(&error.to_string());
+        thrown.set_name("PachisloError");
+        if let Ok(fields) = serde_wasm_bindgen::to_value(&error) {
+            js_sys::Object::assign(&thrown, fields.unchecked_ref());
+        }
+
+        thrown.into()
+    }
+}
+
+/// The error of a callback that threw or rejected with `reason`.
+fn callback_failed(reason: JsValue) -> PachisloError {
+    let message = match reason.dyn_ref::<js_sys::Error>() {
+        Some(error) => error.message().into(),
+        None => reason.as_string().unwrap_or_else(|| format!("{reason:?}")),
+    };
+
+    PachisloError::CallbackFailed { message }
+}
+
+/// Checks that a command is valid in the given game state.
+///
+/// The engine silently ignores some invalid commands and panics on others, so
+/// they are rejected before reaching it.
+fn check_command(input: &str, state: &pachislo::game::GameState) -> Result<(), PachisloError> {
+    let started = !matches!(state, pachislo::game::GameState::Uninitialized);
+
+    match input {
+        "StartGame" if started => Err(PachisloError::AlreadyStarted),
+        "LaunchBall" | "CauseLottery" | "ConsumeHold" | "FinishGame" if !started => {
+            Err(PachisloError::GameNotStarted {
+                command: input.to_string(),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
 /// JavaScript-compatible input handler for the pachislo game.
 ///
 /// This struct implements the `UserInput` trait and serves as a bridge
@@ -116,21 +443,51 @@ impl JsInput {
 /// # Fields
 ///
 /// - `context` - JavaScript context object passed to callback functions
-/// - `default` - Callback for general state transitions
-/// - `finish_game` - Callback when the game session ends
-/// - `lottery_normal` - Callback for normal mode lottery results
-/// - `lottery_rush` - Callback for rush mode lottery results
-/// - `lottery_rush_continue` - Callback for rush continuation lottery results
-/// - `slot_producer` - Generates visual slot machine representations
+/// - `handlers` - The callbacks events are delivered to
+/// - `slot_rng` - Random number generator for slot symbols
+/// - `slot_reels` - Number of reels in a produced slot
+/// - `slot_symbols` - Symbols a reel can show
+/// - `slot_weights` - Symbol weights of a weighted slot layout
+/// - `produce_slots` - Whether lotteries produce a slot
+/// - `symbol_names` - Names delivered instead of the layout's symbols
+/// - `clock` - Clock for event retransmission timeouts
+/// - `label` - Label of the game, passed to every callback
+/// - `diffs` - Whether transitions are emitted as state diffs
+/// - `typed` - Whether transitions are emitted as `TransitionView` handles
+/// - `last_lottery` - The most recent lottery and its slot
+/// - `slots` - The symbols of the most recent slot, read by `WasmGame::slot_view`
+/// - `premium` - Whether the next lottery is reported as `Win::Premium`
+/// - `rust_outputs` - Registered Rust outputs receiving every event first
+/// - `throttle` - Coalesced events while the page is in the background
+/// - `pending` - Callback return values kept to be awaited by an async step
+/// - `batch` - Events collected instead of delivered during a batched step
+/// - `subscribers` - Listeners added with `WasmGame::subscribe`
 #[wasm_bindgen]
 pub struct JsOutput {
     context: JsValue,
-    default: Function,
-    finish_game: Function,
-    lottery_normal: Function,
-    lottery_rush: Function,
-    lottery_rush_continue: Function,
-    slot_producer: SlotProducer<u8>,
+    handlers: Handlers,
+    slot_rng: GameRng,
+    slot_reels: usize,
+    slot_symbols: Vec<u8>,
+    slot_weights: Option<slot::WeightedSlots>,
+    produce_slots: bool,
+    symbol_names: Option<Vec<String>>,
+    clock: SharedClock,
+    label: Option<String>,
+    diffs: bool,
+    typed: bool,
+    last_lottery: Option<event::LotteryEvent>,
+    slots: slot::SlotBuffer,
+    premium: bool,
+    rust_outputs: Vec<registry::RustOutput>,
+    throttle: Option<event::Throttle>,
+    pending: Option<Vec<JsValue>>,
+    batch: Option<Vec<BatchedEvent>>,
+    subscribers: subscription::SharedSubscribers,
+    /// The first callback error since the last `take_failure`
+    failure: Option<PachisloError>,
+    #[cfg(feature = "chaos")]
+    delay: Option<chaos::EventDelay<event::Outgoing>>,
 }
 
 #[wasm_bindgen]
@@ -146,29 +503,669 @@ impl JsOutput {
     /// * `lottery_rush` - Callback function for rush mode lottery results
     /// * `lottery_rush_continue` - Callback function for rush continuation results
     ///
+    /// If the game has a label (see [`WasmGame::set_label`]), it is passed to
+    /// every callback as an additional last argument.
+    ///
+    /// Any callback may be `null` or `undefined` to ignore that event. Slots
+    /// are still produced for ignored lotteries, so seeded games stay
+    /// reproducible regardless of which callbacks are given.
+    ///
     /// # Returns
     ///
     /// A new `JsOutput` instance configured with the provided callbacks.
-    /// The slot producer is automatically initialized with 3 reels and symbols 1-7.
+    /// The slot producer is automatically initialized with 3 reels and symbols 1-7;
+    /// use [`JsOutput::set_slot_layout`] to change it.
+    ///
+    /// # Deprecated
+    ///
+    /// Use [`JsOutput::from_events`] instead; the first use reports a
+    /// [`deprecation::DeprecationWarning`].
     #[wasm_bindgen(constructor)]
     pub fn new(
         context: JsValue,
-        default: Function,
-        finish_game: Function,
-        lottery_normal: Function,
-        lottery_rush: Function,
-        lottery_rush_continue: Function,
+        default: Option<Function>,
+        finish_game: Option<Function>,
+        lottery_normal: Option<Function>,
+        lottery_rush: Option<Function>,
+        lottery_rush_continue: Option<Function>,
     ) -> Self {
+        deprecation::warn(deprecation::JS_OUTPUT_CONSTRUCTOR);
+
+        JsOutput::with_handlers(
+            context,
+            Handlers::Callbacks {
+                default,
+                finish_game,
+                lottery_normal,
+                lottery_rush,
+                lottery_rush_continue,
+                reach: None,
+            },
+        )
+    }
+
+    /// Changes the number of reels and the symbols of produced slots.
+    ///
+    /// # Arguments
+    ///
+    /// * `reels` - Number of reels, at least 2
+    /// * `symbols` - Distinct symbols a reel can show, at least 2
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the layout has fewer than 2 reels or symbols, or
+    /// repeats a symbol, since losing slots could not be told apart from wins.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// output.set_slot_layout(5, new Uint8Array([1, 2, 3, 4, 5, 6, 7, 8, 9]));
+    /// ```
+    #[wasm_bindgen]
+    pub fn set_slot_layout(&mut self, reels: usize, symbols: Vec<u8>) -> Result<(), PachisloError> {
+        check_slot_layout(reels, &symbols).map_err(|message| PachisloError::InvalidConfig {
+            message: message.to_string(),
+        })?;
+
+        self.slot_reels = reels;
+        self.slots = slot::SlotBuffer::new(reels);
+        self.slot_symbols = symbols;
+        self.slot_weights = None;
+        self.symbol_names = None;
+
+        Ok(())
+    }
+
+    /// Changes the slot layout like [`JsOutput::set_slot_layout`], with
+    /// separate weights per symbol for winning and losing slots.
+    ///
+    /// A winning slot shows a symbol picked by the `win` weights on every
+    /// reel; each reel of a losing slot is picked by the `lose` weights.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` for the same layouts as `set_slot_layout`, if a
+    /// weight is negative or not finite, if no symbol has a positive `win`
+    /// weight, or if fewer than 2 symbols have a positive `lose` weight.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// output.set_weighted_slot_layout(3, [
+    ///     { symbol: 1, win: 3, lose: 10 },
+    ///     { symbol: 3, win: 3, lose: 10 },
+    ///     { symbol: 7, win: 4, lose: 1 },
+    /// ]);
+    /// ```
+    #[wasm_bindgen]
+    pub fn set_weighted_slot_layout(
+        &mut self,
+        reels: usize,
+        symbols: Vec<slot::WeightedSymbol>,
+    ) -> Result<(), PachisloError> {
+        let plain: Vec<u8> = symbols.iter().map(|symbol| symbol.symbol).collect();
+        check_slot_layout(reels, &plain).map_err(|message| PachisloError::InvalidConfig {
+            message: message.to_string(),
+        })?;
+        let weights =
+            slot::WeightedSlots::new(&symbols).map_err(|message| PachisloError::InvalidConfig {
+                message: message.to_string(),
+            })?;
+
+        self.slot_reels = reels;
+        self.slots = slot::SlotBuffer::new(reels);
+        self.slot_symbols = plain;
+        self.slot_weights = Some(weights);
+        self.symbol_names = None;
+
+        Ok(())
+    }
+
+    /// Names the symbols of the slot layout, or removes the names if omitted.
+    ///
+    /// Slots are then delivered with the names instead of the symbols, e.g.
+    /// `["7", "🍒", "7"]`. `names[i]` names the `i`-th symbol of the layout;
+    /// changing the layout removes the names.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the number of names differs from the number of
+    /// symbols or a name is repeated.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// output.set_slot_layout(3, new Uint8Array([1, 2, 3, 4]));
+    /// output.set_symbol_names(["🍒", "🔔", "7", "BAR"]);
+    /// ```
+    #[wasm_bindgen]
+    pub fn set_symbol_names(&mut self, names: Option<Vec<String>>) -> Result<(), PachisloError> {
+        if let Some(names) = &names {
+            slot::check_symbol_names(&self.slot_symbols, names).map_err(|message| {
+                PachisloError::InvalidConfig {
+                    message: message.to_string(),
+                }
+            })?;
+        }
+        self.symbol_names = names;
+
+        Ok(())
+    }
+
+    /// Turns the production of slots for lotteries off or back on.
+    ///
+    /// Producing a slot draws random symbols for every reel, which is wasted
+    /// work for headless simulations that never show one. Without slots,
+    /// lottery events carry empty reels (`slot: [[], null]`), no `"reach"`
+    /// events are emitted and [`WasmGame::slot_view`] keeps its last slot.
+    /// The lottery results are unaffected, but the slots of a seeded game
+    /// differ once production was off, since fewer symbols were drawn.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const output = JsOutput.from_events(new JsEventOutput(null, onEvent));
+    /// output.set_slot_production(false);
+    /// ```
+    #[wasm_bindgen]
+    pub fn set_slot_production(&mut self, enabled: bool) {
+        self.produce_slots = enabled;
+    }
+
+    /// Switches transition events to [`diff::StateDiff`] payloads.
+    ///
+    /// Transitions are then emitted as `"state_diff"` events, or passed to
+    /// the `default` callback, carrying only what changed; transitions that
+    /// change nothing are not emitted. The `"finish_game"` event and the
+    /// background summaries still carry full states, so a UI can rebase its
+    /// copy of the state on them.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// output.set_diff_events(true);
+    /// // in the event callback
+    /// if (type === "state_diff") {
+    ///     meter.balls += payload.balls ?? 0;
+    /// }
+    /// ```
+    #[wasm_bindgen]
+    pub fn set_diff_events(&mut self, enabled: bool) {
+        self.diffs = enabled;
+    }
+
+    /// Switches transition events to [`view::TransitionView`] handles.
+    ///
+    /// Transitions are then passed as a class instance whose fields are read
+    /// through getters, instead of a freshly serialized object per event. Call
+    /// `free()` on a handle when done with it, or let the garbage collector
+    /// reclaim it. Diff events, if enabled, take precedence.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// output.set_typed_events(true);
+    /// // in the event callback
+    /// if (type === "transition" && payload.mode_changed) {
+    ///     banner.show(payload.mode);
+    /// }
+    /// ```
+    #[wasm_bindgen]
+    pub fn set_typed_events(&mut self, enabled: bool) {
+        self.typed = enabled;
+    }
+
+    /// Sets the callback called with the first two [`slot::Reels`] right
+    /// before the lottery callback of a slot that starts with a reach.
+    ///
+    /// Event outputs receive `"reach"` events instead and ignore this.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// output.set_reach_callback((reels) => reachAnimation.play(reels));
+    /// ```
+    #[wasm_bindgen]
+    pub fn set_reach_callback(&mut self, callback: Option<Function>) {
+        if let Handlers::Callbacks { reach, .. } = &mut self.handlers {
+            *reach = callback;
+        }
+    }
+}
+
+impl Clone for JsOutput {
+    /// Clones the callbacks, slot layout and clock; the clone gets its own slot
+    /// generator, new instances of the Rust outputs and no subscribers.
+    fn clone(&self) -> Self {
+        JsOutput {
+            slot_reels: self.slot_reels,
+            slots: slot::SlotBuffer::new(self.slot_reels),
+            slot_symbols: self.slot_symbols.clone(),
+            slot_weights: self.slot_weights.clone(),
+            produce_slots: self.produce_slots,
+            symbol_names: self.symbol_names.clone(),
+            clock: self.clock.clone(),
+            label: self.label.clone(),
+            diffs: self.diffs,
+            typed: self.typed,
+            rust_outputs: self.rust_outputs.clone(),
+            ..JsOutput::with_handlers(self.context.clone(), self.handlers.clone())
+        }
+    }
+}
+
+/// The JavaScript callbacks a `JsOutput` delivers events to.
+#[derive(Clone)]
+enum Handlers {
+    /// One callback per event type; events without a callback are ignored
+    Callbacks {
+        default: Option<Function>,
+        finish_game: Option<Function>,
+        lottery_normal: Option<Function>,
+        lottery_rush: Option<Function>,
+        lottery_rush_continue: Option<Function>,
+        reach: Option<Function>,
+    },
+    /// A single callback receiving `(eventType, payload)`, and `seq` if the
+    /// events are acknowledged
+    Emitter {
+        emit: Function,
+        acks: Option<event::AckWindow<(&'static str, JsValue)>>,
+    },
+}
+
+/// The lottery an output event belongs to.
+#[derive(Clone, Copy)]
+enum LotteryKind {
+    Normal,
+    Rush,
+    RushContinue,
+}
+
+impl LotteryKind {
+    /// The event type of an event output for lotteries of this kind.
+    fn event_type(self) -> &'static str {
+        match self {
+            LotteryKind::Normal => event::LOTTERY_NORMAL,
+            LotteryKind::Rush => event::LOTTERY_RUSH,
+            LotteryKind::RushContinue => event::LOTTERY_RUSH_CONTINUE,
+        }
+    }
+}
+
+impl JsOutput {
+    /// An output ignoring every event, for games run only for their result.
+    pub(crate) fn silent() -> Self {
+        JsOutput::with_handlers(
+            JsValue::UNDEFINED,
+            Handlers::Callbacks {
+                default: None,
+                finish_game: None,
+                lottery_normal: None,
+                lottery_rush: None,
+                lottery_rush_continue: None,
+                reach: None,
+            },
+        )
+    }
+
+    fn with_handlers(context: JsValue, handlers: Handlers) -> Self {
         JsOutput {
             context,
-            default,
-            finish_game,
-            lottery_normal,
-            lottery_rush,
-            lottery_rush_continue,
-            slot_producer: SlotProducer::new(3, (1..=7).collect()),
+            handlers,
+            slot_rng: GameRng::default(),
+            slot_reels: 3,
+            slot_symbols: (1..=7).collect(),
+            slot_weights: None,
+            produce_slots: true,
+            symbol_names: None,
+            clock: clock::from_function(None),
+            label: None,
+            diffs: false,
+            typed: false,
+            last_lottery: None,
+            slots: slot::SlotBuffer::new(3),
+            premium: false,
+            rust_outputs: Vec::new(),
+            throttle: None,
+            pending: None,
+            batch: None,
+            subscribers: Default::default(),
+            failure: None,
+            #[cfg(feature = "chaos")]
+            delay: None,
+        }
+    }
+
+    /// Passes an event to the subscribers; the payload is only built if
+    /// there are any.
+    fn notify_subscribers(&mut self, event_type: &'static str, payload: impl FnOnce() -> JsValue) {
+        let listeners = self.subscribers.borrow().listeners();
+        if listeners.is_empty() {
+            return;
+        }
+
+        let event = event::batched(event_type, &payload());
+        for listener in listeners {
+            let returned = listener.call1(&JsValue::UNDEFINED, &event);
+            settle(&mut self.pending, &mut self.failure, returned);
+        }
+    }
+
+    /// Delivers a single-payload event with the priority of its type.
+    fn emit(&mut self, event_type: &'static str, payload: JsValue) {
+        self.emit_with_priority(event_type, event::Priority::of(event_type), payload);
+    }
+
+    /// Delivers a single-payload event.
+    fn emit_with_priority(
+        &mut self,
+        event_type: &'static str,
+        priority: event::Priority,
+        payload: JsValue,
+    ) {
+        if let Some(batch) = &mut self.batch {
+            batch.push((event_type, payload));
+            return;
+        }
+
+        #[cfg(feature = "chaos")]
+        if let Some(delay) = &mut self.delay
+            && let Handlers::Emitter { .. } = self.handlers
+        {
+            for (event_type, priority, payload) in delay.pass((event_type, priority, payload)) {
+                self.dispatch(event_type, priority, payload);
+            }
+            return;
+        }
+
+        self.dispatch(event_type, priority, payload);
+    }
+
+    /// Delivers a single-payload event to the handlers.
+    fn dispatch(&mut self, event_type: &'static str, priority: event::Priority, payload: JsValue) {
+        match &mut self.handlers {
+            Handlers::Callbacks {
+                default,
+                finish_game,
+                ..
+            } => {
+                let callback = if event_type == event::FINISH_GAME {
+                    finish_game
+                } else {
+                    default
+                };
+                if let Some(callback) = callback {
+                    let returned = match &self.label {
+                        Some(label) => {
+                            callback.call2(&self.context, &payload, &JsValue::from_str(label))
+                        }
+                        None => callback.call1(&self.context, &payload),
+                    };
+                    settle(&mut self.pending, &mut self.failure, returned);
+                }
+            }
+            Handlers::Emitter { emit, acks: None } => {
+                let returned = event::deliver(
+                    emit,
+                    &self.context,
+                    event_type,
+                    &payload,
+                    None,
+                    self.label.as_deref(),
+                );
+                settle(&mut self.pending, &mut self.failure, returned);
+            }
+            Handlers::Emitter {
+                emit,
+                acks: Some(acks),
+            } => {
+                let now = read_clock(&self.clock, &mut self.failure);
+                for (seq, (event_type, payload)) in acks.push((event_type, payload), priority, now)
+                {
+                    let returned = event::deliver(
+                        emit,
+                        &self.context,
+                        event_type,
+                        &payload,
+                        Some(seq),
+                        self.label.as_deref(),
+                    );
+                    settle(&mut self.pending, &mut self.failure, returned);
+                }
+            }
+        }
+    }
+
+    /// Produces the slot for a lottery result and delivers the lottery event.
+    fn emit_lottery(&mut self, kind: LotteryKind, result: pachislo::lottery::LotteryResult) {
+        // Produced even when throttled, so seeded games stay reproducible
+        let (first, revealed) = if self.produce_slots {
+            let (first, revealed) = self.produce_slot(&result);
+            self.slots.write(&first, revealed.as_deref());
+            (first, revealed)
+        } else {
+            (Vec::new(), None)
+        };
+        let names = self.symbol_names.as_deref();
+        let reach = slot::reach(&first)
+            .map(|reels| slot::Reels::new(reels.to_vec(), &self.slot_symbols, names));
+        let slot = (
+            slot::Reels::new(first, &self.slot_symbols, names),
+            revealed.map(|revealed| slot::Reels::new(revealed, &self.slot_symbols, names)),
+        );
+        let result = if std::mem::take(&mut self.premium) {
+            LotteryResult::Win(Win::Premium)
+        } else {
+            LotteryResult::from(result)
+        };
+        let lottery = event::LotteryEvent {
+            result,
+            slot: slot.clone(),
+        };
+        self.notify_subscribers(kind.event_type(), || {
+            serde_wasm_bindgen::to_value(&lottery).unwrap()
+        });
+        self.last_lottery = Some(lottery);
+        if self.throttled(event::Observed::Lottery(result)) {
+            return;
+        }
+
+        match &self.handlers {
+            Handlers::Callbacks {
+                lottery_normal,
+                lottery_rush,
+                lottery_rush_continue,
+                reach: on_reach,
+                ..
+            } if self.batch.is_none() => {
+                if let (Some(on_reach), Some(reels)) = (on_reach, &reach) {
+                    let reels = serde_wasm_bindgen::to_value(reels).unwrap();
+                    let returned = match &self.label {
+                        Some(label) => {
+                            on_reach.call2(&self.context, &reels, &JsValue::from_str(label))
+                        }
+                        None => on_reach.call1(&self.context, &reels),
+                    };
+                    settle(&mut self.pending, &mut self.failure, returned);
+                }
+
+                let callback = match kind {
+                    LotteryKind::Normal => lottery_normal,
+                    LotteryKind::Rush => lottery_rush,
+                    LotteryKind::RushContinue => lottery_rush_continue,
+                };
+                let Some(callback) = callback else {
+                    return;
+                };
+                let result = serde_wasm_bindgen::to_value(&result).unwrap();
+                let slot = serde_wasm_bindgen::to_value(&slot).unwrap();
+                let returned = match &self.label {
+                    Some(label) => {
+                        callback.call3(&self.context, &result, &slot, &JsValue::from_str(label))
+                    }
+                    None => callback.call2(&self.context, &result, &slot),
+                };
+                settle(&mut self.pending, &mut self.failure, returned);
+            }
+            // Batched steps collect event payloads whatever the handlers
+            _ => {
+                if let Some(reels) = reach {
+                    let reach = event::ReachEvent { reels };
+                    self.emit(event::REACH, serde_wasm_bindgen::to_value(&reach).unwrap());
+                }
+
+                let payload = event::LotteryEvent { result, slot };
+                self.emit(
+                    kind.event_type(),
+                    serde_wasm_bindgen::to_value(&payload).unwrap(),
+                );
+            }
+        }
+    }
+
+    /// Returns the most recent lottery with its slot, `None` before the first one.
+    fn last_lottery(&self) -> Option<&event::LotteryEvent> {
+        self.last_lottery.as_ref()
+    }
+
+    /// Returns the symbols of the most recent slot.
+    fn slots(&self) -> &slot::SlotBuffer {
+        &self.slots
+    }
+
+    /// Returns the generator used for slot symbols.
+    fn slot_rng(&self) -> &GameRng {
+        &self.slot_rng
+    }
+
+    /// Returns the listeners added with `WasmGame::subscribe`.
+    fn subscribers(&self) -> &subscription::SharedSubscribers {
+        &self.subscribers
+    }
+
+    /// Sets the label passed to every callback.
+    fn set_label(&mut self, label: Option<String>) {
+        self.label = label;
+    }
+
+    /// Starts or stops delaying events of an event output.
+    #[cfg(feature = "chaos")]
+    fn set_event_delay(&mut self, delay: Option<chaos::EventDelay<event::Outgoing>>) {
+        self.delay = delay;
+    }
+
+    /// Starts keeping the values callbacks return, e.g. promises to await.
+    fn start_collecting(&mut self) {
+        self.pending = Some(Vec::new());
+    }
+
+    /// Keeps the error of a callback that threw `reason`, unless one is kept
+    /// already.
+    fn fail(&mut self, reason: JsValue) {
+        self.failure.get_or_insert_with(|| callback_failed(reason));
+    }
+
+    /// Returns the first error a callback threw since the last call.
+    ///
+    /// A throwing callback does not interrupt the engine: the step completes
+    /// and the remaining events are still delivered, so the state stays
+    /// consistent, and the error is reported once the step is done.
+    fn take_failure(&mut self) -> Result<(), PachisloError> {
+        self.failure.take().map_or(Ok(()), Err)
+    }
+
+    /// Starts collecting events instead of delivering them.
+    fn start_batch(&mut self) {
+        self.batch = Some(Vec::new());
+    }
+
+    /// Stops collecting events and returns those collected so far.
+    fn take_batch(&mut self) -> Vec<BatchedEvent> {
+        self.batch.take().unwrap_or_default()
+    }
+
+    /// Stops keeping callback return values and returns those kept so far.
+    fn take_collected(&mut self) -> Vec<JsValue> {
+        self.pending.take().unwrap_or_default()
+    }
+
+    /// Replaces the clock used for event retransmission timeouts.
+    fn set_clock(&mut self, clock: SharedClock) {
+        self.clock = clock;
+    }
+
+    /// Replaces the generator used for slot symbols, e.g. to make them reproducible.
+    fn set_slot_rng(&mut self, rng: GameRng) {
+        self.slot_rng = rng;
+    }
+
+    /// Produces the slot shown for a lottery result, plus the slot revealed
+    /// afterwards for fake results.
+    fn produce_slot(
+        &mut self,
+        result: &pachislo::lottery::LotteryResult,
+    ) -> (Vec<u8>, Option<Vec<u8>>) {
+        if let Some(weights) = &self.slot_weights {
+            return weights.produce(self.slot_reels, result, &mut self.slot_rng);
+        }
+
+        SlotProducer::with_rng(
+            self.slot_reels,
+            self.slot_symbols.clone(),
+            &mut self.slot_rng,
+        )
+        .produce(result)
+    }
+}
+
+/// Keeps a callback's return value if return values are being collected, or
+/// the error it threw if it is the first one.
+fn settle(
+    pending: &mut Option<Vec<JsValue>>,
+    failure: &mut Option<PachisloError>,
+    returned: Result<JsValue, JsValue>,
+) {
+    match returned {
+        Ok(returned) => {
+            if let Some(pending) = pending
+                && !returned.is_undefined()
+            {
+                pending.push(returned);
+            }
         }
+        Err(reason) => {
+            failure.get_or_insert_with(|| callback_failed(reason));
+        }
+    }
+}
+
+/// Returns the time of `clock`, keeping its error like that of a callback
+/// and falling back to the wall clock if it throws.
+fn read_clock(clock: &SharedClock, failure: &mut Option<PachisloError>) -> f64 {
+    clock.now().unwrap_or_else(|error| {
+        failure.get_or_insert(error);
+        environment::now()
+    })
+}
+
+/// Checks that a slot layout can show both winning and losing slots.
+fn check_slot_layout(reels: usize, symbols: &[u8]) -> Result<(), &'static str> {
+    if reels < 2 {
+        return Err("a slot needs at least 2 reels");
+    }
+    if symbols.len() < 2 {
+        return Err("a slot needs at least 2 symbols");
+    }
+    if symbols
+        .iter()
+        .enumerate()
+        .any(|(i, symbol)| symbols[..i].contains(symbol))
+    {
+        return Err("slot symbols must be distinct");
     }
+
+    Ok(())
 }
 
 impl<F, R> UserInput<JsOutput, F, R> for JsInput
@@ -176,64 +1173,90 @@ where
     F: FnMut(usize) -> f64,
     R: Rng,
 {
-    fn wait_for_input(&mut self) -> Command<Self, JsOutput, F, R> {
+    fn wait_for_input(&mut self) -> PachisloCommand<Self, JsOutput, F, R> {
         unreachable!()
     }
 }
 
+impl EngineOutput for JsOutput {
+    fn premium_win(&mut self) {
+        self.premium = true;
+    }
+}
+
 impl UserOutput for JsOutput {
     fn default(&mut self, state: pachislo::game::Transition) {
-        self.default
-            .call1(
-                &self.context,
-                &serde_wasm_bindgen::to_value(&Transition::from(state)).unwrap(),
-            )
-            .unwrap();
+        for rust_output in &mut self.rust_outputs {
+            rust_output.output.default(state);
+        }
+
+        let transition = Transition::from(state);
+        self.notify_subscribers(event::TRANSITION, || {
+            serde_wasm_bindgen::to_value(&transition).unwrap()
+        });
+        if self.throttled(event::Observed::Transition(transition)) {
+            return;
+        }
+
+        let priority = event::Priority::of_transition(transition);
+        if self.diffs {
+            let diff = StateDiff::from(transition);
+            if !diff.is_empty() {
+                self.emit_with_priority(
+                    event::STATE_DIFF,
+                    priority,
+                    serde_wasm_bindgen::to_value(&diff).unwrap(),
+                );
+            }
+            return;
+        }
+
+        let payload = if self.typed {
+            view::TransitionView::from(transition).into()
+        } else {
+            serde_wasm_bindgen::to_value(&transition).unwrap()
+        };
+        self.emit_with_priority(event::TRANSITION, priority, payload);
     }
 
     fn finish_game(&mut self, state: &pachislo::game::GameState) {
-        self.finish_game
-            .call1(
-                &self.context,
-                &serde_wasm_bindgen::to_value(&GameState::from(*state)).unwrap(),
-            )
-            .unwrap();
+        for rust_output in &mut self.rust_outputs {
+            rust_output.output.finish_game(state);
+        }
+
+        let state = GameState::from(*state);
+        self.notify_subscribers(event::FINISH_GAME, || {
+            serde_wasm_bindgen::to_value(&state).unwrap()
+        });
+        if self.throttled(event::Observed::FinishGame(state)) {
+            return;
+        }
+
+        self.emit(
+            event::FINISH_GAME,
+            serde_wasm_bindgen::to_value(&state).unwrap(),
+        );
     }
 
     fn lottery_normal(&mut self, result: pachislo::lottery::LotteryResult) {
-        let slot = self.slot_producer.produce(&result);
-
-        self.lottery_normal
-            .call2(
-                &self.context,
-                &serde_wasm_bindgen::to_value(&LotteryResult::from(result)).unwrap(),
-                &serde_wasm_bindgen::to_value(&slot).unwrap(),
-            )
-            .unwrap();
+        for rust_output in &mut self.rust_outputs {
+            rust_output.output.lottery_normal(result);
+        }
+        self.emit_lottery(LotteryKind::Normal, result);
     }
 
     fn lottery_rush(&mut self, result: pachislo::lottery::LotteryResult) {
-        let slot = self.slot_producer.produce(&result);
-
-        self.lottery_rush
-            .call2(
-                &self.context,
-                &serde_wasm_bindgen::to_value(&LotteryResult::from(result)).unwrap(),
-                &serde_wasm_bindgen::to_value(&slot).unwrap(),
-            )
-            .unwrap();
+        for rust_output in &mut self.rust_outputs {
+            rust_output.output.lottery_rush(result);
+        }
+        self.emit_lottery(LotteryKind::Rush, result);
     }
 
     fn lottery_rush_continue(&mut self, result: pachislo::lottery::LotteryResult) {
-        let slot = self.slot_producer.produce(&result);
-
-        self.lottery_rush_continue
-            .call2(
-                &self.context,
-                &serde_wasm_bindgen::to_value(&LotteryResult::from(result)).unwrap(),
-                &serde_wasm_bindgen::to_value(&slot).unwrap(),
-            )
-            .unwrap();
+        for rust_output in &mut self.rust_outputs {
+            rust_output.output.lottery_rush_continue(result);
+        }
+        self.emit_lottery(LotteryKind::RushContinue, result);
     }
 }
 
@@ -258,10 +1281,56 @@ impl From<std::ops::ControlFlow<()>> for ControlFlow {
     }
 }
 
+/// Default minimum milliseconds between background ticks.
+const DEFAULT_BACKGROUND_INTERVAL: f64 = 1000.0;
+
+/// An event collected by a batched step: its type and payload.
+type BatchedEvent = (&'static str, JsValue);
+
 /// Type alias for the internal game instance with specific type parameters.
-/// This represents a pachislo game with JavaScript input/output and a boxed
+/// This represents a pachislo game with JavaScript output and a boxed
 /// function for rush continuation probability calculation.
-type InnerGame = Game<JsInput, JsOutput, Box<dyn FnMut(usize) -> f64>>;
+type InnerGame = Engine<JsOutput>;
+
+/// Creates a game whose lottery and slot symbols are both drawn from `rng`.
+///
+/// # Panics
+///
+/// Panics if the configuration is invalid.
+fn new_inner_game(output: JsOutput, config: Config, mut rng: GameRng) -> InnerGame {
+    let mechanics = config.mechanics();
+    let Some(start) = config.randomized_start else {
+        let mut engine = new_engine(output, config.into(), rng);
+        engine.set_mechanics(mechanics);
+        return engine;
+    };
+
+    let warm_up_rng = rng.split();
+    let mut engine = new_engine(output, config.clone().into(), rng);
+    engine.set_mechanics(mechanics.clone());
+    engine.merge_stats(&warmup::warm_up(
+        config.into(),
+        mechanics,
+        start,
+        warm_up_rng,
+    ));
+
+    engine
+}
+
+/// Like [`new_inner_game`], for a configuration built natively.
+///
+/// # Panics
+///
+/// Panics if the configuration is invalid.
+fn new_engine(
+    mut output: JsOutput,
+    config: pachislo::config::Config<Box<dyn FnMut(usize) -> f64>>,
+    mut rng: GameRng,
+) -> InnerGame {
+    output.set_slot_rng(rng.split());
+    Engine::new(config, output, rng).unwrap()
+}
 
 /// The main WebAssembly-compatible pachislo game interface.
 ///
@@ -273,13 +1342,45 @@ type InnerGame = Game<JsInput, JsOutput, Box<dyn FnMut(usize) -> f64>>;
 ///
 /// The game instance is wrapped in a `Mutex` to provide thread safety
 /// when accessed from JavaScript, which may call methods from different
-/// contexts or web workers.
+/// contexts or web workers. Methods never wait for the mutex: a call made
+/// while the game is in use, e.g. from one of its own callbacks during a
+/// step, throws a `Busy` error; see [`WasmGame::is_busy`].
+///
+/// Builds with the `single-threaded` feature keep the game in a `RefCell`
+/// instead, saving the locking overhead where games are only used from one
+/// thread, as in most browsers. Re-entrant calls throw `Busy` all the same.
 #[wasm_bindgen]
 pub struct WasmGame {
-    game: Mutex<InnerGame>,
+    game: SessionCell,
 }
 
-#[wasm_bindgen]
+/// Where a `WasmGame` keeps its session.
+#[cfg(not(feature = "single-threaded"))]
+type SessionCell = Mutex<Session>;
+#[cfg(feature = "single-threaded")]
+type SessionCell = RefCell<Session>;
+
+/// A game together with the bookkeeping `WasmGame` keeps about it.
+struct Session {
+    engine: InnerGame,
+    /// The configuration the engine runs with, to build forks from
+    config: Config,
+    ghost: Option<GhostRecorder>,
+    history: History,
+    balls_series: BallsSeries,
+    streak_alerts: StreakAlerts,
+    rules: CommandRules,
+    clock: SharedClock,
+    /// Staged reveals of fake outcomes, `None` while disabled
+    reveal: Option<reveal::StagedReveal>,
+    /// Whether the random number generators were seeded by the application
+    seeded: bool,
+    undo: UndoStack,
+    #[cfg(feature = "chaos")]
+    chaos: Option<chaos::Chaos>,
+}
+
+#[wasm_bindgen]
 impl WasmGame {
     /// Creates a new pachislo game instance.
     ///
@@ -293,14 +1394,322 @@ impl WasmGame {
     ///
     /// A new `WasmGame` instance ready to accept commands.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the game initialization fails due to invalid configuration.
+    /// Throws an `Error` describing every problem if the configuration is
+    /// invalid; see [`Config::validate`].
     #[wasm_bindgen(constructor)]
-    pub fn new(input: JsInput, output: JsOutput, config: Config) -> Self {
-        Self {
-            game: Mutex::new(Game::new(config.into(), input, output).unwrap()),
+    pub fn new(
+        input: JsInput,
+        output: JsOutput,
+        config: Config,
+    ) -> Result<WasmGame, PachisloError> {
+        Self::with_rng(input, output, config, GameRng::default(), false)
+    }
+
+    /// Creates a game whose random outcomes are fully determined by `seed`.
+    ///
+    /// Two games created with the same seed and configuration produce the same
+    /// lottery results and slot symbols for the same sequence of commands, which
+    /// makes demos, automated tests and bug reports reproducible.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The JavaScript input handler
+    /// * `output` - The JavaScript output handler with callback functions
+    /// * `config` - Game configuration including ball settings and probabilities
+    /// * `seed` - Seed for the game's random number generator
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` describing every problem if the configuration is
+    /// invalid; see [`Config::validate`].
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const game = WasmGame.new_with_seed(input, output, config, 12345n);
+    /// ```
+    #[wasm_bindgen]
+    pub fn new_with_seed(
+        input: JsInput,
+        output: JsOutput,
+        config: Config,
+        seed: u64,
+    ) -> Result<WasmGame, PachisloError> {
+        Self::with_rng(input, output, config, GameRng::from_seed(seed), true)
+    }
+
+    /// Creates a seeded game and replays a recorded command sequence on it.
+    ///
+    /// The game is created like [`WasmGame::new_with_seed`] and runs every
+    /// command as `run_step_with_command` would, so the output receives all
+    /// events of the original session again and the game ends up in the same
+    /// state. The seed and commands of a session are all it takes to share
+    /// or reproduce it, e.g. the `command`s of `get_history()`; `"Reveal"`
+    /// entries of staged reveals are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The JavaScript input handler
+    /// * `output` - The JavaScript output handler receiving the replayed events
+    /// * `config` - The configuration of the recorded session
+    /// * `seed` - The seed of the recorded session
+    /// * `commands` - The recorded commands, in order
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the configuration is invalid, or naming the first
+    /// command that fails, e.g. because the recording was made with another
+    /// seed or configuration.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const commands = game.get_history().map((entry) => entry.command);
+    /// share({ seed, commands });
+    /// // Elsewhere
+    /// const replay = WasmGame.replay(input, output, config, shared.seed, shared.commands);
+    /// ```
+    #[wasm_bindgen]
+    pub fn replay(
+        input: JsInput,
+        output: JsOutput,
+        config: Config,
+        seed: u64,
+        commands: Vec<String>,
+    ) -> Result<WasmGame, PachisloError> {
+        let game = Self::new_with_seed(input, output, config, seed)?;
+
+        for (index, command) in commands.iter().enumerate() {
+            if command == reveal::REVEAL_COMMAND {
+                continue;
+            }
+            game.step(command)
+                .map_err(|cause| PachisloError::ReplayFailed {
+                    index,
+                    command: command.clone(),
+                    cause: Box::new(cause),
+                })?;
+        }
+
+        Ok(game)
+    }
+
+    /// Reseeds the game's random number generator.
+    ///
+    /// Every random outcome after this call is determined by `seed`, no matter
+    /// how many were drawn before. The game state itself is left unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    #[wasm_bindgen]
+    pub fn set_seed(&self, seed: u64) -> Result<(), PachisloError> {
+        let mut session = self.session()?;
+
+        let mut rng = GameRng::from_seed(seed);
+        session.engine.output_mut().set_slot_rng(rng.split());
+        session.engine.set_rng(rng);
+        session.seeded = true;
+
+        Ok(())
+    }
+
+    /// Replaces the clock used for every timestamp the game takes: cooldowns,
+    /// ghost timings, history entries and event retransmission.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - Function returning the current time in milliseconds, or
+    ///   `undefined` to go back to `Date.now()`
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired. A clock
+    /// function that throws or returns anything but a number makes the
+    /// methods reading it throw a `CallbackFailed` error.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// let time = 0;
+    /// game.set_clock(() => time);
+    /// game.run_step_with_command("StartGame");
+    /// time += 1000;
+    /// ```
+    #[wasm_bindgen]
+    pub fn set_clock(&self, now: Option<Function>) -> Result<(), PachisloError> {
+        let mut session = self.session()?;
+
+        let clock = clock::from_function(now);
+        session.engine.output_mut().set_clock(clock.clone());
+        session.clock = clock;
+
+        Ok(())
+    }
+
+    /// Sets a label, e.g. a machine name or UUID, to attribute this game's data.
+    ///
+    /// The label is passed to every output callback as an additional last
+    /// argument and included in snapshots. `undefined` removes it.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// game.set_label(crypto.randomUUID());
+    /// const events = new JsEventOutput(this, (type, payload, seq, label) => log.push({ type, label }));
+    /// ```
+    #[wasm_bindgen]
+    pub fn set_label(&self, label: Option<String>) -> Result<(), PachisloError> {
+        let mut session = self.session()?;
+
+        session.engine.output_mut().set_label(label);
+
+        Ok(())
+    }
+
+    /// Returns the label set with [`WasmGame::set_label`].
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    #[wasm_bindgen]
+    pub fn label(&self) -> Result<Option<String>, PachisloError> {
+        let session = self.session()?;
+
+        Ok(session.engine.output().label.clone())
+    }
+
+    /// Captures the complete internal game state.
+    ///
+    /// The snapshot contains the balls, mode and rush counter and, for seeded
+    /// games only, the random number generator states. It can be stored with
+    /// `JSON.stringify` and passed to [`WasmGame::restore`] after a reload.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// addEventListener("pagehide", () => {
+    ///     localStorage.setItem("game", JSON.stringify(game.snapshot()));
+    /// });
+    /// ```
+    #[wasm_bindgen]
+    pub fn snapshot(&self) -> Result<GameSnapshot, PachisloError> {
+        let session = self.session()?;
+        #[cfg(feature = "chaos")]
+        chaos::inject(&session.chaos, chaos::Fault::Serialization)?;
+
+        let output = session.engine.output();
+        let slot_rng = session.seeded.then(|| output.slot_rng());
+
+        Ok(snapshot::capture(
+            &session.engine,
+            slot_rng,
+            output.label.as_deref(),
+        ))
+    }
+
+    /// Restores a snapshot taken with [`WasmGame::snapshot`].
+    ///
+    /// No output callbacks are called; render the restored state with
+    /// [`WasmGame::get_state`]. Snapshots of seeded games also restore the
+    /// random number generators, so the game continues exactly as the
+    /// original would have; otherwise the current generators are kept.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` without changing the game if the snapshot has an
+    /// unsupported version or invalid generator states, or if the game mutex
+    /// cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const saved = localStorage.getItem("game");
+    /// if (saved) game.restore(JSON.parse(saved));
+    /// ```
+    #[wasm_bindgen]
+    pub fn restore(&self, snapshot: GameSnapshot) -> Result<(), PachisloError> {
+        let mut session = self.session()?;
+
+        if let Some(slot_rng) =
+            snapshot::restore(&mut session.engine, snapshot).map_err(|message| {
+                PachisloError::InvalidSnapshot {
+                    message: message.to_string(),
+                }
+            })?
+        {
+            session.engine.output_mut().set_slot_rng(slot_rng);
+            session.seeded = true;
+        }
+        session.undo.clear();
+
+        Ok(())
+    }
+
+    /// Returns the states of the random number generators of a seeded game.
+    ///
+    /// Stored next to application state taken mid-session, e.g. a custom
+    /// save format, the states let [`WasmGame::set_rng_state`] resume with
+    /// exactly the outcomes the original game would have drawn. Snapshots
+    /// include them already.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game is not seeded, since the states would
+    /// let players predict upcoming outcomes, or if the game mutex cannot be
+    /// acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// save.rng = game.rng_state();
+    /// // After a reload
+    /// game.set_rng_state(save.rng);
+    /// ```
+    #[wasm_bindgen]
+    pub fn rng_state(&self) -> Result<RngSnapshot, PachisloError> {
+        let session = self.session()?;
+        if !session.seeded {
+            return Err(PachisloError::NotSeeded);
         }
+
+        Ok(RngSnapshot::capture(
+            session.engine.rng(),
+            session.engine.output().slot_rng(),
+        ))
+    }
+
+    /// Replaces the random number generators with states from
+    /// [`WasmGame::rng_state`]; the game counts as seeded afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` without changing the game if a state is invalid, or
+    /// if the game mutex cannot be acquired.
+    #[wasm_bindgen]
+    pub fn set_rng_state(&self, state: RngSnapshot) -> Result<(), PachisloError> {
+        let (lottery, slot) = state
+            .decode()
+            .ok_or_else(|| PachisloError::InvalidSnapshot {
+                message: "invalid random number generator state".to_string(),
+            })?;
+
+        let mut session = self.session()?;
+        session.engine.set_rng(lottery);
+        session.engine.output_mut().set_slot_rng(slot);
+        session.seeded = true;
+
+        Ok(())
     }
 
     /// Executes a single game step with the specified command.
@@ -315,85 +1724,1201 @@ impl WasmGame {
     /// Returns `ControlFlow::Continue` if the game should continue,
     /// or `ControlFlow::Break` if the game has finished.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if:
+    /// Throws an `Error` without changing the game state if:
     /// - The command string is not recognized
     /// - The game mutex cannot be acquired
-    /// - The game engine encounters an internal error
+    /// - The command is not valid in the current state, e.g. `"LaunchBall"`
+    ///   before `"StartGame"` or `"StartGame"` while a game is running
+    /// - The command breaks a configured usage limit, cooldown or ordering
+    ///   rule; see [`WasmGame::command_availability`]
+    /// - The clock function set with [`WasmGame::set_clock`] throws, with a
+    ///   `CallbackFailed` error
+    ///
+    /// Throws a `CallbackFailed` error if an output callback threw. The
+    /// command has been executed in that case: the remaining callbacks of
+    /// the step were still called and the state is the one after the
+    /// command, so it must not be sent again.
     ///
     /// # Example
     ///
     /// ```javascript
-    /// const result = game.run_step_with_command("StartGame");
-    /// if (result === ControlFlow.Continue) {
-    ///     // Game continues, ready for next command
+    /// try {
+    ///     const result = game.run_step_with_command("StartGame");
+    ///     if (result === ControlFlow.Continue) {
+    ///         // Game continues, ready for next command
+    ///     }
+    /// } catch (error) {
+    ///     console.error(error.message);
     /// }
     /// ```
     #[wasm_bindgen]
-    pub fn run_step_with_command(&self, command: String) -> ControlFlow {
-        let command = convert_string_to_command(&command).unwrap();
+    pub fn run_step_with_command(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "Command")] command: String,
+    ) -> Result<ControlFlow, PachisloError> {
+        self.step(&command)
+    }
+
+    /// Executes a single game step and waits for the promises its callbacks return.
+    ///
+    /// Works like [`WasmGame::run_step_with_command`], but every value an
+    /// output callback returns during the step is awaited, so the game can
+    /// wait for reel or fanfare animations before the next step. Callbacks of
+    /// one step still run in order without waiting for each other; the
+    /// returned promise settles once all of their promises have.
+    ///
+    /// # Errors
+    ///
+    /// Rejects with an `Error` like `run_step_with_command`, or with a
+    /// `CallbackFailed` error carrying the reason of the first callback
+    /// promise that rejects.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const events = new JsEventOutput(null, async (type, payload) => {
+    ///     if (type === "lottery_normal") await spinReels(payload.slot);
+    /// });
+    /// // ...
+    /// await game.run_step_with_command_async("CauseLottery");
+    /// ```
+    #[wasm_bindgen]
+    pub async fn run_step_with_command_async(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "Command")] command: String,
+    ) -> Result<ControlFlow, PachisloError> {
+        let (control_flow, pending) = self.step_collecting(&command)?;
 
-        self.game
-            .lock()
-            .unwrap()
-            .run_step_with_command(command)
-            .into()
+        if !pending.is_empty() {
+            let pending = pending.into_iter().collect::<js_sys::Array>();
+            JsFuture::from(js_sys::Promise::all(&pending))
+                .await
+                .map_err(callback_failed)?;
+        }
+
+        Ok(control_flow)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Executes a single game step and returns its events instead of
+    /// delivering them.
+    ///
+    /// Works like [`WasmGame::run_step_with_command`], but none of the output
+    /// callbacks are called: every event of the step is returned in order as
+    /// `{ type, payload }`, with the event types and payloads of an event
+    /// output (see [`JsOutput::from_events`]), so a UI can apply the step at
+    /// once with a single boundary crossing. Batched events are not numbered
+    /// or held back by an acknowledgement window, and not delayed by chaos
+    /// faults; registered Rust outputs still receive them as usual.
+    ///
+    /// The control flow is not returned, since only the `"FinishGame"`
+    /// command breaks a game.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` like `run_step_with_command`; no events are lost
+    /// since a rejected command produces none.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const events = game.run_step_with_command_batched("CauseLottery");
+    /// for (const { type, payload } of events) {
+    ///     store.apply(type, payload);
+    /// }
+    /// ```
+    #[wasm_bindgen(unchecked_return_type = "{ type: string; payload: any }[]")]
+    pub fn run_step_with_command_batched(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "Command")] command: String,
+    ) -> Result<js_sys::Array, PachisloError> {
+        let (_, events) = self.step_batched(&command)?;
 
-    // Only test functions that don't require WebAssembly bindings
-    // WebAssembly-specific tests should be run with wasm-pack test
+        Ok(events
+            .iter()
+            .map(|(event_type, payload)| event::batched(event_type, payload))
+            .collect())
+    }
 
-    #[test]
-    fn test_convert_string_to_command() {
-        // Test valid commands
-        assert!(
-            convert_string_to_command::<Box<dyn FnMut(usize) -> f64>, rand::rngs::ThreadRng>(
-                "LaunchBall"
-            )
-            .is_some()
-        );
-        assert!(
-            convert_string_to_command::<Box<dyn FnMut(usize) -> f64>, rand::rngs::ThreadRng>(
-                "CauseLottery"
-            )
-            .is_some()
-        );
-        assert!(
-            convert_string_to_command::<Box<dyn FnMut(usize) -> f64>, rand::rngs::ThreadRng>(
-                "StartGame"
-            )
-            .is_some()
-        );
-        assert!(
-            convert_string_to_command::<Box<dyn FnMut(usize) -> f64>, rand::rngs::ThreadRng>(
-                "FinishGame"
-            )
-            .is_some()
-        );
-        assert!(
-            convert_string_to_command::<Box<dyn FnMut(usize) -> f64>, rand::rngs::ThreadRng>(
-                "Finish"
-            )
-            .is_some()
-        );
+    /// Executes a command with arguments, e.g. launches balls in bulk.
+    ///
+    /// A command with a `count` is executed as that many single steps, each
+    /// reported to the output, checked against the rules and recorded in the
+    /// ghost and history. Repetition stops early at the first step that would
+    /// be rejected, e.g. when the balls run out or a cooldown applies, and
+    /// throws at the first step whose callback throws.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - String representation of the command to execute
+    /// * `args` - Optional [`CommandArgs`]
+    ///
+    /// # Returns
+    ///
+    /// The control flow of the last executed step.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` without changing the game state if the arguments are
+    /// invalid for the command or the first step is rejected; see
+    /// [`WasmGame::run_step_with_command`]. Throws a `CallbackFailed` error
+    /// once a step whose callback threw is done; the steps before it are kept.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// game.run_step_with_command_args("LaunchBall", { count: 10 });
+    /// ```
+    #[wasm_bindgen]
+    pub fn run_step_with_command_args(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "Command")] command: String,
+        args: Option<CommandArgs>,
+    ) -> Result<ControlFlow, PachisloError> {
+        let repetitions = convert_string_to_command(&command)
+            .ok_or_else(|| PachisloError::UnknownCommand {
+                command: command.clone(),
+            })
+            .and_then(|parsed| repetitions(parsed, &args.unwrap_or_default()))?;
 
-        // Test invalid command
-        assert!(
-            convert_string_to_command::<Box<dyn FnMut(usize) -> f64>, rand::rngs::ThreadRng>(
-                "InvalidCommand"
-            )
-            .is_none()
-        );
-        assert!(
-            convert_string_to_command::<Box<dyn FnMut(usize) -> f64>, rand::rngs::ThreadRng>("")
-                .is_none()
-        );
+        let mut control_flow = self.step(&command)?;
+        for _ in 1..repetitions {
+            match self.step(&command) {
+                Ok(step) => control_flow = step,
+                Err(error) if error.is_rejection() => break,
+                Err(error) => return Err(error),
+            }
+        }
+
+        Ok(control_flow)
+    }
+
+    /// Repeats a command sequence inside WebAssembly until the game breaks.
+    ///
+    /// Each command runs as a regular step, reported to the output, checked
+    /// against the rules and recorded. The loop stops when a step returns
+    /// `ControlFlow.Break`, when a step is rejected (e.g. `"LaunchBall"` after
+    /// the balls ran out), or after `max_iterations` passes.
+    ///
+    /// # Arguments
+    ///
+    /// * `commands` - The command sequence of one iteration
+    /// * `max_iterations` - Optional limit on the number of iterations;
+    ///   without it, a sequence that never breaks or runs out loops forever
+    ///
+    /// # Returns
+    ///
+    /// The number of iterations whose commands all ran.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` without changing the game state if a command string is
+    /// not recognized or the very first step is rejected. Throws a
+    /// `CallbackFailed` error once a step whose callback threw is done; the
+    /// steps before it are kept.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// game.run_step_with_command("StartGame");
+    /// const launches = game.run_until_break(["LaunchBall", "CauseLottery"], 10000);
+    /// ```
+    #[wasm_bindgen]
+    pub fn run_until_break(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "Command[]")] commands: Vec<String>,
+        max_iterations: Option<usize>,
+    ) -> Result<usize, PachisloError> {
+        if let Some(unknown) = commands
+            .iter()
+            .find(|command| convert_string_to_command(command).is_none())
+        {
+            return Err(PachisloError::UnknownCommand {
+                command: unknown.clone(),
+            });
+        }
+
+        let mut iterations = 0;
+        let mut first = true;
+
+        while !commands.is_empty() && max_iterations.is_none_or(|max| iterations < max) {
+            for (i, command) in commands.iter().enumerate() {
+                match self.step(command) {
+                    Ok(ControlFlow::Continue) => {}
+                    Ok(ControlFlow::Break) => {
+                        let completed = i + 1 == commands.len();
+                        return Ok(iterations + usize::from(completed));
+                    }
+                    Err(error) if first || !error.is_rejection() => return Err(error),
+                    Err(_) => return Ok(iterations),
+                }
+                first = false;
+            }
+
+            iterations += 1;
+        }
+
+        Ok(iterations)
+    }
+
+    /// Returns the current game state.
+    ///
+    /// Unlike the output callbacks, this can be called at any time, e.g. to
+    /// render the machine after the page has been re-rendered.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const state = game.get_state();
+    /// if (state !== "Uninitialized" && "Rush" in state) {
+    ///     showRushBalls(state.Rush.rush_balls);
+    /// }
+    /// ```
+    #[wasm_bindgen]
+    pub fn get_state(&self) -> Result<GameState, PachisloError> {
+        let session = self.session()?;
+        #[cfg(feature = "chaos")]
+        chaos::inject(&session.chaos, chaos::Fault::Serialization)?;
+
+        Ok(GameState::from(*session.engine.state()))
+    }
+
+    /// Returns whether the game is in use, i.e. whether a call now would
+    /// throw a `Busy` error. This is the case inside the game's own
+    /// callbacks during a step.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// // Shared by the output callbacks and the rest of the UI
+    /// function refresh() {
+    ///     if (!game.is_busy()) render(game.get_state());
+    /// }
+    /// ```
+    #[wasm_bindgen]
+    pub fn is_busy(&self) -> bool {
+        #[cfg(not(feature = "single-threaded"))]
+        return matches!(self.game.try_lock(), Err(TryLockError::WouldBlock));
+        #[cfg(feature = "single-threaded")]
+        return self.game.try_borrow_mut().is_err();
+    }
+
+    /// Returns the balls held, 0 before the game starts.
+    ///
+    /// A cheap alternative to [`WasmGame::get_state`] for rendering the ball
+    /// meter every frame.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// requestAnimationFrame(function draw() {
+    ///     meter.textContent = game.balls();
+    ///     requestAnimationFrame(draw);
+    /// });
+    /// ```
+    #[wasm_bindgen]
+    pub fn balls(&self) -> Result<usize, PachisloError> {
+        let session = self.session()?;
+
+        Ok(session.engine.balls())
+    }
+
+    /// Returns the rush balls left, 0 outside rush mode.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    #[wasm_bindgen]
+    pub fn rush_balls(&self) -> Result<usize, PachisloError> {
+        let session = self.session()?;
+
+        Ok(session.engine.rush_balls())
+    }
+
+    /// Returns the losing normal-mode spins since the last win, counted
+    /// towards the ceiling (see `Config::with_ceiling`).
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    #[wasm_bindgen]
+    pub fn spins_since_win(&self) -> Result<usize, PachisloError> {
+        let session = self.session()?;
+
+        Ok(session.engine.spins_since_win())
+    }
+
+    /// Returns the launches that entered the start pocket and await their
+    /// lottery, at most 4; always 0 if every launch enters it (see
+    /// `BallsConfig.start_rate`).
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    #[wasm_bindgen]
+    pub fn starts(&self) -> Result<usize, PachisloError> {
+        let session = self.session()?;
+
+        Ok(session.engine.starts())
+    }
+
+    /// Starts recording a ghost: the executed commands and their timings,
+    /// without their outcomes. Restarts the recording if one is running.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    #[wasm_bindgen]
+    pub fn start_ghost_recording(&self) -> Result<(), PachisloError> {
+        let mut session = self.session()?;
+
+        session.ghost = Some(GhostRecorder::new(session.clock.now()?));
+
+        Ok(())
+    }
+
+    /// Stops recording and returns the ghost.
+    ///
+    /// Only commands that were actually executed are recorded. Replay the ghost
+    /// on another machine with [`ghost::replay_ghost`].
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if no recording is running or the game mutex cannot
+    /// be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// game.start_ghost_recording();
+    /// // ... play ...
+    /// localStorage.setItem("ghost", JSON.stringify(game.stop_ghost_recording()));
+    /// ```
+    #[wasm_bindgen]
+    pub fn stop_ghost_recording(&self) -> Result<Ghost, PachisloError> {
+        let mut session = self.session()?;
+
+        let recorder = session.ghost.take().ok_or(PachisloError::NotRecording)?;
+
+        Ok(recorder.finish())
+    }
+
+    /// Acknowledges every output event up to and including `seq`.
+    ///
+    /// Held-back events that now fit into the ack window are delivered, and
+    /// the unacknowledged range is retransmitted if it timed out. Only
+    /// available for outputs created from a [`event::JsEventOutput`] with an
+    /// ack window.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the output has no ack window or the game mutex
+    /// cannot be acquired.
+    #[wasm_bindgen]
+    pub fn ack_through(&self, seq: u32) -> Result<(), PachisloError> {
+        let mut session = self.session()?;
+
+        let now = session.clock.now()?;
+        session.engine.output_mut().ack_through(seq, now)?;
+
+        Ok(())
+    }
+
+    /// Explains how the last lottery was resolved: the probabilities that
+    /// applied, the values rolled against them, the rush continuation math
+    /// and the balls paid out.
+    ///
+    /// # Returns
+    ///
+    /// A `SpinExplanation`, or `undefined` if no lottery was drawn yet.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// game.run_step_with_command("CauseLottery");
+    /// const { lottery } = game.explain_last_spin();
+    /// console.log(`rolled ${lottery.win.value} against ${lottery.win.probability}`);
+    /// ```
+    #[wasm_bindgen]
+    pub fn explain_last_spin(&self) -> Result<Option<SpinExplanation>, PachisloError> {
+        let session = self.session()?;
+
+        Ok(session.engine.last_spin().copied())
+    }
+
+    /// Returns the most recent lottery result with the slot produced for it,
+    /// e.g. to re-render the last spin after the UI was re-mounted.
+    ///
+    /// # Returns
+    ///
+    /// A `LotteryEvent`, or `undefined` if no lottery was drawn yet.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const last = game.last_lottery();
+    /// if (last) {
+    ///     reels.show(last.slot[1] ?? last.slot[0]);
+    /// }
+    /// ```
+    #[wasm_bindgen]
+    pub fn last_lottery(&self) -> Result<Option<event::LotteryEvent>, PachisloError> {
+        let session = self.session()?;
+
+        Ok(session.engine.output().last_lottery().cloned())
+    }
+
+    /// Returns a view of the symbols of the most recent slot, without copying.
+    ///
+    /// The view covers the reels of the shown slot followed by the reels of
+    /// the slot revealed afterwards; without a reveal the shown reels are
+    /// repeated, so the second half is always the final slot. It is filled
+    /// with zeros before the first lottery.
+    ///
+    /// The view reads the game's memory directly and is updated in place by
+    /// every lottery, so a render loop can fetch it once and compare
+    /// [`WasmGame::slot_generation`] to notice new slots. Fetch it again
+    /// when its `length` turns 0, which happens when WebAssembly memory
+    /// grows, and do not use it after the game is freed. Symbols are the
+    /// numbers of the slot layout, also when the output names them.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// let reels = game.slot_view();
+    /// let seen = game.slot_generation();
+    /// function frame() {
+    ///     if (reels.length === 0) reels = game.slot_view();
+    ///     const generation = game.slot_generation();
+    ///     if (generation !== seen) {
+    ///         seen = generation;
+    ///         renderer.draw(reels.subarray(reels.length / 2));
+    ///     }
+    ///     requestAnimationFrame(frame);
+    /// }
+    /// ```
+    #[wasm_bindgen]
+    pub fn slot_view(&self) -> Result<js_sys::Uint8Array, PachisloError> {
+        let session = self.session()?;
+        let symbols = session.engine.output().slots().symbols();
+
+        // SAFETY: the view is handed to JavaScript without allocating in
+        // between; the buffer is only reallocated when the slot layout
+        // changes, which JavaScript cannot do to a game's output, and its
+        // lifetime ends with the game as documented above.
+        Ok(unsafe { js_sys::Uint8Array::view(symbols) })
+    }
+
+    /// Returns the number of slots produced so far, wrapping around at
+    /// 2^32, to tell whether [`WasmGame::slot_view`] changed.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    #[wasm_bindgen]
+    pub fn slot_generation(&self) -> Result<u32, PachisloError> {
+        let session = self.session()?;
+
+        Ok(session.engine.output().slots().generation())
+    }
+
+    /// Replaces the ball and probability settings of the running game.
+    ///
+    /// The state, history, rules and statistics are kept. New probabilities
+    /// and payouts apply from the next lottery on; the new `init_balls` only
+    /// applies to the next `"StartGame"`.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` describing every problem if the configuration is
+    /// invalid (see `Config.validate`), leaving the old one in effect, or if
+    /// the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// slider.oninput = () => game.update_config(buildConfig({ normalWin: slider.value }));
+    /// ```
+    #[wasm_bindgen]
+    pub fn update_config(&self, config: Config) -> Result<(), PachisloError> {
+        config.validate()?;
+
+        let mut session = self.session()?;
+
+        let mechanics = config.mechanics();
+        session
+            .engine
+            .set_config(config.clone().into())
+            .map_err(|error| PachisloError::InvalidConfig {
+                message: error.to_string(),
+            })?;
+        session.engine.set_mechanics(mechanics);
+        session.config = config;
+
+        Ok(())
+    }
+
+    /// Returns counters over every game played so far: spins, wins by type,
+    /// rush entries, the longest rush chain and balls launched and awarded.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const { spins, wins } = game.stats();
+    /// hitRate.textContent = ((wins.default + wins.fake_win) / spins).toFixed(3);
+    /// ```
+    #[wasm_bindgen]
+    pub fn stats(&self) -> Result<GameStats, PachisloError> {
+        let session = self.session()?;
+        #[cfg(feature = "chaos")]
+        chaos::inject(&session.chaos, chaos::Fault::Serialization)?;
+
+        Ok(*session.engine.stats())
+    }
+
+    /// Returns the lotteries drawn and won in normal mode, in rush mode and
+    /// as rush continuations over every game played so far, like the data
+    /// counter of a real machine. Jitan lotteries count as normal ones.
+    ///
+    /// Like `stats`, the counters are not part of snapshots.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const { normal, rush_continue } = game.counters();
+    /// dataCounter.textContent = `${normal.wins}/${normal.attempts} · 継続 ${rush_continue.wins}`;
+    /// ```
+    #[wasm_bindgen]
+    pub fn counters(&self) -> Result<stats::ModeCounters, PachisloError> {
+        let session = self.session()?;
+
+        Ok(*session.engine.counters())
+    }
+
+    /// Returns the live return figures: balls paid out per ball launched and
+    /// balls launched per spin, exponentially smoothed over the recent spins.
+    ///
+    /// The figures are updated with every spin and are part of snapshots, so
+    /// a dashboard can read them every frame.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const { rtp, balls_per_spin } = game.return_ticker();
+    /// ticker.textContent = `RTP ${(rtp * 100).toFixed(1)}% · ${balls_per_spin.toFixed(1)} balls/spin`;
+    /// ```
+    #[wasm_bindgen]
+    pub fn return_ticker(&self) -> Result<stats::ReturnTicker, PachisloError> {
+        let session = self.session()?;
+
+        Ok(*session.engine.ticker())
+    }
+
+    /// Changes how much each new spin weighs in the return figures; the
+    /// default of 0.05 follows roughly the last 20 spins.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if `smoothing` is not greater than 0 and at most 1,
+    /// or if the game mutex cannot be acquired.
+    #[wasm_bindgen]
+    pub fn set_ticker_smoothing(&self, smoothing: f64) -> Result<(), PachisloError> {
+        if !(smoothing > 0.0 && smoothing <= 1.0) {
+            return Err(PachisloError::InvalidArguments {
+                message: "\"smoothing\" must be greater than 0 and at most 1".to_string(),
+            });
+        }
+
+        let mut session = self.session()?;
+        session.engine.set_ticker_smoothing(smoothing);
+
+        Ok(())
+    }
+
+    /// Merges statistics of an earlier session into this game's statistics.
+    ///
+    /// Statistics are not part of snapshots, so a game restored after a crash
+    /// counts from zero; merging the autosaved statistics continues them.
+    /// Counts add up and the longest rush chain is the longer of both.
+    /// Merge each recovered `GameStats` only once, or it is counted twice.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const saved = JSON.parse(localStorage.getItem("autosave"));
+    /// game.restore(saved.snapshot);
+    /// game.merge_stats(saved.stats);
+    /// ```
+    #[wasm_bindgen]
+    pub fn merge_stats(&self, recovered: GameStats) -> Result<(), PachisloError> {
+        let mut session = self.session()?;
+
+        session.engine.merge_stats(&recovered);
+
+        Ok(())
+    }
+
+    /// Tells the game whether the page is visible.
+    ///
+    /// While hidden, the game keeps running but its events are not delivered;
+    /// instead a `"background_tick"` event with an `EventSummary` of the
+    /// skipped events is delivered at most once per `interval`. When the page
+    /// becomes visible again, a `"foreground_summary"` event summarizes the
+    /// whole background period and events are delivered as usual. Only
+    /// available for outputs created from a [`event::JsEventOutput`].
+    ///
+    /// # Arguments
+    ///
+    /// * `visible` - Whether the page is visible
+    /// * `interval` - Minimum milliseconds between background ticks, 1000 if omitted
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the output is not an event output, `interval` is
+    /// not positive, or the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// document.addEventListener("visibilitychange", () => {
+    ///     game.set_visible(document.visibilityState === "visible", 5000);
+    /// });
+    /// ```
+    #[wasm_bindgen]
+    pub fn set_visible(&self, visible: bool, interval: Option<f64>) -> Result<(), PachisloError> {
+        let mut session = self.session()?;
+
+        let now = session.clock.now()?;
+        session.engine.output_mut().set_visible(
+            visible,
+            interval.unwrap_or(DEFAULT_BACKGROUND_INTERVAL),
+            now,
+        )?;
+
+        Ok(())
+    }
+
+    /// Returns how many output events were not acknowledged yet, including
+    /// events held back by a full ack window; 0 if the output has no ack window.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    #[wasm_bindgen]
+    pub fn unacked_events(&self) -> Result<usize, PachisloError> {
+        let session = self.session()?;
+
+        Ok(session.engine.output().unacked_events())
+    }
+
+    /// Returns how many output events a full event buffer dropped so far; 0
+    /// without a buffer limit.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    #[wasm_bindgen]
+    pub fn dropped_events(&self) -> Result<usize, PachisloError> {
+        let session = self.session()?;
+
+        Ok(session.engine.output().dropped_events())
+    }
+
+    /// Starts or stops recording every executed command and its transition.
+    ///
+    /// History is off by default. Stopping keeps the recorded entries; use
+    /// [`WasmGame::clear_history`] to discard them. Every entry also keeps a
+    /// copy of the game after it for [`WasmGame::seek`].
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    #[wasm_bindgen]
+    pub fn set_history_enabled(&self, enabled: bool) -> Result<(), PachisloError> {
+        let mut session = self.session()?;
+
+        session.history.set_enabled(enabled);
+
+        Ok(())
+    }
+
+    /// Returns the recorded history, oldest entry first.
+    ///
+    /// Only commands that were actually executed are recorded; rejected
+    /// commands are not.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// game.set_history_enabled(true);
+    /// // ... play ...
+    /// for (const { time, command, transition } of game.get_history()) {
+    ///     console.log(time, command, transition.after);
+    /// }
+    /// ```
+    #[wasm_bindgen]
+    pub fn get_history(&self) -> Result<Vec<HistoryEntry>, PachisloError> {
+        let session = self.session()?;
+        #[cfg(feature = "chaos")]
+        chaos::inject(&session.chaos, chaos::Fault::Serialization)?;
+
+        Ok(session.history.entries().to_vec())
+    }
+
+    /// Exports the spins of the recorded history, oldest first, as CSV or
+    /// NDJSON text.
+    ///
+    /// Every spin is written with its time and command, the lottery mode,
+    /// the result (`Win`, `FakeWin`, `Premium`, `Lose` or `FakeLose`) and the
+    /// balls before and after it. Commands that drew no lottery are left
+    /// out. CSV starts with a header line.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const csv = game.export_history("csv");
+    /// const url = URL.createObjectURL(new Blob([csv], { type: "text/csv" }));
+    /// ```
+    #[wasm_bindgen]
+    pub fn export_history(&self, format: ExportFormat) -> Result<String, PachisloError> {
+        let session = self.session()?;
+        #[cfg(feature = "chaos")]
+        chaos::inject(&session.chaos, chaos::Fault::Serialization)?;
+
+        Ok(session.history.export(format))
+    }
+
+    /// Discards the recorded history without changing whether it is recorded.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    #[wasm_bindgen]
+    pub fn clear_history(&self) -> Result<(), PachisloError> {
+        let mut session = self.session()?;
+
+        session.history.clear();
+
+        Ok(())
+    }
+
+    /// Limits how often a command may be executed during this game's lifetime.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the command string is not recognized or the game
+    /// mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// game.set_usage_limit("StartGame", 1);
+    /// ```
+    #[wasm_bindgen]
+    pub fn set_usage_limit(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "Command")] command: String,
+        max_uses: usize,
+    ) -> Result<(), PachisloError> {
+        let command =
+            convert_string_to_command(&command).ok_or_else(|| PachisloError::UnknownCommand {
+                command: command.clone(),
+            })?;
+        let mut session = self.session()?;
+
+        session.rules.set_limit(command, max_uses);
+
+        Ok(())
+    }
+
+    /// Sets the minimum time between two executions of a command; 0 removes it.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the command string is not recognized or the game
+    /// mutex cannot be acquired.
+    #[wasm_bindgen]
+    pub fn set_cooldown(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "Command")] command: String,
+        milliseconds: f64,
+    ) -> Result<(), PachisloError> {
+        let command =
+            convert_string_to_command(&command).ok_or_else(|| PachisloError::UnknownCommand {
+                command: command.clone(),
+            })?;
+        let mut session = self.session()?;
+
+        session.rules.set_cooldown(command, milliseconds);
+
+        Ok(())
+    }
+
+    /// Requires a `"LaunchBall"` between two `"CauseLottery"` commands, so
+    /// every lottery is backed by a launched ball.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    #[wasm_bindgen]
+    pub fn set_launch_required_for_lottery(&self, required: bool) -> Result<(), PachisloError> {
+        let mut session = self.session()?;
+
+        session.rules.set_launch_required(required);
+
+        Ok(())
+    }
+
+    /// Reports for every command whether it can be executed right now, and if
+    /// not, why. Combines the game state with the configured rules.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// for (const { command, available, reason } of game.command_availability()) {
+    ///     buttons[command].disabled = !available;
+    ///     buttons[command].title = reason ?? "";
+    /// }
+    /// ```
+    #[wasm_bindgen]
+    pub fn command_availability(&self) -> Result<Vec<CommandAvailability>, PachisloError> {
+        let session = self.session()?;
+
+        Ok(session
+            .rules
+            .availability(session.engine.state(), session.clock.now()?))
+    }
+}
+
+impl WasmGame {
+    fn with_rng(
+        input: JsInput,
+        output: JsOutput,
+        config: Config,
+        rng: GameRng,
+        seeded: bool,
+    ) -> Result<Self, PachisloError> {
+        // Commands are passed to `run_step_with_command`; the input carries no state
+        let JsInput = input;
+
+        config.validate()?;
+
+        Ok(Self {
+            game: SessionCell::new(Session {
+                engine: new_inner_game(output, config.clone(), rng),
+                config,
+                ghost: None,
+                history: History::default(),
+                balls_series: BallsSeries::default(),
+                streak_alerts: StreakAlerts::default(),
+                rules: CommandRules::default(),
+                clock: clock::from_function(None),
+                reveal: None,
+                seeded,
+                undo: UndoStack::default(),
+                #[cfg(feature = "chaos")]
+                chaos: None,
+            }),
+        })
+    }
+
+    /// Locks the session without waiting, so re-entrant calls from callbacks
+    /// fail instead of deadlocking.
+    #[cfg(not(feature = "single-threaded"))]
+    fn session(&self) -> Result<MutexGuard<'_, Session>, PachisloError> {
+        self.game.try_lock().map_err(|error| match error {
+            TryLockError::Poisoned(_) => PachisloError::LockPoisoned,
+            TryLockError::WouldBlock => PachisloError::Busy,
+        })
+    }
+
+    /// Borrows the session, failing if a re-entrant call already has it.
+    #[cfg(feature = "single-threaded")]
+    fn session(&self) -> Result<RefMut<'_, Session>, PachisloError> {
+        self.game.try_borrow_mut().map_err(|_| PachisloError::Busy)
+    }
+
+    /// Like [`WasmGame::step`], also returning what the callbacks returned.
+    fn step_collecting(&self, input: &str) -> Result<(ControlFlow, Vec<JsValue>), PachisloError> {
+        self.session()?.engine.output_mut().start_collecting();
+
+        let control_flow = self.step(input);
+
+        let pending = self.session()?.engine.output_mut().take_collected();
+
+        Ok((control_flow?, pending))
+    }
+
+    /// Like [`WasmGame::step`], returning the events instead of delivering them.
+    fn step_batched(&self, input: &str) -> Result<(ControlFlow, Vec<BatchedEvent>), PachisloError> {
+        self.session()?.engine.output_mut().start_batch();
+
+        let control_flow = self.step(input);
+
+        let events = self.session()?.engine.output_mut().take_batch();
+
+        Ok((control_flow?, events))
+    }
+
+    /// Executes a command, then reports the first error a callback threw
+    /// while the command ran, unless the command itself failed.
+    fn step(&self, input: &str) -> Result<ControlFlow, PachisloError> {
+        let control_flow = self.apply(input);
+
+        let failure = self.session()?.engine.output_mut().take_failure();
+
+        let control_flow = control_flow?;
+        failure?;
+
+        Ok(control_flow)
+    }
+
+    fn apply(&self, input: &str) -> Result<ControlFlow, PachisloError> {
+        let command =
+            convert_string_to_command(input).ok_or_else(|| PachisloError::UnknownCommand {
+                command: input.to_string(),
+            })?;
+
+        let mut session = self.session()?;
+        #[cfg(feature = "chaos")]
+        chaos::inject(&session.chaos, chaos::Fault::LockContention)?;
+
+        let now = session.clock.now()?;
+        session.poll_reveal(now)?;
+
+        check_command(input, session.engine.state())?;
+
+        session.rules.check(command, now)?;
+        session.rules.record(command, now);
+
+        if let Some(ghost) = &mut session.ghost {
+            ghost.record(now, input);
+        }
+
+        let Session { engine, undo, .. } = &mut *session;
+        undo.record(engine);
+
+        let before = *session.engine.state();
+        let held = session.engine.held();
+        let stocked = session.engine.stock();
+        let spins = session.engine.stats().spins;
+        let control_flow = session.engine.run_step_with_command(command);
+
+        let transition = Transition {
+            before: Some(before.into()),
+            after: (*session.engine.state()).into(),
+        };
+        let spin = session
+            .engine
+            .last_spin()
+            .filter(|_| session.engine.stats().spins > spins)
+            .map(history::SpinRecord::from);
+        session.record_history(now, input, transition, spin);
+        let balls = session.engine.balls();
+        session.balls_series.record(balls);
+
+        if session.engine.output().is_event_output() {
+            let hold = hold::HoldEvent {
+                held: session.engine.held(),
+            };
+            let event_type = match command {
+                Command::CauseLottery if hold.held > held => Some(event::HOLD_ADDED),
+                Command::ConsumeHold if hold.held < held => Some(event::HOLD_CONSUMED),
+                _ => None,
+            };
+            if let Some(event_type) = event_type {
+                session
+                    .engine
+                    .output_mut()
+                    .emit(event_type, serde_wasm_bindgen::to_value(&hold).unwrap());
+            }
+
+            let stock = stock::StockEvent {
+                stock: session.engine.stock(),
+            };
+            let event_type = match stock.stock.cmp(&stocked) {
+                Ordering::Greater => Some(event::STOCK_GAINED),
+                Ordering::Less => Some(event::STOCK_RELEASED),
+                Ordering::Equal => None,
+            };
+            if let Some(event_type) = event_type {
+                session
+                    .engine
+                    .output_mut()
+                    .emit(event_type, serde_wasm_bindgen::to_value(&stock).unwrap());
+            }
+        }
+        session.emit_rush_tier(Some(before));
+        if !session.engine.is_reveal_pending() {
+            if session.engine.stats().spins > spins {
+                session.emit_battle();
+            }
+            session.emit_rounds();
+            session.emit_streaks();
+        }
+        if !matches!(before, pachislo::game::GameState::Uninitialized)
+            && matches!(
+                session.engine.state(),
+                pachislo::game::GameState::Uninitialized
+            )
+        {
+            session.emit_session_summary();
+        }
+        session.start_reveal(now);
+
+        Ok(control_flow.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Only test functions that don't require WebAssembly bindings
+    // WebAssembly-specific tests should be run with wasm-pack test
+
+    #[test]
+    fn test_convert_string_to_command() {
+        // Test valid commands
+        assert!(convert_string_to_command("LaunchBall").is_some());
+        assert!(convert_string_to_command("CauseLottery").is_some());
+        assert!(convert_string_to_command("StartGame").is_some());
+        assert!(convert_string_to_command("FinishGame").is_some());
+        assert!(convert_string_to_command("Finish").is_some());
+
+        // Test invalid command
+        assert!(convert_string_to_command("InvalidCommand").is_none());
+        assert!(convert_string_to_command("").is_none());
+    }
+
+    #[test]
+    fn test_check_command() {
+        use pachislo::game::GameState as PachisloGameState;
+
+        let uninitialized = PachisloGameState::Uninitialized;
+        let normal = PachisloGameState::Normal { balls: 10 };
+
+        assert_eq!(check_command("StartGame", &uninitialized), Ok(()));
+        assert_eq!(check_command("Finish", &uninitialized), Ok(()));
+        assert_eq!(
+            check_command("StartGame", &normal),
+            Err(PachisloError::AlreadyStarted)
+        );
+
+        for command in ["LaunchBall", "CauseLottery", "FinishGame"] {
+            assert_eq!(check_command(command, &normal), Ok(()));
+            assert_eq!(
+                check_command(command, &uninitialized),
+                Err(PachisloError::GameNotStarted {
+                    command: command.to_string()
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_step_error_messages() {
+        let message = PachisloError::UnknownCommand {
+            command: "Launch".to_string(),
+        }
+        .to_string();
+        assert!(message.contains("\"Launch\""));
+        assert!(message.contains("\"LaunchBall\""));
+
+        let message = PachisloError::GameNotStarted {
+            command: "CauseLottery".to_string(),
+        }
+        .to_string();
+        assert!(message.contains("StartGame"));
+
+        let message = PachisloError::ReplayFailed {
+            index: 3,
+            command: "LaunchBall".to_string(),
+            cause: Box::new(PachisloError::RevealPending),
+        }
+        .to_string();
+        assert!(message.starts_with("replay failed at command 3 (\"LaunchBall\")"));
+        assert!(message.contains("ack_reveal"));
+
+        // Handlers branch on `kind`, declared for every variant
+        assert!(PachisloError::DECL.contains("kind: \"GameNotStarted\""));
+        assert!(PachisloError::DECL.contains("cause: PachisloError"));
+    }
+
+    #[test]
+    fn test_repetitions() {
+        let count = |count| CommandArgs { count };
+
+        assert_eq!(repetitions(Command::LaunchBall, &count(None)), Ok(1));
+        assert_eq!(repetitions(Command::LaunchBall, &count(Some(10))), Ok(10));
+        assert_eq!(repetitions(Command::CauseLottery, &count(Some(3))), Ok(3));
+        assert_eq!(repetitions(Command::StartGame, &count(Some(1))), Ok(1));
+        assert!(matches!(
+            repetitions(Command::LaunchBall, &count(Some(0))),
+            Err(PachisloError::InvalidArguments { .. })
+        ));
+        assert!(matches!(
+            repetitions(Command::FinishGame, &count(Some(2))),
+            Err(PachisloError::InvalidArguments { .. })
+        ));
+    }
+
+    #[test]
+    fn test_check_slot_layout() {
+        assert_eq!(check_slot_layout(3, &[1, 2, 3, 4, 5, 6, 7]), Ok(()));
+        assert_eq!(check_slot_layout(5, &[1, 2]), Ok(()));
+        assert!(check_slot_layout(1, &[1, 2, 3]).is_err());
+        assert!(check_slot_layout(3, &[7]).is_err());
+        assert!(check_slot_layout(3, &[1, 2, 1]).is_err());
     }
 
     #[test]
@@ -404,6 +2929,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::default_constructed_unit_structs)]
     fn test_js_input_default() {
         let input = JsInput::default();
         // Test that default implementation works
@@ -416,13 +2942,13 @@ mod tests {
         let break_flow: ControlFlow = std::ops::ControlFlow::Break(()).into();
 
         match continue_flow {
-            ControlFlow::Continue => assert!(true),
+            ControlFlow::Continue => {}
             ControlFlow::Break => panic!("Expected Continue"),
         }
 
         match break_flow {
             ControlFlow::Continue => panic!("Expected Break"),
-            ControlFlow::Break => assert!(true),
+            ControlFlow::Break => {}
         }
     }
 
@@ -0,0 +1,268 @@
+//! # Configuration Linting
+//!
+//! Plausibility checks for game configurations. Unlike validation, linting never
+//! rejects a configuration: every finding is a warning that a designer may choose
+//! to ignore.
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::alias::{BallsConfig, Config, SlotProbability};
+use crate::analysis;
+
+/// Continuation rates above this exceed the 2016 industry self-regulation (内規) cap.
+pub const MAX_PLAUSIBLE_CONTINUATION_RATE: f64 = 0.65;
+
+/// Theoretical payout rates above this are implausible for a shipped machine.
+pub const MAX_PLAUSIBLE_PAYOUT_RATE: f64 = 1.2;
+
+/// Number of rush depths inspected when checking the continuation function.
+const CONTINUATION_SAMPLES: usize = 10;
+
+/// Kind of plausibility issue found by [`Config::lint`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum LintCode {
+    /// The first rush continues more often than regulations allow
+    ContinuationRate,
+    /// The theoretical payout rate is implausibly high
+    PayoutRate,
+    /// `fake_win` is larger than the win probability it applies to
+    FakeWinExceedsWin,
+    /// `fake_lose` is larger than the lose probability it applies to
+    FakeLoseExceedsLose,
+    /// Rush mode wins less often than normal mode
+    RushBelowNormal,
+    /// Entering rush grants no rush balls
+    NoRushBalls,
+    /// The continuation function grows with the rush count
+    IncreasingContinuation,
+}
+
+/// A single plausibility warning.
+#[derive(Clone, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ConfigWarning {
+    /// Machine-readable kind of the warning
+    pub code: LintCode,
+    /// Human-readable description including the offending values
+    pub message: String,
+}
+
+impl ConfigWarning {
+    fn new(code: LintCode, message: String) -> Self {
+        ConfigWarning { code, message }
+    }
+}
+
+#[wasm_bindgen]
+impl Config {
+    /// Checks the configuration for implausible machine specs.
+    ///
+    /// # Returns
+    ///
+    /// A list of warnings; an empty list means nothing suspicious was found.
+    /// Payout figures assume pachislo's example start hole probability.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// for (const warning of config.lint()) {
+    ///     console.warn(warning.code, warning.message);
+    /// }
+    /// ```
+    #[wasm_bindgen]
+    pub fn lint(&self) -> Vec<ConfigWarning> {
+        let probability = &self.probability;
+
+        lint(
+            &self.balls,
+            probability.normal,
+            probability.rush,
+            probability.rush_continue,
+            |n| probability.rush_continue_multiplier(n),
+        )
+    }
+}
+
+/// Core of [`Config::lint`], independent of the JavaScript callback.
+pub(crate) fn lint<F>(
+    balls: &BallsConfig,
+    normal: SlotProbability,
+    rush: SlotProbability,
+    rush_continue: SlotProbability,
+    mut rush_continue_fn: F,
+) -> Vec<ConfigWarning>
+where
+    F: FnMut(usize) -> f64,
+{
+    let mut warnings = Vec::new();
+
+    for (mode, probability) in [
+        ("normal", normal),
+        ("rush", rush),
+        ("rush_continue", rush_continue),
+    ] {
+        if probability.fake_win > probability.win {
+            warnings.push(ConfigWarning::new(
+                LintCode::FakeWinExceedsWin,
+                format!(
+                    "{mode}: fake_win ({}) exceeds the win probability ({}); fake_win only applies to winning draws",
+                    probability.fake_win, probability.win
+                ),
+            ));
+        }
+        if probability.fake_lose > 1.0 - probability.win {
+            warnings.push(ConfigWarning::new(
+                LintCode::FakeLoseExceedsLose,
+                format!(
+                    "{mode}: fake_lose ({}) exceeds the lose probability ({}); fake_lose only applies to losing draws",
+                    probability.fake_lose,
+                    1.0 - probability.win
+                ),
+            ));
+        }
+    }
+
+    if rush.win < normal.win {
+        warnings.push(ConfigWarning::new(
+            LintCode::RushBelowNormal,
+            format!(
+                "rush win probability ({}) is lower than normal win probability ({})",
+                rush.win, normal.win
+            ),
+        ));
+    }
+
+    if balls.incremental_rush == 0 {
+        warnings.push(ConfigWarning::new(
+            LintCode::NoRushBalls,
+            "incremental_rush is 0, so rush mode ends on the first launch".to_string(),
+        ));
+    }
+
+    let multipliers: Vec<f64> = (1..=CONTINUATION_SAMPLES)
+        .map(&mut rush_continue_fn)
+        .collect();
+    if let Some(n) = multipliers.windows(2).position(|pair| pair[1] > pair[0]) {
+        warnings.push(ConfigWarning::new(
+            LintCode::IncreasingContinuation,
+            format!(
+                "rush_continue_fn increases from {} at n = {} to {} at n = {}",
+                multipliers[n],
+                n + 1,
+                multipliers[n + 1],
+                n + 2
+            ),
+        ));
+    }
+
+    let report = analysis::solve(
+        balls,
+        normal,
+        rush,
+        rush_continue,
+        rush_continue_fn,
+        pachislo::START_HOLE_PROBABILITY_EXAMPLE,
+        analysis::DEFAULT_MAX_DEPTH,
+    );
+
+    let continuation_rate = report.continuation_rate();
+    if continuation_rate > MAX_PLAUSIBLE_CONTINUATION_RATE {
+        warnings.push(ConfigWarning::new(
+            LintCode::ContinuationRate,
+            format!(
+                "rush continuation rate ({:.1}%) is above the {:.0}% regulation limit",
+                continuation_rate * 100.0,
+                MAX_PLAUSIBLE_CONTINUATION_RATE * 100.0
+            ),
+        ));
+    }
+
+    if report.payout_rate > MAX_PLAUSIBLE_PAYOUT_RATE {
+        warnings.push(ConfigWarning::new(
+            LintCode::PayoutRate,
+            format!(
+                "theoretical payout rate ({:.1}%) is above {:.0}%",
+                report.payout_rate * 100.0,
+                MAX_PLAUSIBLE_PAYOUT_RATE * 100.0
+            ),
+        ));
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(warnings: &[ConfigWarning]) -> Vec<LintCode> {
+        warnings.iter().map(|warning| warning.code).collect()
+    }
+
+    #[test]
+    fn test_plausible_config_has_no_warnings() {
+        let balls = BallsConfig::new(1000, 15, 20);
+        let normal = SlotProbability::new(0.004, 0.002, 0.01);
+        let rush = SlotProbability::new(0.1, 0.05, 0.05);
+        let rush_continue = SlotProbability::new(0.5, 0.1, 0.1);
+
+        let warnings = lint(&balls, normal, rush, rush_continue, |n| {
+            0.9f64.powi(n as i32 - 1)
+        });
+
+        assert!(warnings.is_empty(), "{warnings:?}");
+    }
+
+    #[test]
+    fn test_fake_probabilities() {
+        let balls = BallsConfig::new(1000, 15, 20);
+        let normal = SlotProbability::new(0.1, 0.2, 0.95);
+        let rush = SlotProbability::new(0.5, 0.1, 0.1);
+
+        let warnings = codes(&lint(&balls, normal, rush, rush, |_| 1.0));
+
+        assert!(warnings.contains(&LintCode::FakeWinExceedsWin));
+        assert!(warnings.contains(&LintCode::FakeLoseExceedsLose));
+    }
+
+    #[test]
+    fn test_rush_below_normal_and_no_rush_balls() {
+        let balls = BallsConfig::new(1000, 15, 0);
+        let normal = SlotProbability::new(0.3, 0.0, 0.0);
+        let rush = SlotProbability::new(0.1, 0.0, 0.0);
+
+        let warnings = codes(&lint(&balls, normal, rush, rush, |_| 1.0));
+
+        assert!(warnings.contains(&LintCode::RushBelowNormal));
+        assert!(warnings.contains(&LintCode::NoRushBalls));
+    }
+
+    #[test]
+    fn test_increasing_continuation() {
+        let balls = BallsConfig::new(1000, 15, 20);
+        let prob = SlotProbability::new(0.1, 0.0, 0.0);
+
+        let warnings = lint(&balls, prob, prob, prob, |n| n as f64 * 0.1);
+
+        let warning = warnings
+            .iter()
+            .find(|warning| warning.code == LintCode::IncreasingContinuation)
+            .unwrap();
+        assert!(warning.message.contains("n = 1"));
+    }
+
+    #[test]
+    fn test_continuation_and_payout_rate() {
+        let balls = BallsConfig::new(1000, 300, 100);
+        let normal = SlotProbability::new(0.5, 0.0, 0.0);
+        let rush = SlotProbability::new(1.0, 0.0, 0.0);
+
+        let warnings = codes(&lint(&balls, normal, rush, rush, |_| 1.0));
+
+        assert!(warnings.contains(&LintCode::ContinuationRate));
+        assert!(warnings.contains(&LintCode::PayoutRate));
+    }
+}
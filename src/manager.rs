@@ -0,0 +1,188 @@
+//! # Game Managers
+//!
+//! A manager owns any number of full games keyed by id, e.g. one per machine
+//! in a row of a hall frontend. Unlike the machines of a `WasmHall`, managed
+//! games are complete `WasmGame`s with their own configuration, rules and
+//! history; they only share the output, so the callback plumbing is set up
+//! once instead of per game.
+
+use std::collections::BTreeMap;
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    ControlFlow, JsInput, JsOutput, PachisloError, WasmGame,
+    alias::{Config, GameState},
+    snapshot::GameSnapshot,
+    stats::GameStats,
+};
+
+/// Many games keyed by id, sharing one output.
+///
+/// Every game reports to a clone of the manager's output, labelled with the
+/// game's id, so a single event handler can tell the games apart.
+///
+/// # Example
+///
+/// ```javascript
+/// const manager = new GameManager(output);
+/// for (const id of ["left", "center", "right"]) {
+///     manager.create(id, createConfig());
+///     manager.step(id, "StartGame");
+/// }
+/// manager.step("center", "LaunchBall");
+/// const { spins } = manager.total_stats();
+/// ```
+#[wasm_bindgen]
+pub struct GameManager {
+    output: JsOutput,
+    games: BTreeMap<String, WasmGame>,
+}
+
+#[wasm_bindgen]
+impl GameManager {
+    /// Creates a manager without games.
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - Output handler; each game gets a clone labelled with its id
+    #[wasm_bindgen(constructor)]
+    pub fn new(output: JsOutput) -> Self {
+        GameManager {
+            output,
+            games: BTreeMap::new(),
+        }
+    }
+
+    /// Creates a game under `id`.
+    ///
+    /// # Errors
+    ///
+    /// Throws a `DuplicateGame` error if a game with the id exists, or an
+    /// `Error` like the `WasmGame` constructor if the configuration is invalid.
+    #[wasm_bindgen]
+    pub fn create(&mut self, id: String, config: Config) -> Result<(), PachisloError> {
+        if self.games.contains_key(&id) {
+            return Err(PachisloError::DuplicateGame { id });
+        }
+
+        let mut output = self.output.clone();
+        output.set_label(Some(id.clone()));
+        let game = WasmGame::new(JsInput::new(), output, config)?;
+        self.games.insert(id, game);
+
+        Ok(())
+    }
+
+    /// Executes a single game step on one game.
+    ///
+    /// # Errors
+    ///
+    /// Throws a `NoSuchGame` error if there is no game with the id, or an
+    /// `Error` like `WasmGame::run_step_with_command`.
+    #[wasm_bindgen]
+    pub fn step(
+        &self,
+        id: &str,
+        #[wasm_bindgen(unchecked_param_type = "Command")] command: String,
+    ) -> Result<ControlFlow, PachisloError> {
+        self.game(id)?.run_step_with_command(command)
+    }
+
+    /// Removes the game with the id.
+    ///
+    /// # Returns
+    ///
+    /// Whether there was a game with the id.
+    #[wasm_bindgen]
+    pub fn remove(&mut self, id: &str) -> bool {
+        self.games.remove(id).is_some()
+    }
+
+    /// Returns the ids of the games, in sorted order.
+    #[wasm_bindgen]
+    pub fn ids(&self) -> Vec<String> {
+        self.games.keys().cloned().collect()
+    }
+
+    /// Returns the number of games.
+    #[wasm_bindgen]
+    pub fn game_count(&self) -> usize {
+        self.games.len()
+    }
+
+    /// Returns the state of one game.
+    ///
+    /// # Errors
+    ///
+    /// Throws a `NoSuchGame` error if there is no game with the id.
+    #[wasm_bindgen]
+    pub fn get_state(&self, id: &str) -> Result<GameState, PachisloError> {
+        self.game(id)?.get_state()
+    }
+
+    /// Returns the counters of one game, like `WasmGame::stats`.
+    ///
+    /// # Errors
+    ///
+    /// Throws a `NoSuchGame` error if there is no game with the id.
+    #[wasm_bindgen]
+    pub fn stats(&self, id: &str) -> Result<GameStats, PachisloError> {
+        self.game(id)?.stats()
+    }
+
+    /// Captures the state of one game, like `WasmGame::snapshot`.
+    ///
+    /// # Errors
+    ///
+    /// Throws a `NoSuchGame` error if there is no game with the id.
+    #[wasm_bindgen]
+    pub fn snapshot(&self, id: &str) -> Result<GameSnapshot, PachisloError> {
+        self.game(id)?.snapshot()
+    }
+
+    /// Restores the state of one game, like `WasmGame::restore`.
+    ///
+    /// # Errors
+    ///
+    /// Throws a `NoSuchGame` error if there is no game with the id, or an
+    /// `InvalidSnapshot` error if the snapshot cannot be restored.
+    #[wasm_bindgen]
+    pub fn restore(&self, id: &str, snapshot: GameSnapshot) -> Result<(), PachisloError> {
+        self.game(id)?.restore(snapshot)
+    }
+
+    /// Returns the counters of all games combined; see `GameStats.merge`.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if a game is in use.
+    #[wasm_bindgen]
+    pub fn total_stats(&self) -> Result<GameStats, PachisloError> {
+        self.games
+            .values()
+            .try_fold(GameStats::default(), |total, game| {
+                Ok(total.merge(&game.stats()?))
+            })
+    }
+
+    /// Returns the balls held by all games combined.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if a game is in use.
+    #[wasm_bindgen]
+    pub fn total_balls(&self) -> Result<usize, PachisloError> {
+        self.games
+            .values()
+            .try_fold(0, |total, game| Ok(total + game.balls()?))
+    }
+}
+
+impl GameManager {
+    fn game(&self, id: &str) -> Result<&WasmGame, PachisloError> {
+        self.games
+            .get(id)
+            .ok_or_else(|| PachisloError::NoSuchGame { id: id.to_string() })
+    }
+}
@@ -0,0 +1,331 @@
+//! # Config Migration
+//!
+//! Moves a running game onto a new spec, e.g. for a scheduled spec change in
+//! the middle of live sessions. Unlike `WasmGame::update_config`, the state
+//! is mapped into what the new spec could have produced, and every
+//! adjustment is reported.
+
+use pachislo::game::GameState as PachisloGameState;
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    PachisloError, WasmGame,
+    alias::{BallsConfig, Config, GameState},
+    engine::{Mechanics, RushModel},
+};
+
+/// How the state of a running game is mapped onto a new spec.
+///
+/// # Variants
+///
+/// * `Keep` - Keep the state unchanged
+/// * `Clamp` - Keep the mode, but cap the rush balls at what the new spec
+///   pays for the current rush chain (`incremental_rush * n`), or at the
+///   spins of an ST rush; a rush left without rush balls ends. Jitan spins
+///   are capped at the new spec's jitan spins, ending jitan without them
+/// * `EndRush` - End a running rush, keeping the balls and dropping the
+///   rush balls; jitan mode is kept
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum MigrationPolicy {
+    Keep,
+    Clamp,
+    EndRush,
+}
+
+/// What a migration changed.
+///
+/// # Fields
+///
+/// * `before` - State before the migration
+/// * `after` - State after the migration
+/// * `adjustments` - One human-readable line per adjustment, empty if the
+///   state was kept
+#[derive(Clone, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct MigrationReport {
+    pub before: GameState,
+    pub after: GameState,
+    pub adjustments: Vec<String>,
+}
+
+#[wasm_bindgen]
+impl WasmGame {
+    /// Switches a running game to a new configuration, mapping its state
+    /// into the new spec according to `policy`.
+    ///
+    /// Like [`WasmGame::update_config`], the history, rules and statistics
+    /// are kept and the new settings apply from the next lottery on. The next
+    /// transition starts from the migrated state. A pending staged reveal is
+    /// completed first.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the configuration is invalid, leaving the game
+    /// unchanged, or if the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const report = game.migrate_config(eveningConfig, "Clamp");
+    /// report.adjustments.forEach((line) => log.info(line));
+    /// ```
+    #[wasm_bindgen]
+    pub fn migrate_config(
+        &self,
+        config: Config,
+        policy: MigrationPolicy,
+    ) -> Result<MigrationReport, PachisloError> {
+        config.validate()?;
+
+        let mut session = self.session()?;
+
+        // The held payout of a pending reveal belongs to the old spec
+        let now = session.clock.now()?;
+        session.complete_reveal(now);
+
+        let before = *session.engine.state();
+        let (after, adjustments) = migrate(before, &config.balls, config.mechanics(), policy);
+
+        let mechanics = config.mechanics();
+        session
+            .engine
+            .set_config(config.clone().into())
+            .map_err(|error| PachisloError::InvalidConfig {
+                message: error.to_string(),
+            })?;
+        session.engine.set_mechanics(mechanics);
+        session.config = config;
+
+        let before_state = session.engine.before_state().map(|_| after);
+        session.engine.restore_state(after, before_state);
+        session.engine.output_mut().take_failure()?;
+
+        Ok(MigrationReport {
+            before: before.into(),
+            after: after.into(),
+            adjustments,
+        })
+    }
+}
+
+/// Maps a state onto the ball settings of a new spec.
+pub(crate) fn migrate(
+    state: PachisloGameState,
+    balls: &BallsConfig,
+    mechanics: Mechanics,
+    policy: MigrationPolicy,
+) -> (PachisloGameState, Vec<String>) {
+    let PachisloGameState::Rush {
+        balls: held,
+        rush_balls,
+        n,
+    } = state
+    else {
+        return (state, Vec::new());
+    };
+
+    if n == 0 {
+        return migrate_jitan(state, held, rush_balls, mechanics, policy);
+    }
+
+    let end_rush = |reason: &str| {
+        (
+            PachisloGameState::Normal { balls: held },
+            vec![format!(
+                "rush ended: {reason}; {rush_balls} rush ball(s) dropped"
+            )],
+        )
+    };
+
+    match policy {
+        MigrationPolicy::Keep => (state, Vec::new()),
+        MigrationPolicy::EndRush => end_rush("the policy ends running rushes"),
+        MigrationPolicy::Clamp => {
+            let (limit, basis) = match mechanics.rush_model {
+                RushModel::Continuation => (
+                    balls.incremental_rush.saturating_mul(n),
+                    format!("incremental_rush {} x rush {n}", balls.incremental_rush),
+                ),
+                RushModel::SpinBudget(spins) => (spins, format!("ST rush of {spins} spins")),
+            };
+
+            if rush_balls <= limit {
+                (state, Vec::new())
+            } else if limit == 0 {
+                end_rush("the new spec pays no rush balls")
+            } else {
+                (
+                    PachisloGameState::Rush {
+                        balls: held,
+                        rush_balls: limit,
+                        n,
+                    },
+                    vec![format!(
+                        "rush_balls clamped from {rush_balls} to {limit} ({basis})"
+                    )],
+                )
+            }
+        }
+    }
+}
+
+/// Maps a jitan state, `spins` left, onto the jitan mode of a new spec.
+fn migrate_jitan(
+    state: PachisloGameState,
+    balls: usize,
+    spins: usize,
+    mechanics: Mechanics,
+    policy: MigrationPolicy,
+) -> (PachisloGameState, Vec<String>) {
+    let MigrationPolicy::Clamp = policy else {
+        return (state, Vec::new());
+    };
+
+    match mechanics.jitan {
+        Some(jitan) if spins <= jitan.spins => (state, Vec::new()),
+        Some(jitan) => (
+            PachisloGameState::Rush {
+                balls,
+                rush_balls: jitan.spins,
+                n: 0,
+            },
+            vec![format!(
+                "jitan spins clamped from {spins} to {}",
+                jitan.spins
+            )],
+        ),
+        None => (
+            PachisloGameState::Normal { balls },
+            vec![format!(
+                "jitan ended: the new spec has no jitan mode; {spins} spin(s) dropped"
+            )],
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{alias::SlotProbability, engine::Jitan};
+
+    const RUSH: PachisloGameState = PachisloGameState::Rush {
+        balls: 100,
+        rush_balls: 40,
+        n: 2,
+    };
+
+    fn rush_balls(state: PachisloGameState) -> Option<usize> {
+        match state {
+            PachisloGameState::Rush { rush_balls, .. } => Some(rush_balls),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_normal_state_is_kept() {
+        let normal = PachisloGameState::Normal { balls: 5 };
+
+        for policy in [
+            MigrationPolicy::Keep,
+            MigrationPolicy::Clamp,
+            MigrationPolicy::EndRush,
+        ] {
+            let (state, adjustments) = migrate(
+                normal,
+                &BallsConfig::new(1, 1, 0),
+                Mechanics::default(),
+                policy,
+            );
+            assert!(matches!(state, PachisloGameState::Normal { balls: 5 }));
+            assert!(adjustments.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_clamp() {
+        let (state, adjustments) = migrate(
+            RUSH,
+            &BallsConfig::new(100, 10, 15),
+            Mechanics::default(),
+            MigrationPolicy::Clamp,
+        );
+        assert_eq!(rush_balls(state), Some(30));
+        assert_eq!(adjustments.len(), 1);
+        assert!(adjustments[0].starts_with("rush_balls clamped from 40 to 30"));
+
+        let (state, adjustments) = migrate(
+            RUSH,
+            &BallsConfig::new(100, 10, 20),
+            Mechanics::default(),
+            MigrationPolicy::Clamp,
+        );
+        assert_eq!(rush_balls(state), Some(40));
+        assert!(adjustments.is_empty());
+
+        let (state, adjustments) = migrate(
+            RUSH,
+            &BallsConfig::new(100, 10, 0),
+            Mechanics::default(),
+            MigrationPolicy::Clamp,
+        );
+        assert!(matches!(state, PachisloGameState::Normal { balls: 100 }));
+        assert!(adjustments[0].contains("40 rush ball(s) dropped"));
+
+        let (state, adjustments) = migrate(
+            RUSH,
+            &BallsConfig::new(100, 10, 0),
+            Mechanics {
+                rush_model: RushModel::SpinBudget(25),
+                ..Mechanics::default()
+            },
+            MigrationPolicy::Clamp,
+        );
+        assert_eq!(rush_balls(state), Some(25));
+        assert!(adjustments[0].ends_with("(ST rush of 25 spins)"));
+    }
+
+    #[test]
+    fn test_jitan() {
+        let jitan = PachisloGameState::Rush {
+            balls: 100,
+            rush_balls: 50,
+            n: 0,
+        };
+        let balls = BallsConfig::new(100, 10, 15);
+        let with_jitan = |spins| Mechanics {
+            jitan: Some(Jitan {
+                spins,
+                probability: SlotProbability::new(0.1, 0.0, 0.0).into(),
+            }),
+            ..Mechanics::default()
+        };
+
+        let (state, adjustments) = migrate(jitan, &balls, with_jitan(20), MigrationPolicy::Clamp);
+        assert_eq!(rush_balls(state), Some(20));
+        assert_eq!(adjustments, ["jitan spins clamped from 50 to 20"]);
+
+        let (state, adjustments) =
+            migrate(jitan, &balls, Mechanics::default(), MigrationPolicy::Clamp);
+        assert!(matches!(state, PachisloGameState::Normal { balls: 100 }));
+        assert!(adjustments[0].starts_with("jitan ended"));
+
+        let (state, _) = migrate(jitan, &balls, with_jitan(20), MigrationPolicy::EndRush);
+        assert_eq!(rush_balls(state), Some(50));
+    }
+
+    #[test]
+    fn test_end_rush_and_keep() {
+        let balls = BallsConfig::new(100, 10, 15);
+
+        let (state, _) = migrate(RUSH, &balls, Mechanics::default(), MigrationPolicy::EndRush);
+        assert!(matches!(state, PachisloGameState::Normal { balls: 100 }));
+
+        let (state, adjustments) =
+            migrate(RUSH, &balls, Mechanics::default(), MigrationPolicy::Keep);
+        assert_eq!(rush_balls(state), Some(40));
+        assert!(adjustments.is_empty());
+    }
+}
@@ -0,0 +1,63 @@
+//! # Command Previews
+//!
+//! Dry runs of a command against a copy of the game (see
+//! `WasmGame::preview_command`), so UIs can tell in advance what a button
+//! would do, e.g. disable one whose command would not change anything.
+//!
+//! Deterministic commands such as `StartGame` and `FinishGame` preview
+//! exactly. Commands that draw random outcomes preview exactly in seeded
+//! games, whose generator is copied. Unseeded games preview with a fresh
+//! generator instead, since an exact preview would let players see upcoming
+//! outcomes.
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    PachisloError, WasmGame, alias::Transition, check_command, convert_string_to_command,
+    rng::GameRng,
+};
+
+#[wasm_bindgen]
+impl WasmGame {
+    /// Returns the transition `command` would cause without changing the
+    /// game. No output callbacks are called, and nothing is recorded.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the command would be rejected by
+    /// `run_step_with_command`, if a fake outcome awaits its reveal, or if
+    /// the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const { before, after } = game.preview_command("LaunchBall");
+    /// launchButton.disabled = JSON.stringify(before) === JSON.stringify(after);
+    /// ```
+    #[wasm_bindgen]
+    pub fn preview_command(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "Command")] command: String,
+    ) -> Result<Transition, PachisloError> {
+        let parsed =
+            convert_string_to_command(&command).ok_or_else(|| PachisloError::UnknownCommand {
+                command: command.clone(),
+            })?;
+
+        let mut session = self.session()?;
+        if session.engine.is_reveal_pending() {
+            return Err(PachisloError::RevealPending);
+        }
+        check_command(&command, session.engine.state())?;
+        let now = session.clock.now()?;
+        session.rules.check(parsed, now)?;
+
+        let rng = if session.seeded {
+            session.engine.rng().clone()
+        } else {
+            GameRng::default()
+        };
+
+        Ok(session.engine.preview(parsed, rng).into())
+    }
+}
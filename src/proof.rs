@@ -0,0 +1,232 @@
+//! # Score Proofs
+//!
+//! A compact binary blob binding a final score to the seed and configuration it
+//! was achieved with, so leaderboard backends can check submissions inside the
+//! same crate.
+//!
+//! The embedded checksum detects corrupted or hand-edited blobs, but it is not
+//! a signature: anyone holding the crate can produce a valid-looking proof.
+//! Backends that need stronger guarantees should have the command log
+//! submitted along with the proof and check it with
+//! [`ScoreProof::verify_replay`], which re-simulates the game.
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::alias::Config;
+use crate::hash::{self, Fnv1a};
+use crate::{JsInput, JsOutput, PachisloError, WasmGame};
+
+/// Version byte at the start of every blob.
+const BLOB_VERSION: u8 = 2;
+
+/// Size of an encoded blob: version, four fields and the checksum.
+const BLOB_LEN: usize = 1 + 8 * 5;
+
+/// Proof that a score was achieved with a given seed and configuration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[wasm_bindgen]
+pub struct ScoreProof {
+    /// Seed the game was played with
+    pub seed: u64,
+    /// Hash of the configuration the game was played with
+    pub config_hash: u64,
+    /// Number of commands executed during the game
+    pub command_count: usize,
+    /// Final score, usually the final ball count
+    pub final_score: usize,
+}
+
+#[wasm_bindgen]
+impl ScoreProof {
+    /// Creates a proof for a finished game.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - Seed the game was played with
+    /// * `config` - Configuration the game was played with
+    /// * `command_count` - Number of commands executed
+    /// * `final_score` - Score to submit
+    #[wasm_bindgen(constructor)]
+    pub fn new(seed: u64, config: &Config, command_count: usize, final_score: usize) -> Self {
+        ScoreProof {
+            seed,
+            config_hash: config_hash(config),
+            command_count,
+            final_score,
+        }
+    }
+
+    /// Encodes the proof as a compact binary blob.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const blob = new ScoreProof(seed, config, commands, balls).to_blob();
+    /// await fetch("/scores", { method: "POST", body: blob });
+    /// ```
+    #[wasm_bindgen]
+    pub fn to_blob(&self) -> Vec<u8> {
+        let mut blob = Vec::with_capacity(BLOB_LEN);
+        blob.push(BLOB_VERSION);
+        blob.extend_from_slice(&self.seed.to_le_bytes());
+        blob.extend_from_slice(&self.config_hash.to_le_bytes());
+        blob.extend_from_slice(&(self.command_count as u64).to_le_bytes());
+        blob.extend_from_slice(&(self.final_score as u64).to_le_bytes());
+        blob.extend_from_slice(&checksum(&blob).to_le_bytes());
+        blob
+    }
+
+    /// Decodes a blob produced by [`ScoreProof::to_blob`].
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the blob has the wrong size or version, or if its
+    /// checksum does not match its contents.
+    #[wasm_bindgen]
+    pub fn from_blob(blob: &[u8]) -> Result<ScoreProof, PachisloError> {
+        decode(blob).map_err(|message| PachisloError::InvalidSnapshot {
+            message: message.to_string(),
+        })
+    }
+
+    /// Checks that the proof was produced for `config`.
+    ///
+    /// This only compares the configuration hash, so a proof with a made-up
+    /// score passes; see [`ScoreProof::verify_replay`].
+    #[wasm_bindgen]
+    pub fn verify(&self, config: &Config) -> bool {
+        self.config_hash == config_hash(config)
+    }
+
+    /// Checks the proof by re-simulating the game: the proof must have been
+    /// produced for `config`, `commands` must hold `command_count`
+    /// commands, and replaying them like `WasmGame::replay` with the proof's
+    /// seed must end with `final_score` final balls.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Configuration the game should have been played with
+    /// * `commands` - Command log of the game, e.g. the `command`s of
+    ///   `get_history()`
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` describing every problem if the configuration is
+    /// invalid. A command log that cannot be replayed is not an error; the
+    /// proof just does not verify.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const proof = ScoreProof.from_blob(submission.blob);
+    /// if (!proof.verify_replay(machineConfig, submission.commands)) reject(submission);
+    /// ```
+    #[wasm_bindgen]
+    pub fn verify_replay(
+        &self,
+        config: Config,
+        commands: Vec<String>,
+    ) -> Result<bool, PachisloError> {
+        config.validate()?;
+        if !self.verify(&config) || commands.len() != self.command_count {
+            return Ok(false);
+        }
+
+        let game = match WasmGame::replay(
+            JsInput::new(),
+            JsOutput::silent(),
+            config,
+            self.seed,
+            commands,
+        ) {
+            Ok(game) => game,
+            Err(PachisloError::ReplayFailed { .. }) => return Ok(false),
+            Err(error) => return Err(error),
+        };
+        let final_balls = game
+            .session_summary()?
+            .map_or(0, |summary| summary.final_balls);
+
+        Ok(final_balls == self.final_score)
+    }
+}
+
+fn config_hash(config: &Config) -> u64 {
+    let mut hasher = Fnv1a::new();
+    hash::hash_config(&mut hasher, config);
+    hasher.finish()
+}
+
+fn checksum(bytes: &[u8]) -> u64 {
+    let mut hasher = Fnv1a::new();
+    hasher.write_str("pachislo-wasm score proof");
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+fn decode(blob: &[u8]) -> Result<ScoreProof, &'static str> {
+    if blob.len() != BLOB_LEN {
+        return Err("score proof blob has the wrong length");
+    }
+    if blob[0] != BLOB_VERSION {
+        return Err("unsupported score proof version");
+    }
+
+    let field = |index: usize| {
+        let start = 1 + 8 * index;
+        u64::from_le_bytes(blob[start..start + 8].try_into().unwrap())
+    };
+
+    if field(4) != checksum(&blob[..BLOB_LEN - 8]) {
+        return Err("score proof checksum does not match");
+    }
+
+    Ok(ScoreProof {
+        seed: field(0),
+        config_hash: field(1),
+        command_count: field(2) as usize,
+        final_score: field(3) as usize,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proof() -> ScoreProof {
+        ScoreProof {
+            seed: 0x0123_4567_89ab_cdef,
+            config_hash: 42,
+            command_count: 1500,
+            final_score: 2300,
+        }
+    }
+
+    #[test]
+    fn test_blob_round_trip() {
+        let blob = proof().to_blob();
+
+        assert_eq!(blob.len(), BLOB_LEN);
+        assert_eq!(decode(&blob), Ok(proof()));
+    }
+
+    #[test]
+    fn test_tampered_blob_is_rejected() {
+        let mut blob = proof().to_blob();
+        // Bump the final score
+        blob[1 + 8 * 3] += 1;
+
+        assert!(decode(&blob).is_err());
+    }
+
+    #[test]
+    fn test_malformed_blob_is_rejected() {
+        let blob = proof().to_blob();
+
+        assert!(decode(&blob[..BLOB_LEN - 1]).is_err());
+
+        let mut wrong_version = blob.clone();
+        wrong_version[0] = BLOB_VERSION + 1;
+        assert!(decode(&wrong_version).is_err());
+    }
+}
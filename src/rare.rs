@@ -0,0 +1,244 @@
+//! # Rare Events
+//!
+//! Estimates probabilities of events too rare for plain simulation, like a
+//! rush chaining 30 times, by importance sampling: rushes are played with
+//! rush and continuation wins drawn more often than configured, which makes
+//! long chains common, and every sampled rush is reweighted by how much
+//! more likely its draws are as configured than as drawn. The reweighted
+//! estimate has the expected value of plain simulation, but reaches a given
+//! relative error with a tiny fraction of the rushes.
+
+use pachislo::game::GameState;
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    PachisloError,
+    alias::Config,
+    engine::{Engine, Mechanics, NullOutput},
+    rng::GameRng,
+};
+
+/// Result of [`estimate_chain_probability`].
+///
+/// # Fields
+///
+/// * `rushes` - Rushes sampled
+/// * `min_chain` - Rush rounds a chain has to reach, counting the first
+/// * `tilt` - Factor the odds of rush and continuation wins were multiplied by
+/// * `probability` - Estimated probability that a rush reaches `min_chain`
+///   rounds
+/// * `standard_error` - Standard error of `probability`
+/// * `relative_error` - `standard_error` per `probability`; 0 if no sampled
+///   rush reached the chain
+/// * `hits` - Sampled rushes that reached the chain
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct RareEventEstimate {
+    pub rushes: usize,
+    pub min_chain: usize,
+    pub tilt: f64,
+    pub probability: f64,
+    pub standard_error: f64,
+    pub relative_error: f64,
+    pub hits: usize,
+}
+
+/// Estimates the probability that a rush reaches `min_chain` rounds by
+/// importance sampling.
+///
+/// Every sampled rush starts as a jackpot of normal mode does, and is
+/// played until it ends or reaches the chain. Its rush and continuation
+/// lotteries win with the odds `p / (1 - p)` multiplied by `tilt`. A tilt of
+/// 1 is plain simulation; larger ones reach long chains more often, but
+/// too large ones weigh the chains so unevenly that the error grows again,
+/// so compare `relative_error` for a few tilts.
+///
+/// # Arguments
+///
+/// * `config` - Configuration to estimate
+/// * `min_chain` - Rush rounds to reach, counting the first
+/// * `rushes` - Number of rushes to sample
+/// * `tilt` - Positive factor for the odds of rush and continuation wins
+/// * `seed` - Seed for a reproducible estimate; random when omitted
+///
+/// # Errors
+///
+/// Throws an `InvalidArguments` error if `tilt` is not positive and finite,
+/// or an `Error` describing every problem if the configuration is invalid.
+///
+/// # Example
+///
+/// ```javascript
+/// const { probability, relative_error } = estimate_chain_probability(config, 30, 10000, 8, 1n);
+/// console.log(`P(30+ chain) = ${probability.toExponential(2)} ± ${(100 * relative_error).toFixed(1)}%`);
+/// ```
+#[wasm_bindgen]
+pub fn estimate_chain_probability(
+    config: &Config,
+    min_chain: usize,
+    rushes: usize,
+    tilt: f64,
+    seed: Option<u64>,
+) -> Result<RareEventEstimate, PachisloError> {
+    config.validate()?;
+    if !(tilt.is_finite() && tilt > 0.0) {
+        return Err(PachisloError::InvalidArguments {
+            message: format!("tilt must be positive and finite, got {tilt}"),
+        });
+    }
+
+    Ok(chain_probability(
+        config.clone().into(),
+        config.mechanics(),
+        min_chain,
+        rushes,
+        tilt,
+        seed,
+    ))
+}
+
+/// Core of [`estimate_chain_probability`], independent of the JavaScript
+/// callback.
+pub(crate) fn chain_probability<F>(
+    config: pachislo::config::Config<F>,
+    mechanics: Mechanics,
+    min_chain: usize,
+    rushes: usize,
+    tilt: f64,
+    seed: Option<u64>,
+) -> RareEventEstimate
+where
+    F: FnMut(usize) -> f64,
+{
+    let rng = seed.map_or_else(GameRng::default, GameRng::from_seed);
+    let mut engine = Engine::new(config, NullOutput, rng).unwrap();
+    engine.set_mechanics(mechanics);
+
+    let (mut sum, mut sum_squared, mut hits) = (0.0, 0.0, 0);
+    for _ in 0..rushes {
+        engine.finish();
+        engine.start();
+        engine.set_tilt(Some(tilt));
+        engine.trigger_rush();
+
+        if reaches_chain(&mut engine, min_chain) {
+            let weight = engine.likelihood_ratio();
+            sum += weight;
+            sum_squared += weight * weight;
+            hits += 1;
+        }
+    }
+    engine.set_tilt(None);
+
+    let n = rushes as f64;
+    let probability = if rushes == 0 { 0.0 } else { sum / n };
+    let standard_error = if rushes > 1 {
+        ((sum_squared - n * probability * probability).max(0.0) / (n - 1.0) / n).sqrt()
+    } else {
+        0.0
+    };
+
+    RareEventEstimate {
+        rushes,
+        min_chain,
+        tilt,
+        probability,
+        standard_error,
+        relative_error: if probability == 0.0 {
+            0.0
+        } else {
+            standard_error / probability
+        },
+        hits,
+    }
+}
+
+/// Plays the running rush until it ends or reaches `min_chain` rounds.
+///
+/// Stopping at the chain leaves the likelihood ratio of the draws that
+/// decided the event, so later draws do not add noise to it.
+fn reaches_chain<F>(engine: &mut Engine<NullOutput, F>, min_chain: usize) -> bool
+where
+    F: FnMut(usize) -> f64,
+{
+    loop {
+        let GameState::Rush { n: n @ 1.., .. } = *engine.state() else {
+            return false;
+        };
+        if n >= min_chain {
+            return true;
+        }
+
+        engine.launch_ball();
+        engine.cause_lottery();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pachislo::config::{BallsConfig, Probability, SlotProbability};
+
+    /// A machine whose every rush lottery wins and draws a continuation
+    /// lottery.
+    fn config(continue_win: f64) -> pachislo::config::Config<fn(usize) -> f64> {
+        let slot = |win| SlotProbability {
+            win,
+            fake_win: 0.0,
+            fake_lose: 0.0,
+        };
+
+        pachislo::config::Config {
+            balls: BallsConfig {
+                init_balls: 100,
+                incremental_balls: 10,
+                incremental_rush: 2,
+            },
+            probability: Probability {
+                normal: slot(0.1),
+                rush: slot(1.0),
+                rush_continue: slot(continue_win),
+                rush_continue_fn: |_| 1.0,
+            },
+        }
+    }
+
+    #[test]
+    fn test_tilt_keeps_the_expected_value() {
+        let estimate =
+            |tilt| chain_probability(config(0.2), Mechanics::default(), 6, 20_000, tilt, Some(1));
+        let (plain, tilted) = (estimate(1.0), estimate(4.0));
+
+        assert_eq!(plain.probability, plain.hits as f64 / 20_000.0);
+        assert!(tilted.hits > 10 * plain.hits);
+        assert!(tilted.standard_error < plain.standard_error / 2.0);
+
+        let error = plain.standard_error.hypot(tilted.standard_error);
+        assert!((plain.probability - tilted.probability).abs() < 3.0 * error);
+    }
+
+    #[test]
+    fn test_tilt_reaches_rare_chains() {
+        let estimate =
+            |tilt| chain_probability(config(0.2), Mechanics::default(), 20, 20_000, tilt, Some(2));
+        let (plain, tilted, steeper) = (estimate(1.0), estimate(4.0), estimate(8.0));
+
+        assert_eq!(plain.hits, 0);
+        assert!(tilted.probability < 1e-5);
+        assert!(tilted.relative_error < 0.1);
+
+        let error = tilted.standard_error.hypot(steeper.standard_error);
+        assert!((tilted.probability - steeper.probability).abs() < 3.0 * error);
+    }
+
+    #[test]
+    fn test_first_round_is_certain() {
+        let estimate = chain_probability(config(0.0), Mechanics::default(), 1, 10, 3.0, Some(2));
+
+        assert_eq!(estimate.probability, 1.0);
+        assert_eq!(estimate.standard_error, 0.0);
+        assert_eq!(estimate.hits, 10);
+    }
+}
@@ -0,0 +1,183 @@
+//! # Rust Outputs
+//!
+//! Applications that compile this crate into their own WebAssembly module can
+//! observe games with outputs written in Rust, such as statistics collectors,
+//! recorders or network forwarders, without routing events through
+//! JavaScript. An output is registered under a name at startup; JavaScript
+//! then attaches it to a `JsOutput` by name before creating the game.
+//!
+//! Rust outputs implement [`UserOutput`] and receive every event of the game,
+//! also while the page is in the background, before the JavaScript callbacks.
+//!
+//! ```ignore
+//! use pachislo_wasm::registry::{UserOutput, register_output};
+//!
+//! #[wasm_bindgen(start)]
+//! fn start() {
+//!     register_output("spin_counter", || Box::new(SpinCounter::default()));
+//! }
+//! ```
+
+use std::{collections::BTreeMap, sync::Mutex};
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::JsOutput;
+use crate::PachisloError;
+
+pub use pachislo::interface::UserOutput;
+
+/// Creates a new instance of a registered output for every output it is
+/// attached to.
+pub type OutputFactory = fn() -> Box<dyn UserOutput>;
+
+/// Output factories by name.
+static REGISTRY: Mutex<BTreeMap<String, OutputFactory>> = Mutex::new(BTreeMap::new());
+
+/// Registers an output factory under `name`, replacing any factory
+/// registered under the same name.
+///
+/// Outputs already attached keep the factory they were created with.
+pub fn register_output(name: &str, factory: OutputFactory) {
+    registry().insert(name.to_string(), factory);
+}
+
+/// Returns the names of all registered Rust outputs in alphabetical order.
+///
+/// # Example
+///
+/// ```javascript
+/// console.log(registered_outputs());  // ["recorder", "spin_counter"]
+/// ```
+#[wasm_bindgen]
+pub fn registered_outputs() -> Vec<String> {
+    registry().keys().cloned().collect()
+}
+
+fn registry() -> std::sync::MutexGuard<'static, BTreeMap<String, OutputFactory>> {
+    // The map stays consistent even if a registration panicked
+    REGISTRY
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// A registered output attached to a `JsOutput`.
+pub(crate) struct RustOutput {
+    factory: OutputFactory,
+    pub(crate) output: Box<dyn UserOutput>,
+}
+
+impl RustOutput {
+    /// Creates an instance of the output registered under `name`.
+    fn new(name: &str) -> Result<Self, String> {
+        let registry = registry();
+        let Some(&factory) = registry.get(name) else {
+            let names: Vec<_> = registry.keys().map(|name| format!("{name:?}")).collect();
+            return Err(format!(
+                "no Rust output is registered as {name:?}; registered outputs: [{}]",
+                names.join(", ")
+            ));
+        };
+
+        Ok(RustOutput {
+            factory,
+            output: factory(),
+        })
+    }
+}
+
+impl Clone for RustOutput {
+    /// Creates a new instance from the same factory; outputs are not shared.
+    fn clone(&self) -> Self {
+        RustOutput {
+            factory: self.factory,
+            output: (self.factory)(),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl JsOutput {
+    /// Attaches a new instance of the Rust output registered under `name`.
+    ///
+    /// The Rust output receives every event of the game before the JavaScript
+    /// callbacks. Outputs can be attached several times; a cloned `JsOutput`,
+    /// e.g. for every machine of a hall, gets new instances.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` listing the registered outputs if no output is
+    /// registered under `name`.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const output = JsOutput.from_events(new JsEventOutput(null, onEvent));
+    /// output.add_rust_output("spin_counter");
+    /// const game = new WasmGame(new JsInput(), output, config);
+    /// ```
+    #[wasm_bindgen]
+    pub fn add_rust_output(&mut self, name: &str) -> Result<(), PachisloError> {
+        self.rust_outputs
+            .push(
+                RustOutput::new(name).map_err(|message| PachisloError::InvalidArguments {
+                    message: message.to_string(),
+                })?,
+            );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use pachislo::{
+        game::{GameState, Transition},
+        lottery::{Lose, LotteryResult},
+    };
+
+    use super::*;
+
+    static CREATED: AtomicUsize = AtomicUsize::new(0);
+    static LOTTERIES: AtomicUsize = AtomicUsize::new(0);
+
+    struct Counter;
+
+    impl UserOutput for Counter {
+        fn default(&mut self, _: Transition) {}
+
+        fn finish_game(&mut self, _: &GameState) {}
+
+        fn lottery_normal(&mut self, _: LotteryResult) {
+            LOTTERIES.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn lottery_rush(&mut self, _: LotteryResult) {}
+
+        fn lottery_rush_continue(&mut self, _: LotteryResult) {}
+    }
+
+    #[test]
+    fn test_registered_outputs() {
+        register_output("test_counter", || {
+            CREATED.fetch_add(1, Ordering::Relaxed);
+            Box::new(Counter)
+        });
+        assert!(registered_outputs().contains(&"test_counter".to_string()));
+
+        let mut output = RustOutput::new("test_counter").unwrap();
+        output
+            .output
+            .lottery_normal(LotteryResult::Lose(Lose::Default));
+        assert_eq!(LOTTERIES.load(Ordering::Relaxed), 1);
+
+        let _clone = output.clone();
+        assert_eq!(CREATED.load(Ordering::Relaxed), 2);
+
+        let error = RustOutput::new("missing").err().unwrap();
+        assert!(error.contains("\"missing\""));
+        assert!(error.contains("\"test_counter\""));
+    }
+}
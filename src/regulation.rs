@@ -0,0 +1,241 @@
+//! # Regulation Profiles
+//!
+//! Selectable sets of limits modelled after Japanese pachinko regulations and
+//! industry self-regulation (内規). Apps that want to mirror legally plausible
+//! machines can check a configuration against a profile and list the violations.
+//!
+//! The presets are simplified approximations intended for games, not a legal
+//! reference.
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::alias::{BallsConfig, Config, SlotProbability};
+use crate::analysis;
+
+/// A set of regulatory limits a configuration can be checked against.
+///
+/// Every limit is optional; `undefined` disables the corresponding check.
+#[derive(Debug, Clone, Copy, Default)]
+#[wasm_bindgen]
+pub struct RegulationProfile {
+    /// Lowest allowed normal-mode win probability per lottery
+    pub min_hit_probability: Option<f64>,
+    /// Highest allowed probability that a rush continues at least once
+    pub max_continuation_rate: Option<f64>,
+    /// Highest allowed number of balls paid out per jackpot
+    pub max_payout_per_hit: Option<usize>,
+}
+
+#[wasm_bindgen]
+impl RegulationProfile {
+    /// Creates a profile without any limits.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits introduced by the 2016 self-regulation: odds no worse than 1/320
+    /// and a continuation rate of at most 65%.
+    #[wasm_bindgen]
+    pub fn naiki_2016() -> Self {
+        RegulationProfile {
+            min_hit_probability: Some(1.0 / 320.0),
+            max_continuation_rate: Some(0.65),
+            max_payout_per_hit: Some(2400),
+        }
+    }
+
+    /// Limits of the 2018 regulation revision (P machines): odds no worse than
+    /// 1/320 and at most 1500 balls per jackpot.
+    #[wasm_bindgen]
+    pub fn p_machine_2018() -> Self {
+        RegulationProfile {
+            min_hit_probability: Some(1.0 / 320.0),
+            max_continuation_rate: None,
+            max_payout_per_hit: Some(1500),
+        }
+    }
+}
+
+/// Rule broken by a configuration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum RegulationRule {
+    /// Normal-mode odds are worse than allowed
+    MinHitProbability,
+    /// Rush continues more often than allowed
+    MaxContinuationRate,
+    /// A single jackpot pays out more balls than allowed
+    MaxPayoutPerHit,
+}
+
+/// A single regulation violation.
+#[derive(Clone, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct RegulationViolation {
+    /// The rule that was broken
+    pub rule: RegulationRule,
+    /// The limit set by the profile
+    pub limit: f64,
+    /// The value derived from the configuration
+    pub actual: f64,
+}
+
+#[wasm_bindgen]
+impl Config {
+    /// Checks the configuration against a regulation profile.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The limits to enforce
+    ///
+    /// # Returns
+    ///
+    /// Every violated rule; an empty list means the configuration complies.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const violations = config.check_regulation(RegulationProfile.p_machine_2018());
+    /// ```
+    #[wasm_bindgen]
+    pub fn check_regulation(&self, profile: &RegulationProfile) -> Vec<RegulationViolation> {
+        let probability = &self.probability;
+
+        check_regulation(
+            profile,
+            &self.balls,
+            probability.normal,
+            probability.rush,
+            probability.rush_continue,
+            |n| probability.rush_continue_multiplier(n),
+        )
+    }
+}
+
+/// Core of [`Config::check_regulation`], independent of the JavaScript callback.
+pub(crate) fn check_regulation<F>(
+    profile: &RegulationProfile,
+    balls: &BallsConfig,
+    normal: SlotProbability,
+    rush: SlotProbability,
+    rush_continue: SlotProbability,
+    rush_continue_fn: F,
+) -> Vec<RegulationViolation>
+where
+    F: FnMut(usize) -> f64,
+{
+    let mut violations = Vec::new();
+
+    if let Some(limit) = profile.min_hit_probability
+        && normal.win < limit
+    {
+        violations.push(RegulationViolation {
+            rule: RegulationRule::MinHitProbability,
+            limit,
+            actual: normal.win,
+        });
+    }
+
+    if let Some(limit) = profile.max_payout_per_hit
+        && balls.incremental_balls > limit
+    {
+        violations.push(RegulationViolation {
+            rule: RegulationRule::MaxPayoutPerHit,
+            limit: limit as f64,
+            actual: balls.incremental_balls as f64,
+        });
+    }
+
+    if let Some(limit) = profile.max_continuation_rate {
+        let continuation_rate = analysis::solve(
+            balls,
+            normal,
+            rush,
+            rush_continue,
+            rush_continue_fn,
+            pachislo::START_HOLE_PROBABILITY_EXAMPLE,
+            analysis::DEFAULT_MAX_DEPTH,
+        )
+        .continuation_rate();
+
+        if continuation_rate > limit {
+            violations.push(RegulationViolation {
+                rule: RegulationRule::MaxContinuationRate,
+                limit,
+                actual: continuation_rate,
+            });
+        }
+    }
+
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(violations: &[RegulationViolation]) -> Vec<RegulationRule> {
+        violations.iter().map(|violation| violation.rule).collect()
+    }
+
+    #[test]
+    fn test_unrestricted_profile() {
+        let balls = BallsConfig::new(1000, 5000, 100);
+        let prob = SlotProbability::new(0.0001, 0.0, 0.0);
+
+        let violations =
+            check_regulation(&RegulationProfile::new(), &balls, prob, prob, prob, |_| 1.0);
+
+        assert!(violations.is_empty());
+    }
+
+    #[test]
+    fn test_p_machine_2018() {
+        let balls = BallsConfig::new(1000, 2000, 100);
+        let normal = SlotProbability::new(1.0 / 400.0, 0.0, 0.0);
+        let rush = SlotProbability::new(1.0, 0.0, 0.0);
+
+        let violations = check_regulation(
+            &RegulationProfile::p_machine_2018(),
+            &balls,
+            normal,
+            rush,
+            rush,
+            |_| 1.0,
+        );
+
+        assert_eq!(
+            rules(&violations),
+            vec![
+                RegulationRule::MinHitProbability,
+                RegulationRule::MaxPayoutPerHit
+            ]
+        );
+        assert_eq!(violations[1].actual, 2000.0);
+    }
+
+    #[test]
+    fn test_naiki_2016_continuation_rate() {
+        let balls = BallsConfig::new(1000, 1500, 100);
+        let normal = SlotProbability::new(1.0 / 300.0, 0.0, 0.0);
+        let rush = SlotProbability::new(1.0, 0.0, 0.0);
+
+        let violations = check_regulation(
+            &RegulationProfile::naiki_2016(),
+            &balls,
+            normal,
+            rush,
+            rush,
+            |_| 1.0,
+        );
+
+        assert_eq!(
+            rules(&violations),
+            vec![RegulationRule::MaxContinuationRate]
+        );
+        assert!(violations[0].actual > 0.65);
+    }
+}
@@ -0,0 +1,248 @@
+//! # Remote Control
+//!
+//! A small message protocol for games hosted on a server and played or
+//! watched over WebSockets. Clients send [`ClientMessage`]s, and the game
+//! answers with [`ServerMessage`]s, all of them JSON text, so a server only
+//! relays strings between the sockets and a [`RemoteGameAdapter`].
+//!
+//! Every message carries a sequence number. Clients number their messages
+//! in increasing order, which lets the adapter recognize a command that was
+//! sent again after a reconnect and answer it without playing it twice. The
+//! adapter numbers its own messages consecutively from 0, so a spectator
+//! that misses one can tell and ask for the current state with `Sync`.
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::{JsValue, prelude::wasm_bindgen};
+
+use crate::{PachisloError, WasmGame, alias::GameState};
+
+/// A message from a client to a [`RemoteGameAdapter`].
+///
+/// # Variants
+///
+/// * `Command` - Executes a command
+/// * `Sync` - Requests the current state
+///
+/// # Example
+///
+/// ```javascript
+/// socket.send(JSON.stringify({ type: "Command", seq: 7, command: "LaunchBall" }));
+/// ```
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(tag = "type")]
+pub enum ClientMessage {
+    Command {
+        seq: u32,
+        #[tsify(type = "Command")]
+        command: String,
+    },
+    Sync {
+        seq: u32,
+    },
+}
+
+/// A message from a [`RemoteGameAdapter`] to its clients.
+///
+/// `seq` numbers the messages of an adapter consecutively, and `reply_to`
+/// is the sequence number of the client message answered.
+///
+/// # Variants
+///
+/// * `Event` - An event of an executed command, with the event type and
+///   payload of an event output (see `JsOutput::from_events`)
+/// * `Ack` - A command was executed, after all its events
+/// * `State` - The current state, answering `Sync` or sent unprompted
+/// * `Error` - A message was rejected; `reply_to` is missing if the
+///   message could not be read
+#[derive(Clone, Debug, Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+#[serde(tag = "type")]
+pub enum ServerMessage {
+    Event {
+        seq: u32,
+        reply_to: u32,
+        event: &'static str,
+        #[serde(with = "serde_wasm_bindgen::preserve")]
+        #[tsify(type = "any")]
+        payload: JsValue,
+    },
+    Ack {
+        seq: u32,
+        reply_to: u32,
+        state: GameState,
+    },
+    State {
+        seq: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        reply_to: Option<u32>,
+        state: GameState,
+    },
+    Error {
+        seq: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        reply_to: Option<u32>,
+        message: String,
+        error: PachisloError,
+    },
+}
+
+/// A game controlled through [`ClientMessage`]s.
+///
+/// The adapter plays commands like `WasmGame::run_step_with_command_batched`,
+/// so the game's output callbacks are not called; the events go out as
+/// messages instead. Messages are meant to be sent to every connected
+/// client, so spectators see the same stream as the player.
+///
+/// # Example
+///
+/// ```javascript
+/// const adapter = new RemoteGameAdapter(new WasmGame(input, output, config));
+/// server.on("connection", (socket) => {
+///     socket.send(adapter.state_message());
+///     socket.on("message", (data) => {
+///         for (const message of adapter.handle_message(data.toString())) {
+///             for (const client of server.clients) client.send(message);
+///         }
+///     });
+/// });
+/// ```
+#[wasm_bindgen]
+pub struct RemoteGameAdapter {
+    game: WasmGame,
+    next_seq: u32,
+    /// Sequence number of the last executed command
+    last_command: Option<u32>,
+}
+
+#[wasm_bindgen]
+impl RemoteGameAdapter {
+    /// Takes control of a game.
+    ///
+    /// Typed events (see `JsOutput::set_typed_events`) cannot be sent as
+    /// JSON, so the game's output should not enable them.
+    #[wasm_bindgen(constructor)]
+    pub fn new(game: WasmGame) -> Self {
+        RemoteGameAdapter {
+            game,
+            next_seq: 0,
+            last_command: None,
+        }
+    }
+
+    /// Handles a JSON [`ClientMessage`] and returns the JSON
+    /// [`ServerMessage`]s answering it, in order.
+    ///
+    /// A command is answered with its events followed by an `Ack`. A
+    /// command numbered at most like the last executed one is not executed
+    /// again, only acknowledged. Rejected commands, e.g. unknown ones, and
+    /// unreadable messages are answered with an `Error`; nothing is thrown.
+    #[wasm_bindgen]
+    pub fn handle_message(&mut self, message: &str) -> Vec<String> {
+        let message = match parse(message) {
+            Ok(message) => message,
+            Err(error) => return vec![self.error(None, error)],
+        };
+
+        match message {
+            ClientMessage::Command { seq, command } => {
+                if self.last_command.is_some_and(|last| seq <= last) {
+                    return vec![self.ack(seq)];
+                }
+
+                match self.game.step_batched(&command) {
+                    Ok((_, events)) => {
+                        self.last_command = Some(seq);
+                        let mut messages: Vec<_> = events
+                            .into_iter()
+                            .map(|(event, payload)| {
+                                self.send(|next| ServerMessage::Event {
+                                    seq: next,
+                                    reply_to: seq,
+                                    event,
+                                    payload,
+                                })
+                            })
+                            .collect();
+                        messages.push(self.ack(seq));
+                        messages
+                    }
+                    Err(error) => vec![self.error(Some(seq), error)],
+                }
+            }
+            ClientMessage::Sync { seq } => vec![self.state(Some(seq))],
+        }
+    }
+
+    /// Returns a JSON `State` message not answering any client message, e.g.
+    /// to greet a newly connected spectator.
+    #[wasm_bindgen]
+    pub fn state_message(&mut self) -> String {
+        self.state(None)
+    }
+
+    /// Returns the sequence number of the last executed command, if any.
+    #[wasm_bindgen]
+    pub fn last_command_seq(&self) -> Option<u32> {
+        self.last_command
+    }
+
+    /// Returns the sequence number the next message will carry.
+    #[wasm_bindgen]
+    pub fn next_seq(&self) -> u32 {
+        self.next_seq
+    }
+}
+
+impl RemoteGameAdapter {
+    fn ack(&mut self, reply_to: u32) -> String {
+        match self.game.get_state() {
+            Ok(state) => self.send(|seq| ServerMessage::Ack {
+                seq,
+                reply_to,
+                state,
+            }),
+            Err(error) => self.error(Some(reply_to), error),
+        }
+    }
+
+    fn state(&mut self, reply_to: Option<u32>) -> String {
+        match self.game.get_state() {
+            Ok(state) => self.send(|seq| ServerMessage::State {
+                seq,
+                reply_to,
+                state,
+            }),
+            Err(error) => self.error(reply_to, error),
+        }
+    }
+
+    fn error(&mut self, reply_to: Option<u32>, error: PachisloError) -> String {
+        self.send(|seq| ServerMessage::Error {
+            seq,
+            reply_to,
+            message: error.to_string(),
+            error,
+        })
+    }
+
+    /// Numbers a message and serializes it.
+    fn send(&mut self, message: impl FnOnce(u32) -> ServerMessage) -> String {
+        let message = message(self.next_seq);
+        self.next_seq += 1;
+
+        let value = serde_wasm_bindgen::to_value(&message).unwrap();
+        js_sys::JSON::stringify(&value).unwrap().into()
+    }
+}
+
+fn parse(message: &str) -> Result<ClientMessage, PachisloError> {
+    let value = js_sys::JSON::parse(message).map_err(|_| PachisloError::InvalidArguments {
+        message: "the message is not JSON".to_string(),
+    })?;
+
+    serde_wasm_bindgen::from_value(value).map_err(|error| PachisloError::InvalidArguments {
+        message: format!("the message is not a client message: {error}"),
+    })
+}
@@ -0,0 +1,272 @@
+//! # Staged Reveals
+//!
+//! Delivers fake outcomes in two phases. With staged reveals enabled, a spin
+//! that draws `FakeWin` or `FakeLose` only reports what the machine pretends
+//! at first: event outputs receive an `"apparent_result"` event, and the
+//! engine holds the spin's state change, including its payout. Once the UI
+//! acknowledges the reveal with [`WasmGame::ack_reveal`], or the configured
+//! delay has passed on the game clock, the state change is applied, its
+//! transition is reported and a `"true_result"` event follows.
+//!
+//! The surprise is thereby enforced by the engine: commands are rejected
+//! while a reveal is pending, so no UI can skip ahead of it. Only the spin's
+//! own lottery is staged, not a rush continuation lottery that follows it.
+//! Lottery events are unchanged and still carry the true result.
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    PachisloError, Session, WasmGame,
+    alias::{Lose, LotteryResult, Transition, Win},
+    event,
+};
+
+/// Command recorded in the history for a completed reveal.
+pub(crate) const REVEAL_COMMAND: &str = "Reveal";
+
+/// Payload of the `"apparent_result"` and `"true_result"` events.
+///
+/// # Fields
+///
+/// * `result` - The pretended result in `"apparent_result"`, the drawn one
+///   in `"true_result"`
+/// * `reveal_at` - Game clock time at which the true result is revealed
+///   without an ack; `None` if the reveal waits for `ack_reveal`, and in
+///   `"true_result"`
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct RevealEvent {
+    pub result: LotteryResult,
+    pub reveal_at: Option<f64>,
+}
+
+/// Timing of staged reveals; the held state itself is kept by the engine.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct StagedReveal {
+    /// Delay after which a reveal completes by itself
+    delay: Option<f64>,
+    /// The pending reveal's drawn result and due time
+    pending: Option<(LotteryResult, Option<f64>)>,
+}
+
+impl StagedReveal {
+    /// Forgets the pending reveal, e.g. when its spin is undone.
+    pub(crate) fn cancel(&mut self) {
+        self.pending = None;
+    }
+}
+
+/// What a fake result pretends to be; `None` for results that are not fake.
+pub(crate) fn apparent(result: LotteryResult) -> Option<LotteryResult> {
+    match result {
+        LotteryResult::Win(Win::FakeWin) => Some(LotteryResult::Lose(Lose::Default)),
+        LotteryResult::Lose(Lose::FakeLose) => Some(LotteryResult::Win(Win::Default)),
+        _ => None,
+    }
+}
+
+impl Session {
+    /// Completes a reveal whose delay has passed; fails if one is still pending.
+    pub(crate) fn poll_reveal(&mut self, now: f64) -> Result<(), PachisloError> {
+        if !self.engine.is_reveal_pending() {
+            return Ok(());
+        }
+
+        match self.reveal.and_then(|reveal| reveal.pending) {
+            Some((_, Some(reveal_at))) if now >= reveal_at => {
+                self.complete_reveal(now);
+                Ok(())
+            }
+            _ => Err(PachisloError::RevealPending),
+        }
+    }
+
+    /// Starts a reveal if the last step held a fake outcome, emitting
+    /// `"apparent_result"`.
+    pub(crate) fn start_reveal(&mut self, now: f64) {
+        let Some(reveal) = &mut self.reveal else {
+            return;
+        };
+        if !self.engine.is_reveal_pending() {
+            return;
+        }
+        let Some(result) = self.engine.last_spin().map(|spin| spin.lottery.result) else {
+            return;
+        };
+        let Some(shown) = apparent(result) else {
+            return;
+        };
+
+        let reveal_at = reveal.delay.map(|delay| now + delay);
+        reveal.pending = Some((result, reveal_at));
+
+        self.emit_reveal(
+            event::APPARENT_RESULT,
+            RevealEvent {
+                result: shown,
+                reveal_at,
+            },
+        );
+    }
+
+    /// Applies a pending reveal, records it and emits `"true_result"`;
+    /// returns `false` if none was pending.
+    pub(crate) fn complete_reveal(&mut self, now: f64) -> bool {
+        let Some(transition) = self.engine.reveal() else {
+            return false;
+        };
+        self.record_history(now, REVEAL_COMMAND, Transition::from(transition), None);
+        let balls = self.engine.balls();
+        self.balls_series.record(balls);
+        self.emit_rush_tier(transition.before);
+        self.emit_battle();
+        self.emit_rounds();
+        self.emit_streaks();
+
+        let pending = self
+            .reveal
+            .as_mut()
+            .and_then(|reveal| reveal.pending.take());
+        if let Some((result, _)) = pending {
+            self.emit_reveal(
+                event::TRUE_RESULT,
+                RevealEvent {
+                    result,
+                    reveal_at: None,
+                },
+            );
+        }
+
+        true
+    }
+
+    fn emit_reveal(&mut self, event_type: &'static str, reveal: RevealEvent) {
+        let output = self.engine.output_mut();
+        if output.is_event_output() {
+            output.emit(event_type, serde_wasm_bindgen::to_value(&reveal).unwrap());
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl WasmGame {
+    /// Enables or disables staged reveals of fake outcomes.
+    ///
+    /// While enabled, a spin drawing `FakeWin` or `FakeLose` holds its state
+    /// change until [`WasmGame::ack_reveal`] is called or `delay`
+    /// milliseconds have passed on the game clock, and every command sent
+    /// before that throws. Disabling completes a pending reveal.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether fake outcomes are staged
+    /// * `delay` - Milliseconds after which a reveal completes by itself on
+    ///   the next command, or `undefined` to always wait for `ack_reveal`
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if `delay` is negative or not finite, or if the game
+    /// mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// game.set_staged_reveal(true);
+    /// game.run_step_with_command("CauseLottery");  // "apparent_result" for a fake outcome
+    /// reels.onAnimationEnd = () => game.ack_reveal();  // "transition", then "true_result"
+    /// ```
+    #[wasm_bindgen]
+    pub fn set_staged_reveal(
+        &self,
+        enabled: bool,
+        delay: Option<f64>,
+    ) -> Result<(), PachisloError> {
+        if let Some(delay) = delay
+            && !(delay.is_finite() && delay >= 0.0)
+        {
+            return Err(PachisloError::InvalidArguments {
+                message: format!(
+                    "delay is {delay}; it must be a finite number of milliseconds, at least 0"
+                ),
+            });
+        }
+
+        let mut session = self.session()?;
+
+        session.engine.set_staged_reveal(enabled);
+        if enabled {
+            let pending = session.reveal.and_then(|reveal| reveal.pending);
+            session.reveal = Some(StagedReveal { delay, pending });
+        } else {
+            let now = session.clock.now()?;
+            session.complete_reveal(now);
+            session.reveal = None;
+        }
+
+        session.engine.output_mut().take_failure()
+    }
+
+    /// Reveals the pending fake outcome: applies its state change and emits
+    /// its transition and `"true_result"`.
+    ///
+    /// # Returns
+    ///
+    /// `false` if no reveal was pending.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    #[wasm_bindgen]
+    pub fn ack_reveal(&self) -> Result<bool, PachisloError> {
+        let mut session = self.session()?;
+        let now = session.clock.now()?;
+
+        let revealed = session.complete_reveal(now);
+        session.engine.output_mut().take_failure()?;
+
+        Ok(revealed)
+    }
+
+    /// Returns the pending reveal with its apparent result, `undefined` if
+    /// none is pending. A reveal whose delay has passed is completed first.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    #[wasm_bindgen]
+    pub fn pending_reveal(&self) -> Result<Option<RevealEvent>, PachisloError> {
+        let mut session = self.session()?;
+        let now = session.clock.now()?;
+
+        if session.poll_reveal(now).is_ok() {
+            session.engine.output_mut().take_failure()?;
+            return Ok(None);
+        }
+
+        let pending = session.reveal.and_then(|reveal| reveal.pending);
+        Ok(pending.and_then(|(result, reveal_at)| {
+            apparent(result).map(|result| RevealEvent { result, reveal_at })
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apparent() {
+        assert!(matches!(
+            apparent(LotteryResult::Win(Win::FakeWin)),
+            Some(LotteryResult::Lose(Lose::Default))
+        ));
+        assert!(matches!(
+            apparent(LotteryResult::Lose(Lose::FakeLose)),
+            Some(LotteryResult::Win(Win::Default))
+        ));
+        assert!(apparent(LotteryResult::Win(Win::Default)).is_none());
+        assert!(apparent(LotteryResult::Lose(Lose::Default)).is_none());
+    }
+}
@@ -0,0 +1,218 @@
+//! # Game Random Number Generator
+//!
+//! A small seedable PRNG (xoshiro256**) used for every random decision made by
+//! a game, so that a game created with a seed replays identically on every
+//! platform.
+//!
+//! Not cryptographically secure.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use rand::{RngCore, rand_core::impls};
+
+use crate::{
+    environment,
+    hash::{self, Fnv1a},
+};
+
+/// xoshiro256** generator seeded through SplitMix64.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct GameRng {
+    state: [u64; 4],
+    /// Complements every output, see [`GameRng::antithetic`]
+    antithetic: bool,
+    /// Stratum `(index, count)` the next output is mapped into, see
+    /// [`GameRng::set_stratum`]
+    stratum: Option<(u64, u64)>,
+}
+
+impl GameRng {
+    /// Creates a generator whose output is fully determined by `seed`.
+    pub(crate) fn from_seed(seed: u64) -> Self {
+        let mut splitmix = seed;
+        GameRng {
+            state: std::array::from_fn(|_| hash::splitmix64(&mut splitmix)),
+            antithetic: false,
+            stratum: None,
+        }
+    }
+
+    /// Returns the antithetic twin of this generator, whose outputs are the
+    /// bitwise complements of this one's. Every uniform value `u` it yields
+    /// is `1 - u` of the twin, so a game played with each is a negatively
+    /// correlated pair, for variance reduction in simulations.
+    pub(crate) fn antithetic(&self) -> Self {
+        GameRng {
+            antithetic: !self.antithetic,
+            ..self.clone()
+        }
+    }
+
+    /// Maps the next output into stratum `index` of `count` equal parts of
+    /// the output range, for stratified sampling in simulations.
+    pub(crate) fn set_stratum(&mut self, index: u64, count: u64) {
+        self.stratum = (index < count).then_some((index, count));
+    }
+
+    /// Derives an independent generator, e.g. for slot symbols, so that
+    /// drawing from one does not shift the sequence of the other.
+    pub(crate) fn split(&mut self) -> Self {
+        GameRng::from_seed(self.next_u64())
+    }
+
+    /// Encodes the internal state as 64 hexadecimal digits, e.g. for snapshots.
+    ///
+    /// The sampling modes of simulations are not part of the state.
+    pub(crate) fn encode(&self) -> String {
+        self.state
+            .iter()
+            .map(|word| format!("{word:016x}"))
+            .collect()
+    }
+
+    /// Decodes a state produced by [`GameRng::encode`].
+    ///
+    /// Returns `None` for malformed input and for the all-zero state, from
+    /// which xoshiro256** would only ever produce zeros.
+    pub(crate) fn decode(encoded: &str) -> Option<Self> {
+        if encoded.len() != 64 || !encoded.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        let mut state = [0; 4];
+        for (i, word) in state.iter_mut().enumerate() {
+            *word = u64::from_str_radix(&encoded[16 * i..16 * (i + 1)], 16).ok()?;
+        }
+
+        (state != [0; 4]).then_some(GameRng {
+            state,
+            antithetic: false,
+            stratum: None,
+        })
+    }
+}
+
+impl Default for GameRng {
+    /// Creates a generator seeded from the system entropy source, or from the
+    /// clock on hosts without `crypto.getRandomValues`.
+    fn default() -> Self {
+        let seed = getrandom::u64().unwrap_or_else(|_| {
+            static FALLBACKS: AtomicU64 = AtomicU64::new(0);
+
+            let mut hasher = Fnv1a::new();
+            hasher.write_f64(environment::now());
+            hasher.write_u64(FALLBACKS.fetch_add(1, Ordering::Relaxed));
+            hasher.finish()
+        });
+
+        GameRng::from_seed(seed)
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut result = self.next_raw();
+        if let Some((index, count)) = self.stratum.take() {
+            result = (((u128::from(index) << 64) + u128::from(result)) / u128::from(count)) as u64;
+        }
+
+        if self.antithetic { !result } else { result }
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dst)
+    }
+}
+
+impl GameRng {
+    /// The next xoshiro256** output.
+    fn next_raw(&mut self) -> u64 {
+        let [s0, s1, s2, s3] = &mut self.state;
+
+        let result = s1.wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = *s1 << 17;
+
+        *s2 ^= *s0;
+        *s3 ^= *s1;
+        *s1 ^= *s2;
+        *s0 ^= *s3;
+        *s2 ^= t;
+        *s3 = s3.rotate_left(45);
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xoshiro256_known_values() {
+        let mut rng = GameRng {
+            state: [1, 2, 3, 4],
+            antithetic: false,
+            stratum: None,
+        };
+
+        assert_eq!(rng.next_u64(), 11520);
+        assert_eq!(rng.next_u64(), 0);
+        assert_eq!(rng.next_u64(), 1_509_978_240);
+    }
+
+    #[test]
+    fn test_same_seed_same_sequence() {
+        let mut a = GameRng::from_seed(42);
+        let mut b = GameRng::from_seed(42);
+        let mut c = GameRng::from_seed(43);
+
+        let a: Vec<u64> = (0..8).map(|_| a.next_u64()).collect();
+        let b: Vec<u64> = (0..8).map(|_| b.next_u64()).collect();
+        let c: Vec<u64> = (0..8).map(|_| c.next_u64()).collect();
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn test_antithetic_and_stratified_outputs() {
+        let rng = GameRng::from_seed(7);
+        let (mut a, mut b) = (rng.clone(), rng.antithetic());
+        for _ in 0..8 {
+            assert_eq!(a.next_u64(), !b.next_u64());
+        }
+
+        let mut stratified = rng.clone();
+        stratified.set_stratum(3, 4);
+        assert!(stratified.next_u64() >= 3 << 62);
+        // Only the next output is stratified
+        let mut plain = rng.clone();
+        plain.next_u64();
+        assert_eq!(stratified.next_u64(), plain.next_u64());
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        let mut rng = GameRng::from_seed(3);
+        rng.next_u64();
+
+        let encoded = rng.encode();
+        assert_eq!(encoded.len(), 64);
+
+        let mut decoded = GameRng::decode(&encoded).unwrap();
+        assert_eq!(decoded, rng);
+        assert_eq!(decoded.next_u64(), rng.next_u64());
+    }
+
+    #[test]
+    fn test_decode_rejects_invalid_states() {
+        assert_eq!(GameRng::decode(""), None);
+        assert_eq!(GameRng::decode(&"g".repeat(64)), None);
+        assert_eq!(GameRng::decode(&"0".repeat(64)), None);
+        assert_eq!(GameRng::decode(&"\u{e9}".repeat(32)), None);
+    }
+}
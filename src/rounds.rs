@@ -0,0 +1,144 @@
+//! # Jackpot Rounds
+//!
+//! Real machines pay a jackpot out over a number of rounds (4R, 10R, 16R,
+//! ...), each opening the attacker for a fixed number of balls. With rounds
+//! configured (see `Config::with_rounds`), every jackpot draws its round
+//! count by weight and pays `balls_per_round` for each round, replacing the
+//! flat `incremental_balls` of normal wins, rush entries and rush tiers.
+//!
+//! The payout itself is applied at once. Event outputs receive a
+//! `"round_started"` and a `"round_finished"` event with a [`RoundEvent`]
+//! for every round, after the transition of the command that paid out the
+//! jackpot, so the UI can present the rounds one after another. A jackpot
+//! held by a staged reveal announces its rounds once it is revealed.
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{PachisloError, Session, alias::Config, engine::Rounds, event};
+
+/// Payload of the `"round_started"` and `"round_finished"` events.
+///
+/// # Fields
+///
+/// * `round` - The round, from 1
+/// * `rounds` - Rounds of the jackpot
+/// * `balls` - Balls paid out by the round
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct RoundEvent {
+    pub round: usize,
+    pub rounds: usize,
+    pub balls: usize,
+}
+
+#[wasm_bindgen]
+impl Config {
+    /// Returns a copy of the configuration whose jackpots pay out in rounds.
+    ///
+    /// Every jackpot draws one of `rounds`, with a chance of its weight in
+    /// `weights` over the sum of all weights, and pays `balls_per_round`
+    /// balls per round instead of `incremental_balls`. Rush balls are still
+    /// paid as configured. See `SpinExplanation.rounds` and the
+    /// `"round_started"` and `"round_finished"` events.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if `rounds` is empty, its length differs from
+    /// `weights`, a round count is 0 or a weight is not a positive number.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// // Half of the jackpots are 4R, the rest 10R or 16R, at 140 balls a round
+    /// const withRounds = config.with_rounds([4, 10, 16], [50, 30, 20], 140);
+    /// ```
+    #[wasm_bindgen]
+    pub fn with_rounds(
+        &self,
+        rounds: Vec<usize>,
+        weights: Vec<f64>,
+        balls_per_round: usize,
+    ) -> Result<Config, PachisloError> {
+        if rounds.is_empty() {
+            return Err(PachisloError::InvalidConfig {
+                message: "rounds need at least 1 round count".to_string(),
+            });
+        }
+        if rounds.len() != weights.len() {
+            return Err(PachisloError::InvalidConfig {
+                message: format!(
+                    "{} round count(s) but {} weight(s); give one weight per round count",
+                    rounds.len(),
+                    weights.len()
+                ),
+            });
+        }
+        if rounds.contains(&0) {
+            return Err(PachisloError::InvalidConfig {
+                message: "a jackpot needs at least 1 round".to_string(),
+            });
+        }
+        if let Some(weight) = weights
+            .iter()
+            .find(|weight| !(weight.is_finite() && **weight > 0.0))
+        {
+            return Err(PachisloError::InvalidConfig {
+                message: format!(
+                    "round weight {weight} is invalid; weights must be positive numbers"
+                ),
+            });
+        }
+
+        let mut config = self.clone();
+        config.mechanics.rounds = Some(Rounds {
+            choices: rounds.into_iter().zip(weights).collect(),
+            balls_per_round,
+        });
+
+        Ok(config)
+    }
+
+    /// Round counts a jackpot can have, empty without rounds.
+    #[wasm_bindgen(getter)]
+    pub fn round_counts(&self) -> Vec<usize> {
+        self.mechanics.rounds.as_ref().map_or(Vec::new(), |rounds| {
+            rounds.choices.iter().map(|&(count, _)| count).collect()
+        })
+    }
+
+    /// Balls paid out per round, or `undefined` without rounds.
+    #[wasm_bindgen(getter)]
+    pub fn balls_per_round(&self) -> Option<usize> {
+        self.mechanics
+            .rounds
+            .as_ref()
+            .map(|rounds| rounds.balls_per_round)
+    }
+}
+
+impl Session {
+    /// Emits `"round_started"` and `"round_finished"` for every round of a
+    /// jackpot paid out since the last call.
+    pub(crate) fn emit_rounds(&mut self) {
+        let Some((rounds, balls)) = self.engine.take_jackpot_rounds() else {
+            return;
+        };
+
+        let output = self.engine.output_mut();
+        if !output.is_event_output() {
+            return;
+        }
+        for round in 1..=rounds {
+            let payload = serde_wasm_bindgen::to_value(&RoundEvent {
+                round,
+                rounds,
+                balls,
+            })
+            .unwrap();
+            output.emit(event::ROUND_STARTED, payload.clone());
+            output.emit(event::ROUND_FINISHED, payload);
+        }
+    }
+}
@@ -0,0 +1,208 @@
+//! # JSON-RPC
+//!
+//! A JSON-RPC 2.0 surface over a [`GameManager`], so the engine can be
+//! embedded behind any transport that carries strings, like `postMessage`
+//! between iframes, browser extension messaging or Electron IPC. The host
+//! passes every request to `handle_rpc` and sends back what it returns.
+//!
+//! | Method     | Params             | Result         |
+//! |------------|--------------------|----------------|
+//! | `newGame`  | `MachinePreset`    | id of the game |
+//! | `step`     | `{ id, command }`  | `GameState`    |
+//! | `getState` | `{ id }`           | `GameState`    |
+//! | `snapshot` | `{ id }`           | `GameSnapshot` |
+//! | `restore`  | `{ id, snapshot }` | `null`         |
+//!
+//! `newGame` creates a game from a machine spec in the format of a hall
+//! catalog, using the spec's name as the id.
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::{JsValue, prelude::wasm_bindgen};
+
+use crate::{
+    PachisloError, alias::Config, hall::MachinePreset, manager::GameManager, snapshot::GameSnapshot,
+};
+
+/// The request is not JSON.
+const PARSE_ERROR: i32 = -32700;
+/// The request is not a JSON-RPC 2.0 request object.
+const INVALID_REQUEST: i32 = -32600;
+const METHOD_NOT_FOUND: i32 = -32601;
+const INVALID_PARAMS: i32 = -32602;
+/// The game rejected the call; the `PachisloError` is the error data.
+const GAME_ERROR: i32 = -32000;
+
+/// The `error` member of a failed JSON-RPC response.
+///
+/// # Fields
+///
+/// * `code` - -32700 if the request is not JSON, -32600 if it is not a
+///   request object, -32601 for unknown methods, -32602 for invalid params
+///   and -32000 if the game threw
+/// * `message` - Description of the problem
+/// * `data` - The error the game threw, for code -32000
+#[derive(Clone, Debug, PartialEq, Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct RpcError {
+    pub code: i32,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<PachisloError>,
+}
+
+impl RpcError {
+    fn new(code: i32, message: impl Into<String>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+}
+
+impl From<PachisloError> for RpcError {
+    fn from(error: PachisloError) -> Self {
+        RpcError {
+            code: GAME_ERROR,
+            message: error.to_string(),
+            data: Some(error),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Request {
+    jsonrpc: String,
+    /// Missing for notifications
+    #[serde(default, with = "serde_wasm_bindgen::preserve")]
+    id: JsValue,
+    method: String,
+    #[serde(default, with = "serde_wasm_bindgen::preserve")]
+    params: JsValue,
+}
+
+#[derive(Deserialize)]
+struct GameParams {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct StepParams {
+    id: String,
+    command: String,
+}
+
+#[derive(Deserialize)]
+struct RestoreParams {
+    id: String,
+    snapshot: GameSnapshot,
+}
+
+#[wasm_bindgen]
+impl GameManager {
+    /// Handles a JSON-RPC 2.0 request and returns the JSON response.
+    ///
+    /// Every error is reported in the response, with an [`RpcError`];
+    /// nothing is thrown. Notifications, i.e. requests without an `id`, are
+    /// executed without a response. Batch requests are not supported.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// window.addEventListener("message", ({ data, source }) => {
+    ///     const response = manager.handle_rpc(data);
+    ///     if (response !== undefined) source.postMessage(response, "*");
+    /// });
+    /// // in the embedding page
+    /// frame.postMessage(JSON.stringify({
+    ///     jsonrpc: "2.0", id: 1, method: "step", params: { id: "left", command: "LaunchBall" },
+    /// }), "*");
+    /// ```
+    #[wasm_bindgen]
+    pub fn handle_rpc(&mut self, request_json: &str) -> Option<String> {
+        let request = match parse(request_json) {
+            Ok(request) => request,
+            Err(error) => return Some(respond(&JsValue::NULL, Err(error))),
+        };
+
+        let result = self.call(&request.method, request.params);
+
+        (!request.id.is_undefined()).then(|| respond(&request.id, result))
+    }
+}
+
+impl GameManager {
+    fn call(&mut self, method: &str, params: JsValue) -> Result<JsValue, RpcError> {
+        match method {
+            "newGame" => {
+                let preset: MachinePreset = params_of(params)?;
+                self.create(preset.name.clone(), Config::from(&preset))?;
+                Ok(preset.name.into())
+            }
+            "step" => {
+                let StepParams { id, command } = params_of(params)?;
+                self.step(&id, command)?;
+                Ok(to_value(&self.get_state(&id)?))
+            }
+            "getState" => {
+                let GameParams { id } = params_of(params)?;
+                Ok(to_value(&self.get_state(&id)?))
+            }
+            "snapshot" => {
+                let GameParams { id } = params_of(params)?;
+                Ok(to_value(&self.snapshot(&id)?))
+            }
+            "restore" => {
+                let RestoreParams { id, snapshot } = params_of(params)?;
+                self.restore(&id, snapshot)?;
+                Ok(JsValue::NULL)
+            }
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("unknown method {method:?}"),
+            )),
+        }
+    }
+}
+
+fn parse(request_json: &str) -> Result<Request, RpcError> {
+    let value = js_sys::JSON::parse(request_json)
+        .map_err(|_| RpcError::new(PARSE_ERROR, "the request is not JSON"))?;
+    let request: Request = serde_wasm_bindgen::from_value(value)
+        .map_err(|error| RpcError::new(INVALID_REQUEST, error.to_string()))?;
+
+    if request.jsonrpc != "2.0" {
+        return Err(RpcError::new(
+            INVALID_REQUEST,
+            "only JSON-RPC 2.0 is supported",
+        ));
+    }
+
+    Ok(request)
+}
+
+fn params_of<T: for<'de> Deserialize<'de>>(params: JsValue) -> Result<T, RpcError> {
+    serde_wasm_bindgen::from_value(params)
+        .map_err(|error| RpcError::new(INVALID_PARAMS, error.to_string()))
+}
+
+fn to_value(value: &impl Serialize) -> JsValue {
+    serde_wasm_bindgen::to_value(value).unwrap()
+}
+
+fn respond(id: &JsValue, result: Result<JsValue, RpcError>) -> String {
+    let response = js_sys::Object::new();
+    let set = |key: &str, value: &JsValue| {
+        js_sys::Reflect::set(&response, &key.into(), value).unwrap();
+    };
+
+    set("jsonrpc", &"2.0".into());
+    set("id", id);
+    match result {
+        Ok(result) => set("result", &result),
+        Err(error) => set("error", &to_value(&error)),
+    }
+
+    js_sys::JSON::stringify(&response).unwrap().into()
+}
@@ -0,0 +1,229 @@
+//! # Command Rules
+//!
+//! Usage limits, cooldowns and ordering requirements for commands, enforced
+//! by the game itself so that business rules live in one place instead of
+//! being repeated in every UI that sends commands.
+
+use std::collections::HashMap;
+
+use pachislo::game::GameState;
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use crate::{PachisloError, check_command, engine::Command};
+
+/// Whether a command can currently be executed.
+///
+/// # Fields
+///
+/// * `command` - The command string
+/// * `available` - Whether sending the command now would succeed
+/// * `reason` - Why the command is unavailable, `None` if it is available
+/// * `remaining_uses` - Uses left in this session, `None` if unlimited
+/// * `ready_in` - Milliseconds until the cooldown ends, 0 if not cooling down
+#[derive(Clone, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct CommandAvailability {
+    #[tsify(type = "Command")]
+    pub command: String,
+    pub available: bool,
+    pub reason: Option<String>,
+    pub remaining_uses: Option<usize>,
+    pub ready_in: f64,
+}
+
+/// Configured rules and the usage they are checked against.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct CommandRules {
+    limits: HashMap<Command, usize>,
+    cooldowns: HashMap<Command, f64>,
+    launch_required: bool,
+    uses: HashMap<Command, usize>,
+    last_used: HashMap<Command, f64>,
+    launched_since_lottery: bool,
+}
+
+impl CommandRules {
+    /// Limits how often a command may be executed per session.
+    pub(crate) fn set_limit(&mut self, command: Command, max_uses: usize) {
+        self.limits.insert(command, max_uses);
+    }
+
+    /// Sets the minimum time between two executions of a command.
+    pub(crate) fn set_cooldown(&mut self, command: Command, milliseconds: f64) {
+        if milliseconds > 0.0 {
+            self.cooldowns.insert(command, milliseconds);
+        } else {
+            self.cooldowns.remove(&command);
+        }
+    }
+
+    /// Requires a `LaunchBall` between two `CauseLottery` commands.
+    pub(crate) fn set_launch_required(&mut self, required: bool) {
+        self.launch_required = required;
+    }
+
+    /// Checks the configured rules for a command at time `now`.
+    pub(crate) fn check(&self, command: Command, now: f64) -> Result<(), PachisloError> {
+        let name = command.name().to_string();
+
+        if let Some(&limit) = self.limits.get(&command)
+            && self.uses(command) >= limit
+        {
+            return Err(PachisloError::UsageLimitReached {
+                command: name,
+                limit,
+            });
+        }
+
+        let remaining = self.ready_in(command, now);
+        if remaining > 0.0 {
+            return Err(PachisloError::CoolingDown {
+                command: name,
+                remaining,
+            });
+        }
+
+        if command == Command::CauseLottery && self.launch_required && !self.launched_since_lottery
+        {
+            return Err(PachisloError::LaunchRequired);
+        }
+
+        Ok(())
+    }
+
+    /// Records that a command was executed at time `now`.
+    pub(crate) fn record(&mut self, command: Command, now: f64) {
+        *self.uses.entry(command).or_default() += 1;
+        self.last_used.insert(command, now);
+
+        match command {
+            Command::LaunchBall => self.launched_since_lottery = true,
+            Command::CauseLottery => self.launched_since_lottery = false,
+            _ => {}
+        }
+    }
+
+    /// Reports the availability of every command in the given state.
+    pub(crate) fn availability(&self, state: &GameState, now: f64) -> Vec<CommandAvailability> {
+        Command::ALL
+            .into_iter()
+            .map(|command| {
+                let reason = check_command(command.name(), state)
+                    .and_then(|()| self.check(command, now))
+                    .err()
+                    .map(|error| error.to_string());
+
+                CommandAvailability {
+                    command: command.name().to_string(),
+                    available: reason.is_none(),
+                    reason,
+                    remaining_uses: self
+                        .limits
+                        .get(&command)
+                        .map(|limit| limit.saturating_sub(self.uses(command))),
+                    ready_in: self.ready_in(command, now),
+                }
+            })
+            .collect()
+    }
+
+    fn uses(&self, command: Command) -> usize {
+        self.uses.get(&command).copied().unwrap_or(0)
+    }
+
+    fn ready_in(&self, command: Command, now: f64) -> f64 {
+        match (self.cooldowns.get(&command), self.last_used.get(&command)) {
+            (Some(cooldown), Some(last_used)) => (last_used + cooldown - now).max(0.0),
+            _ => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_limit() {
+        let mut rules = CommandRules::default();
+        rules.set_limit(Command::StartGame, 1);
+
+        assert_eq!(rules.check(Command::StartGame, 0.0), Ok(()));
+        rules.record(Command::StartGame, 0.0);
+
+        assert!(matches!(
+            rules.check(Command::StartGame, 0.0),
+            Err(PachisloError::UsageLimitReached { limit: 1, .. })
+        ));
+        assert_eq!(rules.check(Command::LaunchBall, 0.0), Ok(()));
+    }
+
+    #[test]
+    fn test_cooldown() {
+        let mut rules = CommandRules::default();
+        rules.set_cooldown(Command::LaunchBall, 100.0);
+
+        assert_eq!(rules.check(Command::LaunchBall, 0.0), Ok(()));
+        rules.record(Command::LaunchBall, 0.0);
+
+        assert!(matches!(
+            rules.check(Command::LaunchBall, 40.0),
+            Err(PachisloError::CoolingDown { remaining, .. }) if remaining == 60.0
+        ));
+        assert_eq!(rules.check(Command::LaunchBall, 100.0), Ok(()));
+
+        rules.set_cooldown(Command::LaunchBall, 0.0);
+        assert_eq!(rules.check(Command::LaunchBall, 40.0), Ok(()));
+    }
+
+    #[test]
+    fn test_launch_required_for_lottery() {
+        let mut rules = CommandRules::default();
+        rules.set_launch_required(true);
+
+        assert_eq!(
+            rules.check(Command::CauseLottery, 0.0),
+            Err(PachisloError::LaunchRequired)
+        );
+
+        rules.record(Command::LaunchBall, 0.0);
+        assert_eq!(rules.check(Command::CauseLottery, 0.0), Ok(()));
+
+        rules.record(Command::CauseLottery, 0.0);
+        assert_eq!(
+            rules.check(Command::CauseLottery, 0.0),
+            Err(PachisloError::LaunchRequired)
+        );
+    }
+
+    #[test]
+    fn test_availability() {
+        let mut rules = CommandRules::default();
+        rules.set_limit(Command::StartGame, 1);
+        rules.set_cooldown(Command::LaunchBall, 50.0);
+        rules.record(Command::LaunchBall, 0.0);
+
+        let availability = rules.availability(&GameState::Uninitialized, 10.0);
+        let get = |name: &str| {
+            availability
+                .iter()
+                .find(|entry| entry.command == name)
+                .unwrap()
+        };
+
+        assert_eq!(availability.len(), Command::ALL.len());
+
+        let start = get("StartGame");
+        assert!(start.available);
+        assert_eq!(start.remaining_uses, Some(1));
+
+        // Not started yet, and cooling down
+        let launch = get("LaunchBall");
+        assert!(!launch.available);
+        assert!(launch.reason.as_ref().unwrap().contains("StartGame"));
+        assert_eq!(launch.ready_in, 40.0);
+
+        assert!(get("Finish").available);
+    }
+}
@@ -0,0 +1,305 @@
+//! # Payload Schema
+//!
+//! A runtime-queryable description of every value the crate serializes
+//! across the JavaScript boundary. The declarations are the ones Tsify
+//! generates from the Rust types, so the description cannot drift from what
+//! is actually serialized, and tooling in other languages can be checked
+//! against it.
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    CommandArgs, PachisloError,
+    alias::{GameState, Lose, LotteryResult, Transition, Win},
+    analysis::{BorderLine, MarkovReport, SpecReport},
+    autoplay::{ArbitrationEvent, Resolution},
+    battle::{BattleEvent, BattleOutcome},
+    campaign::StageTransition,
+    comparison::{ComparisonReport, MachineComparison, ReplaySession},
+    curve::ContinuationCurve,
+    deprecation::DeprecationWarning,
+    diff::{StateDiff, StateMode},
+    engine::Command,
+    environment::RuntimeEnvironment,
+    event::{self, DropPolicy, EventSummary, LotteryEvent, ReachEvent},
+    explain::{ContinuationExplanation, LotteryExplanation, Roll, SpinExplanation, SpinMode},
+    ghost::{Ghost, GhostInput, GhostReplay},
+    guard::InvalidReturn,
+    hall::{MachinePreset, PresetBalls, PresetCatalog, PresetSlot},
+    history::{ExportFormat, HistoryEntry, SpinRecord},
+    hold::HoldEvent,
+    latency::CommandLatency,
+    lint::{ConfigWarning, LintCode},
+    migration::{MigrationPolicy, MigrationReport},
+    rare::RareEventEstimate,
+    regulation::{RegulationRule, RegulationViolation},
+    remote::{ClientMessage, ServerMessage},
+    reveal::RevealEvent,
+    rounds::RoundEvent,
+    rpc::RpcError,
+    rules::CommandAvailability,
+    simulation::{
+        ConfigComparison, ConfigMetrics, PayoutEstimate, SamplingStrategy, SimulationOptions,
+        SimulationProgress, SimulationReport, VolatilityEstimate,
+    },
+    slot::{Reels, WeightedSymbol},
+    snapshot::{self, GameSnapshot, RngSnapshot},
+    stats::{GameStats, HitCounter, ModeCounters, ReturnTicker, WinCounts},
+    stock::StockEvent,
+    stop::{StopConditions, StopReason},
+    streak::{StreakEvent, StreakKind, Streaks},
+    summary::SessionSummary,
+    tier::RushTierEvent,
+    tournament::{Standing, Standings, TournamentOptions},
+};
+
+#[cfg(feature = "chaos")]
+use crate::chaos::ChaosOptions;
+
+/// Version of the schema description format itself.
+const SCHEMA_VERSION: u32 = 1;
+
+/// Description of every serialized payload.
+///
+/// # Fields
+///
+/// * `schema_version` - Version of this description format
+/// * `crate_version` - Version of the crate that produced the description
+/// * `snapshot_version` - `GameSnapshot` format version written by `snapshot()`
+/// * `types` - Every serialized type
+/// * `events` - Every event type of an event output and its payload type
+#[derive(Clone, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct SchemaDescription {
+    pub schema_version: u32,
+    pub crate_version: String,
+    pub snapshot_version: u32,
+    pub types: Vec<TypeSchema>,
+    pub events: Vec<EventSchema>,
+}
+
+/// A serialized type.
+///
+/// # Fields
+///
+/// * `name` - Name of the type
+/// * `typescript` - TypeScript declaration with every field, its type and
+///   every enum variant
+#[derive(Clone, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct TypeSchema {
+    pub name: String,
+    pub typescript: String,
+}
+
+/// An event type and the name of its payload type.
+#[derive(Clone, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct EventSchema {
+    pub event_type: String,
+    pub payload: String,
+}
+
+/// Describes every serialized payload the crate produces or accepts.
+///
+/// # Example
+///
+/// ```javascript
+/// fs.writeFileSync("schema.json", JSON.stringify(describe(), null, 2));
+/// ```
+#[wasm_bindgen]
+pub fn describe() -> SchemaDescription {
+    SchemaDescription {
+        schema_version: SCHEMA_VERSION,
+        crate_version: env!("CARGO_PKG_VERSION").to_string(),
+        snapshot_version: snapshot::SNAPSHOT_VERSION,
+        types: types(),
+        events: [
+            (event::TRANSITION, "Transition"),
+            (event::STATE_DIFF, "StateDiff"),
+            (event::FINISH_GAME, "GameState"),
+            (event::SESSION_SUMMARY, "SessionSummary"),
+            (event::STREAK, "StreakEvent"),
+            (event::REACH, "ReachEvent"),
+            (event::LOTTERY_NORMAL, "LotteryEvent"),
+            (event::LOTTERY_RUSH, "LotteryEvent"),
+            (event::LOTTERY_RUSH_CONTINUE, "LotteryEvent"),
+            (event::BACKGROUND_TICK, "EventSummary"),
+            (event::FOREGROUND_SUMMARY, "EventSummary"),
+            (event::COMMAND_LATENCY, "CommandLatency"),
+            (event::HOLD_ADDED, "HoldEvent"),
+            (event::HOLD_CONSUMED, "HoldEvent"),
+            (event::STOCK_GAINED, "StockEvent"),
+            (event::STOCK_RELEASED, "StockEvent"),
+            (event::RUSH_TIER_ENTERED, "RushTierEvent"),
+            (event::BATTLE, "BattleEvent"),
+            (event::ROUND_STARTED, "RoundEvent"),
+            (event::ROUND_FINISHED, "RoundEvent"),
+            (event::APPARENT_RESULT, "RevealEvent"),
+            (event::TRUE_RESULT, "RevealEvent"),
+            (event::STATE, "GameState"),
+        ]
+        .into_iter()
+        .map(|(event_type, payload)| EventSchema {
+            event_type: event_type.to_string(),
+            payload: payload.to_string(),
+        })
+        .collect(),
+    }
+}
+
+/// Lists types by name with their Tsify declarations.
+macro_rules! type_schemas {
+    ($($ty:ident),* $(,)?) => {
+        vec![$(TypeSchema {
+            name: stringify!($ty).to_string(),
+            typescript: <$ty as Tsify>::DECL.to_string(),
+        }),*]
+    };
+}
+
+fn types() -> Vec<TypeSchema> {
+    #[allow(unused_mut)]
+    let mut types = type_schemas![
+        ArbitrationEvent,
+        BattleEvent,
+        BattleOutcome,
+        BorderLine,
+        ClientMessage,
+        Command,
+        CommandArgs,
+        CommandAvailability,
+        CommandLatency,
+        ComparisonReport,
+        ConfigComparison,
+        ConfigMetrics,
+        ConfigWarning,
+        ContinuationCurve,
+        ContinuationExplanation,
+        DeprecationWarning,
+        DropPolicy,
+        ExportFormat,
+        EventSchema,
+        EventSummary,
+        GameSnapshot,
+        GameState,
+        GameStats,
+        Ghost,
+        GhostInput,
+        GhostReplay,
+        HistoryEntry,
+        HitCounter,
+        HoldEvent,
+        InvalidReturn,
+        LintCode,
+        Lose,
+        LotteryEvent,
+        LotteryExplanation,
+        LotteryResult,
+        MachineComparison,
+        MachinePreset,
+        MarkovReport,
+        MigrationPolicy,
+        MigrationReport,
+        ModeCounters,
+        PachisloError,
+        PayoutEstimate,
+        PresetBalls,
+        PresetCatalog,
+        PresetSlot,
+        RareEventEstimate,
+        ReachEvent,
+        Reels,
+        RegulationRule,
+        RegulationViolation,
+        ReplaySession,
+        RevealEvent,
+        Resolution,
+        ReturnTicker,
+        RngSnapshot,
+        Roll,
+        RoundEvent,
+        RpcError,
+        RuntimeEnvironment,
+        RushTierEvent,
+        SamplingStrategy,
+        SchemaDescription,
+        ServerMessage,
+        SessionSummary,
+        SimulationOptions,
+        SimulationProgress,
+        SimulationReport,
+        SpecReport,
+        SpinExplanation,
+        SpinMode,
+        SpinRecord,
+        StageTransition,
+        Standing,
+        Standings,
+        StateDiff,
+        StateMode,
+        StockEvent,
+        StopConditions,
+        StopReason,
+        StreakEvent,
+        StreakKind,
+        Streaks,
+        TournamentOptions,
+        Transition,
+        TypeSchema,
+        VolatilityEstimate,
+        WeightedSymbol,
+        Win,
+        WinCounts,
+    ];
+
+    #[cfg(feature = "chaos")]
+    types.extend(type_schemas![ChaosOptions]);
+
+    types
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_types_are_unique_and_declared() {
+        let types = types();
+        let mut names: Vec<_> = types.iter().map(|schema| schema.name.as_str()).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), types.len());
+
+        let game_state = types
+            .iter()
+            .find(|schema| schema.name == "GameState")
+            .unwrap();
+        assert!(game_state.typescript.contains("Rush"));
+        let rush_balls = if cfg!(feature = "camel-case") {
+            "rushBalls"
+        } else {
+            "rush_balls"
+        };
+        assert!(game_state.typescript.contains(rush_balls));
+    }
+
+    #[test]
+    fn test_event_payloads_are_described() {
+        let description = describe();
+
+        for event in &description.events {
+            assert!(
+                description
+                    .types
+                    .iter()
+                    .any(|schema| schema.name == event.payload),
+                "{} has no schema",
+                event.payload
+            );
+        }
+        assert_eq!(description.snapshot_version, snapshot::SNAPSHOT_VERSION);
+    }
+}
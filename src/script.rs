@@ -0,0 +1,60 @@
+//! # Outcome Scripts
+//!
+//! For QA, a game can be given a script of lottery results (see
+//! `WasmGame::set_outcome_script`). Every lottery the engine draws, spins and
+//! rush continuations alike, takes the next scripted result instead of
+//! rolling, until the script runs out and random draws resume. Everything
+//! else, e.g. slot symbols, rush tiers and round counts, is still drawn at
+//! random.
+//!
+//! A scripted `Premium` win makes a spin a premium win even without
+//! `Config::with_premium`; for a continuation lottery it is a plain win.
+
+use std::collections::VecDeque;
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{PachisloError, WasmGame, alias::LotteryResult};
+
+#[wasm_bindgen]
+impl WasmGame {
+    /// Replaces the outcome script with `results`, consumed one per lottery
+    /// before falling back to random draws; an empty list removes it.
+    ///
+    /// Scripted lotteries roll nothing, so a seeded game draws different
+    /// random outcomes after the script than without it.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// // Lose, lose, fake win into rush, a rush win and five continuations
+    /// game.set_outcome_script([
+    ///     { Lose: "Default" }, { Lose: "Default" }, { Win: "FakeWin" }, { Win: "Default" },
+    ///     ...Array(5).fill({ Win: "Default" }),
+    /// ]);
+    /// ```
+    #[wasm_bindgen]
+    pub fn set_outcome_script(&self, results: Vec<LotteryResult>) -> Result<(), PachisloError> {
+        let mut session = self.session()?;
+
+        session.engine.set_outcome_script(VecDeque::from(results));
+
+        Ok(())
+    }
+
+    /// Returns how many scripted results are left.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    #[wasm_bindgen]
+    pub fn remaining_outcome_script(&self) -> Result<usize, PachisloError> {
+        let session = self.session()?;
+
+        Ok(session.engine.outcome_script().len())
+    }
+}
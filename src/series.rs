@@ -0,0 +1,146 @@
+//! # Balls Series
+//!
+//! An opt-in record of the ball count after every transition, for slump
+//! graphs (スランプグラフ). The series lives in a bounded buffer: once it is
+//! full, the oldest values are dropped, so a long session never grows it
+//! beyond the chosen capacity.
+
+use std::collections::VecDeque;
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{PachisloError, WasmGame};
+
+/// Ball counts recorded while enabled, at most `capacity` of them.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct BallsSeries {
+    /// Most values kept, `None` while not recording
+    capacity: Option<usize>,
+    values: VecDeque<u32>,
+}
+
+impl BallsSeries {
+    /// Starts recording with the given capacity, or stops with `None`.
+    /// Recorded values are kept, dropping the oldest beyond the capacity.
+    pub(crate) fn set_capacity(&mut self, capacity: Option<usize>) {
+        self.capacity = capacity;
+        if let Some(capacity) = capacity {
+            let excess = self.values.len().saturating_sub(capacity);
+            self.values.drain(..excess);
+        }
+    }
+
+    /// Records a ball count if recording is enabled.
+    pub(crate) fn record(&mut self, balls: usize) {
+        let Some(capacity) = self.capacity else {
+            return;
+        };
+        if capacity == 0 {
+            return;
+        }
+
+        if self.values.len() == capacity {
+            self.values.pop_front();
+        }
+        self.values
+            .push_back(u32::try_from(balls).unwrap_or(u32::MAX));
+    }
+
+    pub(crate) fn values(&self) -> Vec<u32> {
+        self.values.iter().copied().collect()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.values.clear();
+    }
+}
+
+#[wasm_bindgen]
+impl WasmGame {
+    /// Starts recording the ball count after every transition, keeping the
+    /// latest `capacity` values; `undefined` stops recording.
+    ///
+    /// Recorded values are kept when recording stops; a smaller capacity
+    /// drops the oldest ones. Games that have not started count 0 balls.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// game.set_balls_series(10000);
+    /// ```
+    #[wasm_bindgen]
+    pub fn set_balls_series(&self, capacity: Option<usize>) -> Result<(), PachisloError> {
+        let mut session = self.session()?;
+
+        session.balls_series.set_capacity(capacity);
+
+        Ok(())
+    }
+
+    /// Returns the recorded ball counts, oldest first, as a `Uint32Array`.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const series = game.get_balls_series();
+    /// chart.data.datasets[0].data = Array.from(series);
+    /// ```
+    #[wasm_bindgen]
+    pub fn get_balls_series(&self) -> Result<Vec<u32>, PachisloError> {
+        let session = self.session()?;
+
+        Ok(session.balls_series.values())
+    }
+
+    /// Discards the recorded ball counts without changing whether they are
+    /// recorded.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    #[wasm_bindgen]
+    pub fn clear_balls_series(&self) -> Result<(), PachisloError> {
+        let mut session = self.session()?;
+
+        session.balls_series.clear();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bounded_series() {
+        let mut series = BallsSeries::default();
+
+        series.record(100);
+        assert!(series.values().is_empty());
+
+        series.set_capacity(Some(3));
+        for balls in [99, 98, 113, 112] {
+            series.record(balls);
+        }
+        assert_eq!(series.values(), [98, 113, 112]);
+
+        series.set_capacity(Some(2));
+        assert_eq!(series.values(), [113, 112]);
+
+        series.set_capacity(None);
+        series.record(111);
+        assert_eq!(series.values(), [113, 112]);
+
+        series.clear();
+        assert!(series.values().is_empty());
+    }
+}
@@ -0,0 +1,1339 @@
+//! # Batch Simulation
+//!
+//! Runs many complete games entirely inside WebAssembly without calling any
+//! JavaScript output callbacks, and aggregates the results. Crossing the
+//! JavaScript boundary for every event makes large simulations far too slow.
+
+use pachislo::game::GameState;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    PachisloError,
+    alias::Config,
+    check_probability,
+    engine::{Checkpoint, Command, Engine, Mechanics, NullOutput, is_jitan},
+    explain::SpinMode,
+    rng::GameRng,
+};
+
+/// Options for [`simulate`]. Every field is optional in JavaScript.
+///
+/// # Fields
+///
+/// * `start_hole_probability` - Probability that a launched ball triggers a lottery
+/// * `max_launches` - Launches after which a game is stopped, so that games with a
+///   payout rate above 1 terminate
+/// * `seed` - Seed for reproducible results; random when omitted
+/// * `sampling` - How the games draw their random numbers; `Independent`
+///   when omitted
+/// * `outcomes_only` - Skips the bookkeeping that only describes spins, like
+///   their explanations and streaks, to simulate faster; the report is the
+///   same either way
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(default)]
+pub struct SimulationOptions {
+    pub start_hole_probability: f64,
+    pub max_launches: usize,
+    pub seed: Option<u64>,
+    pub sampling: SamplingStrategy,
+    pub outcomes_only: bool,
+}
+
+impl SimulationOptions {
+    fn check(&self) -> Result<(), PachisloError> {
+        check_probability("start_hole_probability", self.start_hole_probability)
+    }
+}
+
+impl Default for SimulationOptions {
+    fn default() -> Self {
+        SimulationOptions {
+            start_hole_probability: pachislo::START_HOLE_PROBABILITY_EXAMPLE,
+            max_launches: 100_000,
+            seed: None,
+            sampling: SamplingStrategy::Independent,
+            outcomes_only: false,
+        }
+    }
+}
+
+/// How the games of a simulation draw their random numbers.
+///
+/// Both variance-reduction strategies give every game fresh random streams
+/// derived from the seed, and still estimate the same figures without bias;
+/// their estimates only scatter less around the true value, so fewer games
+/// reach the same precision. They help most with probabilities far from 0
+/// and 1.
+///
+/// # Variants
+///
+/// * `Independent` - Every game continues the random streams of the one
+///   before
+/// * `Antithetic` - Games come in pairs, the second drawing `1 - u` for every
+///   number `u` the first drew, so a lucky game is offset by an unlucky one
+/// * `Stratified` - The first lottery number of game `i` of `n` is drawn from
+///   the `i`-th of `n` equal parts of the range, so the first lotteries cover
+///   the whole range evenly; partial results of a [`SimulationHandle`] cover
+///   only the first parts
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum SamplingStrategy {
+    #[default]
+    Independent,
+    Antithetic,
+    Stratified,
+}
+
+/// Aggregate results of a batch simulation.
+///
+/// # Fields
+///
+/// * `games` - Number of games simulated
+/// * `average_final_balls` - Mean ball count when a game ended; 0 for games that ran out
+/// * `average_launches` - Mean number of launches per game, including rush launches
+/// * `truncated_games` - Games stopped by `max_launches` instead of running out of balls
+/// * `stopped_games` - Games ended by a stop condition of the configuration
+/// * `rush_hit_rate` - Share of normal-mode lotteries that entered rush mode; jitan
+///   mode counts as normal mode
+/// * `mean_continuation_length` - Mean number of rush rounds per rush, including the first
+/// * `payout_rate` - Balls paid out per ball launched in normal mode
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct SimulationReport {
+    pub games: usize,
+    pub average_final_balls: f64,
+    pub average_launches: f64,
+    pub truncated_games: usize,
+    pub stopped_games: usize,
+    pub rush_hit_rate: f64,
+    pub mean_continuation_length: f64,
+    pub payout_rate: f64,
+}
+
+/// Simulates complete games headlessly and aggregates the results.
+///
+/// Every game starts with the configured initial balls and launches balls
+/// until they run out, `max_launches` is reached or a stop condition of the
+/// configuration holds. Each launch triggers a lottery with probability
+/// `start_hole_probability`.
+///
+/// # Arguments
+///
+/// * `config` - Configuration to simulate
+/// * `n_games` - Number of games to play
+/// * `options` - Optional [`SimulationOptions`]
+///
+/// # Errors
+///
+/// Throws an `Error` describing every problem if the configuration is
+/// invalid, or an `InvalidArguments` error if
+/// `start_hole_probability` is not from 0 to 1.
+///
+/// # Example
+///
+/// ```javascript
+/// const report = simulate(config, 10000, { seed: 1n });
+/// console.log(report.average_final_balls, report.payout_rate);
+/// ```
+#[wasm_bindgen]
+pub fn simulate(
+    config: &Config,
+    n_games: usize,
+    options: Option<SimulationOptions>,
+) -> Result<SimulationReport, PachisloError> {
+    config.validate()?;
+    let options = options.unwrap_or_default();
+    options.check()?;
+
+    Ok(run(
+        config.clone().into(),
+        config.mechanics(),
+        n_games,
+        options,
+    ))
+}
+
+/// Core of [`simulate`], independent of the JavaScript callback.
+pub(crate) fn run<F>(
+    config: pachislo::config::Config<F>,
+    mechanics: Mechanics,
+    n_games: usize,
+    options: SimulationOptions,
+) -> SimulationReport
+where
+    F: FnMut(usize) -> f64,
+{
+    let mut simulation = Simulation::new(config, mechanics, n_games, options);
+    while !simulation.is_done() {
+        simulation.advance();
+    }
+
+    simulation.report()
+}
+
+/// Progress of a [`SimulationHandle`] after a chunk.
+///
+/// # Fields
+///
+/// * `done` - Whether every game has been played
+/// * `iterations` - Iterations run by the chunk, at most `max_iterations`
+/// * `report` - Results so far; `games` counts the games completed
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct SimulationProgress {
+    pub done: bool,
+    pub iterations: usize,
+    pub report: SimulationReport,
+}
+
+/// A simulation of a JavaScript [`Config`].
+type JsSimulation = Simulation<Box<dyn FnMut(usize) -> f64>>;
+
+/// A [`simulate`] run that can be resumed, so large simulations can be
+/// spread over animation frames instead of blocking the main thread.
+///
+/// Every iteration launches one ball, or ends a game that has reached
+/// `max_launches` or a stop condition. A handle run to the end reports
+/// exactly what [`simulate`] reports with the same arguments.
+#[wasm_bindgen]
+pub struct SimulationHandle {
+    simulation: JsSimulation,
+}
+
+#[wasm_bindgen]
+impl SimulationHandle {
+    /// Prepares a simulation of `n_games` games without running any.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Configuration to simulate
+    /// * `n_games` - Number of games to play
+    /// * `options` - Optional [`SimulationOptions`]
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` describing every problem if the configuration is
+    /// invalid, or an `InvalidArguments` error if `start_hole_probability`
+    /// is not from 0 to 1.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const handle = new SimulationHandle(config, 100000, { seed: 1n });
+    /// function frame() {
+    ///     const { done, report } = handle.run_chunk(50000);
+    ///     progressBar.value = report.games;
+    ///     if (!done) requestAnimationFrame(frame);
+    /// }
+    /// requestAnimationFrame(frame);
+    /// ```
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        config: &Config,
+        n_games: usize,
+        options: Option<SimulationOptions>,
+    ) -> Result<SimulationHandle, PachisloError> {
+        config.validate()?;
+        let options = options.unwrap_or_default();
+        options.check()?;
+
+        Ok(SimulationHandle {
+            simulation: Simulation::new(
+                config.clone().into(),
+                config.mechanics(),
+                n_games,
+                options,
+            ),
+        })
+    }
+
+    /// Runs up to `max_iterations` iterations and returns the progress.
+    ///
+    /// Returns at once with `done` set once every game has been played.
+    #[wasm_bindgen]
+    pub fn run_chunk(&mut self, max_iterations: usize) -> SimulationProgress {
+        let mut iterations = 0;
+        while iterations < max_iterations && !self.simulation.is_done() {
+            self.simulation.advance();
+            iterations += 1;
+        }
+
+        SimulationProgress {
+            done: self.simulation.is_done(),
+            iterations,
+            report: self.simulation.report(),
+        }
+    }
+
+    /// Returns whether every game has been played.
+    #[wasm_bindgen]
+    pub fn is_done(&self) -> bool {
+        self.simulation.is_done()
+    }
+
+    /// Returns the results so far without running anything.
+    #[wasm_bindgen]
+    pub fn report(&self) -> SimulationReport {
+        self.simulation.report()
+    }
+}
+
+/// Simulations of one configuration, built once and reused for every run.
+///
+/// [`simulate`] builds its engine from the configuration on every call. For
+/// many runs of the same configuration, e.g. repeated Monte Carlo batches,
+/// a pool builds `size` engines once and only resets them between runs. The
+/// games of a run are split across the engines, each drawing from its own
+/// random stream, and their results are merged. A seeded run is
+/// reproducible for the same pool size, but differs from [`simulate`] with
+/// the same seed.
+#[wasm_bindgen]
+pub struct SimulationPool {
+    members: Vec<JsSimulation>,
+}
+
+#[wasm_bindgen]
+impl SimulationPool {
+    /// Builds the engines of a pool.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Configuration to simulate
+    /// * `size` - Number of engines, at least 1
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` describing every problem if the configuration is
+    /// invalid.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const pool = new SimulationPool(config, 4);
+    /// for (const max_launches of [1000, 5000, 20000]) {
+    ///     const report = pool.run(10000, { max_launches, seed: 1n });
+    ///     plot(max_launches, report.average_final_balls);
+    /// }
+    /// ```
+    #[wasm_bindgen(constructor)]
+    pub fn new(config: &Config, size: usize) -> Result<SimulationPool, PachisloError> {
+        config.validate()?;
+
+        Ok(SimulationPool {
+            members: (0..size.max(1))
+                .map(|_| {
+                    Simulation::new(
+                        config.clone().into(),
+                        config.mechanics(),
+                        0,
+                        SimulationOptions::default(),
+                    )
+                })
+                .collect(),
+        })
+    }
+
+    /// Returns the number of engines.
+    #[wasm_bindgen]
+    pub fn size(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Simulates `n_games` games like [`simulate`], on the pool's engines.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `InvalidArguments` error if `start_hole_probability` is not
+    /// from 0 to 1.
+    #[wasm_bindgen]
+    pub fn run(
+        &mut self,
+        n_games: usize,
+        options: Option<SimulationOptions>,
+    ) -> Result<SimulationReport, PachisloError> {
+        let options = options.unwrap_or_default();
+        options.check()?;
+
+        Ok(run_pooled(&mut self.members, n_games, options))
+    }
+}
+
+/// Core of [`SimulationPool::run`], independent of the JavaScript callback.
+fn run_pooled<F>(
+    members: &mut [Simulation<F>],
+    n_games: usize,
+    options: SimulationOptions,
+) -> SimulationReport
+where
+    F: FnMut(usize) -> f64,
+{
+    let mut rng = options
+        .seed
+        .map_or_else(GameRng::default, GameRng::from_seed);
+    let size = members.len();
+
+    let mut tally = Tally::default();
+    for (i, member) in members.iter_mut().enumerate() {
+        let games = n_games / size + usize::from(i < n_games % size);
+        member.reset(games, options, rng.split());
+        while !member.is_done() {
+            member.advance();
+        }
+        tally = tally.merge(member.tally);
+    }
+
+    tally.report()
+}
+
+/// State of a batch simulation between iterations.
+struct Simulation<F>
+where
+    F: FnMut(usize) -> f64,
+{
+    engine: Engine<NullOutput, F>,
+    /// The engine as built, which every reset returns to
+    fresh: Checkpoint,
+    hole_rng: GameRng,
+    /// Derives the streams of every game unless sampling is `Independent`
+    streams: GameRng,
+    /// Streams of the last game, whose twin the next game draws from with
+    /// `Antithetic` sampling
+    pair: Option<(GameRng, GameRng)>,
+    options: SimulationOptions,
+    n_games: usize,
+    /// Launches of the running game
+    game_launches: usize,
+    tally: Tally,
+}
+
+/// Counters of a batch simulation, from which its report is computed.
+#[derive(Clone, Copy, Debug, Default)]
+struct Tally {
+    /// Games completed
+    games: usize,
+    final_balls: usize,
+    /// Launches of the completed games
+    launches: usize,
+    truncated_games: usize,
+    stopped_games: usize,
+    normal_launches: usize,
+    normal_lotteries: usize,
+    rush_entries: usize,
+    rush_rounds: usize,
+    paid_out: usize,
+}
+
+impl<F> Simulation<F>
+where
+    F: FnMut(usize) -> f64,
+{
+    fn new(
+        config: pachislo::config::Config<F>,
+        mechanics: Mechanics,
+        n_games: usize,
+        options: SimulationOptions,
+    ) -> Self {
+        let mut rng = options
+            .seed
+            .map_or_else(GameRng::default, GameRng::from_seed);
+        let hole_rng = rng.split();
+        let streams = rng.clone();
+
+        let mut engine = Engine::new(config, NullOutput, rng).unwrap();
+        engine.set_mechanics(mechanics);
+        engine.set_outcomes_only(options.outcomes_only);
+
+        Simulation {
+            fresh: engine.checkpoint(),
+            engine,
+            hole_rng,
+            streams,
+            pair: None,
+            options,
+            n_games,
+            game_launches: 0,
+            tally: Tally::default(),
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.tally.games == self.n_games
+    }
+
+    /// Prepares another `n_games` games with fresh counters, keeping the
+    /// engine but none of its state, like the ceiling count of an earlier
+    /// run.
+    fn reset(&mut self, n_games: usize, options: SimulationOptions, mut rng: GameRng) {
+        self.hole_rng = rng.split();
+        self.streams = rng.clone();
+        self.pair = None;
+        self.engine.rewind(self.fresh.clone());
+        self.engine.set_rng(rng);
+        self.engine.set_outcomes_only(options.outcomes_only);
+        self.options = options;
+        self.n_games = n_games;
+        self.game_launches = 0;
+        self.tally = Tally::default();
+    }
+
+    /// Runs one iteration, starting a game first if none is running.
+    fn advance(&mut self) {
+        if matches!(self.engine.state(), GameState::Uninitialized) {
+            self.seed_game();
+            self.engine.start();
+        }
+
+        let engine = &mut self.engine;
+        if engine.check_stop().is_some() {
+            self.tally.final_balls += balls(engine.state());
+            self.tally.stopped_games += 1;
+            engine.finish();
+            self.end_game();
+            return;
+        }
+        if self.game_launches == self.options.max_launches {
+            self.tally.final_balls += balls(engine.state());
+            self.tally.truncated_games += 1;
+            engine.finish();
+            self.end_game();
+            return;
+        }
+
+        self.launch();
+        if matches!(self.engine.state(), GameState::Uninitialized) {
+            self.end_game();
+        }
+    }
+
+    /// Gives a game about to start its random streams, following the
+    /// sampling strategy.
+    fn seed_game(&mut self) {
+        let (engine_rng, hole_rng) = match self.options.sampling {
+            SamplingStrategy::Independent => return,
+            SamplingStrategy::Antithetic => match self.pair.take() {
+                Some((engine_rng, hole_rng)) => (engine_rng.antithetic(), hole_rng.antithetic()),
+                None => {
+                    let pair = (self.streams.split(), self.streams.split());
+                    self.pair = Some(pair.clone());
+                    pair
+                }
+            },
+            SamplingStrategy::Stratified => {
+                let mut engine_rng = self.streams.split();
+                engine_rng.set_stratum(self.tally.games as u64, self.n_games as u64);
+                (engine_rng, self.streams.split())
+            }
+        };
+
+        self.engine.set_rng(engine_rng);
+        self.hole_rng = hole_rng;
+    }
+
+    /// Launches a ball and draws a lottery if it hits the start hole.
+    fn launch(&mut self) {
+        let engine = &mut self.engine;
+        if is_normal_mode(engine.state()) {
+            self.tally.normal_launches += 1;
+        }
+        engine.launch_ball();
+        self.game_launches += 1;
+
+        if !self
+            .hole_rng
+            .random_bool(self.options.start_hole_probability)
+        {
+            return;
+        }
+
+        let before = *engine.state();
+        engine.cause_lottery();
+        let after = *engine.state();
+
+        self.tally.paid_out += balls(&after) - balls(&before);
+
+        match (before, after) {
+            (before, GameState::Rush { n: 1.., .. }) if is_normal_mode(&before) => {
+                self.tally.normal_lotteries += 1;
+                self.tally.rush_entries += 1;
+                self.tally.rush_rounds += 1;
+            }
+            (before, _) if is_normal_mode(&before) => self.tally.normal_lotteries += 1,
+            (GameState::Rush { n: before, .. }, GameState::Rush { n: after, .. })
+                if after > before =>
+            {
+                self.tally.rush_rounds += 1;
+            }
+            _ => {}
+        }
+    }
+
+    fn end_game(&mut self) {
+        self.tally.launches += self.game_launches;
+        self.game_launches = 0;
+        self.tally.games += 1;
+    }
+
+    /// Results of the games completed so far. Rates also count the running
+    /// game.
+    fn report(&self) -> SimulationReport {
+        self.tally.report()
+    }
+}
+
+impl Tally {
+    fn merge(self, other: Tally) -> Tally {
+        Tally {
+            games: self.games + other.games,
+            final_balls: self.final_balls + other.final_balls,
+            launches: self.launches + other.launches,
+            truncated_games: self.truncated_games + other.truncated_games,
+            stopped_games: self.stopped_games + other.stopped_games,
+            normal_launches: self.normal_launches + other.normal_launches,
+            normal_lotteries: self.normal_lotteries + other.normal_lotteries,
+            rush_entries: self.rush_entries + other.rush_entries,
+            rush_rounds: self.rush_rounds + other.rush_rounds,
+            paid_out: self.paid_out + other.paid_out,
+        }
+    }
+
+    fn report(&self) -> SimulationReport {
+        let ratio = |numerator: usize, denominator: usize| {
+            if denominator == 0 {
+                0.0
+            } else {
+                numerator as f64 / denominator as f64
+            }
+        };
+
+        SimulationReport {
+            games: self.games,
+            average_final_balls: ratio(self.final_balls, self.games),
+            average_launches: ratio(self.launches, self.games),
+            truncated_games: self.truncated_games,
+            stopped_games: self.stopped_games,
+            rush_hit_rate: ratio(self.rush_entries, self.normal_lotteries),
+            mean_continuation_length: ratio(self.rush_rounds, self.rush_entries),
+            payout_rate: ratio(self.paid_out, self.normal_launches),
+        }
+    }
+}
+
+/// Monte Carlo estimate of the payout rate (出玉率).
+///
+/// # Fields
+///
+/// * `spins` - Lotteries drawn
+/// * `payout_rate` - Balls paid out per regular ball spent
+/// * `standard_error` - Standard error of `payout_rate`; roughly 95% of
+///   estimates fall within two standard errors of the true rate
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct PayoutEstimate {
+    pub spins: usize,
+    pub payout_rate: f64,
+    pub standard_error: f64,
+}
+
+/// Estimates the payout rate of a configuration by drawing `spins`
+/// lotteries headlessly.
+///
+/// Balls are launched as the configuration has them, including their cost
+/// and start pocket, and every launch that hits the start pocket draws a
+/// lottery. A game that runs out of balls is restarted, so the estimate
+/// describes a player with unlimited funds. Rush balls are free, and
+/// payouts are counted when a jackpot pays out.
+///
+/// The standard error treats every spin as a sample of the balls spent
+/// before it and the balls it paid out; it shrinks with the square root of
+/// `spins`.
+///
+/// # Arguments
+///
+/// * `config` - Configuration to estimate
+/// * `spins` - Number of lotteries to draw
+/// * `seed` - Seed for a reproducible estimate; random when omitted
+///
+/// # Errors
+///
+/// Throws an `Error` describing every problem if the configuration is
+/// invalid.
+///
+/// # Example
+///
+/// ```javascript
+/// const { payout_rate, standard_error } = estimate_payout_rate(config, 1_000_000, 1n);
+/// console.log(`${payout_rate.toFixed(3)} ± ${(2 * standard_error).toFixed(3)}`);
+/// ```
+#[wasm_bindgen]
+pub fn estimate_payout_rate(
+    config: &Config,
+    spins: usize,
+    seed: Option<u64>,
+) -> Result<PayoutEstimate, PachisloError> {
+    config.validate()?;
+
+    Ok(estimate(
+        config.clone().into(),
+        config.mechanics(),
+        spins,
+        seed,
+    ))
+}
+
+/// Core of [`estimate_payout_rate`], independent of the JavaScript callback.
+pub(crate) fn estimate<F>(
+    config: pachislo::config::Config<F>,
+    mechanics: Mechanics,
+    spins: usize,
+    seed: Option<u64>,
+) -> PayoutEstimate
+where
+    F: FnMut(usize) -> f64,
+{
+    let rng = seed.map_or_else(GameRng::default, GameRng::from_seed);
+    let sample = sample(config, mechanics, spins, rng);
+    let (payout_rate, standard_error) = sample.payout_rate();
+
+    PayoutEstimate {
+        spins,
+        payout_rate,
+        standard_error,
+    }
+}
+
+/// Figures of one configuration in a [`ConfigComparison`].
+///
+/// # Fields
+///
+/// * `payout_rate` - Balls paid out per regular ball spent, as estimated by
+///   [`estimate_payout_rate`]
+/// * `standard_error` - Standard error of `payout_rate`
+/// * `hit_rate` - Share of normal-mode spins that won, counting jitan spins
+///   as normal ones
+/// * `volatility` - Standard deviation of the balls won or lost per spin,
+///   the payout minus the balls spent before the spin
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ConfigMetrics {
+    pub payout_rate: f64,
+    pub standard_error: f64,
+    pub hit_rate: f64,
+    pub volatility: f64,
+}
+
+/// Result of [`compare_configs`].
+///
+/// # Fields
+///
+/// * `spins` - Lotteries drawn for each configuration
+/// * `a` - Figures of the first configuration
+/// * `b` - Figures of the second configuration
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ConfigComparison {
+    pub spins: usize,
+    pub a: ConfigMetrics,
+    pub b: ConfigMetrics,
+}
+
+/// Compares two configurations by drawing `spins` lotteries with each.
+///
+/// Both runs play like [`estimate_payout_rate`] and draw from the same
+/// random number stream, so both configurations see the same luck for as
+/// long as their games draw alike. Differences in the figures then come
+/// from the configurations rather than from chance, which makes the
+/// comparison far more precise than two independent estimates.
+///
+/// # Arguments
+///
+/// * `a`, `b` - Configurations to compare
+/// * `spins` - Number of lotteries to draw with each configuration
+/// * `seed` - Seed of the shared stream; random when omitted
+///
+/// # Errors
+///
+/// Throws an `Error` describing every problem of the first invalid
+/// configuration.
+///
+/// # Example
+///
+/// ```javascript
+/// const { a, b } = compare_configs(current, tuned, 1_000_000, 7n);
+/// console.log(`RTP ${a.payout_rate.toFixed(3)} -> ${b.payout_rate.toFixed(3)}`);
+/// ```
+#[wasm_bindgen]
+pub fn compare_configs(
+    a: &Config,
+    b: &Config,
+    spins: usize,
+    seed: Option<u64>,
+) -> Result<ConfigComparison, PachisloError> {
+    a.validate()?;
+    b.validate()?;
+    let rng = seed.map_or_else(GameRng::default, GameRng::from_seed);
+
+    Ok(ConfigComparison {
+        spins,
+        a: sample(a.clone().into(), a.mechanics(), spins, rng.clone()).metrics(),
+        b: sample(b.clone().into(), b.mechanics(), spins, rng).metrics(),
+    })
+}
+
+/// Spread of session outcomes, the result of [`estimate_volatility`].
+///
+/// # Fields
+///
+/// * `sessions` - Sessions simulated
+/// * `spins_per_session` - Lotteries drawn in every session
+/// * `payout_rate` - Balls paid out per regular ball spent over all sessions
+/// * `mean_net_balls` - Mean balls won or lost by a session, paid out minus
+///   spent
+/// * `standard_deviation` - Standard deviation of the balls won or lost by a
+///   session
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct VolatilityEstimate {
+    pub sessions: usize,
+    pub spins_per_session: usize,
+    pub payout_rate: f64,
+    pub mean_net_balls: f64,
+    pub standard_deviation: f64,
+}
+
+/// Estimates how much the outcome of a session varies with a configuration
+/// by simulating `sessions` sessions of `spins_per_session` lotteries each.
+///
+/// Every session starts a new game and plays like
+/// [`estimate_payout_rate`], restarting games that run out of balls, so its
+/// outcome is the balls it paid out minus the regular balls it spent.
+/// Configurations with the same payout rate can differ widely here: rare,
+/// large jackpots make sessions swing far more than frequent, small ones.
+///
+/// # Arguments
+///
+/// * `config` - Configuration to simulate
+/// * `sessions` - Number of sessions to simulate
+/// * `spins_per_session` - Lotteries drawn in every session
+/// * `seed` - Seed for a reproducible estimate; random when omitted
+///
+/// # Errors
+///
+/// Throws an `Error` describing every problem if the configuration is
+/// invalid.
+///
+/// # Example
+///
+/// ```javascript
+/// const { mean_net_balls, standard_deviation } = estimate_volatility(config, 1000, 2000, 3n);
+/// console.log(`${mean_net_balls.toFixed(0)} ± ${standard_deviation.toFixed(0)} balls per session`);
+/// ```
+#[wasm_bindgen]
+pub fn estimate_volatility(
+    config: &Config,
+    sessions: usize,
+    spins_per_session: usize,
+    seed: Option<u64>,
+) -> Result<VolatilityEstimate, PachisloError> {
+    config.validate()?;
+
+    Ok(volatility(
+        config.clone().into(),
+        config.mechanics(),
+        sessions,
+        spins_per_session,
+        seed,
+    ))
+}
+
+/// Core of [`estimate_volatility`], independent of the JavaScript callback.
+pub(crate) fn volatility<F>(
+    config: pachislo::config::Config<F>,
+    mechanics: Mechanics,
+    sessions: usize,
+    spins_per_session: usize,
+    seed: Option<u64>,
+) -> VolatilityEstimate
+where
+    F: FnMut(usize) -> f64,
+{
+    let rng = seed.map_or_else(GameRng::default, GameRng::from_seed);
+    let mut engine = Engine::new(config, NullOutput, rng).unwrap();
+    engine.set_mechanics(mechanics);
+
+    let (mut spent, mut paid_out) = (0.0, 0.0);
+    let (mut net, mut net_squared) = (0.0, 0.0);
+    for _ in 0..sessions {
+        engine.finish();
+        let session = draw(&mut engine, spins_per_session);
+
+        spent += session.spent;
+        paid_out += session.paid_out;
+        let balls = session.paid_out - session.spent;
+        net += balls;
+        net_squared += balls * balls;
+    }
+
+    let n = sessions as f64;
+    let mean_net_balls = if sessions == 0 { 0.0 } else { net / n };
+    let standard_deviation = if sessions > 1 {
+        ((net_squared - n * mean_net_balls * mean_net_balls).max(0.0) / (n - 1.0)).sqrt()
+    } else {
+        0.0
+    };
+
+    VolatilityEstimate {
+        sessions,
+        spins_per_session,
+        payout_rate: if spent == 0.0 { 0.0 } else { paid_out / spent },
+        mean_net_balls,
+        standard_deviation,
+    }
+}
+
+/// Per-spin sums of a headless run, see [`sample`].
+#[derive(Debug, Default)]
+struct Sample {
+    spins: usize,
+    /// Balls spent before a spin
+    spent: f64,
+    spent_squared: f64,
+    /// Balls paid out by a spin
+    paid_out: f64,
+    paid_out_squared: f64,
+    /// Products of the balls spent before and paid out by a spin
+    product: f64,
+    normal_spins: usize,
+    normal_wins: usize,
+}
+
+impl Sample {
+    /// The payout rate and its standard error, by the delta method for a
+    /// ratio of means.
+    fn payout_rate(&self) -> (f64, f64) {
+        if self.spent == 0.0 {
+            return (0.0, 0.0);
+        }
+
+        let n = self.spins as f64;
+        let rate = self.paid_out / self.spent;
+        let residuals =
+            self.paid_out_squared - 2.0 * rate * self.product + rate * rate * self.spent_squared;
+        let standard_error = if self.spins > 1 {
+            (residuals.max(0.0) / (n * (n - 1.0))).sqrt() / (self.spent / n)
+        } else {
+            0.0
+        };
+
+        (rate, standard_error)
+    }
+
+    fn metrics(&self) -> ConfigMetrics {
+        let (payout_rate, standard_error) = self.payout_rate();
+        let n = self.spins as f64;
+
+        // Net balls of a spin are paid out minus spent
+        let volatility = if self.spins > 1 {
+            let mean = (self.paid_out - self.spent) / n;
+            let squares = self.paid_out_squared - 2.0 * self.product + self.spent_squared;
+            ((squares - n * mean * mean).max(0.0) / (n - 1.0)).sqrt()
+        } else {
+            0.0
+        };
+
+        ConfigMetrics {
+            payout_rate,
+            standard_error,
+            hit_rate: if self.normal_spins == 0 {
+                0.0
+            } else {
+                self.normal_wins as f64 / self.normal_spins as f64
+            },
+            volatility,
+        }
+    }
+}
+
+/// Draws `spins` lotteries headlessly, restarting games that run out, and
+/// sums up every spin.
+fn sample<F>(
+    config: pachislo::config::Config<F>,
+    mechanics: Mechanics,
+    spins: usize,
+    rng: GameRng,
+) -> Sample
+where
+    F: FnMut(usize) -> f64,
+{
+    let mut engine = Engine::new(config, NullOutput, rng).unwrap();
+    engine.set_mechanics(mechanics);
+
+    draw(&mut engine, spins)
+}
+
+/// Draws `spins` more lotteries with `engine`, restarting games that run
+/// out, and sums up every spin.
+fn draw<F>(engine: &mut Engine<NullOutput, F>, spins: usize) -> Sample
+where
+    F: FnMut(usize) -> f64,
+{
+    let mut sample = Sample {
+        spins,
+        ..Sample::default()
+    };
+    let target = engine.stats().spins + spins;
+    let mut spin_spent = 0;
+
+    while engine.stats().spins < target {
+        if matches!(engine.state(), GameState::Uninitialized) {
+            engine.start();
+        }
+
+        let before = balls(engine.state());
+        let _ = engine.run_step_with_command(Command::LaunchBall);
+        spin_spent += before.saturating_sub(balls(engine.state()));
+
+        let drawn = engine.stats().spins;
+        let awarded = engine.stats().balls_awarded;
+        let _ = engine.run_step_with_command(Command::CauseLottery);
+        let Some(spin) = engine.last_spin().filter(|_| engine.stats().spins > drawn) else {
+            continue;
+        };
+
+        if matches!(spin.mode, SpinMode::Normal | SpinMode::Jitan) {
+            sample.normal_spins += 1;
+            if spin.lottery.result.is_win() {
+                sample.normal_wins += 1;
+            }
+        }
+
+        let (x, y) = (
+            spin_spent as f64,
+            (engine.stats().balls_awarded - awarded) as f64,
+        );
+        sample.spent += x;
+        sample.spent_squared += x * x;
+        sample.paid_out += y;
+        sample.paid_out_squared += y * y;
+        sample.product += x * y;
+        spin_spent = 0;
+    }
+
+    sample
+}
+
+/// Whether lotteries are drawn outside a rush, i.e. in normal or jitan mode.
+fn is_normal_mode(state: &GameState) -> bool {
+    matches!(state, GameState::Normal { .. }) || is_jitan(state)
+}
+
+fn balls(state: &GameState) -> usize {
+    match *state {
+        GameState::Uninitialized => 0,
+        GameState::Normal { balls } | GameState::Rush { balls, .. } => balls,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stop::StopConditions;
+    use pachislo::config::{BallsConfig, Probability, SlotProbability};
+
+    fn config(normal_win: f64, continue_win: f64) -> pachislo::config::Config<fn(usize) -> f64> {
+        let slot = |win| SlotProbability {
+            win,
+            fake_win: 0.0,
+            fake_lose: 0.0,
+        };
+
+        pachislo::config::Config {
+            balls: BallsConfig {
+                init_balls: 100,
+                incremental_balls: 10,
+                incremental_rush: 5,
+            },
+            probability: Probability {
+                normal: slot(normal_win),
+                rush: slot(0.5),
+                rush_continue: slot(continue_win),
+                rush_continue_fn: |_| 1.0,
+            },
+        }
+    }
+
+    fn options(seed: u64) -> SimulationOptions {
+        SimulationOptions {
+            seed: Some(seed),
+            ..SimulationOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_losing_machine_runs_out() {
+        let report = run(config(0.0, 0.0), Mechanics::default(), 10, options(0));
+
+        assert_eq!(report.games, 10);
+        assert_eq!(report.average_final_balls, 0.0);
+        assert_eq!(report.average_launches, 100.0);
+        assert_eq!(report.truncated_games, 0);
+        assert_eq!(report.rush_hit_rate, 0.0);
+        assert_eq!(report.payout_rate, 0.0);
+    }
+
+    #[test]
+    fn test_truncated_games() {
+        let report = run(
+            config(1.0, 1.0),
+            Mechanics::default(),
+            3,
+            SimulationOptions {
+                start_hole_probability: 1.0,
+                max_launches: 50,
+                seed: Some(0),
+                ..SimulationOptions::default()
+            },
+        );
+
+        assert_eq!(report.truncated_games, 3);
+        assert_eq!(report.average_launches, 50.0);
+        assert_eq!(report.rush_hit_rate, 1.0);
+        assert!(report.average_final_balls > 100.0);
+        assert!(report.mean_continuation_length > 1.0);
+    }
+
+    #[test]
+    fn test_stopped_games() {
+        let mechanics = Mechanics {
+            stop: StopConditions {
+                stop_after_rushes: Some(1),
+                ..StopConditions::default()
+            },
+            ..Mechanics::default()
+        };
+        let report = run(config(1.0, 0.0), mechanics, 5, options(2));
+
+        assert_eq!(report.stopped_games, 5);
+        assert_eq!(report.truncated_games, 0);
+        // Every game stops after the jackpot of its first lottery
+        assert_eq!(report.rush_hit_rate, 1.0);
+        assert!(report.average_final_balls > 0.0);
+    }
+
+    #[test]
+    fn test_rush_statistics() {
+        let report = run(config(0.1, 0.0), Mechanics::default(), 2000, options(1));
+
+        // Every normal-mode win enters rush, so the hit rate estimates `normal.win`
+        assert!((report.rush_hit_rate - 0.1).abs() < 0.01);
+        // Continuations never win, so every rush lasts a single round
+        assert_eq!(report.mean_continuation_length, 1.0);
+        assert!(report.payout_rate > 0.0);
+    }
+
+    #[test]
+    fn test_estimate_payout_rate() {
+        let losing = estimate(config(0.0, 0.0), Mechanics::default(), 500, Some(0));
+        assert_eq!(losing.spins, 500);
+        assert_eq!((losing.payout_rate, losing.standard_error), (0.0, 0.0));
+
+        // Rushes pay nothing, so each jackpot pays 10 balls. The last rush
+        // launch draws a normal lottery for free, so a jackpot takes 9
+        // spent balls on average instead of 10
+        for seed in 0..5 {
+            let mut config = config(0.1, 0.0);
+            config.probability.rush.win = 0.0;
+            let estimate = estimate(config, Mechanics::default(), 20_000, Some(seed));
+            assert!(estimate.standard_error > 0.0);
+            assert!((estimate.payout_rate - 10.0 / 9.0).abs() < 3.0 * estimate.standard_error);
+        }
+    }
+
+    #[test]
+    fn test_compare_configs() {
+        let compare = |a, b| {
+            let rng = GameRng::from_seed(4);
+            (
+                sample(a, Mechanics::default(), 20_000, rng.clone()).metrics(),
+                sample(b, Mechanics::default(), 20_000, rng).metrics(),
+            )
+        };
+
+        // The same configuration sees exactly the same spins
+        let (a, b) = compare(config(0.1, 0.0), config(0.1, 0.0));
+        assert_eq!(a.payout_rate, b.payout_rate);
+        assert_eq!(a.volatility, b.volatility);
+        assert!((a.hit_rate - 0.1).abs() < 0.01);
+        assert!(a.volatility > 0.0);
+
+        let (a, b) = compare(config(0.1, 0.0), config(0.12, 0.0));
+        assert!(b.hit_rate > a.hit_rate);
+        assert!(b.payout_rate > a.payout_rate);
+    }
+
+    #[test]
+    fn test_volatility() {
+        let losing = volatility(config(0.0, 0.0), Mechanics::default(), 20, 50, Some(0));
+        assert_eq!((losing.sessions, losing.spins_per_session), (20, 50));
+        assert_eq!(losing.payout_rate, 0.0);
+        assert!(losing.mean_net_balls < 0.0);
+
+        // Both pay 10 balls per 100 spins on average, but in one jackpot
+        // instead of ten
+        let steady = volatility(config(0.1, 0.0), Mechanics::default(), 500, 100, Some(5));
+        let mut config = config(0.01, 0.0);
+        config.balls.incremental_balls = 100;
+        let swingy = volatility(config, Mechanics::default(), 500, 100, Some(5));
+
+        assert!(steady.standard_deviation > 0.0);
+        assert!(swingy.standard_deviation > 2.0 * steady.standard_deviation);
+    }
+
+    #[test]
+    fn test_chunked_simulation() {
+        let report = run(config(0.1, 0.5), Mechanics::default(), 20, options(6));
+
+        let mut simulation =
+            Simulation::new(config(0.1, 0.5), Mechanics::default(), 20, options(6));
+        let mut chunks = 0;
+        while !simulation.is_done() {
+            for _ in 0..100 {
+                simulation.advance();
+                if simulation.is_done() {
+                    break;
+                }
+            }
+            chunks += 1;
+            assert!(simulation.report().games <= 20);
+        }
+        let chunked = simulation.report();
+
+        assert!(chunks > 1);
+        assert_eq!(chunked.games, 20);
+        assert_eq!(chunked.average_final_balls, report.average_final_balls);
+        assert_eq!(chunked.average_launches, report.average_launches);
+        assert_eq!(chunked.payout_rate, report.payout_rate);
+    }
+
+    #[test]
+    fn test_seed_is_reproducible() {
+        let a = run(config(0.1, 0.5), Mechanics::default(), 100, options(9));
+        let b = run(config(0.1, 0.5), Mechanics::default(), 100, options(9));
+
+        assert_eq!(a.average_final_balls, b.average_final_balls);
+        assert_eq!(a.payout_rate, b.payout_rate);
+    }
+
+    #[test]
+    fn test_outcomes_only_reports_the_same() {
+        let mechanics = Mechanics {
+            premium: Some(0.2),
+            ceiling: Some(20),
+            ..Mechanics::default()
+        };
+        let simulate = |outcomes_only| {
+            let options = SimulationOptions {
+                outcomes_only,
+                ..options(6)
+            };
+            run(config(0.1, 0.5), mechanics.clone(), 200, options)
+        };
+        let (full, fast) = (simulate(false), simulate(true));
+
+        assert_eq!(full.average_final_balls, fast.average_final_balls);
+        assert_eq!(full.average_launches, fast.average_launches);
+        assert_eq!(full.rush_hit_rate, fast.rush_hit_rate);
+        assert_eq!(full.payout_rate, fast.payout_rate);
+
+        let mut simulation = Simulation::new(
+            config(0.1, 0.5),
+            Mechanics::default(),
+            10,
+            SimulationOptions {
+                outcomes_only: true,
+                ..options(6)
+            },
+        );
+        while !simulation.is_done() {
+            simulation.advance();
+        }
+        assert!(simulation.engine.last_spin().is_none());
+    }
+
+    #[test]
+    fn test_sampling_strategies() {
+        // Every game draws a single lottery that wins half the time
+        let coin_flips = |sampling| {
+            let options = SimulationOptions {
+                start_hole_probability: 1.0,
+                max_launches: 1,
+                sampling,
+                ..options(3)
+            };
+            run(config(0.5, 0.0), Mechanics::default(), 40, options).rush_hit_rate
+        };
+
+        assert_ne!(coin_flips(SamplingStrategy::Independent), 0.5);
+        // One game of every pair wins
+        assert_eq!(coin_flips(SamplingStrategy::Antithetic), 0.5);
+        // The first lotteries of half of the games fall below 0.5
+        assert_eq!(coin_flips(SamplingStrategy::Stratified), 0.5);
+    }
+
+    #[test]
+    fn test_antithetic_sampling_reduces_variance() {
+        let spread = |sampling| {
+            let rates: Vec<_> = (0..30)
+                .map(|seed| {
+                    let options = SimulationOptions {
+                        seed: Some(seed),
+                        sampling,
+                        ..SimulationOptions::default()
+                    };
+                    run(config(0.3, 0.5), Mechanics::default(), 20, options).payout_rate
+                })
+                .collect();
+            let mean = rates.iter().sum::<f64>() / rates.len() as f64;
+            rates.iter().map(|rate| (rate - mean).powi(2)).sum::<f64>()
+        };
+
+        assert!(spread(SamplingStrategy::Antithetic) < spread(SamplingStrategy::Independent));
+    }
+
+    #[test]
+    fn test_pool_reuses_engines() {
+        let mut members: Vec<_> = (0..3)
+            .map(|_| Simulation::new(config(0.1, 0.5), Mechanics::default(), 0, options(0)))
+            .collect();
+
+        let first = run_pooled(&mut members, 100, options(4));
+        let second = run_pooled(&mut members, 100, options(4));
+        assert_eq!(first.games, 100);
+        assert_eq!(first.average_final_balls, second.average_final_balls);
+        assert_eq!(first.payout_rate, second.payout_rate);
+
+        let games: Vec<_> = members.iter().map(|member| member.tally.games).collect();
+        assert_eq!(games, [34, 33, 33]);
+
+        let other = run_pooled(&mut members, 100, options(5));
+        assert_ne!(first.average_launches, other.average_launches);
+
+        // A run does not inherit the losing spins of the previous one
+        let ceiling = Mechanics {
+            ceiling: Some(3),
+            ..Mechanics::default()
+        };
+        let mut members = vec![Simulation::new(config(0.0, 0.5), ceiling, 0, options(0))];
+        let options = SimulationOptions {
+            start_hole_probability: 1.0,
+            max_launches: 2,
+            ..options(4)
+        };
+        let first = run_pooled(&mut members, 1, options);
+        let second = run_pooled(&mut members, 1, options);
+        assert_eq!(first.payout_rate, second.payout_rate);
+    }
+}
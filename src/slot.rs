@@ -0,0 +1,315 @@
+//! # Slot Symbols
+//!
+//! By default every symbol is equally likely on every reel. A weighted layout
+//! gives each symbol separate weights for winning and losing slots, so a
+//! premium symbol like "7" can be rare on losing reels and common in wins, as
+//! on a real machine display.
+//!
+//! Symbols are numbers internally. An output can name them, e.g. `"🍒"` or
+//! `"BAR"`, to deliver slots with the display symbols directly.
+//!
+//! A slot of at least 3 reels whose first two reels match is a reach (リーチ):
+//! the result is only decided by the remaining reels.
+//!
+//! The symbols of the most recent slot are also kept in a fixed buffer that
+//! JavaScript can read through a `Uint8Array` view (see `WasmGame::slot_view`),
+//! so render loops can read the reels without an allocation per lottery.
+
+use pachislo::lottery::{Lose, LotteryResult, Win};
+use rand::{Rng, distr::weighted::WeightedIndex, prelude::Distribution};
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+/// A slot symbol with its weights.
+///
+/// # Fields
+///
+/// * `symbol` - The symbol
+/// * `win` - Relative weight of the symbol being the winning symbol
+/// * `lose` - Relative weight of the symbol showing on a reel of a losing slot
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct WeightedSymbol {
+    pub symbol: u8,
+    pub win: f64,
+    pub lose: f64,
+}
+
+/// The reels of a slot as delivered to JavaScript.
+///
+/// # Variants
+///
+/// * `Symbols` - The symbol of every reel
+/// * `Names` - The name of every reel's symbol, if the output names its symbols
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(untagged)]
+pub enum Reels {
+    Symbols(Vec<u8>),
+    Names(Vec<String>),
+}
+
+impl Reels {
+    /// Names the reels of a slot, `symbols[i]` being named `names[i]`.
+    pub(crate) fn new(reels: Vec<u8>, symbols: &[u8], names: Option<&[String]>) -> Self {
+        let Some(names) = names else {
+            return Reels::Symbols(reels);
+        };
+
+        Reels::Names(
+            reels
+                .into_iter()
+                .map(|reel| {
+                    // The layout only produces its own symbols
+                    let index = symbols.iter().position(|&symbol| symbol == reel).unwrap();
+                    names[index].clone()
+                })
+                .collect(),
+        )
+    }
+
+    /// Number of reels.
+    pub fn len(&self) -> usize {
+        match self {
+            Reels::Symbols(symbols) => symbols.len(),
+            Reels::Names(names) => names.len(),
+        }
+    }
+
+    /// Whether the slot has no reels.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The first two reels of a slot if they make it a reach.
+pub(crate) fn reach(slot: &[u8]) -> Option<&[u8]> {
+    (slot.len() >= 3 && slot[0] == slot[1]).then(|| &slot[..2])
+}
+
+/// Checks names for the symbols of a slot layout.
+pub(crate) fn check_symbol_names(symbols: &[u8], names: &[String]) -> Result<(), &'static str> {
+    if names.len() != symbols.len() {
+        return Err("every symbol of the slot layout needs exactly one name");
+    }
+    if names
+        .iter()
+        .enumerate()
+        .any(|(i, name)| names[..i].contains(name))
+    {
+        return Err("symbol names must be distinct");
+    }
+
+    Ok(())
+}
+
+/// Produces slots from weighted symbols.
+#[derive(Clone, Debug)]
+pub(crate) struct WeightedSlots {
+    symbols: Vec<u8>,
+    win: WeightedIndex<f64>,
+    lose: WeightedIndex<f64>,
+}
+
+impl WeightedSlots {
+    /// Checks the weights; the symbols are checked with the slot layout.
+    pub(crate) fn new(symbols: &[WeightedSymbol]) -> Result<Self, &'static str> {
+        if symbols
+            .iter()
+            .any(|symbol| !valid_weight(symbol.win) || !valid_weight(symbol.lose))
+        {
+            return Err("symbol weights must be finite and not negative");
+        }
+        if symbols.iter().filter(|symbol| symbol.lose > 0.0).count() < 2 {
+            return Err("at least 2 symbols need a positive \"lose\" weight to show losing slots");
+        }
+
+        let win = WeightedIndex::new(symbols.iter().map(|symbol| symbol.win))
+            .map_err(|_| "at least 1 symbol needs a positive \"win\" weight")?;
+        let lose = WeightedIndex::new(symbols.iter().map(|symbol| symbol.lose))
+            .map_err(|_| "symbol weights must be finite and not negative")?;
+
+        Ok(WeightedSlots {
+            symbols: symbols.iter().map(|symbol| symbol.symbol).collect(),
+            win,
+            lose,
+        })
+    }
+
+    /// Produces the slot for a lottery result like `SlotProducer::produce`:
+    /// fake results show a second slot revealing the actual result.
+    pub(crate) fn produce<R: Rng>(
+        &self,
+        reels: usize,
+        result: &LotteryResult,
+        rng: &mut R,
+    ) -> (Vec<u8>, Option<Vec<u8>>) {
+        match result {
+            LotteryResult::Win(Win::Default) => (self.win(reels, rng), None),
+            LotteryResult::Win(Win::FakeWin) => (self.lose(reels, rng), Some(self.win(reels, rng))),
+            LotteryResult::Lose(Lose::Default) => (self.lose(reels, rng), None),
+            LotteryResult::Lose(Lose::FakeLose) => {
+                (self.win(reels, rng), Some(self.lose(reels, rng)))
+            }
+        }
+    }
+
+    fn win<R: Rng>(&self, reels: usize, rng: &mut R) -> Vec<u8> {
+        vec![self.symbols[self.win.sample(rng)]; reels]
+    }
+
+    /// Draws every reel independently; if they all match, one random reel is
+    /// drawn again from the other symbols.
+    fn lose<R: Rng>(&self, reels: usize, rng: &mut R) -> Vec<u8> {
+        let mut slot: Vec<usize> = (0..reels).map(|_| self.lose.sample(rng)).collect();
+
+        if slot.iter().all(|&index| index == slot[0]) {
+            let matched = slot[0];
+            let mut others = self.lose.clone();
+            // `new` guarantees another symbol with a positive weight
+            others.update_weights(&[(matched, &0.0)]).unwrap();
+
+            let reel = rng.random_range(0..reels);
+            slot[reel] = others.sample(rng);
+        }
+
+        slot.into_iter().map(|index| self.symbols[index]).collect()
+    }
+}
+
+fn valid_weight(weight: f64) -> bool {
+    weight.is_finite() && weight >= 0.0
+}
+
+/// The symbols of the most recent slot, at a stable address.
+///
+/// Holds the shown reels followed by the revealed reels; without a reveal,
+/// the shown reels are repeated, so the second half is always the final slot.
+#[derive(Debug)]
+pub(crate) struct SlotBuffer {
+    symbols: Box<[u8]>,
+    generation: u32,
+}
+
+impl SlotBuffer {
+    /// An empty buffer for slots of `reels` reels, filled with zeros.
+    pub(crate) fn new(reels: usize) -> Self {
+        SlotBuffer {
+            symbols: vec![0; reels * 2].into_boxed_slice(),
+            generation: 0,
+        }
+    }
+
+    /// Replaces the kept slot and advances the generation.
+    ///
+    /// The buffer is only reallocated if the number of reels changed.
+    pub(crate) fn write(&mut self, first: &[u8], revealed: Option<&[u8]>) {
+        if self.symbols.len() != first.len() * 2 {
+            self.symbols = vec![0; first.len() * 2].into_boxed_slice();
+        }
+
+        let (shown, last) = self.symbols.split_at_mut(first.len());
+        shown.copy_from_slice(first);
+        last.copy_from_slice(revealed.unwrap_or(first));
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    /// The kept symbols.
+    pub(crate) fn symbols(&self) -> &[u8] {
+        &self.symbols
+    }
+
+    /// The number of slots written so far, wrapping around.
+    pub(crate) fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::GameRng;
+
+    fn symbol(symbol: u8, win: f64, lose: f64) -> WeightedSymbol {
+        WeightedSymbol { symbol, win, lose }
+    }
+
+    #[test]
+    fn test_named_reels() {
+        let names = ["🍒".to_string(), "BAR".to_string(), "7".to_string()];
+
+        assert_eq!(
+            Reels::new(vec![7, 1, 7], &[1, 3, 7], Some(&names)),
+            Reels::Names(vec!["7".to_string(), "🍒".to_string(), "7".to_string()])
+        );
+        assert_eq!(
+            Reels::new(vec![3, 3], &[1, 3, 7], None),
+            Reels::Symbols(vec![3, 3])
+        );
+
+        assert!(check_symbol_names(&[1, 3, 7], &names).is_ok());
+        assert!(check_symbol_names(&[1, 3], &names).is_err());
+        assert!(check_symbol_names(&[1, 3], &["7".to_string(), "7".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_reach() {
+        assert_eq!(reach(&[7, 7, 3]), Some(&[7, 7][..]));
+        assert_eq!(reach(&[7, 7, 7]), Some(&[7, 7][..]));
+        assert_eq!(reach(&[7, 3, 7]), None);
+        assert_eq!(reach(&[7, 7]), None);
+    }
+
+    #[test]
+    fn test_invalid_weights() {
+        assert!(WeightedSlots::new(&[symbol(1, 1.0, 1.0), symbol(7, -1.0, 1.0)]).is_err());
+        assert!(WeightedSlots::new(&[symbol(1, 1.0, 1.0), symbol(7, f64::NAN, 1.0)]).is_err());
+        assert!(WeightedSlots::new(&[symbol(1, 1.0, 1.0), symbol(7, 1.0, 0.0)]).is_err());
+        assert!(WeightedSlots::new(&[symbol(1, 0.0, 1.0), symbol(7, 0.0, 1.0)]).is_err());
+        assert!(WeightedSlots::new(&[symbol(1, 0.0, 1.0), symbol(7, 1.0, 1.0)]).is_ok());
+    }
+
+    #[test]
+    fn test_weights_shape_slots() {
+        // "7" only wins and never shows on losing reels
+        let slots = WeightedSlots::new(&[
+            symbol(1, 0.0, 1.0),
+            symbol(2, 0.0, 1.0),
+            symbol(7, 1.0, 0.0),
+        ])
+        .unwrap();
+        let mut rng = GameRng::from_seed(3);
+
+        for _ in 0..100 {
+            let (win, _) = slots.produce(3, &LotteryResult::Win(Win::Default), &mut rng);
+            assert_eq!(win, [7, 7, 7]);
+
+            let (lose, _) = slots.produce(3, &LotteryResult::Lose(Lose::Default), &mut rng);
+            assert!(!lose.contains(&7));
+            assert!(lose.iter().any(|&symbol| symbol != lose[0]));
+        }
+
+        let (first, second) = slots.produce(3, &LotteryResult::Lose(Lose::FakeLose), &mut rng);
+        assert_eq!(first, [7, 7, 7]);
+        assert!(second.is_some());
+    }
+
+    #[test]
+    fn test_slot_buffer() {
+        let mut buffer = SlotBuffer::new(3);
+        assert_eq!(buffer.symbols(), &[0; 6]);
+        assert_eq!(buffer.generation(), 0);
+
+        buffer.write(&[1, 2, 3], None);
+        assert_eq!(buffer.symbols(), &[1, 2, 3, 1, 2, 3]);
+        let address = buffer.symbols().as_ptr();
+
+        buffer.write(&[7, 7, 6], Some(&[7, 7, 7]));
+        assert_eq!(buffer.symbols(), &[7, 7, 6, 7, 7, 7]);
+        assert_eq!(buffer.symbols().as_ptr(), address);
+        assert_eq!(buffer.generation(), 2);
+
+        buffer.write(&[1, 2], None);
+        assert_eq!(buffer.symbols(), &[1, 2, 1, 2]);
+    }
+}
@@ -0,0 +1,270 @@
+//! # Game Snapshots
+//!
+//! Serializable copies of a game's internal state, so browser games can
+//! survive page reloads and tab suspensions. Snapshots only contain strings,
+//! numbers and nulls and can be stored with `JSON.stringify`.
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use crate::{
+    alias,
+    engine::{Engine, EngineOutput},
+    rng::GameRng,
+    stats::ReturnTicker,
+    streak::Streaks,
+    summary::SessionSummary,
+};
+
+/// Version of the snapshot format written by this crate.
+pub(crate) const SNAPSHOT_VERSION: u32 = 1;
+
+/// The complete internal state of a game.
+///
+/// # Fields
+///
+/// * `version` - Format version, checked on restore
+/// * `state` - The current game state, including balls, mode and rush counter
+/// * `before_state` - The state before the last transition, reported as
+///   `before` of the next transition
+/// * `rng` - Random number generator states; only present for seeded games,
+///   since they would let players predict upcoming outcomes
+/// * `label` - Label of the game the snapshot was taken from, for attribution;
+///   restoring does not change the label of the restored game
+/// * `ticker` - The live return figures, so a restored dashboard continues
+///   the ticker instead of starting over
+/// * `spins_since_win` - Losing normal-mode spins since the last win, so a
+///   restored machine keeps its progress towards the ceiling
+/// * `stock` - Stocked rush wins, see `Config::with_stock`
+/// * `starts` - Launches awaiting their lottery, see `BallsConfig.start_rate`
+/// * `rush_tier` - Index of the tier of the running rush, see
+///   `Config::with_rush_tiers`
+/// * `summary` - Figures of the current or last game, see
+///   `WasmGame::session_summary`
+/// * `streaks` - Current and longest win and lose streaks
+#[derive(Clone, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct GameSnapshot {
+    pub version: u32,
+    pub state: alias::GameState,
+    pub before_state: Option<alias::GameState>,
+    pub rng: Option<RngSnapshot>,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub ticker: ReturnTicker,
+    #[serde(default)]
+    pub spins_since_win: usize,
+    #[serde(default)]
+    pub stock: usize,
+    #[serde(default)]
+    pub starts: usize,
+    #[serde(default)]
+    pub rush_tier: usize,
+    #[serde(default)]
+    pub summary: Option<SessionSummary>,
+    #[serde(default)]
+    pub streaks: Streaks,
+}
+
+/// Random number generator states of a seeded game, as hexadecimal strings.
+///
+/// # Fields
+///
+/// * `lottery` - State of the generator drawing lottery results
+/// * `slot` - State of the generator drawing slot symbols
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct RngSnapshot {
+    pub lottery: String,
+    pub slot: String,
+}
+
+impl RngSnapshot {
+    pub(crate) fn capture(lottery: &GameRng, slot: &GameRng) -> Self {
+        RngSnapshot {
+            lottery: lottery.encode(),
+            slot: slot.encode(),
+        }
+    }
+
+    /// The lottery and slot generators, `None` if a state is invalid.
+    pub(crate) fn decode(&self) -> Option<(GameRng, GameRng)> {
+        GameRng::decode(&self.lottery).zip(GameRng::decode(&self.slot))
+    }
+}
+
+/// Captures the state of an engine; `slot_rng` is only given for seeded games.
+pub(crate) fn capture<O, F>(
+    engine: &Engine<O, F>,
+    slot_rng: Option<&GameRng>,
+    label: Option<&str>,
+) -> GameSnapshot
+where
+    O: EngineOutput,
+    F: FnMut(usize) -> f64,
+{
+    GameSnapshot {
+        version: SNAPSHOT_VERSION,
+        state: (*engine.state()).into(),
+        before_state: engine.before_state().map(|&state| state.into()),
+        rng: slot_rng.map(|slot_rng| RngSnapshot::capture(engine.rng(), slot_rng)),
+        label: label.map(str::to_string),
+        ticker: *engine.ticker(),
+        spins_since_win: engine.spins_since_win(),
+        stock: engine.stock(),
+        starts: engine.starts(),
+        rush_tier: engine.rush_tier().map_or(0, |(index, _)| index),
+        summary: engine.summary(),
+        streaks: engine.streaks(),
+    }
+}
+
+/// Restores a snapshot into an engine and returns the slot generator to
+/// install, if the snapshot contains generator states.
+///
+/// The engine is left unchanged if the snapshot is invalid.
+pub(crate) fn restore<O, F>(
+    engine: &mut Engine<O, F>,
+    snapshot: GameSnapshot,
+) -> Result<Option<GameRng>, &'static str>
+where
+    O: EngineOutput,
+    F: FnMut(usize) -> f64,
+{
+    if snapshot.version != SNAPSHOT_VERSION {
+        return Err("unsupported snapshot version");
+    }
+
+    let rngs = snapshot
+        .rng
+        .map(|rng| {
+            rng.decode()
+                .ok_or("snapshot contains an invalid random number generator state")
+        })
+        .transpose()?;
+
+    engine.restore_state(snapshot.state.into(), snapshot.before_state.map(Into::into));
+    engine.set_ticker(snapshot.ticker);
+    engine.set_spins_since_win(snapshot.spins_since_win);
+    engine.set_stock(snapshot.stock);
+    engine.set_starts(snapshot.starts);
+    engine.set_rush_tier(snapshot.rush_tier);
+    engine.set_summary(snapshot.summary);
+    engine.set_streaks(snapshot.streaks);
+
+    Ok(rngs.map(|(lottery, slot)| {
+        engine.set_rng(lottery);
+        slot
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{Command, NullOutput};
+    use pachislo::config::{BallsConfig, Probability, SlotProbability};
+
+    fn engine(seed: u64) -> Engine<NullOutput, fn(usize) -> f64> {
+        let slot = SlotProbability {
+            win: 0.5,
+            fake_win: 0.0,
+            fake_lose: 0.0,
+        };
+
+        let config: pachislo::config::Config<fn(usize) -> f64> = pachislo::config::Config {
+            balls: BallsConfig {
+                init_balls: 50,
+                incremental_balls: 10,
+                incremental_rush: 5,
+            },
+            probability: Probability {
+                normal: slot,
+                rush: slot,
+                rush_continue: slot,
+                rush_continue_fn: |_| 1.0,
+            },
+        };
+
+        Engine::new(config, NullOutput, GameRng::from_seed(seed)).unwrap()
+    }
+
+    fn play(engine: &mut Engine<NullOutput, fn(usize) -> f64>, steps: usize) {
+        for _ in 0..steps {
+            let _ = engine.run_step_with_command(Command::LaunchBall);
+            let _ = engine.run_step_with_command(Command::CauseLottery);
+        }
+    }
+
+    fn state(engine: &Engine<NullOutput, fn(usize) -> f64>) -> String {
+        format!("{:?}", engine.state())
+    }
+
+    #[test]
+    fn test_restored_game_continues_identically() {
+        let mut original = engine(5);
+        let _ = original.run_step_with_command(Command::StartGame);
+        play(&mut original, 10);
+
+        let slot_rng = GameRng::from_seed(6);
+        let snapshot = capture(&original, Some(&slot_rng), Some("machine 1"));
+        assert_eq!(snapshot.label.as_deref(), Some("machine 1"));
+
+        let mut restored = engine(99);
+        let restored_slot_rng = restore(&mut restored, snapshot).unwrap();
+        assert_eq!(restored_slot_rng, Some(slot_rng));
+        assert_eq!(state(&restored), state(&original));
+        assert_eq!(restored.ticker(), original.ticker());
+        assert_eq!(restored.spins_since_win(), original.spins_since_win());
+        assert_eq!(restored.stock(), original.stock());
+        assert_eq!(restored.summary(), original.summary());
+        assert_eq!(restored.streaks(), original.streaks());
+
+        play(&mut original, 10);
+        play(&mut restored, 10);
+        assert_eq!(state(&restored), state(&original));
+    }
+
+    #[test]
+    fn test_unseeded_snapshot_keeps_rng() {
+        let mut original = engine(5);
+        let _ = original.run_step_with_command(Command::StartGame);
+
+        let snapshot = capture(&original, None, None);
+        assert_eq!(snapshot.rng, None);
+
+        let mut restored = engine(7);
+        assert_eq!(restore(&mut restored, snapshot), Ok(None));
+        assert_eq!(state(&restored), state(&original));
+        assert_eq!(restored.rng(), &GameRng::from_seed(7));
+    }
+
+    #[test]
+    fn test_invalid_snapshots_are_rejected() {
+        let original = engine(5);
+        let mut restored = engine(7);
+
+        let mut snapshot = capture(&original, Some(&GameRng::from_seed(1)), None);
+        snapshot.version = 0;
+        assert!(restore(&mut restored, snapshot).is_err());
+
+        let mut snapshot = capture(&original, Some(&GameRng::from_seed(1)), None);
+        snapshot.state = alias::GameState::Normal { balls: 3 };
+        snapshot.rng.as_mut().unwrap().slot = "broken".to_string();
+        assert!(restore(&mut restored, snapshot).is_err());
+        assert_eq!(state(&restored), state(&engine(7)));
+    }
+
+    #[test]
+    fn test_rng_snapshot() {
+        let (lottery, slot) = (GameRng::from_seed(1), GameRng::from_seed(2));
+        let snapshot = RngSnapshot::capture(&lottery, &slot);
+        assert_eq!(snapshot.decode(), Some((lottery, slot)));
+
+        let zero = RngSnapshot {
+            slot: "0".repeat(64),
+            ..snapshot
+        };
+        assert_eq!(zero.decode(), None);
+    }
+}
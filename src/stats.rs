@@ -0,0 +1,235 @@
+//! # Statistics
+//!
+//! Counters the engine maintains for every game it runs, so applications get
+//! session statistics without reconstructing them from output callbacks.
+//!
+//! Next to the counters, the engine keeps exponentially smoothed return
+//! figures over the recent spins, so dashboards can show a live return ticker
+//! without recomputing it every frame.
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use crate::explain::SpinMode;
+
+/// Counters over every game played on one `WasmGame`.
+///
+/// # Fields
+///
+/// * `spins` - Normal and rush lotteries drawn; continuation lotteries are not spins
+/// * `wins` - Spins won, by win type
+/// * `rush_entries` - Times rush mode was entered from normal mode
+/// * `longest_rush_chain` - Highest consecutive rush count reached
+/// * `balls_launched` - Balls and rush balls launched
+/// * `balls_awarded` - Balls paid out
+/// * `rush_balls_awarded` - Rush balls paid out
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct GameStats {
+    pub spins: usize,
+    pub wins: WinCounts,
+    pub rush_entries: usize,
+    pub longest_rush_chain: usize,
+    pub balls_launched: usize,
+    pub balls_awarded: usize,
+    pub rush_balls_awarded: usize,
+}
+
+/// Lotteries drawn in one mode and how many of them won.
+///
+/// # Fields
+///
+/// * `attempts` - Lotteries drawn
+/// * `wins` - Lotteries won, fake wins included
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct HitCounter {
+    pub attempts: usize,
+    pub wins: usize,
+}
+
+impl HitCounter {
+    fn record(&mut self, won: bool) {
+        self.attempts += 1;
+        if won {
+            self.wins += 1;
+        }
+    }
+}
+
+/// Per-mode lottery counters, like the data counters (データカウンター)
+/// mounted on real machines.
+///
+/// # Fields
+///
+/// * `normal` - Normal-mode lotteries; jitan lotteries count as normal ones
+/// * `rush` - Rush-mode lotteries
+/// * `rush_continue` - Continuation lotteries drawn after rush wins
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ModeCounters {
+    pub normal: HitCounter,
+    pub rush: HitCounter,
+    pub rush_continue: HitCounter,
+}
+
+impl ModeCounters {
+    /// Counts a normal or rush lottery.
+    pub(crate) fn record_spin(&mut self, mode: SpinMode, won: bool) {
+        match mode {
+            SpinMode::Normal | SpinMode::Jitan => self.normal.record(won),
+            SpinMode::Rush => self.rush.record(won),
+        }
+    }
+
+    /// Counts a continuation lottery.
+    pub(crate) fn record_continuation(&mut self, won: bool) {
+        self.rush_continue.record(won);
+    }
+}
+
+/// Smoothing factor of the return ticker: each spin weighs 5%, so the
+/// figures follow roughly the last 20 spins.
+pub(crate) const DEFAULT_TICKER_SMOOTHING: f64 = 0.05;
+
+/// Exponentially smoothed return figures over the recent spins.
+///
+/// # Fields
+///
+/// * `rtp` - Balls paid out per ball launched, `awarded_per_spin / balls_per_spin`
+/// * `balls_per_spin` - Balls and rush balls launched per spin
+/// * `awarded_per_spin` - Balls paid out per spin
+/// * `samples` - Spins observed; the first spin initializes the figures
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ReturnTicker {
+    pub rtp: f64,
+    pub balls_per_spin: f64,
+    pub awarded_per_spin: f64,
+    pub samples: usize,
+}
+
+impl ReturnTicker {
+    /// Adds a spin with the balls launched since the previous spin and the
+    /// balls it paid out; `smoothing` is the weight of the new spin.
+    pub(crate) fn record(&mut self, launched: usize, awarded: usize, smoothing: f64) {
+        let weight = if self.samples == 0 { 1.0 } else { smoothing };
+
+        self.balls_per_spin += weight * (launched as f64 - self.balls_per_spin);
+        self.awarded_per_spin += weight * (awarded as f64 - self.awarded_per_spin);
+        self.rtp = if self.balls_per_spin > 0.0 {
+            self.awarded_per_spin / self.balls_per_spin
+        } else {
+            0.0
+        };
+        self.samples += 1;
+    }
+}
+
+/// Spins won, by win type.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct WinCounts {
+    pub default: usize,
+    pub fake_win: usize,
+    #[serde(default)]
+    pub premium: usize,
+}
+
+impl GameStats {
+    /// Counts a normal or rush lottery.
+    pub(crate) fn record_spin(&mut self, result: pachislo::lottery::LotteryResult, premium: bool) {
+        use pachislo::lottery::{LotteryResult, Win};
+
+        self.spins += 1;
+        match result {
+            LotteryResult::Win(_) if premium => self.wins.premium += 1,
+            LotteryResult::Win(Win::Default) => self.wins.default += 1,
+            LotteryResult::Win(Win::FakeWin) => self.wins.fake_win += 1,
+            LotteryResult::Lose(_) => {}
+        }
+    }
+
+    /// Combines the counters of two sessions, e.g. one recovered from an
+    /// autosave after a crash and the fresh session that replaced it.
+    ///
+    /// Counts add up; `longest_rush_chain` is the longer of both chains.
+    pub fn merge(&self, other: &GameStats) -> GameStats {
+        GameStats {
+            spins: self.spins + other.spins,
+            wins: WinCounts {
+                default: self.wins.default + other.wins.default,
+                fake_win: self.wins.fake_win + other.wins.fake_win,
+                premium: self.wins.premium + other.wins.premium,
+            },
+            rush_entries: self.rush_entries + other.rush_entries,
+            longest_rush_chain: self.longest_rush_chain.max(other.longest_rush_chain),
+            balls_launched: self.balls_launched + other.balls_launched,
+            balls_awarded: self.balls_awarded + other.balls_awarded,
+            rush_balls_awarded: self.rush_balls_awarded + other.rush_balls_awarded,
+        }
+    }
+
+    /// Counts reaching the `n`-th consecutive rush.
+    pub(crate) fn record_rush(&mut self, n: usize) {
+        if n == 1 {
+            self.rush_entries += 1;
+        }
+        self.longest_rush_chain = self.longest_rush_chain.max(n);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge() {
+        let recovered = GameStats {
+            spins: 10,
+            wins: WinCounts {
+                default: 2,
+                fake_win: 1,
+                premium: 0,
+            },
+            rush_entries: 1,
+            longest_rush_chain: 4,
+            balls_launched: 30,
+            balls_awarded: 45,
+            rush_balls_awarded: 12,
+        };
+        let fresh = GameStats {
+            spins: 5,
+            longest_rush_chain: 2,
+            balls_launched: 8,
+            ..GameStats::default()
+        };
+
+        let merged = recovered.merge(&fresh);
+        assert_eq!(merged.spins, 15);
+        assert_eq!(merged.wins, recovered.wins);
+        assert_eq!(merged.longest_rush_chain, 4);
+        assert_eq!(merged.balls_launched, 38);
+        assert_eq!(merged, fresh.merge(&recovered));
+        assert_eq!(recovered.merge(&GameStats::default()), recovered);
+    }
+
+    #[test]
+    fn test_return_ticker() {
+        let mut ticker = ReturnTicker::default();
+
+        ticker.record(4, 0, 0.5);
+        assert_eq!(ticker.balls_per_spin, 4.0);
+        assert_eq!(ticker.rtp, 0.0);
+
+        ticker.record(2, 15, 0.5);
+        assert_eq!(ticker.balls_per_spin, 3.0);
+        assert_eq!(ticker.awarded_per_spin, 7.5);
+        assert_eq!(ticker.rtp, 2.5);
+        assert_eq!(ticker.samples, 2);
+
+        let mut idle = ReturnTicker::default();
+        idle.record(0, 0, 0.5);
+        assert_eq!(idle.rtp, 0.0);
+    }
+}
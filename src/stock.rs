@@ -0,0 +1,50 @@
+//! # Stock (ストック)
+//!
+//! With a stock configured (see `Config::with_stock`), wins drawn during a
+//! rush are not resolved right away but stored, up to the configured cap.
+//! When the current rush ends, a stocked win is released as the next
+//! jackpot of the chain: it pays out and starts a new rush round as a rush
+//! continuation would. Wins beyond the cap are resolved immediately as
+//! without a stock. Stock is lost when the game ends.
+//!
+//! Event outputs receive a `"stock_gained"` or `"stock_released"` event with
+//! a [`StockEvent`] after the transition of the command that changed the
+//! stock.
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{PachisloError, WasmGame};
+
+/// Payload of the `"stock_gained"` and `"stock_released"` events.
+///
+/// # Fields
+///
+/// * `stock` - Stocked wins after the change
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct StockEvent {
+    pub stock: usize,
+}
+
+#[wasm_bindgen]
+impl WasmGame {
+    /// Returns the number of stocked rush wins, 0 without a stock.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// stockLamps.forEach((lamp, i) => lamp.classList.toggle("lit", i < game.stock()));
+    /// ```
+    #[wasm_bindgen]
+    pub fn stock(&self) -> Result<usize, PachisloError> {
+        let session = self.session()?;
+
+        Ok(session.engine.stock())
+    }
+}
@@ -0,0 +1,138 @@
+//! # Stop Conditions
+//!
+//! "Play until X": with stop conditions configured (see
+//! `Config::with_stop_conditions`), the engine checks them after every
+//! command of a running game and returns `ControlFlow::Break` once one of
+//! them holds. The game itself is left as it is, so the caller decides
+//! whether to finish it; [`StopReason`] tells which condition ended the run.
+//!
+//! Spins and rush entries are counted from the start of the current game.
+//! Batch simulations end a game as soon as a condition holds.
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{PachisloError, WasmGame, alias::Config};
+
+/// Conditions ending a run. Every field is optional in JavaScript, and
+/// `undefined` disables the condition.
+///
+/// # Fields
+///
+/// * `stop_when_balls_below` - Stop once the balls drop below this count
+/// * `stop_after_spins` - Stop after this many lotteries in the game
+/// * `stop_after_rushes` - Stop after this many rush entries in the game
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(default)]
+pub struct StopConditions {
+    pub stop_when_balls_below: Option<usize>,
+    pub stop_after_spins: Option<usize>,
+    pub stop_after_rushes: Option<usize>,
+}
+
+/// The stop condition that ended a run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum StopReason {
+    /// The balls dropped below `stop_when_balls_below`
+    BallsBelow,
+    /// The game reached `stop_after_spins` lotteries
+    Spins,
+    /// The game reached `stop_after_rushes` rush entries
+    Rushes,
+}
+
+impl StopConditions {
+    /// The first condition met by a running game with `balls` balls that has
+    /// drawn `spins` lotteries and entered `rushes` rushes.
+    pub(crate) fn check(&self, balls: usize, spins: usize, rushes: usize) -> Option<StopReason> {
+        let reached = |limit: Option<usize>, count| limit.is_some_and(|limit| count >= limit);
+
+        if self
+            .stop_when_balls_below
+            .is_some_and(|limit| balls < limit)
+        {
+            Some(StopReason::BallsBelow)
+        } else if reached(self.stop_after_spins, spins) {
+            Some(StopReason::Spins)
+        } else if reached(self.stop_after_rushes, rushes) {
+            Some(StopReason::Rushes)
+        } else {
+            None
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Config {
+    /// Returns a copy of the configuration whose runs stop on `conditions`.
+    ///
+    /// After every command of a running game, `run_step_with_command` returns
+    /// `ControlFlow.Break` once a condition holds; see
+    /// `WasmGame.stop_reason()` for which one.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// // Play until 2 rushes or the balls drop below 50
+    /// const untilX = config.with_stop_conditions({ stop_after_rushes: 2, stop_when_balls_below: 50 });
+    /// ```
+    #[wasm_bindgen]
+    pub fn with_stop_conditions(&self, conditions: StopConditions) -> Config {
+        let mut config = self.clone();
+        config.mechanics.stop = conditions;
+
+        config
+    }
+
+    /// The conditions ending a run; all `undefined` by default.
+    #[wasm_bindgen(getter)]
+    pub fn stop_conditions(&self) -> StopConditions {
+        self.mechanics.stop
+    }
+}
+
+#[wasm_bindgen]
+impl WasmGame {
+    /// Returns the stop condition met by the last command, or `undefined`.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// if (game.run_step_with_command("CauseLottery") === ControlFlow.Break) {
+    ///     showResult(game.stop_reason());
+    /// }
+    /// ```
+    #[wasm_bindgen]
+    pub fn stop_reason(&self) -> Result<Option<StopReason>, PachisloError> {
+        let session = self.session()?;
+
+        Ok(session.engine.stop_reason())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        assert_eq!(StopConditions::default().check(0, 1000, 1000), None);
+
+        let conditions = StopConditions {
+            stop_when_balls_below: Some(50),
+            stop_after_spins: Some(100),
+            stop_after_rushes: Some(2),
+        };
+        assert_eq!(conditions.check(50, 99, 1), None);
+        assert_eq!(conditions.check(49, 100, 2), Some(StopReason::BallsBelow));
+        assert_eq!(conditions.check(50, 100, 2), Some(StopReason::Spins));
+        assert_eq!(conditions.check(50, 0, 2), Some(StopReason::Rushes));
+    }
+}
@@ -0,0 +1,240 @@
+//! # Streaks
+//!
+//! The engine tracks runs of won and lost spins, so presentations can key
+//! off them, e.g. hinting at a long losing streak (ハマり). Like the ceiling
+//! counter, streaks carry over from one game to the next like on a real
+//! machine. Every spin counts, whatever its mode; fake wins are wins and
+//! fake loses are loses.
+//!
+//! With thresholds set (see `WasmGame::set_streak_thresholds`), event
+//! outputs receive a `"streak"` event with a [`StreakEvent`] after the
+//! transition of the spin that made a streak reach a threshold. A spin held
+//! by a staged reveal reports its streak once it is revealed.
+
+use std::ops::RangeInclusive;
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{PachisloError, Session, WasmGame, event};
+
+/// Current and longest streaks.
+///
+/// # Fields
+///
+/// * `wins` - Spins won in a row up to the last spin
+/// * `losses` - Spins lost in a row up to the last spin
+/// * `longest_wins` - Most spins won in a row so far
+/// * `longest_losses` - Most spins lost in a row so far
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct Streaks {
+    pub wins: usize,
+    pub losses: usize,
+    pub longest_wins: usize,
+    pub longest_losses: usize,
+}
+
+impl Streaks {
+    /// Counts a spin.
+    pub(crate) fn record(&mut self, won: bool) {
+        if won {
+            self.wins += 1;
+            self.losses = 0;
+            self.longest_wins = self.longest_wins.max(self.wins);
+        } else {
+            self.losses += 1;
+            self.wins = 0;
+            self.longest_losses = self.longest_losses.max(self.losses);
+        }
+    }
+}
+
+/// Whether a streak is one of wins or of losses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum StreakKind {
+    Win,
+    Lose,
+}
+
+/// Payload of the `"streak"` event.
+///
+/// # Fields
+///
+/// * `kind` - Whether the streak is one of wins or of losses
+/// * `length` - The threshold reached, the current length of the streak
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct StreakEvent {
+    pub kind: StreakKind,
+    pub length: usize,
+}
+
+/// Streak thresholds of a game and the streaks already reported.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct StreakAlerts {
+    winning: Vec<usize>,
+    losing: Vec<usize>,
+    reported: Streaks,
+}
+
+impl StreakAlerts {
+    /// The events of the thresholds `streaks` reached since the last call.
+    pub(crate) fn update(&mut self, streaks: Streaks) -> Vec<StreakEvent> {
+        let mut events = reached(
+            &self.winning,
+            self.reported.wins..=streaks.wins,
+            StreakKind::Win,
+        );
+        events.extend(reached(
+            &self.losing,
+            self.reported.losses..=streaks.losses,
+            StreakKind::Lose,
+        ));
+        self.reported = streaks;
+
+        events
+    }
+}
+
+/// The events of `thresholds` a streak passed growing from the start of
+/// `lengths` to its end.
+fn reached(
+    thresholds: &[usize],
+    lengths: RangeInclusive<usize>,
+    kind: StreakKind,
+) -> Vec<StreakEvent> {
+    thresholds
+        .iter()
+        .filter(|&&length| *lengths.start() < length && length <= *lengths.end())
+        .map(|&length| StreakEvent { kind, length })
+        .collect()
+}
+
+#[wasm_bindgen]
+impl WasmGame {
+    /// Returns the current and longest win and lose streaks.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// hamariCounter.textContent = game.streaks().losses;
+    /// ```
+    #[wasm_bindgen]
+    pub fn streaks(&self) -> Result<Streaks, PachisloError> {
+        let session = self.session()?;
+
+        Ok(session.engine.streaks())
+    }
+
+    /// Emits a `"streak"` event whenever a win streak reaches one of
+    /// `winning` or a lose streak one of `losing`; empty lists disable the
+    /// events.
+    ///
+    /// Streaks already longer than a threshold when it is set do not report
+    /// it until they start over.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// game.set_streak_thresholds([3], [500, 1000]);
+    /// events.on("streak", ({ kind, length }) => {
+    ///     if (kind === "Lose") showHamariHint(length);
+    /// });
+    /// ```
+    #[wasm_bindgen]
+    pub fn set_streak_thresholds(
+        &self,
+        winning: Vec<usize>,
+        losing: Vec<usize>,
+    ) -> Result<(), PachisloError> {
+        let mut session = self.session()?;
+
+        let reported = session.engine.streaks();
+        session.streak_alerts = StreakAlerts {
+            winning,
+            losing,
+            reported,
+        };
+
+        Ok(())
+    }
+}
+
+impl Session {
+    /// Emits `"streak"` for every threshold reached since the last call.
+    pub(crate) fn emit_streaks(&mut self) {
+        let streaks = self.engine.streaks();
+        let events = self.streak_alerts.update(streaks);
+
+        let output = self.engine.output_mut();
+        if !output.is_event_output() {
+            return;
+        }
+        for streak in events {
+            output.emit(
+                event::STREAK,
+                serde_wasm_bindgen::to_value(&streak).unwrap(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_streaks() {
+        let mut streaks = Streaks::default();
+        for won in [false, false, true, true, true, false] {
+            streaks.record(won);
+        }
+
+        assert_eq!((streaks.wins, streaks.losses), (0, 1));
+        assert_eq!((streaks.longest_wins, streaks.longest_losses), (3, 2));
+    }
+
+    #[test]
+    fn test_alerts() {
+        let mut alerts = StreakAlerts {
+            winning: vec![2],
+            losing: vec![1, 3],
+            ..StreakAlerts::default()
+        };
+        let mut streaks = Streaks::default();
+
+        streaks.record(false);
+        assert_eq!(
+            alerts.update(streaks),
+            [StreakEvent {
+                kind: StreakKind::Lose,
+                length: 1
+            }]
+        );
+        streaks.record(false);
+        assert!(alerts.update(streaks).is_empty());
+
+        streaks.record(false);
+        assert_eq!(alerts.update(streaks)[0].length, 3);
+
+        streaks.record(true);
+        assert!(alerts.update(streaks).is_empty());
+        streaks.record(true);
+        assert_eq!(alerts.update(streaks)[0].kind, StreakKind::Win);
+
+        // A new lose streak reports its thresholds again
+        streaks.record(false);
+        assert_eq!(alerts.update(streaks)[0].length, 1);
+    }
+}
@@ -0,0 +1,136 @@
+//! # Subscriptions
+//!
+//! Listeners added to a running game with `WasmGame::subscribe`, for code
+//! that is not known when the output is created, like UI components mounting
+//! and unmounting. A subscription follows the contract shared by RxJS and
+//! Svelte stores: the listener receives values one at a time, and the
+//! returned handle's `unsubscribe` removes it.
+//!
+//! ```javascript
+//! const events$ = new Observable((subscriber) => game.subscribe((event) => subscriber.next(event)));
+//! // In a Svelte component, `$game` is the latest event of `game`
+//! ```
+
+use std::{
+    cell::RefCell,
+    rc::{Rc, Weak},
+};
+
+use js_sys::Function;
+use wasm_bindgen::{JsValue, prelude::wasm_bindgen};
+
+use crate::{PachisloError, WasmGame, alias::GameState, callback_failed, event};
+
+/// The listeners of an output, shared with their [`Subscription`]s.
+#[derive(Default)]
+pub(crate) struct Subscribers {
+    next_id: u32,
+    listeners: Vec<(u32, Function)>,
+}
+
+pub(crate) type SharedSubscribers = Rc<RefCell<Subscribers>>;
+
+impl Subscribers {
+    /// Adds a listener and returns the handle removing it.
+    fn add(subscribers: &SharedSubscribers, listener: Function) -> Subscription {
+        let mut inner = subscribers.borrow_mut();
+        let id = inner.next_id;
+        inner.next_id += 1;
+        inner.listeners.push((id, listener));
+
+        Subscription {
+            subscribers: Rc::downgrade(subscribers),
+            id,
+        }
+    }
+
+    /// Returns the listeners, so they can be called while they change.
+    pub(crate) fn listeners(&self) -> Vec<Function> {
+        self.listeners
+            .iter()
+            .map(|(_, listener)| listener.clone())
+            .collect()
+    }
+}
+
+/// A listener added with `WasmGame::subscribe`.
+#[wasm_bindgen]
+pub struct Subscription {
+    subscribers: Weak<RefCell<Subscribers>>,
+    id: u32,
+}
+
+#[wasm_bindgen]
+impl Subscription {
+    /// Removes the listener. It is not called for the rest of an event being
+    /// delivered, and calling `unsubscribe` again does nothing.
+    #[wasm_bindgen]
+    pub fn unsubscribe(&self) {
+        if let Some(subscribers) = self.subscribers.upgrade() {
+            subscribers
+                .borrow_mut()
+                .listeners
+                .retain(|(id, _)| *id != self.id);
+        }
+    }
+
+    /// Returns whether the listener was removed, or its game freed.
+    #[wasm_bindgen(getter)]
+    pub fn closed(&self) -> bool {
+        self.subscribers.upgrade().is_none_or(|subscribers| {
+            !subscribers
+                .borrow()
+                .listeners
+                .iter()
+                .any(|(id, _)| *id == self.id)
+        })
+    }
+}
+
+#[wasm_bindgen]
+impl WasmGame {
+    /// Adds a listener receiving every transition, finished game and lottery
+    /// as `{ type, payload }`, with the event types and payloads of an event
+    /// output (see `JsOutput::from_events`).
+    ///
+    /// Like a Svelte store, the game calls the listener right away with the
+    /// current state as `{ type: "state", payload: GameState }`. Listeners
+    /// get every event, also while the page is in the background and during
+    /// batched steps, whatever the output's handlers, diff or typed events.
+    /// A listener that throws makes the step throw a `CallbackFailed` error
+    /// like an output callback. Forks start without listeners.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired, or a
+    /// `CallbackFailed` error if the listener throws on the current state; it
+    /// is not added in that case.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const subscription = game.subscribe(({ type, payload }) => {
+    ///     if (type === "transition") meter.balls = payload.after.Normal?.balls;
+    /// });
+    /// onDestroy(() => subscription.unsubscribe());
+    /// ```
+    #[wasm_bindgen]
+    pub fn subscribe(&self, listener: Function) -> Result<Subscription, PachisloError> {
+        let (subscription, state) = {
+            let session = self.session()?;
+            let state = GameState::from(*session.engine.state());
+            let subscription =
+                Subscribers::add(session.engine.output().subscribers(), listener.clone());
+            (subscription, state)
+        };
+
+        // Called without the session, so the listener can use the game
+        let current = event::batched(event::STATE, &serde_wasm_bindgen::to_value(&state).unwrap());
+        if let Err(reason) = listener.call1(&JsValue::UNDEFINED, &current) {
+            subscription.unsubscribe();
+            return Err(callback_failed(reason));
+        }
+
+        Ok(subscription)
+    }
+}
@@ -0,0 +1,134 @@
+//! # Session Summary
+//!
+//! Figures of a single game from `StartGame` to its end, for result
+//! screens. The summary of the running game is available at any time with
+//! `WasmGame::session_summary`, and stays available once the game has
+//! ended, until the next game starts.
+//!
+//! Event outputs receive a `"session_summary"` event with the final
+//! [`SessionSummary`] after the transition that ended a game, whether it was
+//! finished or ran out of balls.
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{PachisloError, Session, WasmGame, event};
+
+/// Figures of a single game.
+///
+/// # Fields
+///
+/// * `spins` - Normal and rush lotteries drawn in the game
+/// * `rush_entries` - Times rush mode was entered from normal mode
+/// * `longest_rush_chain` - Highest consecutive rush count reached
+/// * `initial_balls` - Balls the game started with
+/// * `peak_balls` - Most balls held at once
+/// * `final_balls` - Balls held now, or when the game ended; 0 if it ran out
+/// * `net_balls` - `final_balls - initial_balls`
+/// * `finished` - Whether the game has ended
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct SessionSummary {
+    pub spins: usize,
+    pub rush_entries: usize,
+    pub longest_rush_chain: usize,
+    pub initial_balls: usize,
+    pub peak_balls: usize,
+    pub final_balls: usize,
+    pub net_balls: i64,
+    pub finished: bool,
+}
+
+impl SessionSummary {
+    /// The summary of a game starting with `balls` balls.
+    pub(crate) fn new(balls: usize) -> Self {
+        SessionSummary {
+            spins: 0,
+            rush_entries: 0,
+            longest_rush_chain: 0,
+            initial_balls: balls,
+            peak_balls: balls,
+            final_balls: balls,
+            net_balls: 0,
+            finished: false,
+        }
+    }
+
+    /// Records the balls held by the running game.
+    pub(crate) fn observe_balls(&mut self, balls: usize) {
+        self.peak_balls = self.peak_balls.max(balls);
+        self.final_balls = balls;
+        self.net_balls = balls as i64 - self.initial_balls as i64;
+    }
+
+    /// Counts reaching the `n`-th consecutive rush.
+    pub(crate) fn record_rush(&mut self, n: usize) {
+        if n == 1 {
+            self.rush_entries += 1;
+        }
+        self.longest_rush_chain = self.longest_rush_chain.max(n);
+    }
+}
+
+#[wasm_bindgen]
+impl WasmGame {
+    /// Returns the summary of the running game, or of the last game once it
+    /// has ended; `undefined` before the first game starts.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const summary = game.session_summary();
+    /// if (summary?.finished) {
+    ///     showResult(summary.net_balls, summary.longest_rush_chain);
+    /// }
+    /// ```
+    #[wasm_bindgen]
+    pub fn session_summary(&self) -> Result<Option<SessionSummary>, PachisloError> {
+        let session = self.session()?;
+
+        Ok(session.engine.summary())
+    }
+}
+
+impl Session {
+    /// Emits `"session_summary"` with the summary of the game that ended.
+    pub(crate) fn emit_session_summary(&mut self) {
+        let Some(summary) = self.engine.summary() else {
+            return;
+        };
+
+        let output = self.engine.output_mut();
+        if output.is_event_output() {
+            output.emit(
+                event::SESSION_SUMMARY,
+                serde_wasm_bindgen::to_value(&summary).unwrap(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let mut summary = SessionSummary::new(100);
+        summary.observe_balls(250);
+        summary.observe_balls(40);
+        summary.record_rush(1);
+        summary.record_rush(2);
+        summary.record_rush(1);
+
+        assert_eq!(summary.peak_balls, 250);
+        assert_eq!(summary.final_balls, 40);
+        assert_eq!(summary.net_balls, -60);
+        assert_eq!((summary.rush_entries, summary.longest_rush_chain), (2, 2));
+    }
+}
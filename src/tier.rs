@@ -0,0 +1,218 @@
+//! # Rush Tiers
+//!
+//! Machines often have more than one rush, e.g. a "Rush" and a rarer
+//! "Super Rush" with better odds and bigger payouts. With tiers configured
+//! (see `Config::with_rush_tiers`), every rush entry draws a tier by weight,
+//! and the rush plays with that tier's probabilities, continuation lottery
+//! and ball increments until it ends. Continuations and stocked wins stay in
+//! the tier of the rush they belong to.
+//!
+//! Event outputs receive a `"rush_tier_entered"` event with a
+//! [`RushTierEvent`] after the transition that entered a tiered rush.
+
+use pachislo::game::GameState;
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    PachisloError, Session, WasmGame,
+    alias::{Config, SlotProbability},
+    engine, event,
+};
+
+/// A rush variant for `Config::with_rush_tiers`.
+#[derive(Debug, Clone)]
+#[wasm_bindgen]
+pub struct RushTier {
+    /// Name shown to players, e.g. "Super Rush"
+    name: String,
+    /// Relative chance of entering this tier; weights need not sum to 1
+    pub weight: f64,
+    /// Probabilities during the rush
+    pub rush: SlotProbability,
+    /// Probabilities of the continuation lottery, scaled by the continuation function
+    pub rush_continue: SlotProbability,
+    /// Number of balls gained per jackpot
+    pub incremental_balls: usize,
+    /// Number of rush balls gained per jackpot
+    pub incremental_rush: usize,
+}
+
+#[wasm_bindgen]
+impl RushTier {
+    /// Creates a rush tier.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name shown to players
+    /// * `weight` - Relative chance of entering this tier
+    /// * `rush` - Probabilities during the rush
+    /// * `rush_continue` - Probabilities of the continuation lottery
+    /// * `incremental_balls` - Balls gained per jackpot
+    /// * `incremental_rush` - Rush balls gained per jackpot
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        name: String,
+        weight: f64,
+        rush: SlotProbability,
+        rush_continue: SlotProbability,
+        incremental_balls: usize,
+        incremental_rush: usize,
+    ) -> Self {
+        RushTier {
+            name,
+            weight,
+            rush,
+            rush_continue,
+            incremental_balls,
+            incremental_rush,
+        }
+    }
+
+    /// Name shown to players.
+    #[wasm_bindgen(getter)]
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+impl From<RushTier> for engine::RushTier {
+    fn from(tier: RushTier) -> Self {
+        engine::RushTier {
+            name: tier.name,
+            weight: tier.weight,
+            rush: tier.rush.into(),
+            rush_continue: tier.rush_continue.into(),
+            incremental_balls: tier.incremental_balls,
+            incremental_rush: tier.incremental_rush,
+        }
+    }
+}
+
+/// Payload of the `"rush_tier_entered"` event and result of
+/// [`WasmGame::rush_tier`].
+///
+/// # Fields
+///
+/// * `index` - Position of the tier in the list given to `Config::with_rush_tiers`
+/// * `name` - Name of the tier
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct RushTierEvent {
+    pub index: usize,
+    pub name: String,
+}
+
+#[wasm_bindgen]
+impl Config {
+    /// Returns a copy of the configuration with several rush tiers.
+    ///
+    /// Every rush entry draws one of `tiers`, each with a chance of its
+    /// weight over the sum of all weights. The tier's probabilities,
+    /// continuation probabilities and ball increments replace
+    /// `probability.rush`, `probability.rush_continue`, `incremental_balls`
+    /// and `incremental_rush` until the rush ends; the continuation function
+    /// and the rush model apply to every tier. Normal-mode wins outside a
+    /// rush still pay the configured `incremental_balls`.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if `tiers` is empty or a weight is not a positive
+    /// number. The probabilities are checked by `Config::validate`.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// // One rush in five is a Super Rush
+    /// const tiered = config.with_rush_tiers([
+    ///     new RushTier("Rush", 4, new SlotProbability(0.1, 0.0, 0.0), new SlotProbability(0.7, 0.0, 0.0), 15, 5),
+    ///     new RushTier("Super Rush", 1, new SlotProbability(0.2, 0.0, 0.0), new SlotProbability(0.9, 0.0, 0.0), 30, 10),
+    /// ]);
+    /// ```
+    #[wasm_bindgen]
+    pub fn with_rush_tiers(&self, tiers: Vec<RushTier>) -> Result<Config, PachisloError> {
+        if tiers.is_empty() {
+            return Err(PachisloError::InvalidConfig {
+                message: "rush tiers need at least 1 tier".to_string(),
+            });
+        }
+        if let Some(tier) = tiers
+            .iter()
+            .find(|tier| !(tier.weight.is_finite() && tier.weight > 0.0))
+        {
+            return Err(PachisloError::InvalidConfig {
+                message: format!(
+                    "rush tier \"{}\" has weight {}; weights must be positive numbers",
+                    tier.name, tier.weight
+                ),
+            });
+        }
+
+        let mut config = self.clone();
+        config.mechanics.tiers = tiers.into_iter().map(Into::into).collect();
+
+        Ok(config)
+    }
+
+    /// Names of the rush tiers, empty for a single flat rush.
+    #[wasm_bindgen(getter)]
+    pub fn rush_tier_names(&self) -> Vec<String> {
+        self.mechanics
+            .tiers
+            .iter()
+            .map(|tier| tier.name.clone())
+            .collect()
+    }
+}
+
+impl Session {
+    /// Emits `"rush_tier_entered"` if the engine entered a tiered rush from
+    /// `before`.
+    pub(crate) fn emit_rush_tier(&mut self, before: Option<GameState>) {
+        if let Some(GameState::Rush { n: 1.., .. }) = before {
+            return;
+        }
+        let Some(tier) = self.rush_tier() else {
+            return;
+        };
+
+        let output = self.engine.output_mut();
+        if output.is_event_output() {
+            output.emit(
+                event::RUSH_TIER_ENTERED,
+                serde_wasm_bindgen::to_value(&tier).unwrap(),
+            );
+        }
+    }
+
+    fn rush_tier(&self) -> Option<RushTierEvent> {
+        self.engine.rush_tier().map(|(index, tier)| RushTierEvent {
+            index,
+            name: tier.name.clone(),
+        })
+    }
+}
+
+#[wasm_bindgen]
+impl WasmGame {
+    /// Returns the tier of the running rush, `undefined` outside a rush or
+    /// without rush tiers.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const tier = game.rush_tier();
+    /// banner.textContent = tier ? tier.name : "";
+    /// ```
+    #[wasm_bindgen]
+    pub fn rush_tier(&self) -> Result<Option<RushTierEvent>, PachisloError> {
+        let session = self.session()?;
+
+        Ok(session.rush_tier())
+    }
+}
@@ -0,0 +1,79 @@
+//! # Time Travel
+//!
+//! While history is recorded (see `WasmGame::set_history_enabled`), the game
+//! after every entry is kept along with it, so timeline scrubbers in dev
+//! tools can jump to any recorded step with `WasmGame::seek`.
+//!
+//! Seeking restores what the engine keeps, the same as undo: balls, mode,
+//! statistics, counters and the random number generators. The history
+//! itself is left as it is, and commands sent after a seek are appended to
+//! it, so the entries after the target stay reachable.
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    PachisloError, Session, WasmGame, alias::Transition, history::SpinRecord, undo::SavedGame,
+};
+
+impl Session {
+    /// Records an executed command in the history, with the game after it.
+    pub(crate) fn record_history(
+        &mut self,
+        time: f64,
+        command: &str,
+        transition: Transition,
+        spin: Option<SpinRecord>,
+    ) {
+        if !self.history.is_enabled() {
+            return;
+        }
+
+        self.history.record(time, command, transition, spin);
+        self.history.save_state(SavedGame::capture(&self.engine));
+    }
+}
+
+#[wasm_bindgen]
+impl WasmGame {
+    /// Restores the game to the state right after the history entry at
+    /// `step_index`, counted from 0 like `get_history()`.
+    ///
+    /// No output callbacks are called; render the restored state with
+    /// [`WasmGame::get_state`]. A fake outcome held by a staged reveal at
+    /// that step waits for [`WasmGame::ack_reveal`]. The undo stack is
+    /// cleared.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if no entry was recorded at `step_index`, or if the
+    /// game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// game.set_history_enabled(true);
+    /// // ... play ...
+    /// scrubber.max = game.get_history().length - 1;
+    /// scrubber.oninput = () => {
+    ///     game.seek(scrubber.valueAsNumber);
+    ///     render(game.get_state());
+    /// };
+    /// ```
+    #[wasm_bindgen]
+    pub fn seek(&self, step_index: usize) -> Result<(), PachisloError> {
+        let mut session = self.session()?;
+
+        let Some(saved) = session.history.state(step_index).cloned() else {
+            return Err(PachisloError::InvalidArguments {
+                message: format!(
+                    "step {step_index} is not recorded; the history has {} entries",
+                    session.history.entries().len()
+                ),
+            });
+        };
+        session.load(saved);
+        session.undo.clear();
+
+        Ok(())
+    }
+}
@@ -0,0 +1,345 @@
+//! # Tournaments
+//!
+//! A tournament plays several machines against each other for party-game
+//! frontends. Every entrant plays automatically, all of them in lockstep
+//! rounds of a fixed number of launches, and after every round the entrants
+//! are ranked by the balls they hold.
+//!
+//! An entrant whose game runs out of balls is out and ranks by 0 balls. The
+//! tournament ends after the configured number of rounds, or earlier once
+//! every entrant is out.
+
+use js_sys::Function;
+use pachislo::game::GameState;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    InnerGame, JsOutput, PachisloError, alias::Config, callback_failed, check_probability,
+    engine::Command, new_inner_game, rng::GameRng,
+};
+
+/// Options of a [`Tournament`]. Every field is optional in JavaScript.
+///
+/// # Fields
+///
+/// * `launches_per_round` - Balls every entrant launches per round
+/// * `max_rounds` - Rounds after which the tournament ends
+/// * `start_hole_probability` - Probability that a launched ball triggers a lottery
+/// * `seed` - Seed for reproducible tournaments; random when omitted
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(default)]
+pub struct TournamentOptions {
+    pub launches_per_round: usize,
+    pub max_rounds: usize,
+    pub start_hole_probability: f64,
+    pub seed: Option<u64>,
+}
+
+impl Default for TournamentOptions {
+    fn default() -> Self {
+        TournamentOptions {
+            launches_per_round: 10,
+            max_rounds: 100,
+            start_hole_probability: pachislo::START_HOLE_PROBABILITY_EXAMPLE,
+            seed: None,
+        }
+    }
+}
+
+/// The place of one entrant in [`Standings`].
+///
+/// # Fields
+///
+/// * `rank` - 1 for the leader; entrants with equal balls share a rank
+/// * `name` - Name of the entrant
+/// * `balls` - Balls the entrant holds, 0 once out
+/// * `playing` - Whether the entrant still has balls to play
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct Standing {
+    pub rank: usize,
+    pub name: String,
+    pub balls: usize,
+    pub playing: bool,
+}
+
+/// The ranking of a tournament after a round.
+///
+/// # Fields
+///
+/// * `round` - Rounds played so far
+/// * `finished` - Whether the tournament is over
+/// * `ranking` - The entrants by descending balls; ties keep the entry order
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct Standings {
+    pub round: usize,
+    pub finished: bool,
+    pub ranking: Vec<Standing>,
+}
+
+/// A machine taking part in a tournament.
+struct Entrant {
+    name: String,
+    game: InnerGame,
+}
+
+/// Several machines playing automatically in lockstep rounds.
+///
+/// Every entrant reports to a clone of the tournament's output, labelled
+/// with the entrant's name, so the machines can be animated from a single
+/// event handler.
+///
+/// # Example
+///
+/// ```javascript
+/// const tournament = new Tournament(output, { max_rounds: 20 }, (standings) => {
+///     scoreboard.render(standings.ranking);
+/// });
+/// tournament.add_entrant("Alice", createConfig());
+/// tournament.add_entrant("Bob", createConfig());
+/// const { ranking } = tournament.play_to_end();
+/// ```
+#[wasm_bindgen]
+pub struct Tournament {
+    output: JsOutput,
+    options: TournamentOptions,
+    /// Decides which launches hit the start hole
+    rng: GameRng,
+    entrants: Vec<Entrant>,
+    round: usize,
+    on_standings: Function,
+}
+
+#[wasm_bindgen]
+impl Tournament {
+    /// Creates a tournament without entrants.
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - Output handler; each entrant gets a clone labelled with its name
+    /// * `options` - Optional [`TournamentOptions`]
+    /// * `on_standings` - Called with the [`Standings`] after every round
+    ///
+    /// # Errors
+    ///
+    /// Throws an `InvalidArguments` error if `start_hole_probability` is not
+    /// from 0 to 1.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        output: JsOutput,
+        options: Option<TournamentOptions>,
+        on_standings: Function,
+    ) -> Result<Tournament, PachisloError> {
+        let options = options.unwrap_or_default();
+        check_probability("start_hole_probability", options.start_hole_probability)?;
+
+        Ok(Tournament {
+            output,
+            options,
+            rng: options
+                .seed
+                .map_or_else(GameRng::default, GameRng::from_seed),
+            entrants: Vec::new(),
+            round: 0,
+            on_standings,
+        })
+    }
+
+    /// Adds an entrant whose game starts right away.
+    ///
+    /// # Errors
+    ///
+    /// Throws a `DuplicateGame` error if the name is taken, an
+    /// `InvalidArguments` error once the first round was played, or an
+    /// `Error` describing every problem if the configuration is invalid.
+    #[wasm_bindgen]
+    pub fn add_entrant(&mut self, name: String, config: Config) -> Result<(), PachisloError> {
+        if self.round > 0 {
+            return Err(PachisloError::InvalidArguments {
+                message: "entrants can only be added before the first round".to_string(),
+            });
+        }
+        if self.entrants.iter().any(|entrant| entrant.name == name) {
+            return Err(PachisloError::DuplicateGame { id: name });
+        }
+        config.validate()?;
+
+        let mut output = self.output.clone();
+        output.set_label(Some(name.clone()));
+        let mut game = new_inner_game(output, config, self.rng.split());
+        game.start();
+
+        self.entrants.push(Entrant { name, game });
+
+        Ok(())
+    }
+
+    /// Returns the number of entrants.
+    #[wasm_bindgen]
+    pub fn entrant_count(&self) -> usize {
+        self.entrants.len()
+    }
+
+    /// Returns whether the tournament is over: all rounds are played, or
+    /// every entrant is out.
+    #[wasm_bindgen]
+    pub fn is_finished(&self) -> bool {
+        self.round == self.options.max_rounds
+            || self
+                .entrants
+                .iter()
+                .all(|entrant| !is_playing(&entrant.game))
+    }
+
+    /// Returns the current standings without playing.
+    #[wasm_bindgen]
+    pub fn standings(&self) -> Standings {
+        Standings {
+            round: self.round,
+            finished: self.is_finished(),
+            ranking: rank(
+                self.entrants
+                    .iter()
+                    .map(|entrant| Standing {
+                        rank: 0,
+                        name: entrant.name.clone(),
+                        balls: entrant.game.balls(),
+                        playing: is_playing(&entrant.game),
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Plays one round: every entrant still playing launches
+    /// `launches_per_round` balls, or until it runs out.
+    ///
+    /// # Returns
+    ///
+    /// The standings after the round, also passed to `on_standings`.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `InvalidArguments` error if there are no entrants or the
+    /// tournament is over, or a `CallbackFailed` error if a callback threw;
+    /// the round is played in that case.
+    #[wasm_bindgen]
+    pub fn play_round(&mut self) -> Result<Standings, PachisloError> {
+        if self.entrants.is_empty() {
+            return Err(PachisloError::InvalidArguments {
+                message: "the tournament has no entrants".to_string(),
+            });
+        }
+        if self.is_finished() {
+            return Err(PachisloError::InvalidArguments {
+                message: "the tournament is over".to_string(),
+            });
+        }
+
+        for entrant in &mut self.entrants {
+            for _ in 0..self.options.launches_per_round {
+                if !is_playing(&entrant.game) {
+                    break;
+                }
+                let _ = entrant.game.run_step_with_command(Command::LaunchBall);
+                if is_playing(&entrant.game)
+                    && self.rng.random_bool(self.options.start_hole_probability)
+                {
+                    let _ = entrant.game.run_step_with_command(Command::CauseLottery);
+                }
+            }
+        }
+        self.round += 1;
+
+        let failures: Vec<_> = self
+            .entrants
+            .iter_mut()
+            .map(|entrant| entrant.game.output_mut().take_failure())
+            .collect();
+        let standings = self.standings();
+        self.on_standings
+            .call1(
+                &self.output.context,
+                &serde_wasm_bindgen::to_value(&standings).unwrap(),
+            )
+            .map_err(callback_failed)?;
+        failures.into_iter().collect::<Result<(), _>>()?;
+
+        Ok(standings)
+    }
+
+    /// Plays rounds until the tournament is over.
+    ///
+    /// # Returns
+    ///
+    /// The final standings.
+    ///
+    /// # Errors
+    ///
+    /// Throws like [`Tournament::play_round`], stopping at the first round
+    /// that fails.
+    #[wasm_bindgen]
+    pub fn play_to_end(&mut self) -> Result<Standings, PachisloError> {
+        let mut standings = self.play_round()?;
+        while !standings.finished {
+            standings = self.play_round()?;
+        }
+
+        Ok(standings)
+    }
+}
+
+fn is_playing(game: &InnerGame) -> bool {
+    !matches!(game.state(), GameState::Uninitialized)
+}
+
+/// Sorts entrants by descending balls and numbers their ranks; entrants
+/// with equal balls share a rank.
+fn rank(mut ranking: Vec<Standing>) -> Vec<Standing> {
+    ranking.sort_by_key(|standing| std::cmp::Reverse(standing.balls));
+
+    for i in 0..ranking.len() {
+        ranking[i].rank = if i > 0 && ranking[i - 1].balls == ranking[i].balls {
+            ranking[i - 1].rank
+        } else {
+            i + 1
+        };
+    }
+
+    ranking
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entrant(name: &str, balls: usize) -> Standing {
+        Standing {
+            rank: 0,
+            name: name.to_string(),
+            balls,
+            playing: balls > 0,
+        }
+    }
+
+    #[test]
+    fn test_ranking() {
+        let ranking = rank(vec![
+            entrant("A", 80),
+            entrant("B", 120),
+            entrant("C", 0),
+            entrant("D", 80),
+        ]);
+
+        let places: Vec<_> = ranking
+            .iter()
+            .map(|standing| (standing.rank, standing.name.as_str()))
+            .collect();
+        assert_eq!(places, [(1, "B"), (2, "A"), (2, "D"), (4, "C")]);
+    }
+}
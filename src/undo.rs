@@ -0,0 +1,145 @@
+//! # Undo
+//!
+//! An opt-in stack of checkpoints taken before every command (see
+//! `WasmGame::set_undo_depth`), so puzzle-like frontends and debugging
+//! sessions can step backwards without replaying from scratch.
+//!
+//! Undoing restores everything the command changed in the game: balls, mode,
+//! statistics, counters and the random number generators, so sending the
+//! same command again draws the same outcome. Records kept next to the game,
+//! i.e. the history, ghost, balls series and command rule usage, keep the
+//! undone command.
+
+use std::collections::VecDeque;
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{InnerGame, PachisloError, Session, WasmGame, engine::Checkpoint, rng::GameRng};
+
+/// The engine and slot generator of a game at some point.
+#[derive(Clone, Debug)]
+pub(crate) struct SavedGame {
+    engine: Checkpoint,
+    slot_rng: GameRng,
+}
+
+impl SavedGame {
+    pub(crate) fn capture(engine: &InnerGame) -> Self {
+        SavedGame {
+            engine: engine.checkpoint(),
+            slot_rng: engine.output().slot_rng().clone(),
+        }
+    }
+}
+
+impl Session {
+    /// Puts the game back to `saved` without calling any output callback,
+    /// discarding a pending staged reveal.
+    pub(crate) fn load(&mut self, saved: SavedGame) {
+        self.engine.rewind(saved.engine);
+        self.engine.output_mut().set_slot_rng(saved.slot_rng);
+        if let Some(reveal) = &mut self.reveal {
+            reveal.cancel();
+        }
+    }
+}
+
+/// The checkpoints of the latest commands, at most `depth` of them.
+#[derive(Debug, Default)]
+pub(crate) struct UndoStack {
+    /// Commands that can be undone, 0 while disabled
+    depth: usize,
+    entries: VecDeque<SavedGame>,
+}
+
+impl UndoStack {
+    /// Takes a checkpoint of `engine` before a command, if enabled.
+    pub(crate) fn record(&mut self, engine: &InnerGame) {
+        if self.depth == 0 {
+            return;
+        }
+
+        if self.entries.len() == self.depth {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(SavedGame::capture(engine));
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[wasm_bindgen]
+impl WasmGame {
+    /// Keeps checkpoints of the latest `depth` commands for
+    /// [`WasmGame::undo`]; 0, the default, disables undo.
+    ///
+    /// A smaller depth drops the oldest checkpoints.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// game.set_undo_depth(50);
+    /// ```
+    #[wasm_bindgen]
+    pub fn set_undo_depth(&self, depth: usize) -> Result<(), PachisloError> {
+        let mut session = self.session()?;
+
+        let undo = &mut session.undo;
+        undo.depth = depth;
+        let excess = undo.entries.len().saturating_sub(depth);
+        undo.entries.drain(..excess);
+
+        Ok(())
+    }
+
+    /// Reverts the last command that has not been undone yet.
+    ///
+    /// No output callbacks are called; render the restored state with
+    /// [`WasmGame::get_state`]. A fake outcome awaiting its reveal is
+    /// discarded along with its command.
+    ///
+    /// # Returns
+    ///
+    /// `false` if there was nothing to undo.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// undoButton.onclick = () => {
+    ///     if (game.undo()) render(game.get_state());
+    /// };
+    /// ```
+    #[wasm_bindgen]
+    pub fn undo(&self) -> Result<bool, PachisloError> {
+        let mut session = self.session()?;
+
+        let Some(saved) = session.undo.entries.pop_back() else {
+            return Ok(false);
+        };
+        session.load(saved);
+
+        Ok(true)
+    }
+
+    /// Returns how many commands can be undone.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if the game mutex cannot be acquired.
+    #[wasm_bindgen]
+    pub fn undo_available(&self) -> Result<usize, PachisloError> {
+        let session = self.session()?;
+
+        Ok(session.undo.entries.len())
+    }
+}
@@ -0,0 +1,231 @@
+//! # Configuration Validation
+//!
+//! Rejects configurations that cannot be played. Invalid values would
+//! otherwise only surface as panics deep inside the engine, e.g. a negative
+//! probability in the middle of a lottery. Unlike linting, every finding here
+//! is an error.
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::PachisloError;
+use crate::alias::{BallsConfig, Config, SlotProbability};
+
+/// Number of rush depths at which the continuation function is checked.
+const CONTINUATION_SAMPLES: usize = 10;
+
+#[wasm_bindgen]
+impl Config {
+    /// Checks that the configuration can be played.
+    ///
+    /// `WasmGame`'s constructors call this, so invalid configurations are
+    /// reported there instead of panicking later.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` listing every problem, one per line:
+    /// - `init_balls` or `launch_cost` is 0
+    /// - `start_rate` is outside 0.0 to 1.0 or not a number
+    /// - a probability, including the jitan and rush tier ones, is outside 0.0
+    ///   to 1.0 or not a number
+    /// - `rush_continue_fn` throws, or returns a non-numeric value or one
+    ///   outside 0.0 to 1.0 for a rush count from 1 to 10
+    /// - a parameter of a built-in continuation curve could make it leave 0.0
+    ///   to 1.0 for any rush count
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// try {
+    ///     config.validate();
+    /// } catch (error) {
+    ///     showConfigErrors(error.message.split("\n").slice(1));
+    /// }
+    /// ```
+    #[wasm_bindgen]
+    pub fn validate(&self) -> Result<(), PachisloError> {
+        let probability = &self.probability;
+
+        let mut slots = vec![
+            ("normal", probability.normal),
+            ("rush", probability.rush),
+            ("rush_continue", probability.rush_continue),
+        ];
+        if let Some(jitan) = self.mechanics.jitan {
+            slots.push(("jitan", jitan.probability.into()));
+        }
+        let tier_slots: Vec<_> = self
+            .mechanics
+            .tiers
+            .iter()
+            .enumerate()
+            .flat_map(|(i, tier)| {
+                [
+                    (format!("rush_tiers[{i}].rush"), tier.rush),
+                    (format!("rush_tiers[{i}].rush_continue"), tier.rush_continue),
+                ]
+            })
+            .collect();
+        for (mode, slot) in &tier_slots {
+            slots.push((mode, (*slot).into()));
+        }
+
+        let errors = match probability.curve() {
+            // Checked by its parameters, which covers every rush count
+            Some(curve) => {
+                let mut errors = validate(&self.balls, &slots, |_| Some(1.0));
+                errors.extend(curve.problems());
+                errors
+            }
+            None => validate(&self.balls, &slots, |n| {
+                probability.try_rush_continue_multiplier(n)
+            }),
+        };
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(PachisloError::InvalidConfig {
+                message: format!("invalid configuration:\n{}", errors.join("\n")),
+            })
+        }
+    }
+}
+
+/// Core of [`Config::validate`], independent of the JavaScript callback.
+///
+/// `rush_continue_fn` returns `None` if the continuation function threw or
+/// did not return a number.
+pub(crate) fn validate<F>(
+    balls: &BallsConfig,
+    slots: &[(&str, SlotProbability)],
+    mut rush_continue_fn: F,
+) -> Vec<String>
+where
+    F: FnMut(usize) -> Option<f64>,
+{
+    let mut errors = Vec::new();
+
+    if balls.init_balls == 0 {
+        errors.push("balls.init_balls is 0; a game needs at least 1 initial ball".to_string());
+    }
+    if balls.launch_cost == 0 {
+        errors.push("balls.launch_cost is 0; a launch consumes at least 1 ball".to_string());
+    }
+    if !(0.0..=1.0).contains(&balls.start_rate) {
+        errors.push(format!(
+            "balls.start_rate is {}; it must be between 0.0 and 1.0",
+            balls.start_rate
+        ));
+    }
+
+    for &(mode, slot) in slots {
+        for (name, value) in [
+            ("win", slot.win),
+            ("fake_win", slot.fake_win),
+            ("fake_lose", slot.fake_lose),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                errors.push(format!(
+                    "probability.{mode}.{name} is {value}; probabilities must be between 0.0 and 1.0"
+                ));
+            }
+        }
+    }
+
+    // Only the first failing depth is reported; later ones usually fail the same way
+    let continuation = (1..=CONTINUATION_SAMPLES).find_map(|n| match rush_continue_fn(n) {
+        None => Some(format!(
+            "rush_continue_fn({n}) threw or did not return a number"
+        )),
+        Some(multiplier) if !(0.0..=1.0).contains(&multiplier) => Some(format!(
+            "rush_continue_fn({n}) returned {multiplier}; it must return a number between 0.0 and 1.0"
+        )),
+        Some(_) => None,
+    });
+    errors.extend(continuation);
+
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slot(win: f64) -> SlotProbability {
+        SlotProbability::new(win, 0.1, 0.1)
+    }
+
+    fn slots(normal: f64, rush: f64, rush_continue: f64) -> [(&'static str, SlotProbability); 3] {
+        [
+            ("normal", slot(normal)),
+            ("rush", slot(rush)),
+            ("rush_continue", slot(rush_continue)),
+        ]
+    }
+
+    #[test]
+    fn test_valid_config() {
+        let errors = validate(&BallsConfig::new(100, 10, 5), &slots(0.1, 0.5, 0.8), |n| {
+            Some(1.0 / n as f64)
+        });
+
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn test_reports_every_problem() {
+        let errors = validate(
+            &BallsConfig::new(0, 10, 5),
+            &slots(1.5, f64::NAN, 0.8),
+            |_| Some(1.0),
+        );
+
+        assert_eq!(errors.len(), 3);
+        assert!(errors[0].contains("balls.init_balls"));
+        assert!(errors[1].contains("probability.normal.win is 1.5"));
+        assert!(errors[2].contains("probability.rush.win is NaN"));
+    }
+
+    #[test]
+    fn test_continuation_function() {
+        let negative = validate(&BallsConfig::new(100, 10, 5), &slots(0.1, 0.5, 0.8), |n| {
+            Some(1.0 - 0.25 * n as f64)
+        });
+        assert_eq!(negative.len(), 1);
+        assert!(negative[0].contains("rush_continue_fn(5) returned -0.25"));
+
+        let above_one = validate(&BallsConfig::new(100, 10, 5), &slots(0.1, 0.5, 0.8), |n| {
+            Some(0.5 * n as f64)
+        });
+        assert_eq!(above_one.len(), 1);
+        assert!(above_one[0].contains("rush_continue_fn(3) returned 1.5"));
+
+        let throwing = validate(&BallsConfig::new(100, 10, 5), &slots(0.1, 0.5, 0.8), |_| {
+            None
+        });
+        assert_eq!(throwing.len(), 1);
+        assert!(throwing[0].contains("rush_continue_fn(1) threw"));
+    }
+
+    #[test]
+    fn test_curve_is_checked_beyond_the_samples() {
+        use crate::{alias::Probability, curve::ContinuationCurve};
+
+        // Fine for the first 10 rush counts, negative from the 52nd on
+        let curve = ContinuationCurve::LinearDecay {
+            start: 1.0,
+            step: 0.02,
+            floor: -1.0,
+        };
+        let config = Config::new(
+            BallsConfig::new(100, 10, 5),
+            Probability::with_curve(slot(0.1), slot(0.5), slot(0.8), curve),
+        );
+
+        let Err(PachisloError::InvalidConfig { message }) = config.validate() else {
+            panic!("a curve with a negative floor must be invalid");
+        };
+        assert!(message.contains("probability.curve.floor is -1"));
+        assert!(!message.contains("rush_continue_fn"));
+    }
+}
@@ -0,0 +1,138 @@
+//! # Typed Event Views
+//!
+//! An alternative payload for transition events: instead of a serialized
+//! [`Transition`] object, a [`TransitionView`] handle whose fields are read
+//! through getters. Delivering a handle allocates no JS objects per event, so
+//! hot loops that only look at a few fields, like the ball count, avoid the
+//! cost of serializing both states.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    alias::{GameState, Transition},
+    diff::{self, StateMode},
+};
+
+/// A transition event, read field by field.
+///
+/// Delivered instead of a `Transition` object after
+/// `JsOutput::set_typed_events(true)`. Counts of a mode without them, like the
+/// rush balls in normal mode, are 0; `to_json` returns the full `Transition`.
+///
+/// # Example
+///
+/// ```javascript
+/// output.set_typed_events(true);
+/// // in the event callback
+/// if (type === "transition") {
+///     meter.balls = payload.balls;
+///     payload.free();
+/// }
+/// ```
+#[wasm_bindgen]
+pub struct TransitionView {
+    transition: Transition,
+}
+
+impl From<Transition> for TransitionView {
+    fn from(transition: Transition) -> Self {
+        TransitionView { transition }
+    }
+}
+
+#[wasm_bindgen]
+impl TransitionView {
+    /// The mode after the transition.
+    #[wasm_bindgen(getter)]
+    pub fn mode(&self) -> StateMode {
+        diff::parts(self.transition.after).0
+    }
+
+    /// The ball count after the transition.
+    #[wasm_bindgen(getter)]
+    pub fn balls(&self) -> usize {
+        diff::parts(self.transition.after).1
+    }
+
+    /// The rush ball count after the transition.
+    #[wasm_bindgen(getter)]
+    pub fn rush_balls(&self) -> usize {
+        diff::parts(self.transition.after).2
+    }
+
+    /// The jitan spins left after the transition.
+    #[wasm_bindgen(getter)]
+    pub fn spins(&self) -> usize {
+        diff::parts(self.transition.after).3
+    }
+
+    /// The consecutive rush count after the transition, `undefined` outside
+    /// rush mode.
+    #[wasm_bindgen(getter)]
+    pub fn n(&self) -> Option<usize> {
+        diff::parts(self.transition.after).4
+    }
+
+    /// The mode before the transition, `undefined` for the initial state.
+    #[wasm_bindgen(getter)]
+    pub fn before_mode(&self) -> Option<StateMode> {
+        self.transition.before.map(|state| diff::parts(state).0)
+    }
+
+    /// The ball count before the transition, 0 for the initial state.
+    #[wasm_bindgen(getter)]
+    pub fn before_balls(&self) -> usize {
+        diff::parts(self.transition.before.unwrap_or(GameState::Uninitialized)).1
+    }
+
+    /// Whether the mode changed.
+    #[wasm_bindgen(getter)]
+    pub fn mode_changed(&self) -> bool {
+        self.before_mode() != Some(self.mode())
+    }
+
+    /// The full `Transition`, serialized like the default payload.
+    #[wasm_bindgen]
+    pub fn to_json(&self) -> Transition {
+        self.transition
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_view_fields() {
+        let view = TransitionView::from(Transition {
+            before: Some(GameState::Normal { balls: 99 }),
+            after: GameState::Rush {
+                balls: 114,
+                rush_balls: 3,
+                n: 1,
+            },
+        });
+
+        assert_eq!(view.mode(), StateMode::Rush);
+        assert_eq!(view.balls(), 114);
+        assert_eq!(view.rush_balls(), 3);
+        assert_eq!(view.spins(), 0);
+        assert_eq!(view.n(), Some(1));
+        assert_eq!(view.before_mode(), Some(StateMode::Normal));
+        assert_eq!(view.before_balls(), 99);
+        assert!(view.mode_changed());
+    }
+
+    #[test]
+    fn test_initial_view() {
+        let view = TransitionView::from(Transition {
+            before: None,
+            after: GameState::Normal { balls: 100 },
+        });
+
+        assert_eq!(view.before_mode(), None);
+        assert_eq!(view.before_balls(), 0);
+        assert_eq!(view.n(), None);
+        assert!(view.mode_changed());
+    }
+}
@@ -0,0 +1,178 @@
+//! # Randomized Start
+//!
+//! Machines in a showroom or demo kiosk should not all look freshly switched
+//! on. A configuration with a randomized start plays a random number of spins
+//! headlessly when a game is created, so every machine begins with its own
+//! plausible mid-day statistics: spins, wins, rush entries and payouts.
+//!
+//! The warm-up only seeds the statistics; the game itself still starts
+//! uninitialized and waits for `"StartGame"` as usual.
+
+use pachislo::game::GameState;
+use rand::Rng;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    PachisloError,
+    alias::Config,
+    engine::{Engine, Mechanics, NullOutput},
+    rng::GameRng,
+    stats::GameStats,
+};
+
+/// How many spins a game is warmed up with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct RandomizedStart {
+    pub(crate) min_spins: usize,
+    pub(crate) max_spins: usize,
+}
+
+#[wasm_bindgen]
+impl Config {
+    /// Returns a copy of the configuration whose games start with the
+    /// statistics of `min_spins` to `max_spins` spins already played.
+    ///
+    /// The spin count and the warm-up spins are drawn from the game's random
+    /// number generator, so seeded games stay reproducible. Only games created
+    /// with the configuration are warmed up, not ones it is applied to with
+    /// `update_config`.
+    ///
+    /// # Errors
+    ///
+    /// Throws an `Error` if `min_spins` is greater than `max_spins`.
+    ///
+    /// # Example
+    ///
+    /// ```javascript
+    /// const kiosk = config.with_randomized_start(200, 2000);
+    /// const machines = seeds.map((seed) => WasmGame.new_with_seed(new JsInput(), output(), kiosk, seed));
+    /// ```
+    #[wasm_bindgen]
+    pub fn with_randomized_start(
+        &self,
+        min_spins: usize,
+        max_spins: usize,
+    ) -> Result<Config, PachisloError> {
+        if min_spins > max_spins {
+            return Err(PachisloError::InvalidConfig {
+                message: "min_spins must not be greater than max_spins".to_string(),
+            });
+        }
+
+        Ok(Config {
+            randomized_start: Some(RandomizedStart {
+                min_spins,
+                max_spins,
+            }),
+            ..self.clone()
+        })
+    }
+}
+
+/// Plays a random number of spins within `start`'s range headlessly and
+/// returns the statistics.
+///
+/// Balls are launched like in a batch simulation, and a player who runs out
+/// of balls is replaced by a new one, as on a machine played all day.
+pub(crate) fn warm_up<F>(
+    config: pachislo::config::Config<F>,
+    mechanics: Mechanics,
+    start: RandomizedStart,
+    mut rng: GameRng,
+) -> GameStats
+where
+    F: FnMut(usize) -> f64,
+{
+    let spins = rng.random_range(start.min_spins..=start.max_spins);
+    let mut hole_rng = rng.split();
+    let mut engine = Engine::new(config, NullOutput, rng).unwrap();
+    engine.set_mechanics(mechanics);
+
+    while engine.stats().spins < spins {
+        if let GameState::Uninitialized = engine.state() {
+            engine.start();
+        }
+
+        engine.launch_ball();
+        if hole_rng.random_bool(pachislo::START_HOLE_PROBABILITY_EXAMPLE) {
+            engine.cause_lottery();
+        }
+    }
+
+    *engine.stats()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pachislo::config::{BallsConfig, Probability, SlotProbability};
+
+    fn config() -> pachislo::config::Config<fn(usize) -> f64> {
+        let slot = |win| SlotProbability {
+            win,
+            fake_win: 0.0,
+            fake_lose: 0.0,
+        };
+
+        pachislo::config::Config {
+            balls: BallsConfig {
+                init_balls: 10,
+                incremental_balls: 5,
+                incremental_rush: 3,
+            },
+            probability: Probability {
+                normal: slot(0.05),
+                rush: slot(0.5),
+                rush_continue: slot(0.5),
+                rush_continue_fn: |_| 1.0,
+            },
+        }
+    }
+
+    fn start(min_spins: usize, max_spins: usize) -> RandomizedStart {
+        RandomizedStart {
+            min_spins,
+            max_spins,
+        }
+    }
+
+    #[test]
+    fn test_warm_up_plays_spins_in_range() {
+        for seed in 0..20 {
+            let stats = warm_up(
+                config(),
+                Mechanics::default(),
+                start(50, 80),
+                GameRng::from_seed(seed),
+            );
+
+            assert!((50..=80).contains(&stats.spins), "{}", stats.spins);
+            assert!(stats.balls_launched >= stats.spins);
+        }
+
+        assert_eq!(
+            warm_up(
+                config(),
+                Mechanics::default(),
+                start(0, 0),
+                GameRng::from_seed(1)
+            ),
+            GameStats::default()
+        );
+    }
+
+    #[test]
+    fn test_warm_up_is_reproducible() {
+        let stats = |seed| {
+            warm_up(
+                config(),
+                Mechanics::default(),
+                start(10, 500),
+                GameRng::from_seed(seed),
+            )
+        };
+
+        assert_eq!(stats(3), stats(3));
+        assert_ne!(stats(3), stats(4));
+    }
+}
@@ -66,11 +66,11 @@ fn create_test_output() -> JsOutput {
 
     JsOutput::new(
         context,
-        default_fn,
-        finish_fn,
-        lottery_normal_fn,
-        lottery_rush_fn,
-        lottery_rush_continue_fn,
+        Some(default_fn),
+        Some(finish_fn),
+        Some(lottery_normal_fn),
+        Some(lottery_rush_fn),
+        Some(lottery_rush_continue_fn),
     )
 }
 
@@ -79,7 +79,7 @@ fn create_test_game() -> WasmGame {
     let input = JsInput::new();
     let output = create_test_output();
     let config = create_test_config();
-    WasmGame::new(input, output, config)
+    WasmGame::new(input, output, config).unwrap()
 }
 
 #[wasm_bindgen_test]
@@ -150,7 +150,7 @@ fn test_wasm_game_creation() {
 fn test_game_start_command() {
     let game = create_test_game();
 
-    let result = game.run_step_with_command("StartGame".to_string());
+    let result = game.run_step_with_command("StartGame".to_string()).unwrap();
 
     // The result should be either Continue or Break
     match result {
@@ -166,7 +166,7 @@ fn test_basic_game_commands() {
     let commands = vec!["StartGame", "LaunchBall", "CauseLottery"];
 
     for command in commands {
-        let result = game.run_step_with_command(command.to_string());
+        let result = game.run_step_with_command(command.to_string()).unwrap();
 
         // Each command should execute without panicking
         match result {
@@ -185,8 +185,10 @@ fn test_game_finish_commands() {
     let game = create_test_game();
 
     // Start and finish the game
-    game.run_step_with_command("StartGame".to_string());
-    let result1 = game.run_step_with_command("FinishGame".to_string());
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    let result1 = game
+        .run_step_with_command("FinishGame".to_string())
+        .unwrap();
 
     match result1 {
         ControlFlow::Continue | ControlFlow::Break => assert!(true),
@@ -194,14 +196,1961 @@ fn test_game_finish_commands() {
 
     // Test alternative finish command
     let game2 = create_test_game();
-    game2.run_step_with_command("StartGame".to_string());
-    let result2 = game2.run_step_with_command("Finish".to_string());
+    game2
+        .run_step_with_command("StartGame".to_string())
+        .unwrap();
+    let result2 = game2.run_step_with_command("Finish".to_string()).unwrap();
 
     match result2 {
         ControlFlow::Continue | ControlFlow::Break => assert!(true),
     }
 }
 
+#[wasm_bindgen_test]
+fn test_invalid_commands_return_errors() {
+    let game = create_test_game();
+
+    assert!(
+        game.run_step_with_command("InvalidCommand".to_string())
+            .is_err()
+    );
+    assert!(
+        game.run_step_with_command("LaunchBall".to_string())
+            .is_err()
+    );
+
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    assert!(game.run_step_with_command("StartGame".to_string()).is_err());
+
+    // The game is still usable after an error
+    game.run_step_with_command("LaunchBall".to_string())
+        .unwrap();
+}
+
+#[wasm_bindgen_test]
+fn test_get_state() {
+    let game = create_test_game();
+
+    assert!(matches!(
+        game.get_state().unwrap(),
+        GameState::Uninitialized
+    ));
+
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    assert!(matches!(
+        game.get_state().unwrap(),
+        GameState::Normal { balls: 100 }
+    ));
+
+    game.run_step_with_command("LaunchBall".to_string())
+        .unwrap();
+    assert!(matches!(
+        game.get_state().unwrap(),
+        GameState::Normal { balls: 99 }
+    ));
+}
+
+#[wasm_bindgen_test]
+fn test_ball_counts() {
+    let game = create_test_game();
+    assert_eq!(game.balls().unwrap(), 0);
+
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    game.run_step_with_command("LaunchBall".to_string())
+        .unwrap();
+    assert_eq!(game.balls().unwrap(), 99);
+    assert_eq!(game.rush_balls().unwrap(), 0);
+}
+
+#[wasm_bindgen_test]
+fn test_last_lottery() {
+    let game = create_test_game();
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    assert!(game.last_lottery().unwrap().is_none());
+
+    game.run_step_with_command("LaunchBall".to_string())
+        .unwrap();
+    game.run_step_with_command("CauseLottery".to_string())
+        .unwrap();
+
+    let last = game.last_lottery().unwrap().unwrap();
+    assert_eq!(last.slot.0.len(), 3);
+}
+
+#[wasm_bindgen_test]
+fn test_named_symbols() {
+    let mut output = create_test_output();
+    output.set_slot_layout(3, vec![1, 2]).unwrap();
+    output
+        .set_symbol_names(Some(vec!["🍒".to_string(), "7".to_string()]))
+        .unwrap();
+    assert!(
+        output
+            .set_symbol_names(Some(vec!["7".to_string()]))
+            .is_err()
+    );
+
+    let game = WasmGame::new(JsInput::new(), output, create_test_config()).unwrap();
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    game.run_step_with_command("LaunchBall".to_string())
+        .unwrap();
+    game.run_step_with_command("CauseLottery".to_string())
+        .unwrap();
+
+    let last = game.last_lottery().unwrap().unwrap();
+    assert!(matches!(
+        last.slot.0,
+        pachislo_wasm::slot::Reels::Names(ref names)
+            if names.iter().all(|name| name == "🍒" || name == "7")
+    ));
+}
+
+#[wasm_bindgen_test]
+fn test_seeded_games_are_reproducible() {
+    let play = |game: &WasmGame| {
+        game.run_step_with_command("StartGame".to_string()).unwrap();
+        for _ in 0..50 {
+            game.run_step_with_command("LaunchBall".to_string())
+                .unwrap();
+            game.run_step_with_command("CauseLottery".to_string())
+                .unwrap();
+        }
+        format!("{:?}", game.get_state().unwrap())
+    };
+
+    let seeded = |seed| {
+        WasmGame::new_with_seed(
+            JsInput::new(),
+            create_test_output(),
+            create_test_config(),
+            seed,
+        )
+        .unwrap()
+    };
+
+    assert_eq!(play(&seeded(42)), play(&seeded(42)));
+
+    let reseeded = create_test_game();
+    reseeded.set_seed(42).unwrap();
+    assert_eq!(play(&reseeded), play(&seeded(42)));
+}
+
+#[wasm_bindgen_test]
+fn test_preview_command() {
+    let game = WasmGame::new_with_seed(
+        JsInput::new(),
+        create_test_output(),
+        create_test_config(),
+        2,
+    )
+    .unwrap();
+    assert!(game.preview_command("LaunchBall".to_string()).is_err());
+
+    let preview = game.preview_command("StartGame".to_string()).unwrap();
+    assert!(matches!(preview.after, GameState::Normal { balls: 100 }));
+    assert!(matches!(
+        game.get_state().unwrap(),
+        GameState::Uninitialized
+    ));
+
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    for _ in 0..5 {
+        let preview = game.preview_command("CauseLottery".to_string()).unwrap();
+        game.run_step_with_command("CauseLottery".to_string())
+            .unwrap();
+        assert_eq!(
+            format!("{:?}", preview.after),
+            format!("{:?}", game.get_state().unwrap())
+        );
+    }
+}
+
+#[wasm_bindgen_test]
+fn test_undo() {
+    let game = WasmGame::new_with_seed(
+        JsInput::new(),
+        create_test_output(),
+        create_test_config(),
+        4,
+    )
+    .unwrap();
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    assert!(!game.undo().unwrap());
+
+    game.set_undo_depth(2).unwrap();
+    for _ in 0..3 {
+        game.run_step_with_command("CauseLottery".to_string())
+            .unwrap();
+    }
+    let after = format!("{:?}", game.get_state().unwrap());
+    assert_eq!(game.undo_available().unwrap(), 2);
+
+    assert!(game.undo().unwrap());
+    assert!(game.undo().unwrap());
+    assert!(!game.undo().unwrap());
+    assert_eq!(game.stats().unwrap().spins, 1);
+
+    // The generators are rewound too, so the same commands draw alike
+    for _ in 0..2 {
+        game.run_step_with_command("CauseLottery".to_string())
+            .unwrap();
+    }
+    assert_eq!(format!("{:?}", game.get_state().unwrap()), after);
+
+    game.set_undo_depth(0).unwrap();
+    assert_eq!(game.undo_available().unwrap(), 0);
+}
+
+#[wasm_bindgen_test]
+fn test_seek() {
+    let game = WasmGame::new_with_seed(
+        JsInput::new(),
+        create_test_output(),
+        create_test_config(),
+        5,
+    )
+    .unwrap();
+    assert!(game.seek(0).is_err());
+
+    game.set_history_enabled(true).unwrap();
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    for _ in 0..5 {
+        game.run_step_with_command("CauseLottery".to_string())
+            .unwrap();
+    }
+    let history = game.get_history().unwrap();
+    assert_eq!(history.len(), 6);
+    assert!(game.seek(6).is_err());
+
+    for step in [2, 0, 5, 3] {
+        game.seek(step).unwrap();
+        assert_eq!(
+            format!("{:?}", game.get_state().unwrap()),
+            format!("{:?}", history[step].transition.after)
+        );
+    }
+    assert_eq!(game.stats().unwrap().spins, 3);
+    assert_eq!(game.get_history().unwrap().len(), 6);
+}
+
+#[wasm_bindgen_test]
+fn test_error_kinds() {
+    let game = create_test_game();
+    assert!(matches!(
+        game.run_step_with_command("LaunchBall".to_string()),
+        Err(PachisloError::GameNotStarted { command }) if command == "LaunchBall"
+    ));
+    assert!(matches!(
+        game.run_step_with_command("Launch".to_string()),
+        Err(PachisloError::UnknownCommand { .. })
+    ));
+    assert!(matches!(
+        create_test_config().with_ceiling(0),
+        Err(PachisloError::InvalidConfig { .. })
+    ));
+    assert!(matches!(
+        WasmGame::replay(
+            JsInput::new(),
+            create_test_output(),
+            create_test_config(),
+            1,
+            vec!["StartGame".to_string(), "StartGame".to_string()],
+        ),
+        Err(PachisloError::ReplayFailed { index: 1, .. })
+    ));
+}
+
+#[wasm_bindgen_test]
+fn test_outcome_script() {
+    use pachislo_wasm::alias::{Lose, LotteryResult, Win};
+
+    let game = create_test_game();
+    game.set_outcome_script(vec![
+        LotteryResult::Lose(Lose::Default),
+        LotteryResult::Lose(Lose::FakeLose),
+        LotteryResult::Win(Win::Default),
+    ])
+    .unwrap();
+    assert_eq!(game.remaining_outcome_script().unwrap(), 3);
+
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    for _ in 0..3 {
+        game.run_step_with_command("CauseLottery".to_string())
+            .unwrap();
+    }
+
+    assert_eq!(game.remaining_outcome_script().unwrap(), 0);
+    assert_eq!(game.stats().unwrap().rush_entries, 1);
+}
+
+#[wasm_bindgen_test]
+fn test_rng_state() {
+    let play = |game: &WasmGame| {
+        game.run_step_with_command("StartGame".to_string()).unwrap();
+        for _ in 0..10 {
+            game.run_step_with_command("CauseLottery".to_string())
+                .unwrap();
+        }
+        format!("{:?}", game.get_state().unwrap())
+    };
+
+    let seeded = WasmGame::new_with_seed(
+        JsInput::new(),
+        create_test_output(),
+        create_test_config(),
+        5,
+    )
+    .unwrap();
+    let state = seeded.rng_state().unwrap();
+
+    let resumed = create_test_game();
+    assert!(resumed.rng_state().is_err());
+    resumed.set_rng_state(state.clone()).unwrap();
+    assert_eq!(resumed.rng_state().unwrap(), state);
+    assert_eq!(play(&resumed), play(&seeded));
+
+    let mut broken = state;
+    broken.lottery = "broken".to_string();
+    assert!(resumed.set_rng_state(broken).is_err());
+}
+
+#[wasm_bindgen_test]
+fn test_replay() {
+    let game = WasmGame::new_with_seed(
+        JsInput::new(),
+        create_test_output(),
+        create_test_config(),
+        7,
+    )
+    .unwrap();
+    game.set_history_enabled(true).unwrap();
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    for _ in 0..30 {
+        game.run_step_with_command("LaunchBall".to_string())
+            .unwrap();
+        game.run_step_with_command("CauseLottery".to_string())
+            .unwrap();
+    }
+
+    let commands = game
+        .get_history()
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.command)
+        .collect();
+    let replay = WasmGame::replay(
+        JsInput::new(),
+        create_test_output(),
+        create_test_config(),
+        7,
+        commands,
+    )
+    .unwrap();
+
+    assert_eq!(
+        format!("{:?}", replay.get_state().unwrap()),
+        format!("{:?}", game.get_state().unwrap())
+    );
+    assert_eq!(replay.stats().unwrap(), game.stats().unwrap());
+
+    let invalid = vec!["LaunchBall".to_string()];
+    assert!(
+        WasmGame::replay(
+            JsInput::new(),
+            create_test_output(),
+            create_test_config(),
+            7,
+            invalid
+        )
+        .is_err()
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_event_output_receives_every_event() {
+    use std::{cell::RefCell, rc::Rc};
+
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let recorded = events.clone();
+    let closure = Closure::wrap(Box::new(move |event_type: JsValue, _: JsValue| {
+        recorded.borrow_mut().push(event_type.as_string().unwrap());
+    }) as Box<dyn FnMut(JsValue, JsValue)>);
+    let emit = closure.as_ref().unchecked_ref::<Function>().clone();
+    closure.forget();
+
+    let output = JsOutput::from_events(pachislo_wasm::event::JsEventOutput::new(
+        JsValue::NULL,
+        emit,
+    ));
+    let game = WasmGame::new(JsInput::new(), output, create_test_config()).unwrap();
+
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    game.run_step_with_command("CauseLottery".to_string())
+        .unwrap();
+    game.run_step_with_command("FinishGame".to_string())
+        .unwrap();
+
+    assert_eq!(
+        *events.borrow(),
+        [
+            "transition",
+            "lottery_normal",
+            "transition",
+            "finish_game",
+            "transition"
+        ]
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_diff_events() {
+    use std::{cell::RefCell, rc::Rc};
+
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let recorded = events.clone();
+    let closure = Closure::wrap(Box::new(move |event_type: JsValue, payload: JsValue| {
+        recorded
+            .borrow_mut()
+            .push((event_type.as_string().unwrap(), payload));
+    }) as Box<dyn FnMut(JsValue, JsValue)>);
+    let emit = closure.as_ref().unchecked_ref::<Function>().clone();
+    closure.forget();
+
+    let mut output = JsOutput::from_events(pachislo_wasm::event::JsEventOutput::new(
+        JsValue::NULL,
+        emit,
+    ));
+    output.set_diff_events(true);
+    let game = WasmGame::new(JsInput::new(), output, create_test_config()).unwrap();
+
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    game.run_step_with_command("LaunchBall".to_string())
+        .unwrap();
+
+    let events = events.borrow();
+    assert!(
+        events
+            .iter()
+            .all(|(event_type, _)| event_type == "state_diff")
+    );
+    let launch: pachislo_wasm::diff::StateDiff =
+        serde_wasm_bindgen::from_value(events.last().unwrap().1.clone()).unwrap();
+    assert_eq!(launch.balls, Some(-1));
+    assert_eq!(launch.mode, None);
+}
+
+#[wasm_bindgen_test]
+fn test_tournament() {
+    use pachislo_wasm::tournament::{Tournament, TournamentOptions};
+    use std::{cell::Cell, rc::Rc};
+
+    let rounds = Rc::new(Cell::new(0));
+    let counted = rounds.clone();
+    let on_standings = Closure::wrap(Box::new(move |_: JsValue| {
+        counted.set(counted.get() + 1);
+    }) as Box<dyn FnMut(JsValue)>);
+    let callback = on_standings.as_ref().unchecked_ref::<Function>().clone();
+    on_standings.forget();
+
+    let options = TournamentOptions {
+        max_rounds: 3,
+        seed: Some(5),
+        ..TournamentOptions::default()
+    };
+    let mut tournament =
+        Tournament::new(create_test_output(), Some(options), callback.clone()).unwrap();
+    tournament
+        .add_entrant("Alice".to_string(), create_test_config())
+        .unwrap();
+    tournament
+        .add_entrant("Bob".to_string(), create_test_config())
+        .unwrap();
+    assert!(matches!(
+        tournament.add_entrant("Bob".to_string(), create_test_config()),
+        Err(PachisloError::DuplicateGame { .. })
+    ));
+
+    let standings = tournament.play_to_end().unwrap();
+    assert_eq!(rounds.get(), 3);
+    assert_eq!(standings.round, 3);
+    assert!(standings.finished);
+    assert_eq!(standings.ranking.len(), 2);
+    assert_eq!(standings.ranking[0].rank, 1);
+    assert!(standings.ranking[0].balls >= standings.ranking[1].balls);
+    assert!(tournament.play_round().is_err());
+
+    for start_hole_probability in [1.5, f64::NAN] {
+        let options = TournamentOptions {
+            start_hole_probability,
+            ..TournamentOptions::default()
+        };
+        assert!(matches!(
+            Tournament::new(create_test_output(), Some(options), callback.clone()),
+            Err(PachisloError::InvalidArguments { .. })
+        ));
+    }
+}
+
+#[wasm_bindgen_test]
+fn test_fork() {
+    let game = WasmGame::new_with_seed(
+        JsInput::new(),
+        create_test_output(),
+        create_test_config(),
+        7,
+    )
+    .unwrap();
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    game.run_step_with_command("LaunchBall".to_string())
+        .unwrap();
+
+    let fork = game.fork(None, None).unwrap();
+    for _ in 0..20 {
+        game.run_step_with_command("LaunchBall".to_string())
+            .unwrap();
+        game.run_step_with_command("CauseLottery".to_string())
+            .unwrap();
+    }
+    assert_eq!(fork.balls().unwrap(), 99);
+
+    for _ in 0..20 {
+        fork.run_step_with_command("LaunchBall".to_string())
+            .unwrap();
+        fork.run_step_with_command("CauseLottery".to_string())
+            .unwrap();
+    }
+    assert_eq!(fork.balls().unwrap(), game.balls().unwrap());
+    assert_eq!(fork.stats().unwrap(), game.stats().unwrap());
+}
+
+#[wasm_bindgen_test]
+fn test_game_manager() {
+    use pachislo_wasm::manager::GameManager;
+
+    let mut manager = GameManager::new(create_test_output());
+    manager
+        .create("left".to_string(), create_test_config())
+        .unwrap();
+    manager
+        .create("right".to_string(), create_test_config())
+        .unwrap();
+    assert_eq!(
+        manager.create("left".to_string(), create_test_config()),
+        Err(PachisloError::DuplicateGame {
+            id: "left".to_string()
+        })
+    );
+
+    for id in manager.ids() {
+        manager.step(&id, "StartGame".to_string()).unwrap();
+        manager.step(&id, "LaunchBall".to_string()).unwrap();
+    }
+    assert_eq!(manager.total_stats().unwrap().balls_launched, 2);
+    assert_eq!(manager.total_balls().unwrap(), 2 * 99);
+
+    assert!(manager.remove("left"));
+    assert!(!manager.remove("left"));
+    assert_eq!(manager.ids(), vec!["right".to_string()]);
+    assert!(matches!(
+        manager.step("left", "LaunchBall".to_string()),
+        Err(PachisloError::NoSuchGame { .. })
+    ));
+}
+
+#[wasm_bindgen_test]
+fn test_subscribe() {
+    use std::{cell::RefCell, rc::Rc};
+
+    let types = Rc::new(RefCell::new(Vec::new()));
+    let recorded = types.clone();
+    let closure = Closure::wrap(Box::new(move |event: JsValue| {
+        let event_type = js_sys::Reflect::get(&event, &"type".into()).unwrap();
+        recorded.borrow_mut().push(event_type.as_string().unwrap());
+    }) as Box<dyn FnMut(JsValue)>);
+    let listener = closure.as_ref().unchecked_ref::<Function>().clone();
+    closure.forget();
+
+    let game = WasmGame::new(JsInput::new(), create_test_output(), create_test_config()).unwrap();
+    let subscription = game.subscribe(listener).unwrap();
+    assert_eq!(*types.borrow(), ["state"]);
+
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    game.run_step_with_command_batched("CauseLottery".to_string())
+        .unwrap();
+    assert_eq!(types.borrow()[1], "transition");
+    assert!(types.borrow().contains(&"lottery_normal".to_string()));
+
+    let fork = game.fork(None, None).unwrap();
+    let count = types.borrow().len();
+    fork.run_step_with_command("LaunchBall".to_string())
+        .unwrap();
+    assert_eq!(types.borrow().len(), count);
+
+    assert!(!subscription.closed());
+    subscription.unsubscribe();
+    subscription.unsubscribe();
+    assert!(subscription.closed());
+    game.run_step_with_command("LaunchBall".to_string())
+        .unwrap();
+    assert_eq!(types.borrow().len(), count);
+}
+
+#[wasm_bindgen_test]
+fn test_remote_game_adapter() {
+    use pachislo_wasm::remote::RemoteGameAdapter;
+
+    let game = WasmGame::new(JsInput::new(), create_test_output(), create_test_config()).unwrap();
+    let mut adapter = RemoteGameAdapter::new(game);
+
+    let messages = adapter.handle_message(r#"{"type":"Command","seq":1,"command":"StartGame"}"#);
+    let last = js_sys::JSON::parse(messages.last().unwrap()).unwrap();
+    let field = |message: &JsValue, key: &str| js_sys::Reflect::get(message, &key.into()).unwrap();
+    assert_eq!(field(&last, "type"), "Ack");
+    assert_eq!(field(&last, "reply_to"), 1);
+    assert_eq!(adapter.next_seq() as usize, messages.len());
+
+    // A command sent again is acknowledged without running twice
+    let again = adapter.handle_message(r#"{"type":"Command","seq":1,"command":"StartGame"}"#);
+    assert_eq!(again.len(), 1);
+    assert_eq!(adapter.last_command_seq(), Some(1));
+
+    let rejected = adapter.handle_message(r#"{"type":"Command","seq":2,"command":"Jump"}"#);
+    let error = js_sys::JSON::parse(&rejected[0]).unwrap();
+    assert_eq!(field(&error, "type"), "Error");
+    assert_eq!(field(&field(&error, "error"), "kind"), "UnknownCommand");
+
+    let unreadable = js_sys::JSON::parse(&adapter.handle_message("not json")[0]).unwrap();
+    assert!(field(&unreadable, "reply_to").is_undefined());
+
+    let sync =
+        js_sys::JSON::parse(&adapter.handle_message(r#"{"type":"Sync","seq":3}"#)[0]).unwrap();
+    assert_eq!(field(&sync, "type"), "State");
+    assert_eq!(field(&sync, "seq"), adapter.next_seq() - 1);
+}
+
+#[wasm_bindgen_test]
+fn test_handle_rpc() {
+    use pachislo_wasm::manager::GameManager;
+
+    let mut manager = GameManager::new(create_test_output());
+    let mut call = |request: &str| {
+        let response = manager.handle_rpc(request).unwrap();
+        js_sys::JSON::parse(&response).unwrap()
+    };
+    let field = |value: &JsValue, key: &str| js_sys::Reflect::get(value, &key.into()).unwrap();
+
+    let slot = r#"{"win":0.1,"fake_win":0.1,"fake_lose":0.1}"#;
+    let created = call(&format!(
+        r#"{{"jsonrpc":"2.0","id":1,"method":"newGame","params":{{"name":"left",
+            "balls":{{"init_balls":100,"incremental_balls":10,"incremental_rush":50}},
+            "normal":{slot},"rush":{slot},"rush_continue":{slot}}}}}"#
+    ));
+    assert_eq!(field(&created, "id"), 1);
+    assert_eq!(field(&created, "result"), "left");
+
+    call(
+        r#"{"jsonrpc":"2.0","id":2,"method":"step","params":{"id":"left","command":"StartGame"}}"#,
+    );
+    let snapshot = field(
+        &call(r#"{"jsonrpc":"2.0","id":3,"method":"snapshot","params":{"id":"left"}}"#),
+        "result",
+    );
+    let launched = call(
+        r#"{"jsonrpc":"2.0","id":4,"method":"step","params":{"id":"left","command":"LaunchBall"}}"#,
+    );
+    assert_eq!(
+        field(&field(&field(&launched, "result"), "Normal"), "balls"),
+        99
+    );
+
+    let restore = format!(
+        r#"{{"jsonrpc":"2.0","id":5,"method":"restore","params":{{"id":"left","snapshot":{}}}}}"#,
+        String::from(js_sys::JSON::stringify(&snapshot).unwrap())
+    );
+    assert!(field(&call(&restore), "result").is_null());
+    let state = call(r#"{"jsonrpc":"2.0","id":"s","method":"getState","params":{"id":"left"}}"#);
+    assert_eq!(
+        field(&field(&field(&state, "result"), "Normal"), "balls"),
+        100
+    );
+
+    let error = field(&call(r#"{"jsonrpc":"2.0","id":6,"method":"fly"}"#), "error");
+    assert_eq!(field(&error, "code"), -32601);
+    let error = field(
+        &call(r#"{"jsonrpc":"2.0","id":7,"method":"getState","params":{"id":"right"}}"#),
+        "error",
+    );
+    assert_eq!(field(&field(&error, "data"), "kind"), "NoSuchGame");
+    let unparsed = call("{");
+    assert!(field(&unparsed, "id").is_null());
+    assert_eq!(field(&field(&unparsed, "error"), "code"), -32700);
+
+    assert_eq!(
+        manager.handle_rpc(r#"{"jsonrpc":"2.0","method":"getState","params":{"id":"left"}}"#),
+        None
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_slot_production_off() {
+    let mut output = create_test_output();
+    output.set_slot_production(false);
+    let game = WasmGame::new(JsInput::new(), output, create_test_config()).unwrap();
+
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    game.run_step_with_command("CauseLottery".to_string())
+        .unwrap();
+
+    let slot = game.last_lottery().unwrap().unwrap().slot;
+    assert!(slot.0.is_empty());
+    assert!(slot.1.is_none());
+    assert_eq!(game.slot_generation().unwrap(), 0);
+}
+
+#[wasm_bindgen_test]
+fn test_slot_view() {
+    let game = WasmGame::new(JsInput::new(), create_test_output(), create_test_config()).unwrap();
+    let view = game.slot_view().unwrap();
+    assert_eq!(view.to_vec(), vec![0; 6]);
+    assert_eq!(game.slot_generation().unwrap(), 0);
+
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    game.run_step_with_command("CauseLottery".to_string())
+        .unwrap();
+
+    assert_eq!(game.slot_generation().unwrap(), 1);
+    let slot = game.last_lottery().unwrap().unwrap().slot;
+    let pachislo_wasm::slot::Reels::Symbols(shown) = slot.0 else {
+        panic!("symbols are not named");
+    };
+    assert_eq!(view.to_vec()[..3], shown[..]);
+}
+
+#[wasm_bindgen_test]
+fn test_batched_step() {
+    use std::{cell::Cell, rc::Rc};
+
+    let calls = Rc::new(Cell::new(0));
+    let counted = calls.clone();
+    let closure = Closure::wrap(Box::new(move |_: JsValue, _: JsValue| {
+        counted.set(counted.get() + 1);
+    }) as Box<dyn FnMut(JsValue, JsValue)>);
+    let emit = closure.as_ref().unchecked_ref::<Function>().clone();
+    closure.forget();
+
+    let output = JsOutput::from_events(pachislo_wasm::event::JsEventOutput::new(
+        JsValue::NULL,
+        emit,
+    ));
+    let game = WasmGame::new(JsInput::new(), output, create_test_config()).unwrap();
+
+    let events = game
+        .run_step_with_command_batched("StartGame".to_string())
+        .unwrap();
+    assert_eq!(calls.get(), 0);
+    assert_eq!(events.length(), 1);
+    let event_type = js_sys::Reflect::get(&events.get(0), &"type".into()).unwrap();
+    assert_eq!(event_type.as_string().unwrap(), "transition");
+
+    game.run_step_with_command("LaunchBall".to_string())
+        .unwrap();
+    assert_eq!(calls.get(), 1);
+}
+
+#[wasm_bindgen_test]
+fn test_typed_events() {
+    use std::{cell::RefCell, rc::Rc};
+
+    let balls = Rc::new(RefCell::new(Vec::new()));
+    let recorded = balls.clone();
+    let closure = Closure::wrap(Box::new(move |event_type: JsValue, payload: JsValue| {
+        if event_type.as_string().as_deref() == Some("transition") {
+            let balls = js_sys::Reflect::get(&payload, &"balls".into()).unwrap();
+            recorded.borrow_mut().push(balls.as_f64().unwrap());
+        }
+    }) as Box<dyn FnMut(JsValue, JsValue)>);
+    let emit = closure.as_ref().unchecked_ref::<Function>().clone();
+    closure.forget();
+
+    let mut output = JsOutput::from_events(pachislo_wasm::event::JsEventOutput::new(
+        JsValue::NULL,
+        emit,
+    ));
+    output.set_typed_events(true);
+    let game = WasmGame::new(JsInput::new(), output, create_test_config()).unwrap();
+
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    game.run_step_with_command("LaunchBall".to_string())
+        .unwrap();
+
+    let balls = balls.borrow();
+    assert_eq!(balls.len(), 2);
+    assert_eq!(balls[1], balls[0] - 1.0);
+}
+
+#[wasm_bindgen_test]
+fn test_invalid_rush_continue_returns_are_reported() {
+    use std::{cell::RefCell, rc::Rc};
+
+    let reports = Rc::new(RefCell::new(Vec::new()));
+    let recorded = reports.clone();
+    let handler = Closure::wrap(Box::new(move |report: JsValue| {
+        let report: pachislo_wasm::guard::InvalidReturn =
+            serde_wasm_bindgen::from_value(report).unwrap();
+        recorded.borrow_mut().push(report);
+    }) as Box<dyn FnMut(JsValue)>);
+    pachislo_wasm::guard::set_invalid_return_handler(Some(
+        handler.as_ref().unchecked_ref::<Function>().clone(),
+    ));
+    handler.forget();
+
+    let nan = Closure::wrap(
+        Box::new(|_: JsValue| JsValue::from(f64::NAN)) as Box<dyn FnMut(JsValue) -> JsValue>
+    );
+    let probability = Probability::new(
+        SlotProbability::new(1.0, 0.0, 0.0),
+        SlotProbability::new(1.0, 0.0, 0.0),
+        SlotProbability::new(1.0, 0.0, 0.0),
+        nan.as_ref().unchecked_ref::<Function>().clone(),
+    );
+    nan.forget();
+    let config = Config::new(BallsConfig::new(100, 15, 50), probability);
+
+    let game = WasmGame::new(JsInput::new(), create_test_output(), config).unwrap();
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    game.run_step_with_command("CauseLottery".to_string())
+        .unwrap();
+    game.run_step_with_command("CauseLottery".to_string())
+        .unwrap();
+
+    let reports = reports.borrow();
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].callback, "rush_continue_fn");
+    assert_eq!(reports[0].returned, "NaN");
+    assert_eq!(reports[0].used, 0.0);
+    pachislo_wasm::guard::set_invalid_return_handler(None);
+}
+
+#[wasm_bindgen_test]
+fn test_st_rush() {
+    let config = create_test_config();
+    assert!(config.with_st_rush(0).is_err());
+    assert_eq!(config.st_spins(), None);
+
+    let st = config.with_st_rush(50).unwrap();
+    assert_eq!(st.st_spins(), Some(50));
+
+    let game = WasmGame::new_with_seed(JsInput::new(), create_test_output(), st, 3).unwrap();
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    for _ in 0..200 {
+        game.run_step_with_command("CauseLottery".to_string())
+            .unwrap();
+        if let GameState::Rush { rush_balls, .. } = game.get_state().unwrap() {
+            assert!(rush_balls <= 50);
+        }
+    }
+}
+
+#[wasm_bindgen_test]
+fn test_jitan() {
+    let config = create_test_config();
+    let boosted = SlotProbability::new(0.5, 0.0, 0.0);
+    assert!(config.with_jitan(0, boosted).is_err());
+    assert_eq!(config.jitan_spins(), None);
+
+    let invalid = config
+        .with_jitan(5, SlotProbability::new(1.5, 0.0, 0.0))
+        .unwrap();
+    assert!(invalid.validate().is_err());
+
+    let jitan = config
+        .with_st_rush(1)
+        .unwrap()
+        .with_jitan(5, boosted)
+        .unwrap();
+    assert_eq!(jitan.jitan_spins(), Some(5));
+
+    let game = WasmGame::new_with_seed(JsInput::new(), create_test_output(), jitan, 3).unwrap();
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    for _ in 0..200 {
+        game.run_step_with_command("CauseLottery".to_string())
+            .unwrap();
+        if let GameState::Jitan { spins, .. } = game.get_state().unwrap() {
+            assert!((1..=5).contains(&spins));
+        }
+    }
+}
+
+#[wasm_bindgen_test]
+fn test_staged_reveal() {
+    let game = create_test_game();
+    assert!(game.set_staged_reveal(true, Some(-1.0)).is_err());
+    game.set_staged_reveal(true, None).unwrap();
+
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    let mut reveals = 0;
+    for _ in 0..1000 {
+        game.run_step_with_command("CauseLottery".to_string())
+            .unwrap();
+        if game.pending_reveal().unwrap().is_some() {
+            let held = game.balls().unwrap();
+            assert!(
+                game.run_step_with_command("CauseLottery".to_string())
+                    .is_err()
+            );
+            assert_eq!(game.balls().unwrap(), held);
+            assert!(game.ack_reveal().unwrap());
+            reveals += 1;
+        }
+    }
+    assert!(reveals > 0);
+    assert!(!game.ack_reveal().unwrap());
+}
+
+#[wasm_bindgen_test]
+fn test_ceiling() {
+    let config = create_test_config();
+    assert!(config.with_ceiling(0).is_err());
+    assert_eq!(config.ceiling_spins(), None);
+
+    let with_ceiling = config.with_ceiling(5).unwrap();
+    assert_eq!(with_ceiling.ceiling_spins(), Some(5));
+
+    let game =
+        WasmGame::new_with_seed(JsInput::new(), create_test_output(), with_ceiling, 11).unwrap();
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    for _ in 0..200 {
+        game.run_step_with_command("CauseLottery".to_string())
+            .unwrap();
+        assert!(game.spins_since_win().unwrap() <= 5);
+    }
+    assert_eq!(
+        game.snapshot().unwrap().spins_since_win,
+        game.spins_since_win().unwrap()
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_analysis_from_replays() {
+    use pachislo_wasm::comparison::{Analysis, ReplaySession};
+
+    let game = create_test_game();
+    game.set_history_enabled(true).unwrap();
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    for _ in 0..50 {
+        game.run_step_with_command("LaunchBall".to_string())
+            .unwrap();
+        game.run_step_with_command("CauseLottery".to_string())
+            .unwrap();
+    }
+
+    let history = game.get_history().unwrap();
+    let report = Analysis::from_replays(vec![
+        ReplaySession {
+            machine: "a".to_string(),
+            history: history.clone(),
+        },
+        ReplaySession {
+            machine: "a".to_string(),
+            history,
+        },
+    ]);
+    assert_eq!(report.machines.len(), 1);
+    assert_eq!(report.machines[0].sessions, 2);
+    assert_eq!(report.machines[0].spins, 100);
+}
+
+#[wasm_bindgen_test]
+fn test_stock() {
+    let config = create_test_config();
+    assert!(config.with_stock(0).is_err());
+    assert_eq!(config.stock_cap(), None);
+
+    let with_stock = config.with_stock(3).unwrap();
+    assert_eq!(with_stock.stock_cap(), Some(3));
+
+    let game =
+        WasmGame::new_with_seed(JsInput::new(), create_test_output(), with_stock, 5).unwrap();
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    for _ in 0..100 {
+        game.run_step_with_command("CauseLottery".to_string())
+            .unwrap();
+        assert!(game.stock().unwrap() <= 3);
+    }
+    assert_eq!(game.snapshot().unwrap().stock, game.stock().unwrap());
+}
+
+#[wasm_bindgen_test]
+fn test_rush_tiers() {
+    use pachislo_wasm::tier::RushTier;
+
+    let config = create_test_config();
+    assert!(config.with_rush_tiers(Vec::new()).is_err());
+    assert!(config.rush_tier_names().is_empty());
+
+    let tier = |name: &str, weight| {
+        RushTier::new(
+            name.to_string(),
+            weight,
+            SlotProbability::new(0.5, 0.0, 0.0),
+            SlotProbability::new(0.8, 0.0, 0.0),
+            20,
+            5,
+        )
+    };
+    assert!(config.with_rush_tiers(vec![tier("Rush", 0.0)]).is_err());
+
+    let tiered = config
+        .with_rush_tiers(vec![tier("Rush", 4.0), tier("Super Rush", 1.0)])
+        .unwrap();
+    assert_eq!(tiered.rush_tier_names(), ["Rush", "Super Rush"]);
+
+    let game = WasmGame::new_with_seed(JsInput::new(), create_test_output(), tiered, 9).unwrap();
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    assert!(game.rush_tier().unwrap().is_none());
+    for _ in 0..100 {
+        game.run_step_with_command("CauseLottery".to_string())
+            .unwrap();
+        let in_rush = matches!(game.get_state().unwrap(), GameState::Rush { .. });
+        assert_eq!(game.rush_tier().unwrap().is_some(), in_rush);
+    }
+}
+
+#[wasm_bindgen_test]
+fn test_rounds() {
+    let config = create_test_config();
+    assert!(config.with_rounds(Vec::new(), Vec::new(), 100).is_err());
+    assert!(config.with_rounds(vec![4, 16], vec![1.0], 100).is_err());
+    assert!(config.with_rounds(vec![0], vec![1.0], 100).is_err());
+    assert!(config.with_rounds(vec![4], vec![-1.0], 100).is_err());
+    assert_eq!(config.balls_per_round(), None);
+
+    let with_rounds = config
+        .with_rounds(vec![4, 10, 16], vec![50.0, 30.0, 20.0], 100)
+        .unwrap();
+    assert_eq!(with_rounds.round_counts(), [4, 10, 16]);
+    assert_eq!(with_rounds.balls_per_round(), Some(100));
+
+    let game =
+        WasmGame::new_with_seed(JsInput::new(), create_test_output(), with_rounds, 3).unwrap();
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    for _ in 0..100 {
+        game.run_step_with_command("CauseLottery".to_string())
+            .unwrap();
+        let spin = game.explain_last_spin().unwrap().unwrap();
+        if let Some(rounds) = spin.rounds {
+            assert!([4, 10, 16].contains(&rounds));
+            assert_eq!(spin.balls_awarded, rounds * 100);
+        }
+    }
+}
+
+#[wasm_bindgen_test]
+fn test_premium_win() {
+    let config = create_test_config();
+    assert!(config.with_premium(0.0).is_err());
+    assert!(config.with_premium(1.5).is_err());
+    assert_eq!(config.premium_probability(), None);
+
+    let with_premium = config.with_premium(1.0).unwrap();
+    assert_eq!(with_premium.premium_probability(), Some(1.0));
+
+    let game =
+        WasmGame::new_with_seed(JsInput::new(), create_test_output(), with_premium, 4).unwrap();
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    for _ in 0..100 {
+        game.run_step_with_command("CauseLottery".to_string())
+            .unwrap();
+        let spin = game.explain_last_spin().unwrap().unwrap();
+        if let LotteryResult::Win(Win::Default) = spin.lottery.result {
+            panic!("every plain win should be premium");
+        }
+    }
+    let stats = game.stats().unwrap();
+    assert_eq!(stats.wins.default, 0);
+    assert!(stats.wins.premium > 0);
+}
+
+#[wasm_bindgen_test]
+fn test_battles() {
+    let config = create_test_config();
+    assert!(config.with_battles(Vec::new(), 0.1).is_err());
+    assert!(config.with_battles(vec!["Slime".to_string()], 1.5).is_err());
+    assert!(config.battle_enemies().is_empty());
+
+    let enemies = vec!["Slime".to_string(), "Dragon".to_string()];
+    let with_battles = config.with_battles(enemies.clone(), 1.0).unwrap();
+    assert_eq!(with_battles.battle_enemies(), enemies);
+
+    // Every won continuation is a revival
+    let game =
+        WasmGame::new_with_seed(JsInput::new(), create_test_output(), with_battles, 6).unwrap();
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    for _ in 0..100 {
+        game.run_step_with_command("CauseLottery".to_string())
+            .unwrap();
+        let spin = game.explain_last_spin().unwrap().unwrap();
+        if let Some(lottery) = spin.continuation.and_then(|c| c.lottery)
+            && lottery.result.is_win()
+        {
+            assert!(matches!(lottery.result, LotteryResult::Win(Win::FakeWin)));
+        }
+    }
+}
+
+#[wasm_bindgen_test]
+fn test_ball_cost() {
+    let mut config = create_test_config();
+    config.balls.launch_cost = 0;
+    assert!(WasmGame::new(JsInput::new(), create_test_output(), config.clone()).is_err());
+
+    config.balls.launch_cost = 5;
+    config.balls.start_rate = 0.0;
+    let game = WasmGame::new_with_seed(JsInput::new(), create_test_output(), config, 1).unwrap();
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    game.run_step_with_command("LaunchBall".to_string())
+        .unwrap();
+    assert_eq!(game.starts().unwrap(), 0);
+
+    // Nothing hits the start pocket, so there is nothing to spin
+    game.run_step_with_command("CauseLottery".to_string())
+        .unwrap();
+    let stats = game.stats().unwrap();
+    assert_eq!(stats.spins, 0);
+    assert_eq!(stats.balls_launched, 5);
+}
+
+#[wasm_bindgen_test]
+fn test_stop_conditions() {
+    use pachislo_wasm::stop::{StopConditions, StopReason};
+
+    let config = create_test_config().with_stop_conditions(StopConditions {
+        stop_after_spins: Some(3),
+        ..StopConditions::default()
+    });
+    assert_eq!(config.stop_conditions().stop_after_spins, Some(3));
+
+    let game = WasmGame::new_with_seed(JsInput::new(), create_test_output(), config, 4).unwrap();
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    for _ in 0..2 {
+        let flow = game
+            .run_step_with_command("CauseLottery".to_string())
+            .unwrap();
+        assert!(matches!(flow, ControlFlow::Continue));
+        assert_eq!(game.stop_reason().unwrap(), None);
+    }
+
+    let flow = game
+        .run_step_with_command("CauseLottery".to_string())
+        .unwrap();
+    assert!(matches!(flow, ControlFlow::Break));
+    assert_eq!(game.stop_reason().unwrap(), Some(StopReason::Spins));
+}
+
+#[wasm_bindgen_test]
+fn test_estimate_payout_rate() {
+    use pachislo_wasm::simulation::estimate_payout_rate;
+
+    let config = create_test_config();
+    let estimate = estimate_payout_rate(&config, 1000, Some(1)).unwrap();
+    assert_eq!(estimate.spins, 1000);
+    assert!(estimate.payout_rate > 0.0);
+    assert!(estimate.standard_error > 0.0);
+
+    let again = estimate_payout_rate(&config, 1000, Some(1)).unwrap();
+    assert_eq!(again.payout_rate, estimate.payout_rate);
+}
+
+#[wasm_bindgen_test]
+fn test_border_line() {
+    use pachislo_wasm::analysis::border_line;
+
+    let config = create_test_config();
+    assert!(border_line(&config, 0.0).is_err());
+
+    let four_yen = border_line(&config, 250.0).unwrap();
+    let one_yen = border_line(&config, 1000.0).unwrap();
+    assert!(four_yen.payout_per_spin > 0.0);
+    assert_eq!(one_yen.spins_per_1000_balls, four_yen.spins_per_1000_balls);
+    assert!((one_yen.spins_per_1000_yen - 4.0 * four_yen.spins_per_1000_yen).abs() < 1e-9);
+}
+
+#[wasm_bindgen_test]
+fn test_spec_report() {
+    let config = create_test_config();
+    let spec = config.spec_report();
+
+    assert!((spec.hit_one_in - 10.0).abs() < 1e-9);
+    assert!(spec.continuation_rate > 0.0 && spec.continuation_rate < 1.0);
+    assert!(spec.expected_payout_per_jackpot >= 15.0);
+    let border = pachislo_wasm::analysis::border_line(&config, 250.0).unwrap();
+    assert_eq!(spec.payout_rate, border.payout_per_spin);
+}
+
+#[wasm_bindgen_test]
+fn test_session_summary() {
+    let game = WasmGame::new_with_seed(
+        JsInput::new(),
+        create_test_output(),
+        create_test_config(),
+        2,
+    )
+    .unwrap();
+    assert_eq!(game.session_summary().unwrap(), None);
+
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    for _ in 0..20 {
+        game.run_step_with_command("CauseLottery".to_string())
+            .unwrap();
+    }
+    game.run_step_with_command("FinishGame".to_string())
+        .unwrap();
+
+    let summary = game.session_summary().unwrap().unwrap();
+    assert!(summary.finished);
+    assert_eq!(summary.spins, 20);
+    assert_eq!(summary.initial_balls, 100);
+    assert!(summary.peak_balls >= summary.final_balls);
+    assert_eq!(
+        summary.net_balls,
+        summary.final_balls as i64 - summary.initial_balls as i64
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_balls_series() {
+    let game = WasmGame::new(JsInput::new(), create_test_output(), create_test_config()).unwrap();
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    assert!(game.get_balls_series().unwrap().is_empty());
+
+    game.set_balls_series(Some(2)).unwrap();
+    for _ in 0..3 {
+        game.run_step_with_command("LaunchBall".to_string())
+            .unwrap();
+    }
+    assert_eq!(game.get_balls_series().unwrap(), [98, 97]);
+
+    game.clear_balls_series().unwrap();
+    assert!(game.get_balls_series().unwrap().is_empty());
+}
+
+#[wasm_bindgen_test]
+fn test_compare_configs() {
+    use pachislo_wasm::simulation::compare_configs;
+
+    let config = create_test_config();
+    let comparison = compare_configs(&config, &config, 1000, Some(3)).unwrap();
+    assert_eq!(comparison.spins, 1000);
+    assert_eq!(comparison.a.payout_rate, comparison.b.payout_rate);
+    assert_eq!(comparison.a.hit_rate, comparison.b.hit_rate);
+}
+
+#[wasm_bindgen_test]
+fn test_estimate_volatility() {
+    use pachislo_wasm::simulation::estimate_volatility;
+
+    let config = create_test_config();
+    let estimate = estimate_volatility(&config, 50, 200, Some(3)).unwrap();
+    assert_eq!((estimate.sessions, estimate.spins_per_session), (50, 200));
+    assert!(estimate.standard_deviation >= 0.0);
+
+    let again = estimate_volatility(&config, 50, 200, Some(3)).unwrap();
+    assert_eq!(estimate.mean_net_balls, again.mean_net_balls);
+}
+
+#[wasm_bindgen_test]
+fn test_estimate_chain_probability() {
+    use pachislo_wasm::rare::estimate_chain_probability;
+
+    let config = create_test_config();
+    let estimate = estimate_chain_probability(&config, 3, 500, 2.0, Some(4)).unwrap();
+    assert_eq!((estimate.rushes, estimate.min_chain), (500, 3));
+    assert!(estimate.hits > 0);
+    assert!(estimate.probability > 0.0 && estimate.probability <= 1.0);
+
+    let again = estimate_chain_probability(&config, 3, 500, 2.0, Some(4)).unwrap();
+    assert_eq!(estimate.probability, again.probability);
+
+    let error = estimate_chain_probability(&config, 3, 500, 0.0, None).unwrap_err();
+    assert!(matches!(error, PachisloError::InvalidArguments { .. }));
+}
+
+#[wasm_bindgen_test]
+fn test_simulation_handle() {
+    use pachislo_wasm::simulation::{SimulationHandle, SimulationOptions, simulate};
+
+    let config = create_test_config();
+    let options = SimulationOptions {
+        seed: Some(8),
+        ..SimulationOptions::default()
+    };
+    let mut handle = SimulationHandle::new(&config, 10, Some(options)).unwrap();
+
+    let progress = handle.run_chunk(10);
+    assert_eq!(progress.iterations, 10);
+    assert!(!progress.done);
+
+    while !handle.run_chunk(1000).done {}
+    assert!(handle.is_done());
+    assert_eq!(handle.run_chunk(1000).iterations, 0);
+
+    let report = simulate(&config, 10, Some(options)).unwrap();
+    assert_eq!(handle.report().games, 10);
+    assert_eq!(handle.report().average_launches, report.average_launches);
+}
+
+#[wasm_bindgen_test]
+fn test_simulation_rejects_invalid_arguments() {
+    use pachislo_wasm::{
+        autoplay::AutoPlayer,
+        simulation::{SimulationHandle, SimulationOptions, estimate_payout_rate, simulate},
+    };
+
+    let invalid = Config::new(
+        BallsConfig::new(100, 15, 50),
+        Probability::new(
+            SlotProbability::new(1.5, 0.0, 0.0),
+            SlotProbability::new(0.8, 0.1, 0.05),
+            SlotProbability::new(0.7, 0.1, 0.05),
+            create_mock_js_function(),
+        ),
+    );
+    assert!(matches!(
+        simulate(&invalid, 10, None),
+        Err(PachisloError::InvalidConfig { .. })
+    ));
+    assert!(matches!(
+        estimate_payout_rate(&invalid, 10, None),
+        Err(PachisloError::InvalidConfig { .. })
+    ));
+
+    let config = create_test_config();
+    for start_hole_probability in [-0.1, 1.5, f64::NAN] {
+        let options = SimulationOptions {
+            start_hole_probability,
+            ..SimulationOptions::default()
+        };
+        assert!(matches!(
+            SimulationHandle::new(&config, 10, Some(options)),
+            Err(PachisloError::InvalidArguments { .. })
+        ));
+        assert!(matches!(
+            AutoPlayer::new(
+                create_test_output(),
+                create_test_config(),
+                start_hole_probability,
+                create_mock_single_callback(),
+            ),
+            Err(PachisloError::InvalidArguments { .. })
+        ));
+    }
+}
+
+#[wasm_bindgen_test]
+fn test_simulation_pool() {
+    use pachislo_wasm::simulation::{SimulationOptions, SimulationPool};
+
+    let config = create_test_config();
+    let options = SimulationOptions {
+        seed: Some(8),
+        ..SimulationOptions::default()
+    };
+    let mut pool = SimulationPool::new(&config, 3).unwrap();
+    assert_eq!(pool.size(), 3);
+
+    let report = pool.run(10, Some(options)).unwrap();
+    assert_eq!(report.games, 10);
+    assert_eq!(
+        pool.run(10, Some(options)).unwrap().average_launches,
+        report.average_launches
+    );
+    assert_eq!(SimulationPool::new(&config, 0).unwrap().size(), 1);
+
+    let options = SimulationOptions {
+        start_hole_probability: 2.0,
+        ..options
+    };
+    assert!(matches!(
+        pool.run(10, Some(options)),
+        Err(PachisloError::InvalidArguments { .. })
+    ));
+}
+
+#[wasm_bindgen_test]
+fn test_export_history() {
+    use pachislo_wasm::history::ExportFormat;
+
+    let game = WasmGame::new(JsInput::new(), create_test_output(), create_test_config()).unwrap();
+    game.set_history_enabled(true).unwrap();
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    game.run_step_with_command("LaunchBall".to_string())
+        .unwrap();
+    game.run_step_with_command("CauseLottery".to_string())
+        .unwrap();
+
+    let csv = game.export_history(ExportFormat::Csv).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[1].contains(",CauseLottery,Normal,"));
+
+    let ndjson = game.export_history(ExportFormat::Ndjson).unwrap();
+    assert_eq!(ndjson.lines().count(), 1);
+    assert!(ndjson.contains("\"balls_before\":99"));
+}
+
+#[wasm_bindgen_test]
+fn test_streaks() {
+    let probability = Probability::new(
+        SlotProbability::new(0.0, 0.0, 0.0),
+        SlotProbability::new(0.8, 0.1, 0.05),
+        SlotProbability::new(0.7, 0.1, 0.05),
+        create_mock_js_function(),
+    );
+    let config = Config::new(BallsConfig::new(100, 15, 50), probability);
+    let game = WasmGame::new(JsInput::new(), create_test_output(), config).unwrap();
+    game.set_streak_thresholds(vec![], vec![5]).unwrap();
+
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    for _ in 0..7 {
+        game.run_step_with_command("CauseLottery".to_string())
+            .unwrap();
+    }
+
+    let streaks = game.streaks().unwrap();
+    assert_eq!((streaks.wins, streaks.losses), (0, 7));
+    assert_eq!(streaks.longest_losses, 7);
+}
+
+#[wasm_bindgen_test]
+fn test_counters() {
+    let game = WasmGame::new(JsInput::new(), create_test_output(), create_test_config()).unwrap();
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    for _ in 0..5 {
+        game.run_step_with_command("CauseLottery".to_string())
+            .unwrap();
+    }
+
+    let counters = game.counters().unwrap();
+    let stats = game.stats().unwrap();
+    assert_eq!(
+        counters.normal.attempts + counters.rush.attempts,
+        stats.spins
+    );
+    assert!(counters.normal.wins <= counters.normal.attempts);
+}
+
+#[wasm_bindgen_test]
+fn test_return_ticker() {
+    let game = create_test_game();
+    assert!(game.set_ticker_smoothing(0.0).is_err());
+    game.set_ticker_smoothing(0.2).unwrap();
+
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    for _ in 0..10 {
+        game.run_step_with_command("LaunchBall".to_string())
+            .unwrap();
+        game.run_step_with_command("CauseLottery".to_string())
+            .unwrap();
+    }
+
+    let ticker = game.return_ticker().unwrap();
+    assert_eq!(ticker.samples, 10);
+    assert_eq!(ticker.balls_per_spin, 1.0);
+    assert_eq!(game.snapshot().unwrap().ticker, ticker);
+}
+
+#[wasm_bindgen_test]
+fn test_hold_queue() {
+    use std::{cell::RefCell, rc::Rc};
+
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let recorded = events.clone();
+    let closure = Closure::wrap(Box::new(move |event_type: JsValue, _: JsValue| {
+        recorded.borrow_mut().push(event_type.as_string().unwrap());
+    }) as Box<dyn FnMut(JsValue, JsValue)>);
+    let emit = closure.as_ref().unchecked_ref::<Function>().clone();
+    closure.forget();
+
+    let output = JsOutput::from_events(pachislo_wasm::event::JsEventOutput::new(
+        JsValue::NULL,
+        emit,
+    ));
+    let game = WasmGame::new(JsInput::new(), output, create_test_config()).unwrap();
+    game.set_hold_queue(true).unwrap();
+    assert!(
+        game.run_step_with_command("ConsumeHold".to_string())
+            .is_err()
+    );
+
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    for _ in 0..6 {
+        game.run_step_with_command("CauseLottery".to_string())
+            .unwrap();
+    }
+    assert_eq!(game.held_count().unwrap(), 4);
+    assert_eq!(
+        events
+            .borrow()
+            .iter()
+            .filter(|event_type| *event_type == "hold_added")
+            .count(),
+        4
+    );
+    assert!(
+        !events
+            .borrow()
+            .iter()
+            .any(|event_type| event_type.starts_with("lottery_"))
+    );
+
+    events.borrow_mut().clear();
+    game.run_step_with_command("ConsumeHold".to_string())
+        .unwrap();
+    assert_eq!(game.held_count().unwrap(), 3);
+    let events = events.borrow();
+    assert!(
+        events
+            .iter()
+            .any(|event_type| event_type.starts_with("lottery_"))
+    );
+    assert_eq!(events.last().unwrap(), "hold_consumed");
+}
+
+#[wasm_bindgen_test]
+fn test_reach_precedes_lottery() {
+    use pachislo_wasm::{event::LotteryEvent, event::ReachEvent, slot::Reels};
+    use std::{cell::RefCell, rc::Rc};
+
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let recorded = events.clone();
+    let closure = Closure::wrap(Box::new(move |event_type: JsValue, payload: JsValue| {
+        recorded
+            .borrow_mut()
+            .push((event_type.as_string().unwrap(), payload));
+    }) as Box<dyn FnMut(JsValue, JsValue)>);
+    let emit = closure.as_ref().unchecked_ref::<Function>().clone();
+    closure.forget();
+
+    let mut output = JsOutput::from_events(pachislo_wasm::event::JsEventOutput::new(
+        JsValue::NULL,
+        emit,
+    ));
+    output.set_slot_layout(3, vec![1, 2]).unwrap();
+    let game = WasmGame::new_with_seed(JsInput::new(), output, create_test_config(), 7).unwrap();
+
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    for _ in 0..20 {
+        game.run_step_with_command("CauseLottery".to_string())
+            .unwrap();
+    }
+
+    let events = events.borrow();
+    let mut reaches = 0;
+    for (i, (event_type, payload)) in events.iter().enumerate() {
+        if !event_type.starts_with("lottery_") {
+            continue;
+        }
+        let lottery: LotteryEvent = serde_wasm_bindgen::from_value(payload.clone()).unwrap();
+        let Reels::Symbols(first) = lottery.slot.0 else {
+            panic!("symbols are not named");
+        };
+
+        if first[0] == first[1] {
+            let (previous_type, previous) = &events[i - 1];
+            assert_eq!(previous_type, "reach");
+            let reach: ReachEvent = serde_wasm_bindgen::from_value(previous.clone()).unwrap();
+            assert_eq!(reach.reels, Reels::Symbols(first[..2].to_vec()));
+            reaches += 1;
+        } else {
+            assert_ne!(events[i - 1].0, "reach");
+        }
+    }
+    assert!(reaches > 0);
+
+    // Callback outputs get the reach through their reach callback
+    let calls = Rc::new(RefCell::new(Vec::new()));
+    let recorder = |name: &'static str| {
+        let recorded = calls.clone();
+        let closure = Closure::wrap(Box::new(move |value: JsValue| {
+            recorded.borrow_mut().push((name, value));
+        }) as Box<dyn FnMut(JsValue)>);
+        let function = closure.as_ref().unchecked_ref::<Function>().clone();
+        closure.forget();
+        function
+    };
+    let lottery = Closure::wrap(Box::new({
+        let recorded = calls.clone();
+        move |_: JsValue, slot: JsValue| recorded.borrow_mut().push(("lottery", slot))
+    }) as Box<dyn FnMut(JsValue, JsValue)>);
+    let on_lottery = lottery.as_ref().unchecked_ref::<Function>().clone();
+    lottery.forget();
+
+    let mut output = JsOutput::new(
+        JsValue::NULL,
+        None,
+        None,
+        Some(on_lottery.clone()),
+        Some(on_lottery.clone()),
+        Some(on_lottery),
+    );
+    output.set_reach_callback(Some(recorder("reach")));
+    output.set_slot_layout(3, vec![1, 2]).unwrap();
+    let game = WasmGame::new_with_seed(JsInput::new(), output, create_test_config(), 7).unwrap();
+
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    for _ in 0..20 {
+        game.run_step_with_command("CauseLottery".to_string())
+            .unwrap();
+    }
+
+    let calls = calls.borrow();
+    let mut callback_reaches = 0;
+    for (i, (name, value)) in calls.iter().enumerate() {
+        if *name != "lottery" {
+            continue;
+        }
+        let (first, _): (Reels, Option<Reels>) =
+            serde_wasm_bindgen::from_value(value.clone()).unwrap();
+        let Reels::Symbols(first) = first else {
+            panic!("symbols are not named");
+        };
+
+        if first[0] == first[1] {
+            let (previous_name, previous) = &calls[i - 1];
+            assert_eq!(*previous_name, "reach");
+            let reels: Reels = serde_wasm_bindgen::from_value(previous.clone()).unwrap();
+            assert_eq!(reels, Reels::Symbols(first[..2].to_vec()));
+            callback_reaches += 1;
+        } else {
+            assert!(i == 0 || calls[i - 1].0 != "reach");
+        }
+    }
+    // The same seed and layout produce the same slots
+    assert_eq!(callback_reaches, reaches);
+}
+
+#[wasm_bindgen_test]
+fn test_hidden_game_coalesces_events() {
+    use std::{cell::RefCell, rc::Rc};
+
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let recorded = events.clone();
+    let closure = Closure::wrap(Box::new(move |event_type: JsValue, _: JsValue| {
+        recorded.borrow_mut().push(event_type.as_string().unwrap());
+    }) as Box<dyn FnMut(JsValue, JsValue)>);
+    let emit = closure.as_ref().unchecked_ref::<Function>().clone();
+    closure.forget();
+
+    let output = JsOutput::from_events(pachislo_wasm::event::JsEventOutput::new(
+        JsValue::NULL,
+        emit,
+    ));
+    let game = WasmGame::new(JsInput::new(), output, create_test_config()).unwrap();
+
+    game.set_visible(false, Some(1e9)).unwrap();
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    game.run_step_with_command("CauseLottery".to_string())
+        .unwrap();
+    assert!(events.borrow().is_empty());
+
+    game.set_visible(true, None).unwrap();
+    game.run_step_with_command("FinishGame".to_string())
+        .unwrap();
+
+    assert_eq!(
+        *events.borrow(),
+        ["foreground_summary", "finish_game", "transition"]
+    );
+    assert!(create_test_game().set_visible(false, None).is_err());
+}
+
+#[wasm_bindgen_test]
+fn test_stamped_command_echoes_timestamp() {
+    use std::{cell::RefCell, rc::Rc};
+
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let recorded = events.clone();
+    let closure = Closure::wrap(Box::new(move |event_type: JsValue, payload: JsValue| {
+        recorded
+            .borrow_mut()
+            .push((event_type.as_string().unwrap(), payload));
+    }) as Box<dyn FnMut(JsValue, JsValue)>);
+    let emit = closure.as_ref().unchecked_ref::<Function>().clone();
+    closure.forget();
+
+    let output = JsOutput::from_events(pachislo_wasm::event::JsEventOutput::new(
+        JsValue::NULL,
+        emit,
+    ));
+    let game = WasmGame::new(JsInput::new(), output, create_test_config()).unwrap();
+
+    game.run_step_with_command_stamped("StartGame".to_string(), 42.0)
+        .unwrap();
+
+    let events = events.borrow();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].0, "transition");
+    assert_eq!(events[1].0, "command_latency");
+    let latency: pachislo_wasm::latency::CommandLatency =
+        serde_wasm_bindgen::from_value(events[1].1.clone()).unwrap();
+    assert_eq!(latency.client_timestamp, 42.0);
+    assert!(latency.processing_time >= 0.0);
+
+    assert!(
+        create_test_game()
+            .run_step_with_command_stamped("StartGame".to_string(), 0.0)
+            .is_err()
+    );
+}
+
+#[wasm_bindgen_test]
+async fn test_async_step_awaits_callback_promises() {
+    let resolved = js_sys::Function::new_with_args(
+        "type",
+        "return new Promise((resolve) => setTimeout(() => { globalThis.__pachisloSettled = type; resolve(); }, 10));",
+    );
+    let output = JsOutput::from_events(pachislo_wasm::event::JsEventOutput::new(
+        JsValue::NULL,
+        resolved,
+    ));
+    let game = WasmGame::new(JsInput::new(), output, create_test_config()).unwrap();
+
+    game.run_step_with_command_async("StartGame".to_string())
+        .await
+        .unwrap();
+    assert_eq!(
+        js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("__pachisloSettled")).unwrap(),
+        "transition"
+    );
+
+    let rejected =
+        js_sys::Function::new_no_args("return Promise.reject(new Error(\"animation failed\"));");
+    let output = JsOutput::from_events(pachislo_wasm::event::JsEventOutput::new(
+        JsValue::NULL,
+        rejected,
+    ));
+    let game = WasmGame::new(JsInput::new(), output, create_test_config()).unwrap();
+    assert!(
+        game.run_step_with_command_async("StartGame".to_string())
+            .await
+            .is_err()
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_throwing_callback() {
+    let throwing = js_sys::Function::new_with_args(
+        "type",
+        "if (type === \"transition\") throw new Error(\"render failed\");",
+    );
+    let output = JsOutput::from_events(pachislo_wasm::event::JsEventOutput::new(
+        JsValue::NULL,
+        throwing,
+    ));
+    let game = WasmGame::new(JsInput::new(), output, create_test_config()).unwrap();
+
+    assert!(matches!(
+        game.run_step_with_command("StartGame".to_string()),
+        Err(PachisloError::CallbackFailed { message }) if message == "render failed"
+    ));
+    // The command was applied, and the game stays usable
+    assert!(matches!(
+        game.get_state().unwrap(),
+        GameState::Normal { balls: 100 }
+    ));
+    assert!(
+        game.run_step_with_command("LaunchBall".to_string())
+            .is_err()
+    );
+    assert!(matches!(
+        game.get_state().unwrap(),
+        GameState::Normal { balls: 99 }
+    ));
+}
+
+#[wasm_bindgen_test]
+fn test_reentrant_call_is_busy() {
+    use std::{cell::RefCell, rc::Rc};
+
+    let game = Rc::new(RefCell::new(None::<WasmGame>));
+    let observed = Rc::new(RefCell::new(Vec::new()));
+
+    let callback = {
+        let game = game.clone();
+        let observed = observed.clone();
+        Closure::<dyn FnMut(JsValue, JsValue)>::new(move |_: JsValue, _: JsValue| {
+            if let Some(game) = game.borrow().as_ref() {
+                observed
+                    .borrow_mut()
+                    .push((game.is_busy(), game.get_state().err()));
+            }
+        })
+    };
+    let output = JsOutput::from_events(pachislo_wasm::event::JsEventOutput::new(
+        JsValue::NULL,
+        callback.as_ref().unchecked_ref::<Function>().clone(),
+    ));
+    callback.forget();
+
+    *game.borrow_mut() = Some(WasmGame::new(JsInput::new(), output, create_test_config()).unwrap());
+    let game = game.borrow();
+    let game = game.as_ref().unwrap();
+    assert!(!game.is_busy());
+
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    let observed = observed.borrow();
+    assert!(!observed.is_empty());
+    assert!(
+        observed
+            .iter()
+            .all(|(busy, error)| *busy && *error == Some(PachisloError::Busy))
+    );
+    assert!(!game.is_busy());
+}
+
+#[wasm_bindgen_test]
+fn test_virtual_clock_drives_cooldowns() {
+    let now = js_sys::Function::new_no_args("return globalThis.__pachisloTestTime;");
+    let set_time = |time: f64| {
+        js_sys::Reflect::set(
+            &js_sys::global(),
+            &JsValue::from_str("__pachisloTestTime"),
+            &JsValue::from(time),
+        )
+        .unwrap();
+    };
+
+    let game = create_test_game();
+    game.set_clock(Some(now)).unwrap();
+    game.set_cooldown("LaunchBall".to_string(), 100.0).unwrap();
+    set_time(0.0);
+
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    game.run_step_with_command("LaunchBall".to_string())
+        .unwrap();
+
+    set_time(50.0);
+    assert!(
+        game.run_step_with_command("LaunchBall".to_string())
+            .is_err()
+    );
+
+    set_time(100.0);
+    assert!(game.run_step_with_command("LaunchBall".to_string()).is_ok());
+}
+
+#[wasm_bindgen_test]
+fn test_run_until_break() {
+    let game = create_test_game();
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+
+    let commands = vec!["LaunchBall".to_string(), "CauseLottery".to_string()];
+    assert_eq!(game.run_until_break(commands.clone(), Some(5)).unwrap(), 5);
+
+    let finish = vec!["LaunchBall".to_string(), "Finish".to_string()];
+    assert_eq!(game.run_until_break(finish, None).unwrap(), 1);
+
+    assert!(
+        game.run_until_break(vec!["Jump".to_string()], Some(1))
+            .is_err()
+    );
+}
+
+/// A game whose event callback throws on the second transition after
+/// `"StartGame"`, i.e. on the second launch.
+fn create_game_failing_on_second_launch() -> WasmGame {
+    let throwing = Function::new_no_args(
+        "let transitions = 0;
+         return (type) => {
+             if (type === \"transition\" && ++transitions === 3) throw new Error(\"render failed\");
+         };",
+    )
+    .call0(&JsValue::NULL)
+    .unwrap()
+    .unchecked_into::<Function>();
+    let output = JsOutput::from_events(pachislo_wasm::event::JsEventOutput::new(
+        JsValue::NULL,
+        throwing,
+    ));
+
+    let game = WasmGame::new(JsInput::new(), output, create_test_config()).unwrap();
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    game
+}
+
+#[wasm_bindgen_test]
+fn test_repeated_steps_throw_callback_failures() {
+    let game = create_game_failing_on_second_launch();
+    let result = game.run_step_with_command_args(
+        "LaunchBall".to_string(),
+        Some(CommandArgs { count: Some(5) }),
+    );
+    assert!(matches!(
+        result,
+        Err(PachisloError::CallbackFailed { message }) if message == "render failed"
+    ));
+    // Both launches ran, and repetition stopped at the failing one
+    assert!(matches!(
+        game.get_state().unwrap(),
+        GameState::Normal { balls: 98 }
+    ));
+
+    let game = create_game_failing_on_second_launch();
+    let result = game.run_until_break(vec!["LaunchBall".to_string()], Some(5));
+    assert!(matches!(result, Err(PachisloError::CallbackFailed { .. })));
+    assert!(matches!(
+        game.get_state().unwrap(),
+        GameState::Normal { balls: 98 }
+    ));
+}
+
+#[wasm_bindgen_test]
+fn test_throwing_clock() {
+    let game = create_test_game();
+    game.set_clock(Some(Function::new_no_args(
+        "throw new Error(\"clock failed\");",
+    )))
+    .unwrap();
+
+    assert!(matches!(
+        game.run_step_with_command("StartGame".to_string()),
+        Err(PachisloError::CallbackFailed { message }) if message == "clock failed"
+    ));
+    assert!(matches!(
+        game.get_state().unwrap(),
+        GameState::Uninitialized
+    ));
+
+    game.set_clock(Some(Function::new_no_args("return \"noon\";")))
+        .unwrap();
+    assert!(matches!(
+        game.run_step_with_command("StartGame".to_string()),
+        Err(PachisloError::CallbackFailed { .. })
+    ));
+
+    game.set_clock(None).unwrap();
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+}
+
+#[wasm_bindgen_test]
+fn test_output_with_only_default_callback() {
+    let output = JsOutput::new(
+        JsValue::NULL,
+        Some(create_mock_single_callback()),
+        None,
+        None,
+        None,
+        None,
+    );
+    let game = WasmGame::new(JsInput::new(), output, create_test_config()).unwrap();
+
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    game.run_step_with_command("CauseLottery".to_string())
+        .unwrap();
+    game.run_step_with_command("FinishGame".to_string())
+        .unwrap();
+}
+
 #[wasm_bindgen_test]
 fn test_lottery_result_is_win() {
     let win_result = LotteryResult::Win(Win::Default);
@@ -223,9 +2172,15 @@ fn test_multiple_games() {
     let game3 = create_test_game();
 
     // Each game should start successfully
-    let result1 = game1.run_step_with_command("StartGame".to_string());
-    let result2 = game2.run_step_with_command("StartGame".to_string());
-    let result3 = game3.run_step_with_command("StartGame".to_string());
+    let result1 = game1
+        .run_step_with_command("StartGame".to_string())
+        .unwrap();
+    let result2 = game2
+        .run_step_with_command("StartGame".to_string())
+        .unwrap();
+    let result3 = game3
+        .run_step_with_command("StartGame".to_string())
+        .unwrap();
 
     // All should return valid results
     match (result1, result2, result3) {
@@ -252,14 +2207,58 @@ fn test_config_with_different_settings() {
     let input = JsInput::new();
     let output = create_test_output();
 
-    let game = WasmGame::new(input, output, config);
-    let result = game.run_step_with_command("StartGame".to_string());
+    let game = WasmGame::new(input, output, config).unwrap();
+    let result = game.run_step_with_command("StartGame".to_string()).unwrap();
 
     match result {
         ControlFlow::Continue | ControlFlow::Break => assert!(true),
     }
 }
 
+#[wasm_bindgen_test]
+fn test_update_config_keeps_state() {
+    let game = create_test_game();
+    game.run_step_with_command("StartGame".to_string()).unwrap();
+    game.run_step_with_command("LaunchBall".to_string())
+        .unwrap();
+
+    game.update_config(create_test_config()).unwrap();
+    assert!(matches!(
+        game.get_state().unwrap(),
+        GameState::Normal { balls: 99 }
+    ));
+
+    let invalid = Config::new(
+        BallsConfig::new(0, 15, 50),
+        Probability::new(
+            SlotProbability::new(0.1, 0.05, 0.02),
+            SlotProbability::new(0.8, 0.1, 0.05),
+            SlotProbability::new(0.7, 0.1, 0.05),
+            create_mock_js_function(),
+        ),
+    );
+    assert!(game.update_config(invalid).is_err());
+}
+
+#[wasm_bindgen_test]
+fn test_invalid_config_is_rejected() {
+    let balls_config = BallsConfig::new(0, 15, 50);
+    let normal_prob = SlotProbability::new(1.5, 0.0, 0.0);
+    let rush_prob = SlotProbability::new(0.8, 0.1, 0.05);
+    let rush_continue_prob = SlotProbability::new(0.7, 0.1, 0.05);
+    let probability = Probability::new(
+        normal_prob,
+        rush_prob,
+        rush_continue_prob,
+        create_mock_js_function(),
+    );
+    let config = Config::new(balls_config, probability);
+
+    assert!(config.validate().is_err());
+    assert!(WasmGame::new(JsInput::new(), create_test_output(), config).is_err());
+    assert!(create_test_config().validate().is_ok());
+}
+
 #[wasm_bindgen_test]
 fn test_extreme_probabilities() {
     // Test with zero probabilities
@@ -275,8 +2274,8 @@ fn test_extreme_probabilities() {
     let input = JsInput::new();
     let output = create_test_output();
 
-    let game = WasmGame::new(input, output, config);
-    let result = game.run_step_with_command("StartGame".to_string());
+    let game = WasmGame::new(input, output, config).unwrap();
+    let result = game.run_step_with_command("StartGame".to_string()).unwrap();
 
     match result {
         ControlFlow::Continue | ControlFlow::Break => assert!(true),
@@ -298,7 +2297,7 @@ fn test_sequential_commands() {
     ];
 
     for command in commands {
-        let result = game.run_step_with_command(command.to_string());
+        let result = game.run_step_with_command(command.to_string()).unwrap();
 
         match result {
             ControlFlow::Continue => continue,
@@ -309,3 +2308,219 @@ fn test_sequential_commands() {
     // If we reach here, the sequence completed successfully
     assert!(true);
 }
+
+#[wasm_bindgen_test]
+fn test_config_hash_covers_every_setting() {
+    use pachislo_wasm::{proof::ScoreProof, stop::StopConditions, tier::RushTier};
+
+    let hash = |config: &Config| ScoreProof::new(0, config, 0, 0).config_hash;
+    let base = create_test_config();
+    let variants = [
+        create_test_config().with_randomized_start(10, 20).unwrap(),
+        create_test_config().with_st_rush(50).unwrap(),
+        create_test_config().with_st_rush(60).unwrap(),
+        create_test_config()
+            .with_jitan(100, SlotProbability::new(0.1, 0.0, 0.0))
+            .unwrap(),
+        create_test_config()
+            .with_jitan(100, SlotProbability::new(0.2, 0.0, 0.0))
+            .unwrap(),
+        create_test_config().with_ceiling(500).unwrap(),
+        create_test_config().with_stock(4).unwrap(),
+        create_test_config()
+            .with_rush_tiers(vec![RushTier::new(
+                "Rush".to_string(),
+                1.0,
+                SlotProbability::new(0.8, 0.1, 0.05),
+                SlotProbability::new(0.7, 0.1, 0.05),
+                15,
+                50,
+            )])
+            .unwrap(),
+        create_test_config()
+            .with_rush_tiers(vec![RushTier::new(
+                "Super Rush".to_string(),
+                1.0,
+                SlotProbability::new(0.8, 0.1, 0.05),
+                SlotProbability::new(0.7, 0.1, 0.05),
+                15,
+                50,
+            )])
+            .unwrap(),
+        create_test_config()
+            .with_rounds(vec![4, 10], vec![50.0, 50.0], 140)
+            .unwrap(),
+        create_test_config()
+            .with_rounds(vec![4, 10], vec![60.0, 40.0], 140)
+            .unwrap(),
+        create_test_config().with_premium(0.05).unwrap(),
+        create_test_config()
+            .with_battles(vec!["Slime".to_string(), "Dragon".to_string()], 0.1)
+            .unwrap(),
+        create_test_config()
+            .with_battles(vec!["Slime".to_string(), "Dragon".to_string()], 0.2)
+            .unwrap(),
+        {
+            let mut config = create_test_config();
+            config.balls.launch_cost = 2;
+            config
+        },
+        {
+            let mut config = create_test_config();
+            config.balls.start_rate = 0.5;
+            config
+        },
+        create_test_config().with_stop_conditions(StopConditions {
+            stop_after_spins: Some(1000),
+            ..StopConditions::default()
+        }),
+        create_test_config().with_stop_conditions(StopConditions {
+            stop_after_rushes: Some(1000),
+            ..StopConditions::default()
+        }),
+    ];
+
+    let mut hashes = vec![hash(&base)];
+    for config in &variants {
+        let variant = hash(config);
+        assert!(!hashes.contains(&variant));
+        hashes.push(variant);
+    }
+    assert_eq!(hash(&create_test_config()), hashes[0]);
+}
+
+#[wasm_bindgen_test]
+fn test_score_proof_verify_replay() {
+    use pachislo_wasm::proof::ScoreProof;
+
+    let mut commands = vec!["StartGame".to_string()];
+    for _ in 0..20 {
+        commands.push("LaunchBall".to_string());
+        commands.push("CauseLottery".to_string());
+    }
+    commands.push("FinishGame".to_string());
+
+    let game = WasmGame::replay(
+        JsInput::new(),
+        create_test_output(),
+        create_test_config(),
+        11,
+        commands.clone(),
+    )
+    .unwrap();
+    let final_balls = game.session_summary().unwrap().unwrap().final_balls;
+
+    let config = create_test_config();
+    let proof = ScoreProof::new(11, &config, commands.len(), final_balls);
+    assert!(
+        proof
+            .verify_replay(create_test_config(), commands.clone())
+            .unwrap()
+    );
+
+    // A made-up score passes the hash check, but not the replay
+    let forged = ScoreProof::new(11, &config, commands.len(), final_balls + 1000);
+    assert!(forged.verify(&config));
+    assert!(
+        !forged
+            .verify_replay(create_test_config(), commands.clone())
+            .unwrap()
+    );
+
+    commands.pop();
+    assert!(!proof.verify_replay(create_test_config(), commands).unwrap());
+}
+
+#[wasm_bindgen_test]
+fn test_campaign_stage_progression() {
+    use pachislo_wasm::campaign::Campaign;
+
+    let mut campaign = Campaign::new(create_test_output(), create_mock_single_callback());
+    assert!(matches!(
+        campaign.run_step_with_command("StartGame".to_string()),
+        Err(PachisloError::InvalidArguments { .. })
+    ));
+    campaign.add_stage(create_test_config(), 0).unwrap();
+    campaign.add_stage(create_test_config(), 0).unwrap();
+    assert_eq!(campaign.stage_count(), 2);
+
+    // A target of zero balls is reached as soon as the stage is playing
+    let result = campaign
+        .run_step_with_command("StartGame".to_string())
+        .unwrap();
+    assert!(matches!(result, ControlFlow::Continue));
+    assert_eq!(campaign.current_stage(), 1);
+
+    let result = campaign
+        .run_step_with_command("LaunchBall".to_string())
+        .unwrap();
+    assert!(matches!(result, ControlFlow::Break));
+    assert!(campaign.is_complete());
+
+    // The last stage is not cleared again
+    assert!(matches!(
+        campaign.run_step_with_command("LaunchBall".to_string()),
+        Err(PachisloError::InvalidArguments { .. })
+    ));
+}
+
+#[wasm_bindgen_test]
+fn test_campaign_rejects_invalid_stage() {
+    use pachislo_wasm::campaign::Campaign;
+
+    let mut campaign = Campaign::new(create_test_output(), create_mock_single_callback());
+    let invalid = Config::new(
+        BallsConfig::new(100, 15, 50),
+        Probability::new(
+            SlotProbability::new(1.5, 0.0, 0.0),
+            SlotProbability::new(0.8, 0.1, 0.05),
+            SlotProbability::new(0.7, 0.1, 0.05),
+            create_mock_js_function(),
+        ),
+    );
+
+    assert!(matches!(
+        campaign.add_stage(invalid, 1000),
+        Err(PachisloError::InvalidConfig { .. })
+    ));
+    assert_eq!(campaign.stage_count(), 0);
+}
+
+#[wasm_bindgen_test]
+fn test_auto_player_runs_manual_commands_first() {
+    use pachislo_wasm::autoplay::AutoPlayer;
+
+    let mut player = AutoPlayer::new(
+        create_test_output(),
+        create_test_config(),
+        0.0,
+        create_mock_single_callback(),
+    )
+    .unwrap();
+
+    // Nothing queued and automatic play stopped
+    assert!(matches!(player.tick().unwrap(), ControlFlow::Break));
+
+    player.queue_command("StartGame".to_string()).unwrap();
+    player.start_auto();
+    assert_eq!(player.pending_commands(), 1);
+
+    player.tick().unwrap();
+    assert!(matches!(
+        player.get_state(),
+        GameState::Normal { balls: 100 }
+    ));
+
+    player.tick().unwrap();
+    assert!(matches!(
+        player.get_state(),
+        GameState::Normal { balls: 99 }
+    ));
+
+    player.queue_command("FinishGame".to_string()).unwrap();
+    player.tick().unwrap();
+    assert!(matches!(player.get_state(), GameState::Uninitialized));
+    assert!(!player.is_auto_running());
+
+    assert!(player.queue_command("Jump".to_string()).is_err());
+}